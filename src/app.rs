@@ -1,21 +1,54 @@
+use std::collections::HashMap;
+use std::future::Future;
 use std::io::Stdout;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use ratatui::{
     backend::CrosstermBackend,
     crossterm::event::{self, Event},
+    layout::Rect,
     Terminal,
 };
 use tokio::sync::{mpsc, RwLock};
+use tokio::task::JoinHandle;
 
-use crate::buffer::Buffer;
-use crate::events::EventBus;
+use crate::buffer::{Buffer, PendingSave, SaveOptions, SearchQuery, WordCount};
+use crate::commands::CommandRegistry;
+use crate::config::{Config, ConfigManager};
+use crate::error::OpenError;
+use crate::events::{AppEvent, EventBus, EventHandler};
 use crate::handlers::{AppStateHandler, KeyboardHandler, MouseHandler};
 use crate::input_system::InputSystem;
-use crate::widgets::CursorManager;
+use crate::jumps::JumpList;
+use crate::marks::{self, GlobalMark};
+use crate::panes::Pane;
+use crate::performance::PerformanceMonitor;
+use crate::theme::{Theme, THEMES_DIR};
+use crate::ui::EditorRenderCache;
+use crate::widgets::{CursorManager, SlotAlignment, StatusSlot};
+
+/// How long a status message stays up unless given its own timeout
+pub const STATUS_MESSAGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Consecutive slow frames before the user is warned about performance
+pub const DEGRADED_FRAMES_BEFORE_WARNING: usize = 30;
+
+/// Most commands kept in the command palette history
+pub const MAX_COMMAND_HISTORY: usize = 100;
+
+/// Status bar slot counting the words and chars of the buffer edited last
+pub const WORD_COUNT_SLOT: &str = "word_count";
+
+/// Source of the current time, swappable so tests can move time forward
+pub type Clock = Arc<dyn Fn() -> Instant + Send + Sync>;
+
+/// Clock that reads the system time
+pub fn system_clock() -> Clock {
+    Arc::new(Instant::now)
+}
 
 /// Contains global state that needs to be shared
 pub struct App {
@@ -40,6 +73,12 @@ pub struct App {
     /// Message to display on status bar
     pub status_message: Option<String>,
 
+    /// When the status message expires, if it does
+    pub status_message_expires_at: Option<Instant>,
+
+    /// Time source for expiring status messages
+    pub clock: Clock,
+
     /// Directory where user extensions and config will be stored
     pub user_dir: PathBuf,
 
@@ -60,6 +99,121 @@ pub struct App {
 
     /// Mouse drag start position for text selection
     pub mouse_drag_start: Option<(usize, usize)>,
+
+    /// Whether the current mouse drag is moving the scrollbar thumb
+    pub scrollbar_drag: bool,
+
+    /// Last click in the editor, to recognize double and triple clicks
+    pub last_click: Option<crate::input::clicks::ClickState>,
+
+    /// Keys held back while a bound key sequence is being typed
+    pub pending_chord: Option<crate::input::chords::PendingChord>,
+
+    /// Colors and spacing used to draw the editor
+    pub theme: Theme,
+
+    /// User settings, loaded from the user directory at startup
+    pub config: Config,
+
+    /// Cached editor region so animation-only frames skip rebuilding it
+    pub editor_cache: EditorRenderCache,
+
+    /// Where the focused editor was drawn in the last frame, `None` before
+    /// the first one
+    pub last_editor_area: Option<Rect>,
+
+    /// Local marks (`a`-`z`), as the id of a buffer and a position in it
+    pub marks: HashMap<char, (usize, crate::buffer::Position)>,
+
+    /// Global marks (`A`-`Z`) that point into files, persisted across sessions
+    pub global_marks: HashMap<char, GlobalMark>,
+
+    /// Editor panes while the view is split, empty when there's a single editor
+    pub panes: Vec<Pane>,
+
+    /// Index of the pane that has focus and receives input
+    pub focused_pane: usize,
+
+    /// Frame and event handling times
+    pub performance: PerformanceMonitor,
+
+    /// Whether to show frame timings in the corner of the editor
+    pub show_performance_overlay: bool,
+
+    /// Whether the help listing the commands is shown
+    pub show_help: bool,
+
+    /// First line of the help in view, when it's longer than the screen
+    pub help_scroll: usize,
+
+    /// Action waiting on the user to decide what to do with unsaved changes
+    pub unsaved_prompt: Option<UnsavedPrompt>,
+
+    /// Query from the search prompt whose matches are highlighted, until Esc
+    pub search_query: Option<SearchQuery>,
+
+    /// Whether the search prompt compiles queries as regexes
+    pub search_regex: bool,
+
+    /// Commands the palette can run, built in or added by plugins
+    pub commands: CommandRegistry<KeyboardHandler>,
+
+    /// Commands fuzzy-matching the palette input, best first
+    pub command_suggestions: Vec<String>,
+
+    /// Suggestion picked with Up/Down, run in place of the input on Enter
+    pub selected_suggestion: Option<usize>,
+
+    /// Matches listed while the palette input is a `search_all_buffers` command
+    pub buffer_matches: Vec<crate::commands::BufferMatch>,
+
+    /// Recently opened files, most recent first, persisted in the user directory
+    pub recent_files: Vec<PathBuf>,
+
+    /// Recent files listed while the palette input is an `open recent` command
+    pub recent_matches: Vec<PathBuf>,
+
+    /// Where the cursor was left in recently edited files, most recent first,
+    /// persisted in the user directory
+    pub cursor_positions: Vec<(PathBuf, crate::buffer::Position)>,
+
+    /// Ids of the buffers, most recently added or switched to first
+    pub buffer_use: Vec<usize>,
+
+    /// Counts behind the word-count slot, of the buffer last edited
+    pub word_count: Option<WordCount>,
+
+    /// Locations the cursor jumped away from, for Ctrl+O and Ctrl+I
+    pub jump_list: JumpList,
+
+    /// Commands run from the palette, oldest first
+    pub command_history: Vec<String>,
+
+    /// Entry of `command_history` recalled into the palette input, if any
+    pub history_index: Option<usize>,
+
+    /// When a buffer was last edited, for auto-save to wait for a pause
+    pub last_edit_at: Option<Instant>,
+
+    /// Handlers registered with `on_event`, by event type, subscribed to the
+    /// event bus when the app runs
+    pub event_hooks: Vec<(String, EventHandler)>,
+}
+
+/// Actions that ask before throwing away unsaved changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsavedPrompt {
+    /// Quitting while any buffer is modified
+    Quit,
+
+    /// Closing the active buffer while it's modified
+    CloseBuffer,
+
+    /// Reloading the active buffer from disk while it's modified
+    Reload,
+
+    /// Saving the active buffer over a file another program changed
+    Overwrite,
 }
 
 /// Command input modes
@@ -99,6 +253,8 @@ impl App {
             command_mode: CommandMode::Normal,
             command_input: String::new(),
             status_message: None,
+            status_message_expires_at: None,
+            clock: system_clock(),
             user_dir,
             background_tasks: BackgroundTasks::default(),
             toast_manager: crate::widgets::toast::ToastManager::new(),
@@ -106,25 +262,70 @@ impl App {
             cursor_manager: CursorManager::new(),
             status_bar: crate::widgets::StatusBar::new(),
             mouse_drag_start: None,
+            scrollbar_drag: false,
+            last_click: None,
+            pending_chord: None,
+            theme: Theme::default(),
+            config: Config::default(),
+            editor_cache: EditorRenderCache::default(),
+            last_editor_area: None,
+            marks: HashMap::new(),
+            global_marks: HashMap::new(),
+            panes: Vec::new(),
+            focused_pane: 0,
+            performance: PerformanceMonitor::default(),
+            show_performance_overlay: false,
+            show_help: false,
+            help_scroll: 0,
+            search_query: None,
+            search_regex: false,
+            commands: CommandRegistry::with_builtins(),
+            command_suggestions: Vec::new(),
+            selected_suggestion: None,
+            buffer_matches: Vec::new(),
+            recent_files: Vec::new(),
+            recent_matches: Vec::new(),
+            cursor_positions: Vec::new(),
+            buffer_use: Vec::new(),
+            word_count: None,
+            jump_list: JumpList::default(),
+            command_history: Vec::new(),
+            history_index: None,
+            last_edit_at: None,
+            event_hooks: Vec::new(),
+            unsaved_prompt: None,
         };
 
+        app.load_config();
+        app.load_global_marks();
+        app.load_recent_files();
+        app.load_cursor_positions();
         app.init_status_bar();
         app
     }
 
-    pub async fn with_file(file_path: &str) -> Result<Self> {
+    /// Start with `file_path` open in the only buffer
+    ///
+    /// A path that doesn't exist opens an empty buffer the first save
+    /// creates the file from; any other failure to read it is an error.
+    pub async fn with_file(file_path: &str) -> Result<Self, OpenError> {
         let user_dir = dirs::config_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join("editor");
 
         // Create user directory if it doesn't exist
         if !user_dir.exists() {
-            tokio::fs::create_dir_all(&user_dir).await?;
+            tokio::fs::create_dir_all(&user_dir)
+                .await
+                .map_err(|e| OpenError::from_io(&user_dir, e))?;
         }
 
-        let buffer = Buffer::from_path_async(PathBuf::from(file_path))
-            .await
-            .map_err(|e| anyhow!("Failed to open file '{}': {}", file_path, e))?;
+        let path = PathBuf::from(file_path);
+        let buffer = match Buffer::from_path_async(path.clone()).await {
+            Ok(buffer) => buffer,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Buffer::new_file(path),
+            Err(e) => return Err(OpenError::from_io(file_path, e)),
+        };
 
         let mut app = Self {
             running: true,
@@ -134,6 +335,8 @@ impl App {
             command_mode: CommandMode::Normal,
             command_input: String::new(),
             status_message: None,
+            status_message_expires_at: None,
+            clock: system_clock(),
             user_dir,
             background_tasks: BackgroundTasks::default(),
             toast_manager: crate::widgets::toast::ToastManager::new(),
@@ -141,12 +344,97 @@ impl App {
             cursor_manager: CursorManager::new(),
             status_bar: crate::widgets::StatusBar::new(),
             mouse_drag_start: None,
+            scrollbar_drag: false,
+            last_click: None,
+            pending_chord: None,
+            theme: Theme::default(),
+            config: Config::default(),
+            editor_cache: EditorRenderCache::default(),
+            last_editor_area: None,
+            marks: HashMap::new(),
+            global_marks: HashMap::new(),
+            panes: Vec::new(),
+            focused_pane: 0,
+            performance: PerformanceMonitor::default(),
+            show_performance_overlay: false,
+            show_help: false,
+            help_scroll: 0,
+            search_query: None,
+            search_regex: false,
+            commands: CommandRegistry::with_builtins(),
+            command_suggestions: Vec::new(),
+            selected_suggestion: None,
+            buffer_matches: Vec::new(),
+            recent_files: Vec::new(),
+            recent_matches: Vec::new(),
+            cursor_positions: Vec::new(),
+            buffer_use: Vec::new(),
+            word_count: None,
+            jump_list: JumpList::default(),
+            command_history: Vec::new(),
+            history_index: None,
+            last_edit_at: None,
+            event_hooks: Vec::new(),
+            unsaved_prompt: None,
         };
 
+        app.load_config();
+        app.load_global_marks();
+        app.load_recent_files();
+        app.load_cursor_positions();
+        app.restore_cursor_position(0);
         app.init_status_bar();
+        if let Some(warning) = app.buffers[0].encoding_warning() {
+            app.toast_manager.add_warning(warning);
+        }
         Ok(app)
     }
 
+    /// Register `handler` for events of `event_type` (e.g. "buffer_changed"),
+    /// for code embedding the editor
+    ///
+    /// Handlers are subscribed to the event bus when `run` creates it, after
+    /// the editor's own handlers, so they see every event of that type from
+    /// the start. Register them before calling `run`.
+    pub fn on_event<F>(&mut self, event_type: &str, handler: F)
+    where
+        F: Fn(&AppEvent) -> Result<()> + Send + Sync + 'static,
+    {
+        self.event_hooks
+            .push((event_type.to_string(), Arc::new(handler)));
+    }
+
+    /// Subscribe the keyboard, mouse and app state handlers for `app_state`
+    /// to `event_bus`, followed by the ones registered with `on_event`
+    pub async fn subscribe_handlers(
+        app_state: &Arc<RwLock<App>>,
+        event_bus: &EventBus,
+    ) -> Result<()> {
+        let keyboard_handler = KeyboardHandler::new(app_state.clone(), event_bus.sender());
+        let mouse_handler = MouseHandler::new(app_state.clone(), event_bus.sender());
+        let app_state_handler = AppStateHandler::new(app_state.clone());
+
+        keyboard_handler.subscribe(event_bus).await?;
+        mouse_handler.subscribe(event_bus).await?;
+        app_state_handler.subscribe(event_bus).await?;
+        app_state
+            .read()
+            .await
+            .subscribe_event_hooks(event_bus)
+            .await;
+        Ok(())
+    }
+
+    /// Subscribe the handlers registered with `on_event` to `event_bus`
+    pub async fn subscribe_event_hooks(&self, event_bus: &EventBus) {
+        for (event_type, handler) in &self.event_hooks {
+            let handler = handler.clone();
+            event_bus
+                .subscribe(event_type, move |event| handler(event))
+                .await;
+        }
+    }
+
     /// Run the application with the new event-driven architecture
     pub async fn run(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<bool> {
         // Create user config directory if it doesn't exist
@@ -162,62 +450,73 @@ impl App {
         let input_system = InputSystem::new(event_bus.clone());
 
         // Create and subscribe event handlers
-        let keyboard_handler = KeyboardHandler::new(app_state.clone(), input_system.event_sender());
-        let mouse_handler = MouseHandler::new(app_state.clone(), input_system.event_sender());
-        let app_state_handler = AppStateHandler::new(app_state.clone());
-
-        keyboard_handler.subscribe(&event_bus).await?;
-        mouse_handler.subscribe(&event_bus).await?;
-        app_state_handler.subscribe(&event_bus).await?;
-
-        // Start event processing in background
-        let event_bus_clone = event_bus.clone();
-        tokio::spawn(async move {
-            if let Err(e) = event_bus_clone.start_processing().await {
-                eprintln!("Event processing error: {}", e);
-            }
-        });
+        Self::subscribe_handlers(&app_state, &event_bus).await?;
 
         // Create redraw channel for animations to signal render needs
         let (redraw_tx, mut redraw_rx) = mpsc::unbounded_channel::<()>();
 
+        {
+            let mut app = app_state.write().await;
+
+            // Start event processing in background
+            let event_bus_clone = event_bus.clone();
+            app.background_tasks.spawn("event processing", async move {
+                if let Err(e) = event_bus_clone.start_processing().await {
+                    eprintln!("Event processing error: {}", e);
+                }
+            });
+
+            // Save modified buffers in the background once editing pauses
+            app.background_tasks.spawn(
+                "auto-save",
+                crate::autosave::auto_save_task(app_state.clone(), redraw_tx.clone()),
+            );
+        }
+
         // Pure event-driven architecture for 0% CPU usage when idle
         let mut needs_redraw = true; // Initial render
+        let mut editor_dirty = false; // Input arrived, so the editor region must be rebuilt
+        let mut event_time = None; // How long the last input took to handle
 
         // Spawn cursor animation task using Tokio
         let app_state_cursor = app_state.clone();
         let redraw_signal = redraw_tx.clone();
-        let cursor_animation_handle = tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_millis(500));
-            loop {
-                interval.tick().await;
-
-                // Check if we need cursor updates
-                let (should_update, has_changes) = {
-                    let mut app = app_state_cursor.write().await;
-                    if !app.running {
-                        return; // Exit if app is closing
-                    }
+        let blink_ms = app_state.read().await.config.ui.cursor_blink_ms.max(1);
+        app_state
+            .write()
+            .await
+            .background_tasks
+            .spawn("cursor animation", async move {
+                let mut interval = tokio::time::interval(Duration::from_millis(blink_ms));
+                loop {
+                    interval.tick().await;
+
+                    // Check if we need cursor updates
+                    let (should_update, has_changes) = {
+                        let mut app = app_state_cursor.write().await;
+                        if !app.running {
+                            return; // Exit if app is closing
+                        }
 
-                    let has_active = app.cursor_manager.has_active_cursors();
-                    if has_active {
-                        let old_state = app.cursor_manager.get_blink_states();
-                        app.cursor_manager.tick_animation();
-                        let new_state = app.cursor_manager.get_blink_states();
-                        (true, old_state != new_state)
-                    } else {
-                        (false, false)
-                    }
-                };
+                        let has_active = app.cursor_manager.has_active_cursors();
+                        if has_active {
+                            let old_state = app.cursor_manager.get_blink_states();
+                            app.cursor_manager.tick_animation();
+                            let new_state = app.cursor_manager.get_blink_states();
+                            (true, old_state != new_state)
+                        } else {
+                            (false, false)
+                        }
+                    };
 
-                if should_update && has_changes {
-                    // Signal that a redraw is needed
-                    let _ = redraw_signal.send(());
-                } else if !should_update {
-                    break; // Exit if no active cursors
+                    if should_update && has_changes {
+                        // Signal that a redraw is needed
+                        let _ = redraw_signal.send(());
+                    } else if !should_update {
+                        break; // Exit if no active cursors
+                    }
                 }
-            }
-        });
+            });
 
         // Main event loop - pure event-driven, 0% CPU when idle
         loop {
@@ -232,10 +531,20 @@ impl App {
             // Render immediately if needed, then wait for events
             if needs_redraw {
                 let mut app = app_state.write().await;
+                if editor_dirty {
+                    app.editor_cache.mark_dirty();
+                    editor_dirty = false;
+                }
+                if let Some(duration) = event_time.take() {
+                    app.performance.add_event_time(duration);
+                }
+                app.performance.frame_start();
                 if let Err(e) = terminal.draw(|f| app.render(f)) {
                     eprintln!("Rendering error: {}", e);
                     break;
                 }
+                app.performance.frame_end();
+                app.warn_if_performance_degraded();
                 drop(app);
                 needs_redraw = false;
             }
@@ -245,25 +554,45 @@ impl App {
                 // Terminal events (user input, resize, etc.) - direct read for minimal latency
                 event_result = async {
                     // Use async blocking to avoid task spawn overhead
-                    tokio::task::block_in_place(|| event::read())
+                    tokio::task::block_in_place(event::read)
                 } => {
                     match event_result {
                         Ok(event) => {
                             match event {
                                 Event::Key(key) => {
+                                    let started = Instant::now();
                                     if let Err(e) = input_system.handle_key_input(key) {
                                         eprintln!("Error handling key input: {}", e);
                                     }
+                                    event_time = Some(started.elapsed());
                                     needs_redraw = true;
+                                    editor_dirty = true;
                                 }
                                 Event::Mouse(mouse) => {
+                                    let started = Instant::now();
                                     if let Err(e) = input_system.handle_mouse_input(mouse) {
                                         eprintln!("Error handling mouse input: {}", e);
                                     }
+                                    event_time = Some(started.elapsed());
+                                    needs_redraw = true;
+                                    editor_dirty = true;
+                                }
+                                Event::Paste(text) => {
+                                    let started = Instant::now();
+                                    if let Err(e) = input_system.handle_paste(text) {
+                                        eprintln!("Error handling paste: {}", e);
+                                    }
+                                    event_time = Some(started.elapsed());
+                                    needs_redraw = true;
+                                    editor_dirty = true;
+                                }
+                                Event::FocusGained => {
+                                    app_state.write().await.check_files_on_disk();
                                     needs_redraw = true;
                                 }
                                 Event::Resize(_, _) => {
                                     needs_redraw = true;
+                                    editor_dirty = true;
                                 }
                                 _ => {
                                     // Other events don't need redraw
@@ -277,15 +606,15 @@ impl App {
                     }
                 }
 
-                // Animation redraw signals
+                // Animation redraw signals (the editor region is reused unless it changed)
                 _ = redraw_rx.recv() => {
                     needs_redraw = true;
                 }
             }
         }
 
-        // Clean up cursor animation task
-        cursor_animation_handle.abort();
+        // Stop every background task rather than leaving them running
+        app_state.write().await.background_tasks.abort_all();
 
         match Arc::try_unwrap(app_state) {
             Ok(app_mutex) => {
@@ -301,6 +630,14 @@ impl App {
             }
         }
 
+        // Remember the open files for the next run
+        if let Err(e) = self.save_session() {
+            eprintln!("Warning: Could not save session: {}", e);
+        }
+        if let Err(e) = self.remember_all_cursor_positions() {
+            eprintln!("Warning: Could not save cursor positions: {}", e);
+        }
+
         Ok(true)
     }
 
@@ -314,10 +651,29 @@ impl App {
         self.buffers.get_mut(self.active_buffer)
     }
 
+    /// Find the current index of the buffer with the given stable id
+    pub fn buffer_index_by_id(&self, id: usize) -> Option<usize> {
+        self.buffers.iter().position(|buffer| buffer.id == id)
+    }
+
+    /// Look for open files other programs changed, offering to reload each
+    /// one the first time it's noticed
+    pub fn check_files_on_disk(&mut self) {
+        for buffer in &mut self.buffers {
+            if buffer.check_disk() {
+                self.toast_manager.add_warning(format!(
+                    "{} changed on disk - run reload to load it",
+                    buffer.name
+                ));
+            }
+        }
+    }
+
     /// Switch to a different buffer by index
     pub fn switch_to_buffer(&mut self, index: usize) -> bool {
         if index < self.buffers.len() {
             self.active_buffer = index;
+            self.note_buffer_used(index);
             // Reset scroll when switching buffers
             self.scroll_offset = (0, 0);
             true
@@ -327,41 +683,242 @@ impl App {
     }
 
     /// Close the current buffer
+    ///
+    /// Closing the last buffer leaves a fresh empty one in its place.
     pub fn close_current_buffer(&mut self) -> bool {
-        if self.buffers.len() <= 1 {
-            // Don't close the last buffer
+        if self.buffers.is_empty() {
             return false;
         }
+        if self.buffers.len() == 1 {
+            self.buffers[0] = Buffer::new();
+            self.active_buffer = 0;
+            self.scroll_offset = (0, 0);
+            self.buffer_use.clear();
+            self.note_buffer_used(0);
+            return true;
+        }
 
-        self.buffers.remove(self.active_buffer);
+        let removed = self.active_buffer;
+        let id = self.buffers.remove(removed).id;
+        self.buffer_use.retain(|&used| used != id);
 
         // Adjust active buffer index if necessary
         if self.active_buffer >= self.buffers.len() {
             self.active_buffer = self.buffers.len() - 1;
         }
+        self.remove_buffer_from_panes(removed);
+        self.note_buffer_used(self.active_buffer);
 
         // Reset scroll when closing buffer
         self.scroll_offset = (0, 0);
         true
     }
 
-    /// Add a new buffer
+    /// Add a new buffer and make it the active one, returning its index
+    ///
+    /// Going over `max_open_buffers` warns, or closes the least recently used
+    /// buffer with `close_lru` set.
     pub fn add_buffer(&mut self, buffer: Buffer) -> usize {
         self.buffers.push(buffer);
         let new_index = self.buffers.len() - 1;
         self.active_buffer = new_index;
         self.scroll_offset = (0, 0);
-        new_index
+        self.note_buffer_used(new_index);
+        self.enforce_buffer_limit();
+        self.active_buffer
+    }
+
+    /// Load the user config, keeping the defaults if it can't be read
+    pub fn load_config(&mut self) {
+        let mut config_manager = ConfigManager::new(&self.user_dir);
+        match config_manager.load() {
+            Ok(()) => self.config = config_manager.get_config().clone(),
+            Err(e) => eprintln!("Warning: Could not load config: {}", e),
+        }
+        self.toast_manager
+            .configure(self.config.ui.toast_position, self.config.ui.max_toasts);
+        self.cursor_manager.configure(&self.config.ui);
+
+        let theme_name = self.config.ui.theme.clone();
+        if let Err(e) = self.set_theme(&theme_name) {
+            eprintln!("Warning: Could not load theme: {}", e);
+        }
+    }
+
+    /// Switch to the theme called `name`, built in or from the themes
+    /// directory, and remember it in the config
+    pub fn set_theme(&mut self, name: &str) -> Result<()> {
+        self.theme = Theme::load(&self.user_dir.join(THEMES_DIR), name)?;
+        self.config.ui.theme = name.to_string();
+        self.init_status_bar();
+        Ok(())
+    }
+
+    /// Clean-ups to apply to buffers on save, from the editor settings
+    pub fn save_options(&self) -> SaveOptions {
+        SaveOptions {
+            trim_trailing_whitespace: self.config.editor.trim_trailing_whitespace,
+            insert_final_newline: self.config.editor.insert_final_newline,
+        }
+    }
+
+    /// Record a save written to disk in the buffer it was taken from,
+    /// returning its index, or `None` if it was closed while writing
+    pub fn finish_save(&mut self, save: &PendingSave) -> Option<usize> {
+        let index = self
+            .buffers
+            .iter()
+            .position(|buffer| buffer.id == save.buffer_id)?;
+        self.buffers[index].finish_save(save);
+        Some(index)
+    }
+
+    /// Load persisted global marks, keeping an empty set if they can't be read
+    pub fn load_global_marks(&mut self) {
+        match marks::load_global_marks(&self.user_dir) {
+            Ok(global_marks) => self.global_marks = global_marks,
+            Err(e) => eprintln!("Warning: Could not load global marks: {}", e),
+        }
     }
 
     /// Set a status message with automatic timeout
     pub fn set_status_message(&mut self, message: String) {
+        self.set_status_message_with_timeout(message, STATUS_MESSAGE_TIMEOUT);
+    }
+
+    /// Set a status message that clears itself after `timeout`
+    pub fn set_status_message_with_timeout(&mut self, message: String, timeout: Duration) {
         self.status_message = Some(message);
+        self.status_message_expires_at = Some((self.clock)() + timeout);
     }
 
     /// Clear the status message
     pub fn clear_status_message(&mut self) {
         self.status_message = None;
+        self.status_message_expires_at = None;
+    }
+
+    /// Clear the status message if its timeout has run out
+    pub fn prune_status_message(&mut self) {
+        let expired = self
+            .status_message_expires_at
+            .is_some_and(|expires_at| (self.clock)() >= expires_at);
+        if expired {
+            self.clear_status_message();
+        }
+    }
+
+    /// Show a warning toast once frames have been slow for a while
+    ///
+    /// Fires once per slow stretch, when it reaches
+    /// `DEGRADED_FRAMES_BEFORE_WARNING` frames.
+    pub fn warn_if_performance_degraded(&mut self) {
+        if self.performance.degraded_streak() == DEGRADED_FRAMES_BEFORE_WARNING {
+            let warning = format!(
+                "Rendering is slow ({}); F10 shows frame timings",
+                self.performance.stats_string()
+            );
+            self.toast_manager.add_warning(warning);
+        }
+    }
+
+    /// Match the command palette input against the built-in commands again,
+    /// or search every buffer for a `search_all_buffers` query
+    ///
+    /// Called whenever the input is edited, which also stops browsing history.
+    pub fn update_command_suggestions(&mut self) {
+        self.command_suggestions.clear();
+        self.buffer_matches.clear();
+        self.recent_matches.clear();
+        if let Some(query) = crate::commands::search_all_buffers_query(&self.command_input) {
+            self.buffer_matches = crate::commands::search_all_buffers(&self.buffers, query);
+        } else if let Some(query) = crate::recent::open_recent_query(&self.command_input) {
+            self.recent_matches = self.recent_file_matches(query);
+        } else {
+            // `open recent` is suggested like a command of its own
+            let names = self.commands.names().chain([crate::recent::OPEN_RECENT]);
+            self.command_suggestions =
+                crate::commands::suggest_commands(names, &self.command_input);
+        }
+        self.selected_suggestion = None;
+        self.history_index = None;
+    }
+
+    /// Number of entries listed under the palette input, commands or matches
+    pub fn suggestion_count(&self) -> usize {
+        self.command_suggestions.len() + self.buffer_matches.len() + self.recent_matches.len()
+    }
+
+    /// Switch to the buffer of a `search_all_buffers` match and put the
+    /// cursor at it
+    pub fn jump_to_buffer_match(&mut self, found: &crate::commands::BufferMatch) -> bool {
+        if found.buffer >= self.buffers.len() {
+            return false;
+        }
+        self.record_jump();
+        self.switch_to_buffer(found.buffer);
+        let buffer = &mut self.buffers[found.buffer];
+        buffer.clear_selection();
+        buffer.clear_secondary_cursors();
+        buffer.cursor_pos = found.position.to_tuple();
+        true
+    }
+
+    /// Remember a command that ran, unless it repeats the last one
+    pub fn push_command_history(&mut self, command: &str) {
+        self.history_index = None;
+        if self
+            .command_history
+            .last()
+            .is_some_and(|last| last == command)
+        {
+            return;
+        }
+        self.command_history.push(command.to_string());
+        let overflow = self
+            .command_history
+            .len()
+            .saturating_sub(MAX_COMMAND_HISTORY);
+        self.command_history.drain(..overflow);
+    }
+
+    /// Step through the command history into the palette input, like a shell
+    ///
+    /// Up (`-1`) goes to older commands and stops at the oldest; Down (`1`)
+    /// goes back towards the newest and then to an empty input.
+    pub fn recall_command_history(&mut self, delta: isize) {
+        let newest = match self.command_history.len() {
+            0 => return,
+            len => len - 1,
+        };
+        self.history_index = match (self.history_index, delta < 0) {
+            (None, true) => Some(newest),
+            (None, false) => return,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index < newest => Some(index + 1),
+            (Some(_), false) => None,
+        };
+        self.command_input = self
+            .history_index
+            .map_or_else(String::new, |index| self.command_history[index].clone());
+        self.command_suggestions.clear();
+        self.buffer_matches.clear();
+        self.recent_matches.clear();
+        self.selected_suggestion = None;
+    }
+
+    /// Move the picked suggestion by `delta`, wrapping around the list
+    pub fn select_suggestion(&mut self, delta: isize) {
+        let count = self.suggestion_count() as isize;
+        if count == 0 {
+            return;
+        }
+        let index = match self.selected_suggestion {
+            Some(index) => (index as isize + delta).rem_euclid(count),
+            None if delta < 0 => count - 1,
+            None => 0,
+        };
+        self.selected_suggestion = Some(index as usize);
     }
 
     /// Check if any buffers have unsaved changes
@@ -369,76 +926,150 @@ impl App {
         self.buffers.iter().any(|buffer| buffer.is_dirty())
     }
 
+    /// Indices of the modified buffers an unsaved-changes prompt is about
+    pub fn unsaved_buffers_for(&self, prompt: UnsavedPrompt) -> Vec<usize> {
+        match prompt {
+            UnsavedPrompt::Quit => (0..self.buffers.len())
+                .filter(|&index| self.buffers[index].is_dirty())
+                .collect(),
+            UnsavedPrompt::Overwrite => self
+                .buffers
+                .get(self.active_buffer)
+                .filter(|buffer| buffer.changed_on_disk)
+                .map(|_| vec![self.active_buffer])
+                .unwrap_or_default(),
+            UnsavedPrompt::CloseBuffer | UnsavedPrompt::Reload => self
+                .buffers
+                .get(self.active_buffer)
+                .filter(|buffer| buffer.is_dirty())
+                .map(|_| vec![self.active_buffer])
+                .unwrap_or_default(),
+        }
+    }
+
     /// Get the number of open buffers
     pub fn buffer_count(&self) -> usize {
         self.buffers.len()
     }
 
     /// Initialize the status bar with default slots
+    ///
+    /// Called again after the theme changes, it also moves added slots drawn
+    /// in the old theme's styles over to the new ones.
     pub fn init_status_bar(&mut self) {
-        use crate::widgets::{SlotAlignment, StatusSlot};
-        use ratatui::style::{Color, Style};
+        let style = self.theme.status_bar_style();
+        let dim_style = self.theme.status_bar_dim_style();
+        let mode_style = self.theme.status_bar_mode_style();
+        let restyled: Vec<_> = [
+            ("file_info", style),
+            ("buffer_count", dim_style),
+            ("mode_indicator", mode_style),
+        ]
+        .into_iter()
+        .filter_map(|(id, new)| Some((self.status_bar.get_slot(id)?.style, new)))
+        .collect();
+        self.status_bar.restyle_slots(&restyled);
+        self.status_bar = std::mem::take(&mut self.status_bar).with_background_style(style);
 
         // File info slot (left side, high priority)
-        let file_slot = StatusSlot::new("file", "")
+        let file_slot = StatusSlot::new("file_info", "")
             .with_alignment(SlotAlignment::Left)
             .with_priority(100)
-            .with_style(Style::default().fg(Color::White).bg(Color::LightBlue));
+            .with_style(style);
         self.status_bar.set_slot(file_slot);
 
         // Cursor position slot (left side, medium priority)
-        let cursor_slot = StatusSlot::new("cursor", "")
+        let cursor_slot = StatusSlot::new("cursor_pos", "")
             .with_alignment(SlotAlignment::Left)
             .with_priority(90)
-            .with_style(Style::default().fg(Color::White).bg(Color::LightBlue));
+            .with_style(style);
         self.status_bar.set_slot(cursor_slot);
 
+        // Selection info slot (left side, only visible while text is selected)
+        let selection_slot = StatusSlot::new("selection_info", "")
+            .with_alignment(SlotAlignment::Left)
+            .with_priority(85)
+            .with_visibility(false)
+            .with_style(style);
+        self.status_bar.set_slot(selection_slot);
+
         // Modified status slot (left side, medium priority)
-        let modified_slot = StatusSlot::new("modified", "")
+        let modified_slot = StatusSlot::new("modified_status", "")
             .with_alignment(SlotAlignment::Left)
             .with_priority(80)
-            .with_style(Style::default().fg(Color::White).bg(Color::LightBlue));
+            .with_style(style);
         self.status_bar.set_slot(modified_slot);
 
         // Mode indicator slot (right side, high priority)
-        let mode_slot = StatusSlot::new("mode", "NORMAL")
+        let mode_slot = StatusSlot::new("mode_indicator", "NORMAL")
             .with_alignment(SlotAlignment::Right)
             .with_priority(100)
-            .with_style(Style::default().fg(Color::White).bg(Color::DarkGray));
+            .with_style(mode_style);
         self.status_bar.set_slot(mode_slot);
 
+        // File encoding and line ending slots (right side), clicked to change
+        let encoding_slot = StatusSlot::new("encoding", "")
+            .with_alignment(SlotAlignment::Right)
+            .with_priority(75)
+            .with_style(dim_style);
+        self.status_bar.set_slot(encoding_slot);
+        let line_ending_slot = StatusSlot::new("line_ending", "")
+            .with_alignment(SlotAlignment::Right)
+            .with_priority(70)
+            .with_style(dim_style);
+        self.status_bar.set_slot(line_ending_slot);
+
         // Buffer count slot (right side, low priority)
         let buffer_count_slot = StatusSlot::new("buffer_count", "")
             .with_alignment(SlotAlignment::Right)
             .with_priority(60)
-            .with_style(Style::default().fg(Color::Gray).bg(Color::LightBlue));
+            .with_style(dim_style);
         self.status_bar.set_slot(buffer_count_slot);
+
+        // Focused pane slot (right side, only visible while the editor is split)
+        let pane_slot = StatusSlot::new("pane_info", "")
+            .with_alignment(SlotAlignment::Right)
+            .with_priority(55)
+            .with_visibility(false)
+            .with_style(dim_style);
+        self.status_bar.set_slot(pane_slot);
     }
 
     /// Update status bar slots with current application state
     pub fn update_status_bar(&mut self) {
+        self.prune_status_message();
+
         if let Some(buffer) = self.buffers.get(self.active_buffer) {
             let (row, col) = buffer.cursor_pos;
 
             // Update file info
-            self.status_bar.update_slot_content("file", &buffer.name);
+            self.status_bar
+                .update_slot_content("file_info", &buffer.name);
+
+            // Update cursor position
+            let cursor_info = format!("Ln {}, Col {}", row + 1, col + 1);
+            self.status_bar
+                .update_slot_content("cursor_pos", cursor_info);
 
+            // Update selection info, hiding the slot when nothing is selected
             if let Some(selected_text) = buffer.get_selected_text() {
-                // Update cursor position with selection info
-                let char_count = selected_text.len();
-                let cursor_info =
-                    format!("Ln {}, Col {} ({} selected)", row + 1, col + 1, char_count);
-                self.status_bar.update_slot_content("cursor", cursor_info);
+                let char_count = selected_text.chars().count();
+                self.status_bar
+                    .update_slot_content("selection_info", format!("{} chars", char_count));
+                self.status_bar.show_slot("selection_info");
             } else {
-                // Update cursor position without selection info
-                let cursor_info = format!("Ln {}, Col {}", row + 1, col + 1);
-                self.status_bar.update_slot_content("cursor", cursor_info);
+                self.status_bar.hide_slot("selection_info");
             }
 
-            // Update modified status
-            let modified_text = if buffer.modified { "Unsaved" } else { "Saved" };
+            // Update how the file is stored
+            self.status_bar
+                .update_slot_content("encoding", buffer.encoding.label());
             self.status_bar
-                .update_slot_content("modified", modified_text);
+                .update_slot_content("line_ending", buffer.line_ending.name());
+
+            // Update modified status
+            let modified = buffer.modified;
+            self.update_modified_status(modified);
 
             // Update mode indicator
             let mode_text = match self.command_mode {
@@ -447,12 +1078,85 @@ impl App {
                 CommandMode::FileSearch => "FILE SEARCH",
                 CommandMode::TextSearch => "TEXT SEARCH",
             };
-            self.status_bar.update_slot_content("mode", mode_text);
+            self.status_bar
+                .update_slot_content("mode_indicator", mode_text);
 
             // Update buffer count
             let buffer_info = format!("Buffer {}/{}", self.active_buffer + 1, self.buffers.len());
             self.status_bar
                 .update_slot_content("buffer_count", buffer_info);
+
+            // Update focused pane
+            if self.is_split() {
+                let pane_info = format!("Pane {}/{}", self.focused_pane + 1, self.panes.len());
+                self.status_bar.update_slot_content("pane_info", pane_info);
+                self.status_bar.show_slot("pane_info");
+            } else {
+                self.status_bar.hide_slot("pane_info");
+            }
+        }
+    }
+
+    /// Show whether the active buffer has unsaved changes in the status bar
+    pub fn update_modified_status(&mut self, modified: bool) {
+        let modified_text = if modified { "Unsaved" } else { "Saved" };
+        self.status_bar
+            .update_slot_content("modified_status", modified_text);
+    }
+
+    /// Add a slot to the status bar, replacing any slot with the same id
+    ///
+    /// The built-in slots are refreshed every frame, but added ones keep
+    /// their content until `update_status_slot` changes it.
+    pub fn set_status_slot(&mut self, slot: StatusSlot) {
+        self.status_bar.set_slot(slot);
+    }
+
+    /// Change what a status bar slot shows, returning false if there's no
+    /// slot with that id
+    pub fn update_status_slot(&mut self, id: &str, content: impl Into<String>) -> bool {
+        match self.status_bar.get_slot_mut(id) {
+            Some(slot) => {
+                slot.content = content.into();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take a slot off the status bar
+    pub fn remove_status_slot(&mut self, id: &str) {
+        self.status_bar.remove_slot(id);
+    }
+
+    /// Show the word and char counts of the buffer at `index` in the
+    /// word-count slot, adding the slot the first time
+    ///
+    /// Only the lines edited since the last count are recounted, unless the
+    /// counts kept are of another buffer.
+    pub fn update_word_count(&mut self, index: usize) {
+        let Some(buffer) = self.buffers.get_mut(index) else {
+            return;
+        };
+        let change = buffer.take_uncounted_change();
+        let updated = match (&mut self.word_count, change) {
+            (Some(count), Some(change)) => count.update(buffer, change),
+            _ => false,
+        };
+        if !updated {
+            self.word_count = Some(WordCount::of(buffer));
+        }
+        let Some(count) = &self.word_count else {
+            return;
+        };
+
+        let content = format!("{} words, {} chars", count.words(), count.chars());
+        if !self.update_status_slot(WORD_COUNT_SLOT, content.clone()) {
+            let slot = StatusSlot::new(WORD_COUNT_SLOT, content)
+                .with_alignment(SlotAlignment::Right)
+                .with_priority(70)
+                .with_style(self.theme.status_bar_dim_style());
+            self.set_status_slot(slot);
         }
     }
 }
@@ -472,6 +1176,8 @@ impl Clone for App {
             command_mode: self.command_mode.clone(),
             command_input: self.command_input.clone(),
             status_message: self.status_message.clone(),
+            status_message_expires_at: self.status_message_expires_at,
+            clock: self.clock.clone(),
             user_dir: self.user_dir.clone(),
             background_tasks: BackgroundTasks::default(), // Don't clone background tasks
             toast_manager: crate::widgets::toast::ToastManager::new(), // Create new instance
@@ -479,6 +1185,38 @@ impl Clone for App {
             cursor_manager: CursorManager::new(), // Create new instance
             status_bar: crate::widgets::StatusBar::new(), // Create new instance
             mouse_drag_start: self.mouse_drag_start,
+            scrollbar_drag: self.scrollbar_drag,
+            last_click: self.last_click,
+            pending_chord: self.pending_chord.clone(),
+            theme: self.theme.clone(),
+            config: self.config.clone(),
+            editor_cache: self.editor_cache.clone(),
+            last_editor_area: self.last_editor_area,
+            marks: self.marks.clone(),
+            global_marks: self.global_marks.clone(),
+            panes: self.panes.clone(),
+            focused_pane: self.focused_pane,
+            performance: PerformanceMonitor::default(), // Timings aren't shared
+            show_performance_overlay: self.show_performance_overlay,
+            show_help: self.show_help,
+            help_scroll: self.help_scroll,
+            search_query: self.search_query.clone(),
+            search_regex: self.search_regex,
+            commands: self.commands.clone(),
+            command_suggestions: self.command_suggestions.clone(),
+            selected_suggestion: self.selected_suggestion,
+            buffer_matches: self.buffer_matches.clone(),
+            recent_files: self.recent_files.clone(),
+            recent_matches: self.recent_matches.clone(),
+            cursor_positions: self.cursor_positions.clone(),
+            buffer_use: self.buffer_use.clone(),
+            word_count: self.word_count.clone(),
+            jump_list: self.jump_list.clone(),
+            command_history: self.command_history.clone(),
+            history_index: self.history_index,
+            last_edit_at: self.last_edit_at,
+            event_hooks: self.event_hooks.clone(),
+            unsaved_prompt: self.unsaved_prompt,
         };
 
         app.init_status_bar();
@@ -496,6 +1234,8 @@ impl Default for App {
             command_mode: CommandMode::Normal,
             command_input: String::new(),
             status_message: None,
+            status_message_expires_at: None,
+            clock: system_clock(),
             user_dir: PathBuf::from("."),
             background_tasks: BackgroundTasks::default(),
             toast_manager: crate::widgets::toast::ToastManager::new(),
@@ -503,6 +1243,38 @@ impl Default for App {
             cursor_manager: CursorManager::new(),
             status_bar: crate::widgets::StatusBar::new(),
             mouse_drag_start: None,
+            scrollbar_drag: false,
+            last_click: None,
+            pending_chord: None,
+            theme: Theme::default(),
+            config: Config::default(),
+            editor_cache: EditorRenderCache::default(),
+            last_editor_area: None,
+            marks: HashMap::new(),
+            global_marks: HashMap::new(),
+            panes: Vec::new(),
+            focused_pane: 0,
+            performance: PerformanceMonitor::default(),
+            show_performance_overlay: false,
+            show_help: false,
+            help_scroll: 0,
+            search_query: None,
+            search_regex: false,
+            commands: CommandRegistry::with_builtins(),
+            command_suggestions: Vec::new(),
+            selected_suggestion: None,
+            buffer_matches: Vec::new(),
+            recent_files: Vec::new(),
+            recent_matches: Vec::new(),
+            cursor_positions: Vec::new(),
+            buffer_use: Vec::new(),
+            word_count: None,
+            jump_list: JumpList::default(),
+            command_history: Vec::new(),
+            history_index: None,
+            last_edit_at: None,
+            event_hooks: Vec::new(),
+            unsaved_prompt: None,
         };
 
         app.init_status_bar();
@@ -511,7 +1283,38 @@ impl Default for App {
 }
 
 /// Background task management
+///
+/// Keeps a handle to every task the app spawns so they can all be aborted
+/// when it quits, instead of outliving it.
 #[derive(Default)]
 pub struct BackgroundTasks {
-    // TODO: This would contain task handles for background operations
+    /// Running tasks, with the names they were spawned under
+    tasks: Vec<(String, JoinHandle<()>)>,
+}
+
+impl BackgroundTasks {
+    /// Spawn `future` as a background task called `name`
+    pub fn spawn<F>(&mut self, name: impl Into<String>, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tasks.retain(|(_, handle)| !handle.is_finished());
+        self.tasks.push((name.into(), tokio::spawn(future)));
+    }
+
+    /// Names of the tasks that are still running
+    pub fn running(&self) -> Vec<&str> {
+        self.tasks
+            .iter()
+            .filter(|(_, handle)| !handle.is_finished())
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Abort every task
+    pub fn abort_all(&mut self) {
+        for (_, handle) in self.tasks.drain(..) {
+            handle.abort();
+        }
+    }
 }
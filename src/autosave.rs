@@ -0,0 +1,122 @@
+//! Auto-save
+//!
+//! With `auto_save` enabled, every modified buffer that has a file is saved
+//! once editing has paused for `auto_save_delay` milliseconds. Each
+//! `BufferEdited` event restarts the wait, and untitled buffers are left
+//! alone since there's nowhere to save them. Neither are files another
+//! program changed, so those changes aren't silently overwritten.
+//!
+//! A background task polls [`auto_save_if_idle`] while the app runs; the
+//! timing goes through `App::clock` so it can be tested without waiting.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{mpsc, RwLock};
+
+use crate::buffer::{write_save, PendingSave};
+use crate::App;
+
+impl App {
+    /// Note that a buffer was just edited, restarting the auto-save wait
+    pub fn note_edit(&mut self) {
+        self.last_edit_at = Some((self.clock)());
+    }
+
+    /// Begin saving the modified buffers that have a file, if auto-save is
+    /// on and nothing has been edited for `auto_save_delay`
+    ///
+    /// Returns the saves to write with the names of their buffers, or
+    /// `None` if it isn't time yet.
+    fn begin_auto_save(&mut self) -> Option<Vec<(String, std::io::Result<PendingSave>)>> {
+        let delay = Duration::from_millis(self.config.editor.auto_save_delay);
+        let idle = self
+            .last_edit_at
+            .is_some_and(|last_edit| (self.clock)().duration_since(last_edit) >= delay);
+        if !self.config.editor.auto_save || !idle {
+            return None;
+        }
+        self.last_edit_at = None;
+
+        let save_options = self.save_options();
+        let mut saves = Vec::new();
+        for buffer in &mut self.buffers {
+            let Some(path) = buffer.path.clone().filter(|_| buffer.is_dirty()) else {
+                continue;
+            };
+            // Changes another program made are only overwritten by hand
+            if buffer.check_disk() {
+                self.toast_manager.add_warning(format!(
+                    "{} changed on disk - not auto-saving it",
+                    buffer.name
+                ));
+            }
+            if buffer.changed_on_disk {
+                continue;
+            }
+            buffer.save_options = save_options;
+            saves.push((buffer.name.clone(), buffer.begin_save(path)));
+        }
+        Some(saves)
+    }
+}
+
+/// Save the modified buffers of `app_state` that have a file, if auto-save
+/// is on and nothing has been edited for `auto_save_delay`
+///
+/// The files are written without holding the app, so a slow disk doesn't
+/// hold up input or drawing. Reports what was saved in the status message
+/// and failures as toasts. Returns whether anything was saved or failed, so
+/// the screen can be redrawn.
+pub async fn auto_save_if_idle(app_state: &RwLock<App>) -> bool {
+    let Some(saves) = app_state.write().await.begin_auto_save() else {
+        return false;
+    };
+
+    let mut written = Vec::new();
+    let mut errors = Vec::new();
+    for (name, save) in saves {
+        match write_save(save).await {
+            Ok((save, _)) => written.push((name, save)),
+            Err(e) => errors.push(format!("{}: {}", name, e)),
+        }
+    }
+
+    let mut app = app_state.write().await;
+    let mut saved = Vec::new();
+    for (name, save) in written {
+        if app.finish_save(&save).is_some() {
+            saved.push(name);
+        }
+    }
+    if !saved.is_empty() {
+        app.set_status_message(format!("Auto-saved {}", saved.join(", ")));
+    }
+    if !errors.is_empty() {
+        app.toast_manager
+            .add_error(format!("Auto-save failed for {}", errors.join(", ")));
+    }
+    !saved.is_empty() || !errors.is_empty()
+}
+
+/// Task that auto-saves `app_state`, asking for a redraw through `redraw`
+/// whenever it saves something
+///
+/// It checks a few times per `auto_save_delay`, so saves land shortly after
+/// the delay runs out. The task ends once the app stops running.
+pub async fn auto_save_task(app_state: Arc<RwLock<App>>, redraw: mpsc::UnboundedSender<()>) {
+    loop {
+        let poll = {
+            let app = app_state.read().await;
+            if !app.running {
+                return;
+            }
+            Duration::from_millis((app.config.editor.auto_save_delay / 4).clamp(50, 500))
+        };
+        tokio::time::sleep(poll).await;
+
+        if auto_save_if_idle(&app_state).await {
+            let _ = redraw.send(());
+        }
+    }
+}
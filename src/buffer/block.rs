@@ -0,0 +1,98 @@
+//! Block (column) selection
+//!
+//! In [`SelectionMode::Block`] the selection is the rectangle with
+//! `selection_start` and the cursor at opposite corners. Its columns are
+//! counted in chars, so a block over lines holding multi-byte text still
+//! lines up; lines too short to reach the rectangle contribute nothing.
+
+use std::ops::Range;
+
+use super::{Buffer, LineChange, SelectionMode};
+
+impl Buffer {
+    /// Start a block selection at `pos`, with the cursor as the other corner
+    pub fn start_block_selection(&mut self, pos: (usize, usize)) {
+        self.selection_start = Some(pos);
+        self.visual_mode = true;
+        self.selection_mode = SelectionMode::Block;
+    }
+
+    /// Rows and char columns covered by the block selection
+    pub fn block_selection(&self) -> Option<(Range<usize>, Range<usize>)> {
+        if self.selection_mode != SelectionMode::Block {
+            return None;
+        }
+        let (start_row, start_col) = self.selection_start?;
+        let (end_row, end_col) = self.cursor_pos;
+        let start_col = self.char_col(start_row, start_col);
+        let end_col = self.char_col(end_row, end_col);
+
+        let rows = start_row.min(end_row)..start_row.max(end_row) + 1;
+        let cols = start_col.min(end_col)..start_col.max(end_col);
+        Some((rows, cols))
+    }
+
+    /// Byte range of `row` inside the selection, whatever its mode
+    ///
+    /// For a normal selection that runs past the end of the row, the range
+    /// goes to the end of the line.
+    pub fn selected_cols(&self, row: usize) -> Option<Range<usize>> {
+        let line = self.content.get(row)?;
+        if let Some((rows, cols)) = self.block_selection() {
+            return rows
+                .contains(&row)
+                .then(|| byte_col(line, cols.start)..byte_col(line, cols.end));
+        }
+
+        let (start, end) = self.get_selection_range()?;
+        if row < start.row || row > end.row {
+            return None;
+        }
+        let from = if row == start.row { start.col } else { 0 };
+        let to = if row == end.row { end.col } else { line.len() };
+        Some(from.min(line.len())..to.min(line.len()))
+    }
+
+    /// The block's slice of every row it covers, one per line
+    pub(super) fn get_block_text(&self) -> Option<String> {
+        let (rows, _) = self.block_selection()?;
+        let pieces: Vec<&str> = rows
+            .filter_map(|row| {
+                let range = self.selected_cols(row)?;
+                Some(&self.content[row][range])
+            })
+            .collect();
+        Some(pieces.join("\n"))
+    }
+
+    /// Cut the block's slice out of every row it covers, leaving the cursor
+    /// at its top left corner
+    pub(super) fn delete_block(&mut self) -> bool {
+        let Some((rows, cols)) = self.block_selection() else {
+            return false;
+        };
+        for row in rows.clone() {
+            if let Some(range) = self.selected_cols(row) {
+                self.content[row].replace_range(range, "");
+            }
+        }
+
+        let top = rows.start.min(self.content.len().saturating_sub(1));
+        self.cursor_pos = (top, byte_col(&self.content[top], cols.start));
+        self.clear_selection();
+        self.mark_changed(LineChange::rows(rows));
+        true
+    }
+
+    /// Char column of the byte offset `col` in `row`
+    fn char_col(&self, row: usize, col: usize) -> usize {
+        self.content.get(row).map_or(0, |line| {
+            line.char_indices().take_while(|&(i, _)| i < col).count()
+        })
+    }
+}
+
+/// Byte offset of char column `col` in `line`, or its length if it's shorter
+fn byte_col(line: &str, col: usize) -> usize {
+    line.char_indices().nth(col).map_or(line.len(), |(i, _)| i)
+}
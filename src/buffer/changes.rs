@@ -0,0 +1,116 @@
+//! Which lines the edits touched
+//!
+//! Every edit records the lines it replaced as a [`LineChange`]. Edits made
+//! before anyone asks are merged into one change covering all of them, which
+//! [`Buffer::take_change`] hands out and clears. Handlers use it to report
+//! only the region a keystroke touched, and nothing at all when a key didn't
+//! change the text. An edit that turns a saved buffer into a modified one is
+//! remembered the same way until [`Buffer::take_became_modified`].
+
+use std::ops::Range;
+
+use super::Buffer;
+
+/// `old_count` lines from `start` were replaced by `new_count` lines
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineChange {
+    pub start: usize,
+    pub old_count: usize,
+    pub new_count: usize,
+}
+
+impl LineChange {
+    pub fn new(start: usize, old_count: usize, new_count: usize) -> Self {
+        Self {
+            start,
+            old_count,
+            new_count,
+        }
+    }
+
+    /// A change to the lines in `rows` that leaves their number alone
+    pub fn rows(rows: Range<usize>) -> Self {
+        Self::new(rows.start, rows.len(), rows.len())
+    }
+
+    /// Rows of the new content that changed
+    pub fn lines(&self) -> Range<usize> {
+        self.start..self.start + self.new_count
+    }
+
+    /// Lines added, negative if lines were removed
+    fn delta(&self) -> isize {
+        self.new_count as isize - self.old_count as isize
+    }
+
+    /// One change covering this one followed by `next`
+    ///
+    /// Lines between the two changes count as changed too, so the result may
+    /// be wider than the edits were.
+    pub(super) fn then(self, next: Self) -> Self {
+        let start = self.start.min(next.start);
+        // End of both changes in the content between them
+        let end = (self.start + self.new_count).max(next.start + next.old_count);
+        Self {
+            start,
+            old_count: (end as isize - self.delta()) as usize - start,
+            new_count: (end as isize + next.delta()) as usize - start,
+        }
+    }
+}
+
+impl Buffer {
+    /// Record an edit of `change` and flag the buffer as modified
+    pub(super) fn mark_changed(&mut self, change: LineChange) {
+        self.pending_change = Some(match self.pending_change {
+            Some(pending) => pending.then(change),
+            None => change,
+        });
+        self.uncounted_change = Some(match self.uncounted_change {
+            Some(uncounted) => uncounted.then(change),
+            None => change,
+        });
+        self.became_modified |= !self.modified;
+        self.mark_modified();
+        self.highlight_cache.edited(change.start, self.version);
+        if let Some(base) = &self.diff_base {
+            base.edited(change, self.version);
+        }
+    }
+
+    /// Record a change to how the content is written to disk, like its line
+    /// endings, that leaves every line alone
+    pub(super) fn mark_format_changed(&mut self) {
+        self.became_modified |= !self.modified;
+        self.mark_modified();
+    }
+
+    /// Record an edit that may have touched any line but didn't add or
+    /// remove any
+    pub(super) fn mark_all_changed(&mut self) {
+        self.mark_changed(LineChange::rows(0..self.content.len()));
+    }
+
+    /// The lines changed since the last call, merged into one change, or
+    /// `None` if nothing was edited
+    pub fn take_change(&mut self) -> Option<LineChange> {
+        self.pending_change.take()
+    }
+
+    /// The lines changed since the word count last caught up, merged into
+    /// one change like `take_change`
+    ///
+    /// Kept apart from `take_change` because the count catches up when the
+    /// edit event is handled, by which time later keys may have edited more.
+    pub fn take_uncounted_change(&mut self) -> Option<LineChange> {
+        self.uncounted_change.take()
+    }
+
+    /// Whether an edit since the last call turned the buffer from saved to
+    /// modified
+    ///
+    /// Saving in between clears it, since the buffer is back to saved.
+    pub fn take_became_modified(&mut self) -> bool {
+        std::mem::take(&mut self.became_modified)
+    }
+}
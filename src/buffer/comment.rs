@@ -0,0 +1,68 @@
+//! Commenting out lines
+//!
+//! Line comments go right after a line's indentation, followed by a space.
+//! Blank lines are skipped, both when deciding whether to comment or
+//! uncomment and when changing the lines.
+
+use super::{Buffer, LineChange};
+
+impl Buffer {
+    /// Comment out the selected lines (or the cursor's line) with `prefix`,
+    /// or uncomment them if they're all commented already
+    ///
+    /// Uncommenting removes the prefix and one space after it. Returns false
+    /// if there was nothing but blank lines to change.
+    pub fn toggle_line_comment(&mut self, prefix: &str) -> bool {
+        let rows = self
+            .selected_rows()
+            .unwrap_or(self.cursor_pos.0..self.cursor_pos.0 + 1);
+
+        // Indentation width of every non-blank line
+        let lines: Vec<(usize, usize)> = rows
+            .clone()
+            .filter_map(|row| {
+                let line = self.content.get(row)?;
+                let trimmed = line.trim_start();
+                (!trimmed.is_empty()).then_some((row, line.len() - trimmed.len()))
+            })
+            .collect();
+        if lines.is_empty() {
+            return false;
+        }
+
+        let commented = lines
+            .iter()
+            .all(|&(row, indent)| self.content[row][indent..].starts_with(prefix));
+        for (row, indent) in lines {
+            let Some(line) = self.content.get_mut(row) else {
+                continue;
+            };
+            if commented {
+                let mut removed = prefix.len();
+                if line[indent + removed..].starts_with(' ') {
+                    removed += 1;
+                }
+                line.replace_range(indent..indent + removed, "");
+                self.shift_selection_ends(row, |col| {
+                    if col > indent {
+                        col.saturating_sub(removed).max(indent)
+                    } else {
+                        col
+                    }
+                });
+            } else {
+                let inserted = format!("{} ", prefix);
+                line.insert_str(indent, &inserted);
+                self.shift_selection_ends(row, |col| {
+                    if col > indent {
+                        col + inserted.len()
+                    } else {
+                        col
+                    }
+                });
+            }
+        }
+        self.mark_changed(LineChange::rows(rows));
+        true
+    }
+}
@@ -0,0 +1,219 @@
+//! Multiple cursors
+//!
+//! Besides the primary `cursor_pos` a buffer can hold any number of secondary
+//! cursors. Typing, Enter and Backspace apply at every cursor, and cursor
+//! movement moves them all. Edits run from the last cursor in the buffer to
+//! the first, and the cursors already edited are shifted by what each later
+//! edit inserted or removed, so every edit lands where its cursor was.
+
+use std::ops::Range;
+
+use super::{Buffer, CursorMovement, Position};
+
+impl Buffer {
+    /// Whether there's more than one cursor
+    pub fn has_multiple_cursors(&self) -> bool {
+        !self.secondary_cursors.is_empty()
+    }
+
+    /// Add a secondary cursor at `pos`, unless a cursor is already there
+    pub fn add_cursor(&mut self, pos: Position) -> bool {
+        if pos == Position::from_tuple(self.cursor_pos) || self.secondary_cursors.contains(&pos) {
+            return false;
+        }
+        self.secondary_cursors.push(pos);
+        true
+    }
+
+    /// Drop every cursor except the primary one
+    pub fn clear_secondary_cursors(&mut self) {
+        self.secondary_cursors.clear();
+    }
+
+    /// Put the cursor at `pos`, pulled back onto the text if the lines have
+    /// changed since the position was recorded, and drop any selection
+    pub fn place_cursor(&mut self, pos: Position) {
+        let row = pos.row.min(self.content.len().saturating_sub(1));
+        let line = self.content.get(row).map_or("", |line| line.as_str());
+        let mut col = pos.col.min(line.len());
+        while !line.is_char_boundary(col) {
+            col -= 1;
+        }
+        self.clear_selection();
+        self.cursor_pos = (row, col);
+    }
+
+    /// Byte range of the word (letters, digits and `_`) touching `pos`
+    pub fn word_at(&self, pos: Position) -> Option<Range<usize>> {
+        let line = self.content.get(pos.row)?;
+        let col = pos.col.min(line.len());
+        let start = line[..col]
+            .char_indices()
+            .rev()
+            .take_while(|&(_, c)| is_word_char(c))
+            .last()
+            .map_or(col, |(i, _)| i);
+        let end = line[col..]
+            .char_indices()
+            .find(|&(_, c)| !is_word_char(c))
+            .map_or(line.len(), |(i, _)| col + i);
+        (start < end).then_some(start..end)
+    }
+
+    /// Add a cursor at the next occurrence of the selection or of the word
+    /// at the cursor (Ctrl+D)
+    ///
+    /// A word only matches whole words. The new cursor sits at the same
+    /// offset into the occurrence as the primary cursor does in its own. The
+    /// search starts after the cursor added last and wraps around the end of
+    /// the buffer. Returns false if there's nothing to search for or every
+    /// occurrence has a cursor.
+    pub fn add_cursor_at_next_occurrence(&mut self) -> bool {
+        let (row, col) = self.cursor_pos;
+        let (needle, whole_word) = match self.get_selection_range() {
+            Some((start, end)) if start.row == end.row && start.col < end.col => {
+                (start.col..end.col, false)
+            }
+            Some(_) => return false,
+            None => match self.word_at(Position::new(row, col)) {
+                Some(word) => (word, true),
+                None => return false,
+            },
+        };
+        let offset = col - needle.start;
+        let text = self.content[row][needle].to_string();
+
+        // Look for occurrences after the one the last added cursor is in,
+        // wrapping back around to it
+        let last = self
+            .secondary_cursors
+            .last()
+            .copied()
+            .unwrap_or(Position::new(row, col));
+        let after = Position::new(last.row, last.col.saturating_sub(offset));
+        let rows = self.content.len();
+        for step in 0..=rows {
+            let search_row = (after.row + step) % rows;
+            let candidates: Vec<Position> = self.content[search_row]
+                .match_indices(text.as_str())
+                .map(|(i, _)| i)
+                .filter(|&i| match step {
+                    0 => i > after.col,
+                    _ if step == rows => i <= after.col,
+                    _ => true,
+                })
+                .filter(|&i| {
+                    !whole_word
+                        || self.word_at(Position::new(search_row, i)) == Some(i..i + text.len())
+                })
+                .map(|i| Position::new(search_row, i + offset))
+                .collect();
+            for pos in candidates {
+                if self.add_cursor(pos) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Run a single-cursor edit at every cursor
+    ///
+    /// `edit` must leave the cursor on the row that ends with the text that
+    /// followed it, like inserting a char or a pair, a newline or a backspace
+    /// does. The cursors are edited last to first so an edit never moves a
+    /// cursor that's still waiting, and the ones already done are shifted by
+    /// `shift_after_edit`. Returns whether any edit changed the text.
+    pub(super) fn edit_at_each_cursor(&mut self, mut edit: impl FnMut(&mut Self)) -> bool {
+        let version = self.version;
+        if self.secondary_cursors.is_empty() {
+            edit(self);
+            return self.version != version;
+        }
+
+        let primary = Position::from_tuple(self.cursor_pos);
+        let mut cursors = self.all_cursors();
+        cursors.reverse();
+
+        // Positions after editing, in the same (last to first) order
+        let mut done: Vec<Position> = Vec::with_capacity(cursors.len());
+        for &cursor in &cursors {
+            let rows_before = self.content.len();
+            let len_before = self.content.get(cursor.row).map_or(0, |line| line.len());
+            self.cursor_pos = cursor.to_tuple();
+            edit(self);
+            let after = Position::from_tuple(self.cursor_pos);
+            let rows_added = self.content.len() as isize - rows_before as isize;
+            let len_after = self.content.get(after.row).map_or(0, |line| line.len());
+            let growth = len_after as isize - len_before as isize;
+            for pos in &mut done {
+                *pos = shift_after_edit(*pos, cursor, after, rows_added, growth);
+            }
+            done.push(after);
+        }
+
+        let primary_index = cursors.iter().position(|&c| c == primary).unwrap_or(0);
+        self.cursor_pos = done[primary_index].to_tuple();
+        self.secondary_cursors.clear();
+        for (i, pos) in done.into_iter().enumerate().rev() {
+            if i != primary_index {
+                self.add_cursor(pos);
+            }
+        }
+        self.version != version
+    }
+
+    /// Move every cursor, merging any that end up in the same place
+    pub(super) fn move_each_cursor(&mut self, direction: CursorMovement) {
+        let secondary = std::mem::take(&mut self.secondary_cursors);
+        let primary = self.cursor_pos;
+        let mut moved = Vec::with_capacity(secondary.len());
+        for pos in secondary {
+            self.cursor_pos = pos.to_tuple();
+            self.move_primary_cursor(direction);
+            moved.push(Position::from_tuple(self.cursor_pos));
+        }
+
+        self.cursor_pos = primary;
+        self.move_primary_cursor(direction);
+        for pos in moved {
+            self.add_cursor(pos);
+        }
+    }
+
+    /// Every cursor, primary included, in buffer order
+    pub fn all_cursors(&self) -> Vec<Position> {
+        let mut cursors = self.secondary_cursors.clone();
+        cursors.push(Position::from_tuple(self.cursor_pos));
+        cursors.sort();
+        cursors.dedup();
+        cursors
+    }
+}
+
+/// Where `pos`, which comes after `edited`, ends up once an edit there moved
+/// that cursor to `after`, added `rows_added` lines, and left the text that
+/// followed it at the end of a row `growth` bytes longer than its own was
+///
+/// Text the edit put after the cursor, like the closing char of a pair,
+/// shifts `pos` too. A position in text the edit deleted ends up at `after`.
+fn shift_after_edit(
+    pos: Position,
+    edited: Position,
+    after: Position,
+    rows_added: isize,
+    growth: isize,
+) -> Position {
+    if pos.row == edited.row && pos.col >= edited.col {
+        let col = (pos.col as isize + growth).max(after.col as isize) as usize;
+        Position::new(after.row, col)
+    } else if pos.row > edited.row {
+        Position::new((pos.row as isize + rows_added) as usize, pos.col)
+    } else {
+        pos
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
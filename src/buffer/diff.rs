@@ -0,0 +1,266 @@
+//! Changes since the file was last read or written
+//!
+//! A buffer opened from a file keeps the lines the file had on disk and
+//! compares its content against them, so the gutter can mark which lines were
+//! added or modified and where lines were removed. The comparison is redone
+//! the first time it's asked for after an edit and cached until the next one.
+//!
+//! Lines shared at the start and end are skipped, and only the part between
+//! them is diffed line by line. When that part is too large to diff, all of it
+//! counts as changed. Edits are noted as they're made, so lines that matched
+//! before the first edited line or after the last one aren't compared again.
+
+use std::sync::{Arc, Mutex};
+
+use super::{Buffer, LineChange, Lines};
+
+/// Largest number of old × new lines diffed line by line
+const MAX_DIFF_CELLS: usize = 1_000_000;
+
+/// How a line differs from the file on disk
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineStatus {
+    /// The line isn't in the file
+    Added,
+
+    /// The line replaced a different one in the file
+    Modified,
+
+    /// Lines of the file were removed right before this one (or after it,
+    /// for the last line)
+    Removed,
+}
+
+/// Status of every line of a buffer, `None` for unchanged lines
+pub type LineStatuses = Arc<[Option<LineStatus>]>;
+
+/// Statuses last computed, and the edits made since
+#[derive(Clone)]
+struct DiffCache {
+    /// Version of the content the statuses are for
+    version: u64,
+    statuses: LineStatuses,
+
+    /// Lines at the start and at the end that matched the file then
+    prefix: usize,
+    suffix: usize,
+
+    /// Lines edited since, merged into one change, and the version of the
+    /// content after them
+    since: Option<LineChange>,
+    latest: u64,
+}
+
+/// Lines the file had on disk, with the statuses last computed against them
+pub(super) struct DiffBase {
+    lines: Arc<Vec<String>>,
+    cache: Mutex<Option<DiffCache>>,
+}
+
+impl DiffBase {
+    fn new(lines: Vec<String>) -> Self {
+        Self {
+            lines: Arc::new(lines),
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Note an edit of `change` that brought the content to `version`, so
+    /// the next comparison knows which lines still match
+    pub(super) fn edited(&self, change: LineChange, version: u64) {
+        let mut cache = self.cache.lock().unwrap();
+        let Some(state) = cache.as_mut() else {
+            return;
+        };
+        // An edit that wasn't noted leaves nothing known
+        if state.latest + 1 != version {
+            *cache = None;
+            return;
+        }
+        state.since = Some(match state.since {
+            Some(since) => since.then(change),
+            None => change,
+        });
+        state.latest = version;
+    }
+}
+
+impl Clone for DiffBase {
+    fn clone(&self) -> Self {
+        Self {
+            lines: self.lines.clone(),
+            cache: Mutex::new(self.cache.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl Buffer {
+    /// Whether the buffer tracks its changes against the file on disk
+    pub fn has_diff_base(&self) -> bool {
+        self.diff_base.is_some()
+    }
+
+    /// Take the current content as what's on disk, once it's been read or
+    /// written
+    pub(super) fn reset_diff_base(&mut self) {
+        let lines = self.content.iter().cloned().collect();
+        self.diff_base = Some(DiffBase::new(lines));
+    }
+
+    /// Status of every line against the file on disk, `None` for unchanged
+    /// lines
+    ///
+    /// Empty when the buffer doesn't track its changes.
+    pub fn line_statuses(&self) -> LineStatuses {
+        let Some(base) = &self.diff_base else {
+            return Arc::from([]);
+        };
+
+        let mut cache = base.cache.lock().unwrap();
+        // Lines before the edits and after them still match if they did
+        let mut known = (0, 0);
+        if let Some(state) = cache.as_ref() {
+            if state.version == self.version {
+                return state.statuses.clone();
+            }
+            if let Some(since) = state.since.filter(|_| state.latest == self.version) {
+                let after_edits = self.content.len().saturating_sub(since.lines().end);
+                known = (state.prefix.min(since.start), state.suffix.min(after_edits));
+            }
+        }
+
+        let (statuses, prefix, suffix) = diff_lines(&base.lines, &self.content, known);
+        let statuses: LineStatuses = statuses.into();
+        *cache = Some(DiffCache {
+            version: self.version,
+            statuses: statuses.clone(),
+            prefix,
+            suffix,
+            since: None,
+            latest: self.version,
+        });
+        statuses
+    }
+
+    /// Status of one line against the file on disk
+    pub fn line_status(&self, row: usize) -> Option<LineStatus> {
+        self.line_statuses().get(row).copied().flatten()
+    }
+}
+
+/// One step of turning the old lines into the new ones
+#[derive(Clone, Copy)]
+enum Op {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// Status of every line of `new` compared with `old`, with how many lines
+/// match at the start and at the end
+///
+/// `known` are as many lines as are already known to match at the start and
+/// end, so only the lines between them are compared.
+fn diff_lines(
+    old: &[String],
+    new: &Lines,
+    known: (usize, usize),
+) -> (Vec<Option<LineStatus>>, usize, usize) {
+    let common = old.len().min(new.len());
+    let mut prefix = known.0.min(common);
+    while prefix < common && old[prefix] == new[prefix] {
+        prefix += 1;
+    }
+    let mut suffix = known.1.min(common - prefix);
+    while suffix < common - prefix && old[old.len() - 1 - suffix] == new[new.len() - 1 - suffix] {
+        suffix += 1;
+    }
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle: Vec<&str> = new
+        .range(prefix..new.len() - suffix)
+        .map(String::as_str)
+        .collect();
+
+    let ops = if old_middle.len() * new_middle.len() <= MAX_DIFF_CELLS {
+        edit_script(old_middle, &new_middle)
+    } else {
+        let mut ops = vec![Op::Delete; old_middle.len()];
+        ops.extend(std::iter::repeat_n(Op::Insert, new_middle.len()));
+        ops
+    };
+
+    // Each run of deletions and insertions between kept lines is a hunk:
+    // inserted lines pair up with deleted ones as modifications, the rest are
+    // additions, and deletions left over are marked on the following line
+    let mut statuses = vec![None; new.len()];
+    let mut row = prefix;
+    let mut deleted = 0;
+    let mut inserted = 0;
+    for op in ops.into_iter().chain([Op::Keep]) {
+        match op {
+            Op::Delete => deleted += 1,
+            Op::Insert => {
+                inserted += 1;
+                row += 1;
+            }
+            Op::Keep => {
+                let hunk_start = row - inserted;
+                for (i, status) in statuses[hunk_start..row].iter_mut().enumerate() {
+                    *status = Some(if i < deleted {
+                        LineStatus::Modified
+                    } else {
+                        LineStatus::Added
+                    });
+                }
+                if deleted > inserted {
+                    let marked = row.min(new.len().saturating_sub(1));
+                    if let Some(status @ None) = statuses.get_mut(marked) {
+                        *status = Some(LineStatus::Removed);
+                    }
+                }
+                deleted = 0;
+                inserted = 0;
+                row += 1;
+            }
+        }
+    }
+    (statuses, prefix, suffix)
+}
+
+/// Shortest way to turn `old` into `new`, found through their longest common
+/// subsequence
+fn edit_script(old: &[String], new: &[&str]) -> Vec<Op> {
+    let width = new.len() + 1;
+
+    // lengths[i * width + j] is the longest common subsequence of old[i..]
+    // and new[j..]
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push(Op::Keep);
+            i += 1;
+            j += 1;
+        } else if j == new.len()
+            || (i < old.len() && lengths[(i + 1) * width + j] >= lengths[i * width + j + 1])
+        {
+            ops.push(Op::Delete);
+            i += 1;
+        } else {
+            ops.push(Op::Insert);
+            j += 1;
+        }
+    }
+    ops
+}
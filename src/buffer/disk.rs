@@ -0,0 +1,79 @@
+//! Noticing when the file changes on disk
+//!
+//! The file's size and modification time are recorded whenever the buffer is
+//! loaded from or saved to it. Comparing them with the file later tells
+//! whether another program wrote to it in between, so the user can reload
+//! it, or be warned before a save overwrites it.
+//!
+//! A hash of the bytes last read or written is kept too. Saving content that
+//! would write the same bytes to a file nobody touched since skips the write,
+//! so file watchers don't see a change that isn't one.
+
+use std::hash::{DefaultHasher, Hasher};
+use std::path::Path;
+use std::time::SystemTime;
+
+use super::Buffer;
+
+/// Size and modification time of a file, as last seen by the buffer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiskSnapshot {
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl DiskSnapshot {
+    /// Snapshot of the file at `path`, or `None` if there's no file there
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+/// Hash of a file's bytes
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+impl Buffer {
+    /// Remember `bytes` as what the file holds, after reading or writing it
+    pub(super) fn record_disk_bytes(&mut self, bytes: &[u8]) {
+        self.disk_hash = Some(hash_bytes(bytes));
+    }
+
+    /// Whether the file at `path` already holds exactly `bytes`, going by
+    /// what was last read from or written to it, so writing can be skipped
+    pub(super) fn disk_holds(&self, path: &Path, bytes: &[u8]) -> bool {
+        self.path.as_deref() == Some(path)
+            && self.disk_snapshot.is_some()
+            && DiskSnapshot::of(path) == self.disk_snapshot
+            && self.disk_hash == Some(hash_bytes(bytes))
+    }
+
+    /// Remember the file as it is now, as matching the buffer
+    pub(super) fn record_disk_snapshot(&mut self) {
+        self.disk_snapshot = self.path.as_deref().and_then(DiskSnapshot::of);
+        self.changed_on_disk = false;
+    }
+
+    /// Compare the file with how it was when last loaded or saved, setting
+    /// `changed_on_disk` if another program has written to it since
+    ///
+    /// Returns true only when the change is first noticed, so it's reported
+    /// once.
+    pub fn check_disk(&mut self) -> bool {
+        let Some(path) = self.path.as_deref() else {
+            return false;
+        };
+        if self.changed_on_disk || DiskSnapshot::of(path) == self.disk_snapshot {
+            return false;
+        }
+        self.changed_on_disk = true;
+        true
+    }
+}
@@ -0,0 +1,230 @@
+//! Text encodings and line endings of files on disk
+//!
+//! Buffers always hold UTF-8. Files are decoded when they're loaded and
+//! encoded back to their original encoding (and byte order mark) when saved.
+//! Lines are split on either line break and written back with the one the
+//! file used, going by its first line.
+//!
+//! Detection looks for a byte order mark first, then accepts valid UTF-8,
+//! then falls back to windows-1252 (a superset of Latin-1) for text that
+//! isn't. Anything that doesn't look like text in either is decoded as lossy
+//! UTF-8 and flagged as a guess so the user can be warned.
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+/// How a file's text is stored on disk
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileEncoding {
+    /// Character encoding of the file
+    pub encoding: &'static Encoding,
+
+    /// Whether the file starts with a byte order mark
+    pub bom: bool,
+}
+
+impl Default for FileEncoding {
+    fn default() -> Self {
+        Self {
+            encoding: UTF_8,
+            bom: false,
+        }
+    }
+}
+
+impl FileEncoding {
+    /// Display name of the encoding, e.g. "UTF-8" or "UTF-16LE"
+    pub fn name(&self) -> &'static str {
+        self.encoding.name()
+    }
+
+    /// Name shown in the status bar, which also tells whether UTF-8 has a
+    /// byte order mark
+    pub fn label(&self) -> String {
+        if self.bom && self.encoding == UTF_8 {
+            format!("{} with BOM", self.name())
+        } else {
+            self.name().to_string()
+        }
+    }
+
+    /// The encoding after this one when cycling through UTF-8, UTF-8 with a
+    /// byte order mark, UTF-16LE and UTF-16BE
+    ///
+    /// UTF-16 always gets a byte order mark, since that's how it's recognized
+    /// when the file is opened again. Any other encoding goes to UTF-8.
+    pub fn cycled(&self) -> Self {
+        let (encoding, bom) = if self.encoding == UTF_8 && !self.bom {
+            (UTF_8, true)
+        } else if self.encoding == UTF_8 {
+            (UTF_16LE, true)
+        } else if self.encoding == UTF_16LE {
+            (UTF_16BE, true)
+        } else {
+            (UTF_8, false)
+        };
+        Self { encoding, bom }
+    }
+
+    /// Encode UTF-8 text back into this encoding, with the byte order mark if
+    /// the file had one
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(text.len() + 3);
+        bytes.extend_from_slice(self.bom_bytes());
+        self.encode_into(text, &mut bytes);
+        bytes
+    }
+
+    /// The byte order mark to start the file with, if it had one
+    pub fn bom_bytes(&self) -> &'static [u8] {
+        if !self.bom {
+            b""
+        } else if self.encoding == UTF_16LE {
+            b"\xFF\xFE"
+        } else if self.encoding == UTF_16BE {
+            b"\xFE\xFF"
+        } else if self.encoding == UTF_8 {
+            b"\xEF\xBB\xBF"
+        } else {
+            b""
+        }
+    }
+
+    /// Append UTF-8 text encoded in this encoding to `bytes`, without a byte
+    /// order mark, so a file can be written a piece at a time
+    pub fn encode_into(&self, text: &str, bytes: &mut Vec<u8>) {
+        if self.encoding == UTF_16LE || self.encoding == UTF_16BE {
+            // encoding_rs only decodes UTF-16, so encode it by hand
+            let little_endian = self.encoding == UTF_16LE;
+            for unit in text.encode_utf16() {
+                let unit = if little_endian {
+                    unit.to_le_bytes()
+                } else {
+                    unit.to_be_bytes()
+                };
+                bytes.extend_from_slice(&unit);
+            }
+        } else if self.encoding == UTF_8 {
+            bytes.extend_from_slice(text.as_bytes());
+        } else {
+            let (encoded, _, _) = self.encoding.encode(text);
+            bytes.extend_from_slice(&encoded);
+        }
+    }
+}
+
+/// Line break a file's lines end with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// "\n", as on Unix
+    #[default]
+    Lf,
+
+    /// "\r\n", as on Windows
+    Crlf,
+}
+
+impl LineEnding {
+    /// Line ending of decoded text, going by its first line break
+    pub fn detect(text: &str) -> Self {
+        match text.find('\n') {
+            Some(end) if text[..end].ends_with('\r') => Self::Crlf,
+            _ => Self::Lf,
+        }
+    }
+
+    /// Line ending named "lf" or "unix", or "crlf" or "dos", in any case
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "lf" | "unix" => Some(Self::Lf),
+            "crlf" | "dos" => Some(Self::Crlf),
+            _ => None,
+        }
+    }
+
+    /// The line break itself
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::Crlf => "\r\n",
+        }
+    }
+
+    /// Display name, "LF" or "CRLF"
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Lf => "LF",
+            Self::Crlf => "CRLF",
+        }
+    }
+
+    /// The other line ending
+    pub fn toggled(&self) -> Self {
+        match self {
+            Self::Lf => Self::Crlf,
+            Self::Crlf => Self::Lf,
+        }
+    }
+}
+
+/// A file's contents decoded to UTF-8
+#[derive(Debug)]
+pub struct Decoded {
+    /// The decoded text, without any byte order mark
+    pub text: String,
+
+    /// Encoding the text was decoded from
+    pub encoding: FileEncoding,
+
+    /// Whether the encoding couldn't be detected and the text was decoded as
+    /// UTF-8 with invalid bytes replaced
+    pub guessed: bool,
+}
+
+/// Detect the encoding of a file's bytes and decode them to UTF-8
+pub fn decode(bytes: &[u8]) -> Decoded {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return Decoded {
+            text: text.into_owned(),
+            encoding: FileEncoding {
+                encoding,
+                bom: true,
+            },
+            guessed: false,
+        };
+    }
+
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Decoded {
+            text: text.to_owned(),
+            encoding: FileEncoding::default(),
+            guessed: false,
+        };
+    }
+
+    if looks_like_single_byte_text(bytes) {
+        let (text, _) = WINDOWS_1252.decode_without_bom_handling(bytes);
+        return Decoded {
+            text: text.into_owned(),
+            encoding: FileEncoding {
+                encoding: WINDOWS_1252,
+                bom: false,
+            },
+            guessed: false,
+        };
+    }
+
+    Decoded {
+        text: String::from_utf8_lossy(bytes).into_owned(),
+        encoding: FileEncoding::default(),
+        guessed: true,
+    }
+}
+
+/// Whether the bytes read as text in a single-byte encoding: no control
+/// characters other than tabs, line breaks and form feeds
+fn looks_like_single_byte_text(bytes: &[u8]) -> bool {
+    bytes
+        .iter()
+        .all(|&b| b >= 0x20 || matches!(b, b'\t' | b'\n' | b'\r' | 0x0C))
+}
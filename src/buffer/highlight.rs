@@ -0,0 +1,98 @@
+//! Highlighting state at the start of a line
+//!
+//! Multiline comments and strings carry the highlighting of one line into the
+//! next, so highlighting a line needs the state left by every line above it.
+//! The state is kept at checkpoints every `CHECKPOINT_INTERVAL` lines, so
+//! finding it only runs the highlighter from the nearest checkpoint above.
+//! An edit drops the checkpoints below the first line it touched; any other
+//! change of content, or of the file and so the highlighter, drops them all.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use super::Buffer;
+use crate::syntax::{HighlightState, Highlighter};
+
+/// Lines between checkpoints
+const CHECKPOINT_INTERVAL: usize = 256;
+
+/// States at the checkpoints, for one version of the content
+#[derive(Clone, Default)]
+struct Checkpoints {
+    version: u64,
+    path: Option<PathBuf>,
+
+    /// State at the start of every `CHECKPOINT_INTERVAL`th line, from the
+    /// first
+    states: Vec<HighlightState>,
+}
+
+/// Checkpoints computed while drawing, behind a lock since that only has
+/// the buffer to read
+#[derive(Default)]
+pub(super) struct HighlightCache(Mutex<Checkpoints>);
+
+impl Clone for HighlightCache {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.0.lock().unwrap().clone()))
+    }
+}
+
+impl HighlightCache {
+    /// Drop the checkpoints an edit from line `start` made stale, now that
+    /// the content is at `version`
+    pub(super) fn edited(&self, start: usize, version: u64) {
+        let mut checkpoints = self.0.lock().unwrap();
+        if checkpoints.version + 1 == version {
+            // A checkpoint only depends on the lines above it
+            checkpoints.states.truncate(start / CHECKPOINT_INTERVAL + 1);
+        } else {
+            checkpoints.states.clear();
+        }
+        checkpoints.version = version;
+    }
+}
+
+impl Buffer {
+    /// Highlighting state at the start of line `row`, as `highlighter`
+    /// leaves it after every line above
+    ///
+    /// Lazily opened files start every lookup from the default state, so
+    /// only the lines in view are read.
+    pub fn highlight_state_at(&self, highlighter: &dyn Highlighter, row: usize) -> HighlightState {
+        if self.content.is_lazy() {
+            return HighlightState::default();
+        }
+        let row = row.min(self.content.len());
+
+        let mut checkpoints = self.highlight_cache.0.lock().unwrap();
+        if checkpoints.version != self.version || checkpoints.path != self.path {
+            *checkpoints = Checkpoints {
+                version: self.version,
+                path: self.path.clone(),
+                states: Vec::new(),
+            };
+        }
+        if checkpoints.states.is_empty() {
+            checkpoints.states.push(HighlightState::default());
+        }
+
+        // Fill in the checkpoints up to the one above `row`
+        let nearest = row / CHECKPOINT_INTERVAL;
+        while checkpoints.states.len() <= nearest {
+            let last = checkpoints.states.len() - 1;
+            let mut state = checkpoints.states[last];
+            let start = last * CHECKPOINT_INTERVAL;
+            for line in self.content.range(start..start + CHECKPOINT_INTERVAL) {
+                highlighter.highlight_line(line, &mut state);
+            }
+            checkpoints.states.push(state);
+        }
+
+        let mut state = checkpoints.states[nearest];
+        for line in self.content.range(nearest * CHECKPOINT_INTERVAL..row) {
+            highlighter.highlight_line(line, &mut state);
+        }
+        state
+    }
+}
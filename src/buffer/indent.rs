@@ -0,0 +1,149 @@
+//! Indenting selected lines
+//!
+//! With a selection spanning several lines, Tab and Shift+Tab indent or
+//! unindent every line in it by one level. The selection keeps covering the
+//! same text: its ends move with the indentation, except an end at the very
+//! start of a line, which stays there so the whole line stays selected.
+//!
+//! `retab` rewrites the indentation of the whole buffer in the configured
+//! style, leaving whitespace after the first non-blank char alone.
+
+use std::ops::Range;
+
+use super::{Buffer, LineChange};
+
+impl Buffer {
+    /// Rows of a selection that spans more than one line
+    ///
+    /// A selection ending at the start of a line doesn't include that line.
+    pub fn selected_rows(&self) -> Option<Range<usize>> {
+        let (start, end) = self.get_selection_range()?;
+        if start.row == end.row {
+            return None;
+        }
+        let last = if end.col == 0 { end.row - 1 } else { end.row };
+        Some(start.row..last + 1)
+    }
+
+    /// Indent every selected line by one level, with `tab_size` spaces or a
+    /// tab
+    ///
+    /// Empty lines are left alone. Returns false if the selection doesn't
+    /// span several lines.
+    pub fn indent_selection(&mut self, use_spaces: bool, tab_size: usize) -> bool {
+        let Some(rows) = self.selected_rows() else {
+            return false;
+        };
+        let indent = if use_spaces {
+            " ".repeat(tab_size)
+        } else {
+            "\t".to_string()
+        };
+
+        for row in rows.clone() {
+            let Some(line) = self.content.get_mut(row) else {
+                continue;
+            };
+            if line.is_empty() {
+                continue;
+            }
+            line.insert_str(0, &indent);
+            self.shift_selection_ends(row, |col| if col > 0 { col + indent.len() } else { 0 });
+        }
+        self.mark_changed(LineChange::rows(rows));
+        true
+    }
+
+    /// Remove one level of indentation from every selected line
+    ///
+    /// A level is a leading tab or up to `tab_size` leading spaces, so lines
+    /// indented less than that lose what they have and text is never removed.
+    /// Returns false if the selection doesn't span several lines or nothing
+    /// was indented.
+    pub fn unindent_selection(&mut self, tab_size: usize) -> bool {
+        let Some(rows) = self.selected_rows() else {
+            return false;
+        };
+
+        let mut changed = false;
+        for row in rows.clone() {
+            let Some(line) = self.content.get_mut(row) else {
+                continue;
+            };
+            let removed = if line.starts_with('\t') {
+                1
+            } else {
+                line.bytes()
+                    .take(tab_size)
+                    .take_while(|&b| b == b' ')
+                    .count()
+            };
+            if removed == 0 {
+                continue;
+            }
+            line.replace_range(..removed, "");
+            self.shift_selection_ends(row, |col| col.saturating_sub(removed));
+            changed = true;
+        }
+        if changed {
+            self.mark_changed(LineChange::rows(rows));
+        }
+        changed
+    }
+
+    /// Convert the indentation of every line to `tab_size` spaces per level,
+    /// or to tabs with spaces for any remainder, as one edit
+    ///
+    /// Only leading whitespace changes. Returns how many lines changed.
+    pub fn retab(&mut self, use_spaces: bool, tab_size: usize) -> usize {
+        let tab_size = tab_size.max(1);
+        let mut changed = 0;
+        for row in 0..self.content.len() {
+            let Some(line) = self.content.get_mut(row) else {
+                continue;
+            };
+            let old_len = line.len() - line.trim_start_matches([' ', '\t']).len();
+            let width = line[..old_len].chars().fold(0, |width, c| match c {
+                '\t' => (width / tab_size + 1) * tab_size,
+                _ => width + 1,
+            });
+            let indent = if use_spaces {
+                " ".repeat(width)
+            } else {
+                "\t".repeat(width / tab_size) + &" ".repeat(width % tab_size)
+            };
+            if line[..old_len] == indent {
+                continue;
+            }
+
+            line.replace_range(..old_len, &indent);
+            let new_len = indent.len();
+            self.shift_selection_ends(row, |col| {
+                if col >= old_len {
+                    col - old_len + new_len
+                } else {
+                    col.min(new_len)
+                }
+            });
+            changed += 1;
+        }
+        if changed > 0 {
+            self.clear_secondary_cursors();
+            self.mark_all_changed();
+        }
+        changed
+    }
+
+    /// Move the selection ends on `row` to where `shift` maps their columns
+    pub(super) fn shift_selection_ends(&mut self, row: usize, shift: impl Fn(usize) -> usize) {
+        for (end_row, col) in self
+            .selection_start
+            .iter_mut()
+            .chain([&mut self.cursor_pos])
+        {
+            if *end_row == row {
+                *col = shift(*col);
+            }
+        }
+    }
+}
@@ -0,0 +1,488 @@
+//! Line storage for buffers
+//!
+//! `Lines` behaves like a `Vec<String>` of the buffer's lines, but keeps them
+//! in chunks of around [`CHUNK_LINES`] lines, rope style. Inserting or
+//! removing a line only shifts the lines of its own chunk, so editing the top
+//! of a huge file costs about the same as editing the bottom. Chunks that grow
+//! past twice the target size are split, and empty ones are dropped. The
+//! first row of every chunk is kept in an index so finding a line is a binary
+//! search.
+//!
+//! A chunk is either held in memory or, for buffers opened with
+//! [`Buffer::open_lazy`](super::Buffer::open_lazy), a byte range of the file
+//! that's only read the first time one of its lines is needed. Editing a line
+//! pulls its chunk into memory; the rest of the file stays on disk.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::ops::{Index, IndexMut, Range};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// Target number of lines in a chunk
+const CHUNK_LINES: usize = 1024;
+
+/// UTF-8 byte order mark, skipped when indexing a file
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// The lines of a buffer
+#[derive(Clone, Default)]
+pub struct Lines {
+    chunks: Vec<Chunk>,
+    starts: Vec<usize>, // First row of each chunk
+    len: usize,
+}
+
+#[derive(Clone)]
+enum Chunk {
+    /// Lines held in memory
+    Loaded(Vec<String>),
+
+    /// Lines still on disk, read on first access
+    Lazy(LazyChunk),
+}
+
+#[derive(Clone)]
+struct LazyChunk {
+    path: Arc<PathBuf>,
+    bytes: Range<u64>,
+    len: usize,
+    lines: OnceLock<Vec<String>>,
+}
+
+impl LazyChunk {
+    /// The chunk's lines, reading them from the file the first time
+    fn lines(&self) -> &Vec<String> {
+        self.lines.get_or_init(|| self.read())
+    }
+
+    /// Read the chunk's lines from the file
+    ///
+    /// Invalid UTF-8 is replaced. If the file changed or can't be read the
+    /// lines are padded or cut to the indexed count, so line numbers stay valid.
+    fn read(&self) -> Vec<String> {
+        let mut lines: Vec<String> = match self.read_bytes() {
+            Ok(bytes) => String::from_utf8_lossy(&bytes)
+                .lines()
+                .map(str::to_owned)
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        lines.resize(self.len, String::new());
+        lines
+    }
+
+    fn read_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut file = File::open(self.path.as_path())?;
+        file.seek(SeekFrom::Start(self.bytes.start))?;
+        let mut bytes = Vec::with_capacity((self.bytes.end - self.bytes.start) as usize);
+        file.take(self.bytes.end - self.bytes.start)
+            .read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl Chunk {
+    fn len(&self) -> usize {
+        match self {
+            Chunk::Loaded(lines) => lines.len(),
+            Chunk::Lazy(chunk) => chunk.len,
+        }
+    }
+
+    fn lines(&self) -> &Vec<String> {
+        match self {
+            Chunk::Loaded(lines) => lines,
+            Chunk::Lazy(chunk) => chunk.lines(),
+        }
+    }
+
+    /// The chunk's lines for editing, loading them into memory for good
+    fn lines_mut(&mut self) -> &mut Vec<String> {
+        if let Chunk::Lazy(chunk) = self {
+            let lines = chunk.lines.take().unwrap_or_else(|| chunk.read());
+            *self = Chunk::Loaded(lines);
+        }
+        match self {
+            Chunk::Loaded(lines) => lines,
+            Chunk::Lazy(_) => unreachable!("lazy chunk was just loaded"),
+        }
+    }
+
+    fn into_lines(self) -> Vec<String> {
+        match self {
+            Chunk::Loaded(lines) => lines,
+            Chunk::Lazy(mut chunk) => chunk.lines.take().unwrap_or_else(|| chunk.read()),
+        }
+    }
+
+    /// Number of lines currently held in memory
+    fn materialized(&self) -> usize {
+        match self {
+            Chunk::Loaded(lines) => lines.len(),
+            Chunk::Lazy(chunk) => chunk.lines.get().map_or(0, Vec::len),
+        }
+    }
+}
+
+impl Lines {
+    /// Index a file's line offsets without reading its lines into memory
+    ///
+    /// The file is treated as UTF-8 (with an optional byte order mark).
+    pub fn open_lazy(path: &Path) -> io::Result<Self> {
+        let shared_path = Arc::new(path.to_path_buf());
+        let mut reader = BufReader::with_capacity(1 << 16, File::open(path)?);
+
+        let mut pos = 0u64;
+        if reader.fill_buf()?.starts_with(UTF8_BOM) {
+            reader.consume(UTF8_BOM.len());
+            pos = UTF8_BOM.len() as u64;
+        }
+
+        let mut chunks = Vec::new();
+        let mut chunk_start = pos;
+        let mut chunk_lines = 0;
+        let mut last_byte = b'\n';
+        let mut push_chunk = |start: u64, end: u64, len: usize| {
+            chunks.push(Chunk::Lazy(LazyChunk {
+                path: shared_path.clone(),
+                bytes: start..end,
+                len,
+                lines: OnceLock::new(),
+            }));
+        };
+
+        loop {
+            let block = reader.fill_buf()?;
+            if block.is_empty() {
+                break;
+            }
+            for &byte in block {
+                pos += 1;
+                if byte == b'\n' {
+                    chunk_lines += 1;
+                    if chunk_lines == CHUNK_LINES {
+                        push_chunk(chunk_start, pos, chunk_lines);
+                        chunk_start = pos;
+                        chunk_lines = 0;
+                    }
+                }
+            }
+            last_byte = *block.last().unwrap_or(&last_byte);
+            let block_len = block.len();
+            reader.consume(block_len);
+        }
+
+        // Whatever is left, including a last line without a newline
+        if pos > chunk_start {
+            let unterminated = usize::from(last_byte != b'\n');
+            push_chunk(chunk_start, pos, chunk_lines + unterminated);
+        }
+
+        Ok(Self::from_chunks(chunks))
+    }
+
+    fn from_chunks(chunks: Vec<Chunk>) -> Self {
+        let mut lines = Self {
+            chunks,
+            ..Self::default()
+        };
+        lines.reindex(0);
+        lines
+    }
+
+    /// Number of lines
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no lines at all
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether some lines are still on disk rather than in memory
+    pub fn is_lazy(&self) -> bool {
+        self.chunks
+            .iter()
+            .any(|chunk| matches!(chunk, Chunk::Lazy(_)))
+    }
+
+    /// Number of lines currently held in memory
+    pub fn materialized_lines(&self) -> usize {
+        self.chunks.iter().map(Chunk::materialized).sum()
+    }
+
+    /// Most lines held in one chunk, which bounds how many lines an insert
+    /// or removal has to shift
+    pub fn largest_chunk(&self) -> usize {
+        self.chunks.iter().map(Chunk::len).max().unwrap_or(0)
+    }
+
+    /// Read every line still on disk into memory
+    pub fn load_all(&mut self) {
+        for chunk in &mut self.chunks {
+            chunk.lines_mut();
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&String> {
+        let (chunk, offset) = self.locate(index)?;
+        self.chunks[chunk].lines().get(offset)
+    }
+
+    /// The line at `index` if it's in memory, without reading it from disk
+    pub fn get_loaded(&self, index: usize) -> Option<&String> {
+        let (chunk, offset) = self.locate(index)?;
+        match &self.chunks[chunk] {
+            Chunk::Loaded(lines) => lines.get(offset),
+            Chunk::Lazy(chunk) => chunk.lines.get()?.get(offset),
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut String> {
+        let (chunk, offset) = self.locate(index)?;
+        self.chunks[chunk].lines_mut().get_mut(offset)
+    }
+
+    pub fn first(&self) -> Option<&String> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<&String> {
+        self.len.checked_sub(1).and_then(|last| self.get(last))
+    }
+
+    /// Insert a line at `index`, shifting the ones after it down
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, line: String) {
+        assert!(index <= self.len, "insert index {} out of bounds", index);
+        let (chunk, offset) = self.locate_insert(index);
+        self.chunks[chunk].lines_mut().insert(offset, line);
+        self.rebalance(chunk);
+    }
+
+    /// Remove and return the line at `index`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn remove(&mut self, index: usize) -> String {
+        let (chunk, offset) = self
+            .locate(index)
+            .unwrap_or_else(|| panic!("remove index {} out of bounds", index));
+        let line = self.chunks[chunk].lines_mut().remove(offset);
+        self.rebalance(chunk);
+        line
+    }
+
+    /// Replace the lines in `rows` with `lines`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rows` is out of bounds.
+    pub fn splice(&mut self, rows: Range<usize>, lines: Vec<String>) {
+        assert!(
+            rows.start <= rows.end && rows.end <= self.len,
+            "splice range {:?} out of bounds ({} lines)",
+            rows,
+            self.len
+        );
+        let (chunk, offset) = self.locate_insert(rows.start);
+
+        // Merge the chunks the range runs into so it's contiguous
+        let end = offset + rows.len();
+        while end > self.chunks[chunk].len() {
+            let next = self.chunks.remove(chunk + 1).into_lines();
+            self.chunks[chunk].lines_mut().extend(next);
+        }
+
+        self.chunks[chunk].lines_mut().splice(offset..end, lines);
+        self.rebalance(chunk);
+    }
+
+    pub fn push(&mut self, line: String) {
+        self.insert(self.len, line);
+    }
+
+    /// Iterate over all lines, reading any still on disk
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &String> + '_ {
+        self.chunks.iter().flat_map(|chunk| chunk.lines().iter())
+    }
+
+    /// Iterate over the lines in `rows`, only reading the chunks they're in
+    pub fn range(&self, rows: Range<usize>) -> impl Iterator<Item = &String> + '_ {
+        let end = rows.end.min(self.len);
+        let start = rows.start.min(end);
+        let (first_chunk, skip) = self.locate(start).unwrap_or((self.chunks.len(), 0));
+        self.chunks[first_chunk..]
+            .iter()
+            .flat_map(|chunk| chunk.lines().iter())
+            .skip(skip)
+            .take(end - start)
+    }
+
+    /// Call `f` with the row and text of every line, reading the chunks
+    /// still on disk one at a time without keeping them in memory
+    pub fn scan(&self, mut f: impl FnMut(usize, &str)) {
+        let mut row = 0;
+        for chunk in &self.chunks {
+            let read;
+            let lines = match chunk {
+                Chunk::Loaded(lines) => lines,
+                Chunk::Lazy(chunk) => match chunk.lines.get() {
+                    Some(lines) => lines,
+                    None => {
+                        read = chunk.read();
+                        &read
+                    }
+                },
+            };
+            for line in lines {
+                f(row, line);
+                row += 1;
+            }
+        }
+    }
+
+    /// Iterate mutably over all lines, loading every one into memory
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut String> + '_ {
+        self.chunks
+            .iter_mut()
+            .flat_map(|chunk| chunk.lines_mut().iter_mut())
+    }
+
+    /// Chunk and offset within it of the line at `index`
+    fn locate(&self, index: usize) -> Option<(usize, usize)> {
+        if index >= self.len {
+            return None;
+        }
+        let chunk = self.starts.partition_point(|&start| start <= index) - 1;
+        Some((chunk, index - self.starts[chunk]))
+    }
+
+    /// Like `locate`, but `index == len` goes at the end of the last chunk
+    fn locate_insert(&mut self, index: usize) -> (usize, usize) {
+        if let Some(location) = self.locate(index) {
+            return location;
+        }
+        if self.chunks.is_empty() {
+            self.chunks.push(Chunk::Loaded(Vec::new()));
+            self.starts.push(0);
+        }
+        let last = self.chunks.len() - 1;
+        (last, self.chunks[last].len())
+    }
+
+    /// Split the chunk at `index` if it grew too large or drop it if it's
+    /// empty, then bring the row index up to date
+    fn rebalance(&mut self, index: usize) {
+        let len = self.chunks[index].len();
+        if len == 0 {
+            self.chunks.remove(index);
+        } else if len > 2 * CHUNK_LINES {
+            let mut lines = self.chunks[index].lines_mut().split_off(CHUNK_LINES);
+            let mut at = index + 1;
+            while lines.len() > 2 * CHUNK_LINES {
+                let rest = lines.split_off(CHUNK_LINES);
+                self.chunks.insert(at, Chunk::Loaded(lines));
+                lines = rest;
+                at += 1;
+            }
+            self.chunks.insert(at, Chunk::Loaded(lines));
+        }
+        self.reindex(index);
+    }
+
+    /// Recompute the first rows of the chunks from `index` on, and the total
+    fn reindex(&mut self, index: usize) {
+        self.starts.truncate(index);
+        let mut start = match index {
+            0 => 0,
+            _ => self.starts[index - 1] + self.chunks[index - 1].len(),
+        };
+        for chunk in &self.chunks[index..] {
+            self.starts.push(start);
+            start += chunk.len();
+        }
+        self.len = start;
+    }
+}
+
+impl Index<usize> for Lines {
+    type Output = String;
+
+    fn index(&self, index: usize) -> &String {
+        match self.get(index) {
+            Some(line) => line,
+            None => panic!("line {} out of bounds ({} lines)", index, self.len),
+        }
+    }
+}
+
+impl IndexMut<usize> for Lines {
+    fn index_mut(&mut self, index: usize) -> &mut String {
+        let len = self.len;
+        match self.get_mut(index) {
+            Some(line) => line,
+            None => panic!("line {} out of bounds ({} lines)", index, len),
+        }
+    }
+}
+
+impl From<Vec<String>> for Lines {
+    fn from(lines: Vec<String>) -> Self {
+        let mut lines = lines.into_iter();
+        let chunks = std::iter::from_fn(|| {
+            let chunk: Vec<String> = lines.by_ref().take(CHUNK_LINES).collect();
+            (!chunk.is_empty()).then_some(Chunk::Loaded(chunk))
+        });
+        Self::from_chunks(chunks.collect())
+    }
+}
+
+impl FromIterator<String> for Lines {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<'a> IntoIterator for &'a Lines {
+    type Item = &'a String;
+    type IntoIter = Box<dyn DoubleEndedIterator<Item = &'a String> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Lines {
+    type Item = &'a mut String;
+    type IntoIter = Box<dyn Iterator<Item = &'a mut String> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter_mut())
+    }
+}
+
+impl fmt::Debug for Lines {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for Lines {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: AsRef<str>> PartialEq<Vec<T>> for Lines {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        self.len == other.len() && self.iter().zip(other).all(|(a, b)| a == b.as_ref())
+    }
+}
@@ -4,7 +4,7 @@
 //!
 //! ## What it does
 //!
-//! - Stores text as lines in memory
+//! - Stores text as lines, in memory or read from disk on demand
 //! - Tracks cursor position and text selections  
 //! - Handles file loading/saving
 //! - Manages undo/redo history
@@ -13,7 +13,7 @@
 //! ## Structure
 //!
 //! Each buffer keeps track of:
-//! - File content (vector of lines)
+//! - File content (chunked list of lines, see [`Lines`])
 //! - File path and whether it's been modified
 //! - Cursor position and any selected text
 //! - Undo history for changes
@@ -21,19 +21,112 @@
 //! ## Performance
 //!
 //! Designed to handle large files efficiently while keeping
-//! cursor movement and editing operations fast.
+//! cursor movement and editing operations fast. Files opened with
+//! [`Buffer::open_lazy`] only read the lines that are actually viewed or
+//! edited.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+mod block;
+mod changes;
+mod comment;
+mod cursors;
+mod diff;
+mod disk;
+mod encoding;
+mod highlight;
+mod indent;
+mod lines;
+mod pairs;
+mod provider;
+mod save;
+mod search;
+mod sort;
+mod stats;
+mod transform;
+mod wrap;
+
+pub use changes::LineChange;
+pub use diff::{LineStatus, LineStatuses};
+pub use encoding::{decode, Decoded, FileEncoding, LineEnding};
+pub use lines::Lines;
+pub use provider::LineProvider;
+pub use save::{write_save, PendingSave};
+pub use search::SearchQuery;
+pub use stats::{BufferStats, WordCount};
+pub use transform::toggle_case;
+pub use wrap::{byte_col_at_visual, char_width, visual_col, wrap_ranges};
+
+/// Source of unique buffer ids
+static NEXT_BUFFER_ID: AtomicUsize = AtomicUsize::new(0);
+
+fn next_buffer_id() -> usize {
+    NEXT_BUFFER_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Whether the file at `path` ends with a line break, reading only its last
+/// byte
+fn file_ends_with_newline(path: &Path) -> std::io::Result<bool> {
+    use std::io::{Read, Seek, SeekFrom};
 
-use std::path::PathBuf;
+    let mut file = std::fs::File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(false);
+    }
+    let mut last = [0];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
 
 #[derive(Clone)]
 pub struct Buffer {
-    pub content: Vec<String>,
+    pub id: usize, // Stable id, unaffected by closing other buffers
+    pub content: Lines,
     pub path: Option<PathBuf>,
     pub name: String,
     pub modified: bool,
     pub cursor_pos: (usize, usize),              // (row, column)
+    pub secondary_cursors: Vec<Position>, // Extra carets that edit along with the primary one
     pub selection_start: Option<(usize, usize)>, // Start position of selection (row, column), if any
     pub visual_mode: bool,                       // Whether we're in visual (selection) mode
+    pub selection_mode: SelectionMode,           // Whether the selection is a range or a rectangle
+    pub save_options: SaveOptions,               // Clean-ups applied to the content on save
+    pub encoding: FileEncoding,                  // Encoding the file is read from and written in
+    pub encoding_guessed: bool, // Encoding couldn't be detected; invalid bytes were replaced
+    pub line_ending: LineEnding, // Line break the file's lines are written with
+    pub final_newline: bool,    // The file ended with a line break when loaded
+    pub changed_on_disk: bool,  // Another program wrote the file since it was loaded or saved
+    disk_snapshot: Option<disk::DiskSnapshot>, // The file as last loaded or saved
+    disk_hash: Option<u64>,     // Hash of the bytes last read from or written to the file
+    version: u64,               // Bumped on every content change
+    pending_change: Option<LineChange>, // Lines edited since the last `take_change`
+    uncounted_change: Option<LineChange>, // Lines edited since the last word count
+    became_modified: bool,      // An edit flipped `modified` on since the last check
+    diff_base: Option<diff::DiffBase>, // Lines on disk, to mark the changes against
+    highlight_cache: highlight::HighlightCache, // Highlighting states at checkpoint lines
+}
+
+/// Clean-ups applied to a buffer's content right before it's written
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SaveOptions {
+    /// Strip spaces and tabs from the end of every line
+    pub trim_trailing_whitespace: bool,
+
+    /// End the last line with a line break, unless the buffer is empty
+    pub insert_final_newline: bool,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self {
+            trim_trailing_whitespace: false,
+            insert_final_newline: true,
+        }
+    }
 }
 
 impl Default for Buffer {
@@ -45,95 +138,105 @@ impl Default for Buffer {
 impl Buffer {
     pub fn new() -> Self {
         Self {
-            content: vec![String::new()],
+            id: next_buffer_id(),
+            content: vec![String::new()].into(),
             path: None,
             name: String::from("untitled"),
             modified: false,
             cursor_pos: (0, 0),
+            secondary_cursors: Vec::new(),
             selection_start: None,
             visual_mode: false,
+            selection_mode: SelectionMode::Normal,
+            save_options: SaveOptions::default(),
+            encoding: FileEncoding::default(),
+            line_ending: LineEnding::default(),
+            final_newline: false,
+            encoding_guessed: false,
+            version: 0,
+            pending_change: None,
+            uncounted_change: None,
+            became_modified: false,
+            changed_on_disk: false,
+            disk_snapshot: None,
+            disk_hash: None,
+            diff_base: None,
+            highlight_cache: Default::default(),
         }
     }
 
     pub fn from_path(path: PathBuf) -> std::io::Result<Self> {
-        use std::fs;
-        use std::io::{BufRead, BufReader};
-
-        let file = fs::File::open(&path)?;
-        let reader = BufReader::new(file);
+        let bytes = std::fs::read(&path)?;
+        let decoded = encoding::decode(&bytes);
+        let content = decoded.text.lines().map(str::to_owned).collect();
+        Ok(Self::from_decoded(path, content, &decoded, &bytes))
+    }
 
-        let content: Vec<String> = reader.lines().collect::<Result<Vec<String>, _>>()?;
+    pub async fn from_path_async(path: PathBuf) -> std::io::Result<Self> {
+        let bytes = tokio::fs::read(&path).await?;
+        let decoded = encoding::decode(&bytes);
+        let content = decoded.text.lines().map(str::to_owned).collect();
+        Ok(Self::from_decoded(path, content, &decoded, &bytes))
+    }
 
+    /// An empty buffer for a file that doesn't exist yet, written to `path`
+    /// on the first save
+    pub fn new_file(path: PathBuf) -> Self {
         let name = path
             .file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("untitled")
             .to_string();
 
-        Ok(Self {
-            content: if content.is_empty() {
-                vec![String::new()]
-            } else {
-                content
-            },
+        let mut buffer = Self {
             path: Some(path),
             name,
-            modified: false,
-            cursor_pos: (0, 0),
-            selection_start: None,
-            visual_mode: false,
-        })
+            ..Self::new()
+        };
+        buffer.reset_diff_base();
+        buffer
     }
 
-    pub async fn from_path_async(path: PathBuf) -> std::io::Result<Self> {
-        use tokio::fs;
-        use tokio::io::{AsyncBufReadExt, BufReader};
-
-        let file = fs::File::open(&path).await?;
-        let reader = BufReader::new(file);
-        let mut lines = reader.lines();
-
-        let mut content = Vec::new();
-        while let Some(line) = lines.next_line().await? {
-            content.push(line);
+    /// Open a file without reading its lines into memory
+    ///
+    /// Only the line offsets are indexed up front; lines are read from disk
+    /// in chunks as they're viewed and kept in memory once edited. Meant for
+    /// files too large to load whole, so the encoding isn't detected: the file
+    /// is read as UTF-8 with invalid bytes replaced.
+    pub fn open_lazy(path: PathBuf) -> std::io::Result<Self> {
+        let mut content = Lines::open_lazy(&path)?;
+        if content.is_empty() {
+            content.push(String::new());
         }
 
         let name = path
             .file_name()
             .and_then(|n| n.to_str())
-            .map(|s| s.to_owned())
-            .unwrap_or_else(|| "untitled".to_owned());
+            .unwrap_or("untitled")
+            .to_string();
 
-        Ok(Self {
-            content: if content.is_empty() {
-                vec![String::new()]
-            } else {
-                content
-            },
+        let mut buffer = Self {
+            content,
+            final_newline: file_ends_with_newline(&path)?,
             path: Some(path),
             name,
-            modified: false,
-            cursor_pos: (0, 0),
-            selection_start: None,
-            visual_mode: false,
-        })
+            ..Self::new()
+        };
+        buffer.record_disk_snapshot();
+        Ok(buffer)
     }
 
     /// Load a large file with chunked reading for better performance
     pub async fn from_large_file_async(path: PathBuf, chunk_size: usize) -> std::io::Result<Self> {
-        use tokio::fs;
-        use tokio::io::{AsyncBufReadExt, BufReader};
-
-        let file = fs::File::open(&path).await?;
-        let reader = BufReader::new(file);
-        let mut lines = reader.lines();
+        let bytes = tokio::fs::read(&path).await?;
+        let decoded = encoding::decode(&bytes);
 
         let mut content = Vec::new();
         let mut lines_read = 0;
 
-        // Read in chunks to avoid blocking the UI
-        while let Some(line) = lines.next_line().await? {
-            content.push(line);
+        // Split in chunks to avoid blocking the UI
+        for line in decoded.text.lines() {
+            content.push(line.to_owned());
             lines_read += 1;
 
             // Yield control every chunk_size lines
@@ -142,27 +245,74 @@ impl Buffer {
             }
         }
 
+        Ok(Self::from_decoded(path, content, &decoded, &bytes))
+    }
+
+    /// Build a buffer for a file whose lines have already been decoded from
+    /// `bytes`
+    fn from_decoded(path: PathBuf, content: Vec<String>, decoded: &Decoded, bytes: &[u8]) -> Self {
         let name = path
             .file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("untitled")
             .to_string();
 
-        Ok(Self {
+        let mut buffer = Self {
             content: if content.is_empty() {
-                vec![String::new()]
+                vec![String::new()].into()
             } else {
-                content
+                content.into()
             },
             path: Some(path),
             name,
-            modified: false,
-            cursor_pos: (0, 0),
-            selection_start: None,
-            visual_mode: false,
+            encoding: decoded.encoding,
+            encoding_guessed: decoded.guessed,
+            line_ending: LineEnding::detect(&decoded.text),
+            final_newline: decoded.text.ends_with('\n'),
+            ..Self::new()
+        };
+        buffer.record_disk_snapshot();
+        buffer.record_disk_bytes(bytes);
+        buffer.reset_diff_base();
+        buffer
+    }
+
+    /// Warning for the user when the file's encoding couldn't be detected
+    pub fn encoding_warning(&self) -> Option<String> {
+        self.encoding_guessed.then(|| {
+            format!(
+                "Couldn't detect the encoding of {}; opened as UTF-8 with invalid bytes replaced",
+                self.name
+            )
         })
     }
 
+    /// Write the file in `encoding` from now on, returning false if it
+    /// already was
+    ///
+    /// Counts as an edit, though no line changes, so the next save writes it.
+    pub fn set_encoding(&mut self, encoding: FileEncoding) -> bool {
+        if self.encoding == encoding {
+            return false;
+        }
+        self.encoding = encoding;
+        self.mark_format_changed();
+        true
+    }
+
+    /// End the file's lines with `line_ending` from now on, returning false
+    /// if they already were
+    ///
+    /// Counts as an edit, though no line changes, so the next save writes it.
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) -> bool {
+        if self.line_ending == line_ending {
+            return false;
+        }
+        self.line_ending = line_ending;
+        self.mark_format_changed();
+        true
+    }
+
     /// Get buffer content as a string efficiently without allocating intermediate strings
     /// This is optimized to avoid the expensive `join()` operation on every call
     pub fn content_as_string(&self) -> String {
@@ -180,9 +330,44 @@ impl Buffer {
         result
     }
 
-    pub fn insert_char(&mut self, c: char) {
+    /// Write the content in the buffer's encoding a line at a time, without
+    /// building the whole document as one string first
+    ///
+    /// The last line ends with a line break like every other one if the file
+    /// had one when loaded, or `insert_final_newline` is set and there's any
+    /// text. Every save method writes through here, so they agree on it.
+    pub fn write_to(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        writer.write_all(self.encoding.bom_bytes())?;
+
+        let empty = self.content.len() == 1 && self.content[0].is_empty();
+        let final_newline =
+            self.final_newline || (self.save_options.insert_final_newline && !empty);
+        let mut bytes = Vec::new();
+        let last = self.content.len().saturating_sub(1);
+        for (i, line) in self.content.iter().enumerate() {
+            bytes.clear();
+            self.encoding.encode_into(line, &mut bytes);
+            if i < last || final_newline {
+                self.encoding
+                    .encode_into(self.line_ending.as_str(), &mut bytes);
+            }
+            writer.write_all(&bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Insert `c` at every cursor
+    ///
+    /// Like every edit, returns whether the text changed.
+    pub fn insert_char(&mut self, c: char) -> bool {
+        self.edit_at_each_cursor(|buffer| buffer.insert_char_at_cursor(c))
+    }
+
+    fn insert_char_at_cursor(&mut self, c: char) {
         let (row, col) = self.cursor_pos;
+        let mut change = LineChange::rows(row..row + 1);
         if row >= self.content.len() {
+            change = LineChange::new(self.content.len(), 0, 1);
             self.content.push(String::new());
         }
 
@@ -192,13 +377,79 @@ impl Buffer {
         }
 
         line.insert(col, c);
-        self.cursor_pos.1 += 1;
-        self.modified = true;
+        self.cursor_pos.1 += c.len_utf8();
+        self.mark_changed(change);
     }
 
-    pub fn insert_newline(&mut self) {
+    /// Insert `text` at every cursor, leaving each cursor after it
+    ///
+    /// Newlines in the text (`\n` or `\r\n`) split the line, so a paste of
+    /// several lines is one edit.
+    pub fn insert_str(&mut self, text: &str) -> bool {
+        if text.is_empty() {
+            return false;
+        }
+        let text = text.replace("\r\n", "\n");
+        self.edit_at_each_cursor(|buffer| buffer.insert_str_at_cursor(&text))
+    }
+
+    fn insert_str_at_cursor(&mut self, text: &str) {
         let (row, col) = self.cursor_pos;
+        let mut old_count = 1;
         if row >= self.content.len() {
+            old_count = 0;
+            self.content.push(String::new());
+        }
+
+        let line = &mut self.content[row];
+        if col > line.len() {
+            line.push_str(&" ".repeat(col - line.len()));
+        }
+
+        let mut pieces = text.split('\n');
+        let tail = line.split_off(col);
+        line.push_str(pieces.next().unwrap_or_default());
+        let mut new_lines: Vec<String> = pieces.map(str::to_owned).collect();
+        let added = new_lines.len();
+        match new_lines.last_mut() {
+            Some(last) => {
+                self.cursor_pos = (row + added, last.len());
+                last.push_str(&tail);
+            }
+            None => {
+                self.cursor_pos.1 = line.len();
+                line.push_str(&tail);
+            }
+        }
+
+        let new_count = 1 + new_lines.len();
+        self.content
+            .splice(row + 1..row + 1, std::mem::take(&mut new_lines));
+        self.mark_changed(LineChange::new(row, old_count, new_count));
+    }
+
+    /// Split the line at every cursor
+    pub fn insert_newline(&mut self) -> bool {
+        self.edit_at_each_cursor(Self::insert_newline_at_cursor)
+    }
+
+    /// Start a new line at every cursor like `insert_newline`, also
+    /// stripping trailing spaces and tabs from each line left behind
+    pub fn insert_newline_trimming(&mut self) -> bool {
+        self.edit_at_each_cursor(|buffer| {
+            buffer.insert_newline_at_cursor();
+            let left_behind = buffer.cursor_pos.0 - 1;
+            if let Some(line) = buffer.content.get_mut(left_behind) {
+                let trimmed_len = line.trim_end_matches([' ', '\t']).len();
+                line.truncate(trimmed_len);
+            }
+        })
+    }
+
+    fn insert_newline_at_cursor(&mut self) {
+        let (row, col) = self.cursor_pos;
+        if row >= self.content.len() {
+            self.mark_changed(LineChange::new(self.content.len(), 0, 1));
             self.content.push(String::new());
             self.cursor_pos = (row + 1, 0);
             return;
@@ -216,16 +467,23 @@ impl Buffer {
         }
 
         self.cursor_pos = (row + 1, 0);
-        self.modified = true;
+        self.mark_changed(LineChange::new(row, 1, 2));
+    }
+
+    /// Delete the char before every cursor
+    pub fn backspace(&mut self) -> bool {
+        self.edit_at_each_cursor(Self::backspace_at_cursor)
     }
 
-    pub fn backspace(&mut self) {
+    fn backspace_at_cursor(&mut self) {
         let (row, col) = self.cursor_pos;
         if col > 0 {
             // Delete character before cursor
             let line = &mut self.content[row];
-            line.remove(col - 1);
-            self.cursor_pos.1 -= 1;
+            let len = line[..col].chars().next_back().map_or(1, char::len_utf8);
+            line.remove(col - len);
+            self.cursor_pos.1 -= len;
+            self.mark_changed(LineChange::rows(row..row + 1));
         } else if row > 0 {
             // Join with previous line
             let current_line = self.content.remove(row);
@@ -233,47 +491,172 @@ impl Buffer {
             let new_cursor_col = prev_line.len();
             prev_line.push_str(&current_line);
             self.cursor_pos = (row - 1, new_cursor_col);
+            self.mark_changed(LineChange::new(row - 1, 2, 1));
         }
-        self.modified = true;
     }
 
-    pub fn delete(&mut self) {
+    /// Delete the char at the cursor, joining the next line at a line end
+    pub fn delete(&mut self) -> bool {
         let (row, col) = self.cursor_pos;
-        if row < self.content.len() {
-            let line = &mut self.content[row];
-            if col < line.len() {
-                // Delete character at cursor
-                line.remove(col);
-            } else if row + 1 < self.content.len() {
-                // Join with next line
-                let next_line = self.content.remove(row + 1);
-                self.content[row].push_str(&next_line);
+        let Some(line) = self.content.get_mut(row) else {
+            return false;
+        };
+        if col < line.len() {
+            // Delete character at cursor
+            line.remove(col);
+            self.mark_changed(LineChange::rows(row..row + 1));
+        } else if row + 1 < self.content.len() {
+            // Join with next line
+            let next_line = self.content.remove(row + 1);
+            self.content[row].push_str(&next_line);
+            self.mark_changed(LineChange::new(row, 2, 1));
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Delete from every cursor to the end of its line
+    pub fn delete_to_line_end(&mut self) -> bool {
+        self.edit_at_each_cursor(Self::delete_to_line_end_at_cursor)
+    }
+
+    fn delete_to_line_end_at_cursor(&mut self) {
+        let (row, col) = self.cursor_pos;
+        let Some(line) = self.content.get_mut(row).filter(|line| col < line.len()) else {
+            return;
+        };
+        line.truncate(col);
+        self.mark_changed(LineChange::rows(row..row + 1));
+    }
+
+    /// Swap the chars either side of every cursor and move past them
+    ///
+    /// At the end of a line the two chars before the cursor are swapped
+    /// instead and the cursor stays put. Nothing happens at the start of a
+    /// line, so chars are never swapped across lines.
+    pub fn transpose_chars(&mut self) -> bool {
+        self.edit_at_each_cursor(Self::transpose_chars_at_cursor)
+    }
+
+    fn transpose_chars_at_cursor(&mut self) {
+        let (row, col) = self.cursor_pos;
+        let Some(line) = self
+            .content
+            .get_mut(row)
+            .filter(|line| col > 0 && col <= line.len())
+        else {
+            return;
+        };
+
+        // Byte range of the two chars, ending at the cursor at a line end
+        let end = match line[col..].chars().next() {
+            Some(c) => col + c.len_utf8(),
+            None => col,
+        };
+        let mut chars = line[..end].char_indices().rev();
+        let (Some((second, _)), Some((start, _))) = (chars.next(), chars.next()) else {
+            return;
+        };
+
+        let swapped = format!("{}{}", &line[second..end], &line[start..second]);
+        line.replace_range(start..end, &swapped);
+        self.cursor_pos.1 = end;
+        self.mark_changed(LineChange::rows(row..row + 1));
+    }
+
+    /// Delete the line of every cursor
+    ///
+    /// The cursor goes to the start of the line that followed, or of the one
+    /// before if it was the last. Deleting the only line leaves it empty.
+    pub fn delete_line(&mut self) -> bool {
+        self.edit_at_each_cursor(Self::delete_line_at_cursor)
+    }
+
+    fn delete_line_at_cursor(&mut self) {
+        let row = self.cursor_pos.0;
+        if row >= self.content.len() {
+            return;
+        }
+        if self.content.len() == 1 {
+            if !self.content[0].is_empty() {
+                self.content[0].clear();
+                self.mark_changed(LineChange::rows(0..1));
             }
-            self.modified = true;
+            self.cursor_pos = (0, 0);
+            return;
         }
+
+        self.content.remove(row);
+        self.cursor_pos = (row.min(self.content.len() - 1), 0);
+        self.mark_changed(LineChange::new(row, 1, 0));
     }
 
-    pub fn save(&mut self) -> std::io::Result<()> {
-        if let Some(path) = &self.path {
-            use std::fs;
-            use std::io::Write;
+    /// Save the buffer to its file, returning whether it was written
+    ///
+    /// Content that matches the file as last loaded or saved isn't written
+    /// again, but still counts as saved.
+    pub fn save(&mut self) -> std::io::Result<bool> {
+        self.prepare_for_save();
+        let Some(path) = self.path.clone() else {
+            return Err(std::io::Error::other("No file path specified"));
+        };
 
-            let mut file = fs::File::create(path)?;
-            for line in &self.content {
-                writeln!(file, "{}", line)?;
+        let mut content = Vec::new();
+        self.write_to(&mut content)?;
+        let written = !self.disk_holds(&path, &content);
+        if written {
+            std::fs::write(&path, &content)?;
+        }
+        self.mark_saved();
+        self.record_disk_bytes(&content);
+        Ok(written)
+    }
+
+    /// Apply the `save_options` clean-ups to the content
+    ///
+    /// The cursor is pulled back to the new line end if it was sitting in
+    /// trimmed whitespace. Called by every save method before writing.
+    ///
+    /// Lines of a lazily opened file are read into memory first, since the
+    /// file they'd be read from is about to be overwritten.
+    pub fn prepare_for_save(&mut self) {
+        self.content.load_all();
+        if !self.save_options.trim_trailing_whitespace {
+            return;
+        }
+
+        let mut trimmed_any = false;
+        for line in &mut self.content {
+            let trimmed_len = line.trim_end_matches([' ', '\t']).len();
+            if trimmed_len < line.len() {
+                line.truncate(trimmed_len);
+                trimmed_any = true;
             }
-            self.modified = false;
-            Ok(())
-        } else {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "No file path specified",
-            ))
+        }
+
+        if trimmed_any {
+            let (row, col) = self.cursor_pos;
+            let line_len = self.content.get(row).map_or(0, |line| line.len());
+            self.cursor_pos = (row, col.min(line_len));
+            if let Some((start_row, start_col)) = self.selection_start {
+                let line_len = self.content.get(start_row).map_or(0, |line| line.len());
+                self.selection_start = Some((start_row, start_col.min(line_len)));
+            }
+            self.mark_all_changed();
         }
     }
 
-    /// Save buffer content to its associated file path asynchronously
-    pub async fn save_async(&mut self) -> std::io::Result<()> {
+    /// Take the saved content as what's on disk, if changes are tracked
+    fn refresh_diff_base(&mut self) {
+        if self.has_diff_base() {
+            self.reset_diff_base();
+        }
+    }
+
+    /// Save buffer content to its associated file path asynchronously,
+    /// returning whether it was written
+    pub async fn save_async(&mut self) -> std::io::Result<bool> {
         if let Some(path) = &self.path {
             self.save_to_path_async(path.clone()).await
         } else {
@@ -284,25 +667,55 @@ impl Buffer {
         }
     }
 
-    /// Save buffer content to a specific path asynchronously
-    pub async fn save_to_path_async(&mut self, path: PathBuf) -> std::io::Result<()> {
-        use tokio::fs;
-        use tokio::io::AsyncWriteExt;
+    /// Save buffer content to a specific path asynchronously, returning
+    /// whether it was written
+    ///
+    /// Content that matches the file as last loaded or saved isn't written
+    /// again, so its modification time stays put, but still counts as saved.
+    pub async fn save_to_path_async(&mut self, path: PathBuf) -> std::io::Result<bool> {
+        let save = self.begin_save(path)?;
+        let written = save.write().await?;
+        self.finish_save(&save);
+        Ok(written)
+    }
 
-        let content = self.content_as_string();
-        let mut file = fs::File::create(&path).await?;
-        file.write_all(content.as_bytes()).await?;
-        file.sync_all().await?;
+    /// Replace the content with what's in the file on disk, throwing away
+    /// any edits
+    ///
+    /// The cursor is pulled back onto the new text and the selection and
+    /// extra cursors are dropped. The replaced lines are recorded as a change.
+    pub async fn reload_async(&mut self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "No file path associated with buffer",
+            ));
+        };
+        let bytes = tokio::fs::read(path).await?;
+        self.reload_from(&bytes);
+        Ok(())
+    }
 
-        self.modified = false;
-        self.path = Some(path.clone());
-        self.name = path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("untitled")
-            .to_string();
+    /// Replace the content with `bytes` read from the file, like
+    /// `reload_async` does once it has them
+    pub fn reload_from(&mut self, bytes: &[u8]) {
+        let decoded = encoding::decode(bytes);
+        let mut content: Vec<String> = decoded.text.lines().map(str::to_owned).collect();
+        if content.is_empty() {
+            content.push(String::new());
+        }
 
-        Ok(())
+        let change = LineChange::new(0, self.content.len(), content.len());
+        self.content = content.into();
+        self.encoding = decoded.encoding;
+        self.encoding_guessed = decoded.guessed;
+        self.line_ending = LineEnding::detect(&decoded.text);
+        self.final_newline = decoded.text.ends_with('\n');
+        self.clear_secondary_cursors();
+        self.place_cursor(Position::from_tuple(self.cursor_pos));
+        self.mark_changed(change);
+        self.mark_saved();
+        self.record_disk_bytes(bytes);
     }
 
     /// Toggle visual (selection) mode
@@ -312,16 +725,67 @@ impl Buffer {
         if self.visual_mode {
             // Start selection at current cursor position
             self.selection_start = Some(self.cursor_pos);
+            self.selection_mode = SelectionMode::Normal;
         } else {
             // Clear selection when exiting visual mode
             self.selection_start = None;
         }
     }
 
+    /// Toggle linewise visual mode, selecting whole lines from the cursor's line
+    ///
+    /// A character selection in progress turns linewise, keeping its start.
+    pub fn toggle_line_visual_mode(&mut self) {
+        if self.visual_mode && self.selection_mode == SelectionMode::Line {
+            self.clear_selection();
+            return;
+        }
+        if !self.visual_mode || self.selection_start.is_none() {
+            self.selection_start = Some(self.cursor_pos);
+        }
+        self.visual_mode = true;
+        self.selection_mode = SelectionMode::Line;
+    }
+
+    /// Select everything, from the start of the first line to the end of the
+    /// last, leaving the cursor at the end
+    pub fn select_all(&mut self) {
+        let last = self.content.len().saturating_sub(1);
+        let last_len = self.content.get(last).map_or(0, |line| line.len());
+        self.clear_secondary_cursors();
+        self.selection_start = Some((0, 0));
+        self.cursor_pos = (last, last_len);
+        self.visual_mode = true;
+        self.selection_mode = SelectionMode::Normal;
+    }
+
     /// Clear the current selection
     pub fn clear_selection(&mut self) {
         self.selection_start = None;
         self.visual_mode = false;
+        self.selection_mode = SelectionMode::Normal;
+    }
+
+    /// Counter that changes whenever the content is edited
+    ///
+    /// Unlike `modified`, this keeps increasing after a save, so it can be
+    /// used to tell whether anything derived from the content is out of date.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Flag the buffer as changed after an edit
+    fn mark_modified(&mut self) {
+        self.modified = true;
+        self.version += 1;
+    }
+
+    /// Flag the buffer as matching its file after the content was written
+    pub fn mark_saved(&mut self) {
+        self.modified = false;
+        self.became_modified = false;
+        self.record_disk_snapshot();
+        self.refresh_diff_base();
     }
 
     /// Check if the buffer has unsaved changes
@@ -340,16 +804,31 @@ impl Buffer {
             let end_pos = Position::from_tuple(end);
 
             // Ensure start is before end for consistent ordering
-            if start_pos <= end_pos {
+            let (start, end) = if start_pos <= end_pos {
                 (start_pos, end_pos)
             } else {
                 (end_pos, start_pos)
+            };
+            if self.selection_mode != SelectionMode::Line {
+                return (start, end);
             }
+
+            // Whole lines, up to the start of the line after the last
+            let end = if end.row + 1 < self.content.len() {
+                Position::new(end.row + 1, 0)
+            } else {
+                let last_len = self.content.get(end.row).map_or(0, |line| line.len());
+                Position::new(end.row, last_len)
+            };
+            (Position::new(start.row, 0), end)
         })
     }
 
     /// Get the text content of the current selection
     pub fn get_selected_text(&self) -> Option<String> {
+        if self.selection_mode == SelectionMode::Block {
+            return self.get_block_text();
+        }
         self.get_selection_range().map(|(start, end)| {
             // If selection is within a single line
             if start.row == end.row {
@@ -408,16 +887,24 @@ impl Buffer {
 
     /// Delete the selected text
     pub fn delete_selection(&mut self) -> bool {
+        if self.selection_mode == SelectionMode::Block {
+            return self.delete_block();
+        }
         if let Some((start, end)) = self.get_selection_range() {
             // Handle single-line selection
             if start.row == end.row {
                 if start.row < self.content.len() {
                     let line = &mut self.content[start.row];
-                    if start.col < line.len() {
+                    if start.col < line.len().min(end.col) {
                         line.replace_range(start.col..end.col.min(line.len()), "");
+                        self.mark_changed(LineChange::rows(start.row..start.row + 1));
                     }
                 }
             } else {
+                let old_count = (end.row + 1)
+                    .min(self.content.len())
+                    .saturating_sub(start.row);
+                self.mark_changed(LineChange::new(start.row, old_count, 1));
                 // Handle multi-line selection
                 if start.row < self.content.len() && end.row < self.content.len() {
                     let first_line = &self.content[start.row];
@@ -464,14 +951,22 @@ impl Buffer {
             // Set cursor to the start of the deleted selection
             self.cursor_pos = start.to_tuple();
             self.clear_selection();
-            self.modified = true;
             true
         } else {
             false
         }
     }
 
+    /// Move every cursor
     pub fn move_cursor(&mut self, direction: CursorMovement) {
+        if self.secondary_cursors.is_empty() {
+            self.move_primary_cursor(direction);
+        } else {
+            self.move_each_cursor(direction);
+        }
+    }
+
+    fn move_primary_cursor(&mut self, direction: CursorMovement) {
         let (mut row, mut col) = self.cursor_pos;
 
         match direction {
@@ -511,35 +1006,24 @@ impl Buffer {
             CursorMovement::LineStart => {
                 col = 0;
             }
+            CursorMovement::SmartLineStart => {
+                let indent = self.content.get(row).map_or(0, |line| {
+                    line.len() - line.trim_start_matches([' ', '\t']).len()
+                });
+                col = if col == indent { 0 } else { indent };
+            }
             CursorMovement::LineEnd => {
                 if row < self.content.len() {
                     col = self.content[row].len();
                 }
             }
-            CursorMovement::PageUp => {
-                // Use a larger number for page scrolling (default to 8 but will be overridden by actual area height)
-                // This is just a fallback if the terminal size isn't available
-                let page_size = 8;
-                if row >= page_size {
-                    row -= page_size;
-                } else {
-                    row = 0;
-                }
-                // Adjust column if needed
-                let line = &self.content[row];
-                col = col.min(line.len());
+            CursorMovement::PageUp(page_size) => {
+                row = row.saturating_sub(page_size);
+                col = col.min(self.content[row].len());
             }
-            CursorMovement::PageDown => {
-                // Use a larger number for page scrolling (default to 8 but will be overridden by actual area height)
-                let page_size = 8;
-                if row + page_size < self.content.len() {
-                    row += page_size;
-                } else {
-                    row = self.content.len() - 1;
-                }
-                // Adjust column if needed
-                let line = &self.content[row];
-                col = col.min(line.len());
+            CursorMovement::PageDown(page_size) => {
+                row = (row + page_size).min(self.content.len().saturating_sub(1));
+                col = col.min(self.content[row].len());
             }
             CursorMovement::BufferStart => {
                 row = 0;
@@ -582,29 +1066,149 @@ impl Buffer {
     /// Get the width needed for line numbers display
     /// Always reserves space for at least 4 digits to prevent UI shifts
     pub fn line_number_width(&self) -> usize {
-        let total_lines = self.content.len().max(1);
-        let calculated_width = Self::count_digits(total_lines);
+        self.gutter_width(1) // single space between numbers and text
+    }
+
+    /// Get the full gutter width: the change marker column when changes are
+    /// tracked, line number digits, plus `gap` spaces before the text
+    pub fn gutter_width(&self, gap: usize) -> usize {
+        self.diff_marker_width() + Self::number_column_width(self.content.len()) + gap
+    }
+
+    /// Width of the line numbers for a document of `total_lines` lines
+    pub fn number_column_width(total_lines: usize) -> usize {
+        let calculated_width = Self::count_digits(total_lines.max(1));
         // Reserve space for at least 4 digits (up to 9999 lines) to prevent UI shifts
         let min_width = 4;
-        calculated_width.max(min_width) + 1 // +1 for spacing
+        calculated_width.max(min_width)
+    }
+
+    /// Width of the column marking changed lines left of the line numbers
+    pub fn diff_marker_width(&self) -> usize {
+        usize::from(self.has_diff_base())
+    }
+
+    /// Position of the bracket matching the one at `pos`
+    ///
+    /// Scans forward from an opening bracket or backward from a closing one,
+    /// skipping nested pairs of the same kind. Returns `None` when `pos` isn't
+    /// on a bracket or the bracket is unbalanced. Brackets inside strings and
+    /// comments are counted like any other.
+    pub fn matching_bracket(&self, pos: Position) -> Option<Position> {
+        let line = self.content.get(pos.row)?;
+        let bracket = line.get(pos.col..)?.chars().next()?;
+        let (open, close, forward) = match bracket {
+            '(' => ('(', ')', true),
+            '[' => ('[', ']', true),
+            '{' => ('{', '}', true),
+            ')' => ('(', ')', false),
+            ']' => ('[', ']', false),
+            '}' => ('{', '}', false),
+            _ => return None,
+        };
+
+        // Nesting depth relative to `pos`; the match is where it drops back to 0
+        let mut depth = 0isize;
+        let mut visit = |row: usize, col: usize, ch: char| {
+            if ch == open {
+                depth += if forward { 1 } else { -1 };
+            } else if ch == close {
+                depth += if forward { -1 } else { 1 };
+            }
+            (depth == 0).then_some(Position::new(row, col))
+        };
+
+        if forward {
+            for row in pos.row..self.content.len() {
+                let start = if row == pos.row { pos.col } else { 0 };
+                for (col, ch) in self.content[row][start..].char_indices() {
+                    if let Some(found) = visit(row, start + col, ch) {
+                        return Some(found);
+                    }
+                }
+            }
+        } else {
+            for row in (0..=pos.row).rev() {
+                let line = &self.content[row];
+                let end = if row == pos.row {
+                    pos.col + 1
+                } else {
+                    line.len()
+                };
+                for (col, ch) in line[..end].char_indices().rev() {
+                    if let Some(found) = visit(row, col, ch) {
+                        return Some(found);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// The bracket pair to highlight for the cursor
+    ///
+    /// Prefers a bracket under the cursor, then the one just before it, so a
+    /// closing bracket still matches right after it's typed.
+    pub fn bracket_pair_at_cursor(&self) -> Option<(Position, Position)> {
+        let (row, col) = self.cursor_pos;
+        let at_cursor = Position::new(row, col);
+        if let Some(other) = self.matching_bracket(at_cursor) {
+            return Some((at_cursor, other));
+        }
+
+        let line = self.content.get(row)?;
+        let (before, _) = line[..col.min(line.len())].char_indices().next_back()?;
+        let before = Position::new(row, before);
+        self.matching_bracket(before).map(|other| (before, other))
     }
 }
 
+/// Shape of the selection between `selection_start` and the cursor
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// Everything from the start to the cursor, wrapping across lines
+    #[default]
+    Normal,
+
+    /// The rectangle with the start and the cursor at opposite corners
+    Block,
+
+    /// Every line from the start's to the cursor's, whatever the columns,
+    /// along with the newline ending the last one
+    Line,
+}
+
+#[derive(Clone, Copy)]
 pub enum CursorMovement {
     Up,
     Down,
     Left,
     Right,
     LineStart,
+    /// The first non-blank char of the line, or column 0 if already there
+    SmartLineStart,
     LineEnd,
-    PageUp,
-    PageDown,
+    /// Up by this many lines, usually the height of the view
+    PageUp(usize),
+    /// Down by this many lines, usually the height of the view
+    PageDown(usize),
     BufferStart,
     BufferEnd,
 }
 
+impl CursorMovement {
+    /// Whether the movement can take the cursor far enough to be recorded
+    /// in the jump list
+    pub fn is_jump(&self) -> bool {
+        matches!(
+            self,
+            Self::PageUp(_) | Self::PageDown(_) | Self::BufferStart | Self::BufferEnd
+        )
+    }
+}
+
 /// Represents a text position (row, column)
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Position {
     pub row: usize,
     pub col: usize,
@@ -0,0 +1,106 @@
+//! Auto-closing brackets and quotes
+//!
+//! Typing an opening bracket or quote also inserts its closing char after
+//! the cursor, and typing a closing char that's already right after the
+//! cursor steps over it instead. Nothing is closed in front of a word char,
+//! and a quote right after one (as in "don't") isn't closed either.
+//!
+//! With text selected, typing an opening char wraps the selection in the
+//! pair and keeps the wrapped text selected.
+
+use super::{Buffer, LineChange, SelectionMode};
+
+/// Opening chars and the chars that close them
+pub const PAIRS: &[(char, char)] = &[
+    ('(', ')'),
+    ('[', ']'),
+    ('{', '}'),
+    ('"', '"'),
+    ('\'', '\''),
+    ('`', '`'),
+];
+
+/// The char closing `open`, if it opens a pair
+fn closing(open: char) -> Option<char> {
+    PAIRS
+        .iter()
+        .find(|(o, _)| *o == open)
+        .map(|&(_, close)| close)
+}
+
+/// Whether `c` closes a pair
+fn is_closing(c: char) -> bool {
+    PAIRS.iter().any(|&(_, close)| close == c)
+}
+
+impl Buffer {
+    /// Insert `c` at every cursor, closing brackets and quotes
+    ///
+    /// Like every edit, returns whether the text changed; stepping over a
+    /// closing char only moves the cursor.
+    pub fn insert_char_paired(&mut self, c: char) -> bool {
+        self.edit_at_each_cursor(|buffer| buffer.insert_char_paired_at_cursor(c))
+    }
+
+    fn insert_char_paired_at_cursor(&mut self, c: char) {
+        let (row, col) = self.cursor_pos;
+        let Some(line) = self.content.get(row).filter(|line| col <= line.len()) else {
+            return self.insert_char_at_cursor(c);
+        };
+        let before = line[..col].chars().next_back();
+        let after = line[col..].chars().next();
+
+        if is_closing(c) && after == Some(c) {
+            self.cursor_pos.1 += c.len_utf8();
+            return;
+        }
+
+        let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let close = closing(c)
+            .filter(|_| !is_word(after))
+            .filter(|&close| close != c || !is_word(before));
+        self.insert_char_at_cursor(c);
+        if let Some(close) = close {
+            self.content[row].insert(self.cursor_pos.1, close);
+        }
+    }
+
+    /// Wrap the selected text in the pair `open` starts, keeping it selected
+    ///
+    /// Returns false, changing nothing, if `open` doesn't start a pair or
+    /// nothing is selected. Block selections aren't wrapped.
+    pub fn wrap_selection(&mut self, open: char) -> bool {
+        let Some(close) = closing(open) else {
+            return false;
+        };
+        if self.selection_mode == SelectionMode::Block {
+            return false;
+        }
+        let Some((start, end)) = self
+            .get_selection_range()
+            .filter(|(start, end)| start != end)
+        else {
+            return false;
+        };
+        if self
+            .content
+            .get(end.row)
+            .is_none_or(|line| end.col > line.len())
+        {
+            return false;
+        }
+
+        // Closing first, so the start column stays put
+        self.content[end.row].insert(end.col, close);
+        self.content[start.row].insert(start.col, open);
+        self.shift_selection_ends(start.row, |col| {
+            if col >= start.col {
+                col + open.len_utf8()
+            } else {
+                col
+            }
+        });
+        self.mark_changed(LineChange::rows(start.row..end.row + 1));
+        true
+    }
+}
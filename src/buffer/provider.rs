@@ -0,0 +1,48 @@
+//! Line access for rendering
+//!
+//! Widgets that only draw text read it through [`LineProvider`] rather than
+//! reaching into a buffer's storage, so lazily loaded or otherwise virtual
+//! documents can be drawn by asking for just the lines in view.
+
+use std::borrow::Cow;
+
+use super::{Buffer, Lines};
+
+/// Source of numbered lines of text
+pub trait LineProvider {
+    /// The line at `idx`, or `None` past the end
+    fn line(&self, idx: usize) -> Option<Cow<'_, str>>;
+
+    /// Number of lines
+    fn line_count(&self) -> usize;
+
+    /// The buffer behind these lines, if any, for its cursor, selection and
+    /// other editing state
+    fn as_buffer(&self) -> Option<&Buffer> {
+        None
+    }
+}
+
+impl LineProvider for Lines {
+    fn line(&self, idx: usize) -> Option<Cow<'_, str>> {
+        self.get(idx).map(|line| Cow::Borrowed(line.as_str()))
+    }
+
+    fn line_count(&self) -> usize {
+        self.len()
+    }
+}
+
+impl LineProvider for Buffer {
+    fn line(&self, idx: usize) -> Option<Cow<'_, str>> {
+        self.content.line(idx)
+    }
+
+    fn line_count(&self) -> usize {
+        self.content.len()
+    }
+
+    fn as_buffer(&self) -> Option<&Buffer> {
+        Some(self)
+    }
+}
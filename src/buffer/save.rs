@@ -0,0 +1,90 @@
+//! Saving without holding the buffer during the write
+//!
+//! A save is split in three so callers sharing the buffer behind a lock
+//! only hold it while touching the buffer: [`Buffer::begin_save`] encodes
+//! the content, [`PendingSave::write`] does the file I/O on its own, and
+//! [`Buffer::finish_save`] records the result. Edits made while the file
+//! was being written keep the buffer modified, since they aren't in it.
+
+use std::path::PathBuf;
+
+use super::Buffer;
+
+/// Bytes of a buffer on their way to a file
+#[derive(Debug)]
+pub struct PendingSave {
+    /// Id of the buffer saved
+    pub buffer_id: usize,
+
+    pub path: PathBuf,
+    content: Vec<u8>,
+
+    /// Whether the file already holds the content, so writing is skipped
+    unchanged: bool,
+
+    /// Version of the buffer the content was taken from
+    version: u64,
+}
+
+impl PendingSave {
+    /// Write the content to the file, returning whether it was written
+    ///
+    /// Content that matches the file as last loaded or saved isn't written
+    /// again, so its modification time stays put.
+    pub async fn write(&self) -> std::io::Result<bool> {
+        use tokio::io::AsyncWriteExt;
+
+        if self.unchanged {
+            return Ok(false);
+        }
+        let mut file = tokio::fs::File::create(&self.path).await?;
+        file.write_all(&self.content).await?;
+        file.sync_all().await?;
+        Ok(true)
+    }
+}
+
+/// Write a save begun with [`Buffer::begin_save`], handing it back to be
+/// finished along with whether the file was written
+pub async fn write_save(
+    save: std::io::Result<PendingSave>,
+) -> std::io::Result<(PendingSave, bool)> {
+    let save = save?;
+    let written = save.write().await?;
+    Ok((save, written))
+}
+
+impl Buffer {
+    /// Apply the save clean-ups and encode the content for writing to
+    /// `path`
+    pub fn begin_save(&mut self, path: PathBuf) -> std::io::Result<PendingSave> {
+        self.prepare_for_save();
+        let mut content = Vec::new();
+        self.write_to(&mut content)?;
+        Ok(PendingSave {
+            buffer_id: self.id,
+            unchanged: self.disk_holds(&path, &content),
+            path,
+            content,
+            version: self.version,
+        })
+    }
+
+    /// Take the file `save` was written to as the buffer's own, flagging
+    /// the buffer saved unless it was edited since the save began
+    pub fn finish_save(&mut self, save: &PendingSave) {
+        self.path = Some(save.path.clone());
+        self.name = save
+            .path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("untitled")
+            .to_string();
+        if self.version == save.version {
+            self.mark_saved();
+        } else {
+            self.record_disk_snapshot();
+        }
+        self.record_disk_bytes(&save.content);
+    }
+}
@@ -0,0 +1,186 @@
+//! Text search
+//!
+//! Matches are found one line at a time, so a query never spans lines. A
+//! [`SearchQuery`] is what the search prompt compiles the typed text into,
+//! either a literal substring or a regex; the app keeps it around so every
+//! match in view can be highlighted.
+
+use std::ops::Range;
+
+use regex::Regex;
+
+use super::{Buffer, Position};
+
+/// A query typed into the search prompt, ready to be matched against buffers
+#[derive(Clone, Debug, Default)]
+pub struct SearchQuery {
+    /// The text to look for, or the regex source in regex mode
+    pub pattern: String,
+
+    /// The compiled pattern, when searching with a regex
+    regex: Option<Regex>,
+}
+
+impl PartialEq for SearchQuery {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern && self.is_regex() == other.is_regex()
+    }
+}
+
+impl Eq for SearchQuery {}
+
+impl SearchQuery {
+    /// A query matching `pattern` literally
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            regex: None,
+        }
+    }
+
+    /// A query matching the regex `pattern`, or the compile error
+    pub fn regex(pattern: impl Into<String>) -> Result<Self, regex::Error> {
+        let pattern = pattern.into();
+        let regex = Regex::new(&pattern)?;
+        Ok(Self {
+            pattern,
+            regex: Some(regex),
+        })
+    }
+
+    /// Whether the pattern is a regex rather than literal text
+    pub fn is_regex(&self) -> bool {
+        self.regex.is_some()
+    }
+
+    /// Whether the query matches nothing because it's empty
+    pub fn is_empty(&self) -> bool {
+        self.pattern.is_empty()
+    }
+
+    /// Matches of the query on `rows` of `buffer`
+    pub fn matches_in(&self, buffer: &Buffer, rows: Range<usize>) -> Vec<(Position, Position)> {
+        match &self.regex {
+            Some(regex) if !self.is_empty() => buffer.search_regex_rows(regex, rows),
+            Some(_) => Vec::new(),
+            None => buffer.search_rows(&self.pattern, rows),
+        }
+    }
+
+    /// The first match starting at or after `from`, wrapping around to the
+    /// start of the buffer
+    pub fn next_match(&self, buffer: &Buffer, from: Position) -> Option<(Position, Position)> {
+        let matches = self.matches_in(buffer, 0..buffer.content.len());
+        matches
+            .iter()
+            .find(|(start, _)| *start >= from)
+            .or(matches.first())
+            .copied()
+    }
+}
+
+impl Buffer {
+    /// Every occurrence of `query` as (start, end) positions, in buffer order
+    ///
+    /// Occurrences don't overlap; an empty query matches nothing.
+    pub fn search(&self, query: &str) -> Vec<(Position, Position)> {
+        self.search_rows(query, 0..self.content.len())
+    }
+
+    /// Occurrences of `query` on `rows` only, so a viewport can be searched
+    /// without reading the rest of a large file
+    pub fn search_rows(&self, query: &str, rows: Range<usize>) -> Vec<(Position, Position)> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let first = rows.start;
+        self.content
+            .range(rows)
+            .enumerate()
+            .flat_map(|(i, line)| {
+                line.match_indices(query).map(move |(col, text)| {
+                    (
+                        Position::new(first + i, col),
+                        Position::new(first + i, col + text.len()),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Every match of `re` as (start, end) positions, in buffer order
+    pub fn search_regex(&self, re: &Regex) -> Vec<(Position, Position)> {
+        self.search_regex_rows(re, 0..self.content.len())
+    }
+
+    /// Matches of `re` on `rows` only
+    pub fn search_regex_rows(&self, re: &Regex, rows: Range<usize>) -> Vec<(Position, Position)> {
+        let first = rows.start;
+        self.content
+            .range(rows)
+            .enumerate()
+            .flat_map(|(i, line)| {
+                re.find_iter(line).map(move |found| {
+                    (
+                        Position::new(first + i, found.start()),
+                        Position::new(first + i, found.end()),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Replace every match of `re`, returning how many were replaced
+    ///
+    /// `$1`, `${name}` and the like in `replacement` insert capture groups,
+    /// as in [`Regex::replace_all`].
+    pub fn replace_all_regex(&mut self, re: &Regex, replacement: &str) -> usize {
+        self.replace_regex(re, replacement, 0)
+    }
+
+    /// Replace up to `limit` matches of `re` on every line (0 for all of
+    /// them), returning how many were replaced
+    ///
+    /// Only the lines with a match are loaded for editing, so a lazily opened
+    /// file stays on disk elsewhere.
+    pub fn replace_regex(&mut self, re: &Regex, replacement: &str, limit: usize) -> usize {
+        let mut rows = Vec::new();
+        self.content.scan(|row, line| {
+            if re.is_match(line) {
+                rows.push(row);
+            }
+        });
+
+        let mut replaced = 0;
+        for row in rows {
+            let line = &mut self.content[row];
+            let found = re.find_iter(line).count();
+            replaced += if limit == 0 { found } else { found.min(limit) };
+            *line = re.replacen(line, limit, replacement).into_owned();
+        }
+
+        if replaced > 0 {
+            self.clamp_cursors();
+            self.clear_selection();
+            self.mark_all_changed();
+        }
+        replaced
+    }
+
+    /// Pull the cursors back onto the text after lines changed under them
+    fn clamp_cursors(&mut self) {
+        let clamp = |content: &super::Lines, (row, col): (usize, usize)| {
+            let line = content.get(row).map_or("", |line| line.as_str());
+            let mut col = col.min(line.len());
+            while !line.is_char_boundary(col) {
+                col -= 1;
+            }
+            (row, col)
+        };
+        self.cursor_pos = clamp(&self.content, self.cursor_pos);
+        for cursor in &mut self.secondary_cursors {
+            *cursor = Position::from_tuple(clamp(&self.content, cursor.to_tuple()));
+        }
+    }
+}
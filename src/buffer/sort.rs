@@ -0,0 +1,112 @@
+//! Sorting and deduplicating lines
+//!
+//! Line-wise commands like `sort` and `uniq` work on the lines of a
+//! selection spanning several of them, or on the whole buffer when there's
+//! no such selection. A selection afterwards covers the whole rewritten
+//! block.
+
+use std::ops::Range;
+
+use super::{Buffer, LineChange};
+
+impl Buffer {
+    /// Sort the selected lines, or every line, keeping equal lines in order
+    ///
+    /// Returns false if the lines were already in order.
+    pub fn sort_lines(&mut self, reverse: bool, ignore_case: bool) -> bool {
+        let (rows, selected) = self.linewise_rows();
+        let mut lines: Vec<String> = self.content.range(rows.clone()).cloned().collect();
+        let key = |line: &String| {
+            if ignore_case {
+                line.to_lowercase()
+            } else {
+                line.clone()
+            }
+        };
+        if reverse {
+            lines.sort_by_cached_key(|line| std::cmp::Reverse(key(line)));
+        } else {
+            lines.sort_by_cached_key(key);
+        }
+        if self.content.range(rows.clone()).eq(lines.iter()) {
+            return false;
+        }
+
+        self.content.splice(rows.clone(), lines);
+        self.clear_secondary_cursors();
+        self.finish_linewise_edit(selected, rows.start, rows.len(), |row| row);
+        self.mark_changed(LineChange::rows(rows));
+        true
+    }
+
+    /// Remove lines that repeat the line right before them from the selected
+    /// lines, or from every line, returning how many were removed
+    ///
+    /// A cursor on a removed line moves to the copy that was kept.
+    pub fn dedup_lines(&mut self) -> usize {
+        let (rows, selected) = self.linewise_rows();
+        let mut kept: Vec<String> = Vec::with_capacity(rows.len());
+        // Row each old row ends up on, relative to the start of the block
+        let mut new_rows = Vec::with_capacity(rows.len());
+        for line in self.content.range(rows.clone()) {
+            if kept.last() != Some(line) {
+                kept.push(line.clone());
+            }
+            new_rows.push(kept.len() - 1);
+        }
+        let removed = rows.len() - kept.len();
+        if removed == 0 {
+            return 0;
+        }
+
+        let new_count = kept.len();
+        self.content.splice(rows.clone(), kept);
+        self.clear_secondary_cursors();
+        self.finish_linewise_edit(selected, rows.start, new_count, |row| {
+            if row < rows.start {
+                row
+            } else if row < rows.end {
+                rows.start + new_rows[row - rows.start]
+            } else {
+                row - removed
+            }
+        });
+        self.mark_changed(LineChange::new(rows.start, rows.len(), new_count));
+        removed
+    }
+
+    /// Rows a line-wise command works on: those of a selection spanning
+    /// several lines, or every row; and whether they're the selected ones
+    fn linewise_rows(&self) -> (Range<usize>, bool) {
+        match self.selected_rows() {
+            Some(rows) => (rows, true),
+            None => (0..self.content.len(), false),
+        }
+    }
+
+    /// Select the `count` rewritten lines from `start` if they were the
+    /// selected ones, or move the cursor to the row `new_row` maps its row to
+    fn finish_linewise_edit(
+        &mut self,
+        selected: bool,
+        start: usize,
+        count: usize,
+        new_row: impl Fn(usize) -> usize,
+    ) {
+        if selected && count > 0 {
+            let last = start + count - 1;
+            self.selection_start = Some((start, 0));
+            self.cursor_pos = (last, self.content[last].len());
+            return;
+        }
+
+        self.clear_selection();
+        let row = new_row(self.cursor_pos.0).min(self.content.len().saturating_sub(1));
+        let line = self.content.get(row).map_or("", |line| line.as_str());
+        let mut col = self.cursor_pos.1.min(line.len());
+        while !line.is_char_boundary(col) {
+            col -= 1;
+        }
+        self.cursor_pos = (row, col);
+    }
+}
@@ -0,0 +1,103 @@
+//! Line, word, char and byte counts
+//!
+//! Counted like `wc` would: words are runs of non-whitespace, and a final
+//! line without a newline still counts as a line.
+
+use super::{Buffer, LineChange};
+
+/// Counts of the text in a buffer or selection
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BufferStats {
+    pub lines: usize,
+    pub words: usize,
+    pub chars: usize,
+    pub bytes: usize,
+}
+
+impl BufferStats {
+    /// Count the lines, words, chars and bytes of `text`
+    pub fn of(text: &str) -> Self {
+        Self {
+            lines: text.lines().count(),
+            words: text.split_whitespace().count(),
+            chars: text.chars().count(),
+            bytes: text.len(),
+        }
+    }
+}
+
+impl Buffer {
+    /// Counts of the selected text, or of the whole buffer when nothing is
+    /// selected
+    pub fn stats(&self) -> BufferStats {
+        match self.get_selected_text() {
+            Some(text) => BufferStats::of(&text),
+            None => BufferStats::of(&self.content_as_string()),
+        }
+    }
+}
+
+/// Running word and char counts of a buffer, kept per line so an edit only
+/// recounts the lines it touched
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WordCount {
+    /// Id of the buffer counted
+    pub buffer_id: usize,
+
+    /// Words and chars of each line
+    lines: Vec<(usize, usize)>,
+
+    words: usize,
+    chars: usize,
+}
+
+impl WordCount {
+    /// Count every line of `buffer`
+    pub fn of(buffer: &Buffer) -> Self {
+        let lines: Vec<(usize, usize)> = buffer.content.iter().map(|l| line_counts(l)).collect();
+        Self {
+            buffer_id: buffer.id,
+            words: lines.iter().map(|&(words, _)| words).sum(),
+            chars: lines.iter().map(|&(_, chars)| chars).sum(),
+            lines,
+        }
+    }
+
+    /// Recount the lines of `buffer` that `change` replaced, returning false
+    /// if the counts don't line up with the buffer anymore
+    pub fn update(&mut self, buffer: &Buffer, change: LineChange) -> bool {
+        let old = change.start..change.start + change.old_count;
+        if buffer.id != self.buffer_id || old.end > self.lines.len() {
+            return false;
+        }
+        let new: Vec<(usize, usize)> = buffer
+            .content
+            .range(change.lines())
+            .map(|line| line_counts(line))
+            .collect();
+        for (words, chars) in self.lines.splice(old, new.iter().copied()) {
+            self.words -= words;
+            self.chars -= chars;
+        }
+        for (words, chars) in new {
+            self.words += words;
+            self.chars += chars;
+        }
+        self.lines.len() == buffer.content.len()
+    }
+
+    /// Words in the buffer
+    pub fn words(&self) -> usize {
+        self.words
+    }
+
+    /// Chars in the buffer, counting the line breaks between lines
+    pub fn chars(&self) -> usize {
+        self.chars + self.lines.len().saturating_sub(1)
+    }
+}
+
+/// Words and chars of a single line
+fn line_counts(line: &str) -> (usize, usize) {
+    (line.split_whitespace().count(), line.chars().count())
+}
@@ -0,0 +1,102 @@
+//! Rewriting the selected text in place
+//!
+//! `transform_selection` replaces the selection with whatever a function
+//! makes of it, or the word at the cursor when nothing is selected. The
+//! selection keeps covering the rewritten text even when its length changes,
+//! as it can with case mapping ("ß" uppercases to "SS"). The `upper`,
+//! `lower` and `togglecase` commands are built on it.
+
+use super::{Buffer, LineChange, Position, SelectionMode};
+
+/// Swap the case of every char, using the full Unicode case mappings
+pub fn toggle_case(text: &str) -> String {
+    let mut toggled = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_uppercase() {
+            toggled.extend(c.to_lowercase());
+        } else {
+            toggled.extend(c.to_uppercase());
+        }
+    }
+    toggled
+}
+
+impl Buffer {
+    /// Replace the selected text, or the word at the cursor if nothing is
+    /// selected, with `transform` of it
+    ///
+    /// The selection stays on the new text, and without one the cursor stays
+    /// at the same offset into the word. Block selections aren't
+    /// transformed. Returns false if there's nothing to transform or the
+    /// text came out the same.
+    pub fn transform_selection(&mut self, transform: impl Fn(&str) -> String) -> bool {
+        if self.selection_mode == SelectionMode::Block {
+            return false;
+        }
+        let (row, col) = self.cursor_pos;
+        let (start, end, old) = match self.get_selection_range() {
+            Some((start, end)) => match self.get_selected_text() {
+                Some(text) => (start, end, text),
+                None => return false,
+            },
+            None => match self.word_at(Position::new(row, col)) {
+                Some(word) => (
+                    Position::new(row, word.start),
+                    Position::new(row, word.end),
+                    self.content[row][word].to_string(),
+                ),
+                None => return false,
+            },
+        };
+        if start.row >= self.content.len() || end.row >= self.content.len() {
+            return false;
+        }
+        let new = transform(&old);
+        if new == old {
+            return false;
+        }
+
+        let new_end = self.replace_text(start, end, &new);
+        match self.selection_start {
+            Some(anchor) if Position::from_tuple(anchor) == start => {
+                self.cursor_pos = new_end.to_tuple();
+            }
+            Some(_) => {
+                self.selection_start = Some(new_end.to_tuple());
+                self.cursor_pos = start.to_tuple();
+            }
+            None => {
+                let line = &self.content[row];
+                let mut col = col.min(new_end.col);
+                while !line.is_char_boundary(col) {
+                    col -= 1;
+                }
+                self.cursor_pos.1 = col;
+            }
+        }
+        true
+    }
+
+    /// Replace the text from `start` to `end` with `text`, returning where
+    /// the new text ends
+    fn replace_text(&mut self, start: Position, end: Position, text: &str) -> Position {
+        let end_col = end.col.min(self.content[end.row].len());
+        let head = self.content[start.row][..start.col].to_string();
+        let tail = self.content[end.row][end_col..].to_string();
+
+        let mut lines: Vec<String> = text.split('\n').map(str::to_owned).collect();
+        let last = lines.len() - 1;
+        let new_end = if last == 0 {
+            Position::new(start.row, start.col + lines[0].len())
+        } else {
+            Position::new(start.row + last, lines[last].len())
+        };
+        lines[0].insert_str(0, &head);
+        lines[last].push_str(&tail);
+
+        let old_count = end.row - start.row + 1;
+        self.content.splice(start.row..end.row + 1, lines);
+        self.mark_changed(LineChange::new(start.row, old_count, last + 1));
+        new_end
+    }
+}
@@ -0,0 +1,134 @@
+//! Screen columns of lines, and soft-wrapping them to the editor's width
+//!
+//! Every char is measured by [`char_width`]: a tab reaches the next tab stop
+//! of the line, a wide char like `日` takes two columns, and other chars
+//! without a width, like control chars, take one. Drawing, wrapping, placing
+//! the cursor and mapping clicks all measure with it, so they agree on where
+//! each char is. A wrapped row keeps the line's tab stops, and a char that
+//! doesn't fit what's left of a row moves to the next one whole.
+
+use std::ops::Range;
+
+use unicode_width::UnicodeWidthChar;
+
+use super::Buffer;
+
+/// Screen columns taken by `ch` when it starts at visual column `col`
+///
+/// Tabs stretch to the next multiple of `tab_size`; other chars take their
+/// display width, or one column if they have none.
+pub fn char_width(ch: char, col: usize, tab_size: usize) -> usize {
+    if ch == '\t' {
+        let tab_size = tab_size.max(1);
+        tab_size - col % tab_size
+    } else {
+        ch.width().unwrap_or(1)
+    }
+}
+
+/// Visual column of the byte offset `byte_col` in `line`
+pub fn visual_col(line: &str, byte_col: usize, tab_size: usize) -> usize {
+    line.char_indices()
+        .take_while(|&(i, _)| i < byte_col)
+        .fold(0, |col, (_, ch)| col + char_width(ch, col, tab_size))
+}
+
+/// Byte offset of the char drawn at visual column `x` of `line`
+///
+/// A column inside an expanded tab or a wide char maps to that char. Columns
+/// past the end of the line map to the line's length.
+pub fn byte_col_at_visual(line: &str, x: usize, tab_size: usize) -> usize {
+    let mut col = 0;
+    for (i, ch) in line.char_indices() {
+        col += char_width(ch, col, tab_size);
+        if x < col {
+            return i;
+        }
+    }
+    line.len()
+}
+
+/// Split a line into byte ranges at most `width` columns wide, one per
+/// wrapped screen row
+///
+/// A line that exactly fills its last row gets a trailing empty range, so
+/// the cursor after its last char has somewhere to go.
+pub fn wrap_ranges(line: &str, width: usize, tab_size: usize) -> Vec<Range<usize>> {
+    let width = width.max(1);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+    let mut col = 0;
+    for (i, c) in line.char_indices() {
+        let char_width = char_width(c, col, tab_size);
+        if used > 0 && used + char_width > width {
+            ranges.push(start..i);
+            start = i;
+            used = 0;
+        }
+        used += char_width;
+        col += char_width;
+    }
+    if used >= width {
+        ranges.push(start..line.len());
+        start = line.len();
+    }
+    ranges.push(start..line.len());
+    ranges
+}
+
+impl Buffer {
+    /// Number of screen rows a line takes when soft-wrapped to `width` columns
+    pub fn visual_rows(&self, row: usize, width: usize, tab_size: usize) -> usize {
+        let line = self.content.get(row).map_or("", |line| line.as_str());
+        wrap_ranges(line, width, tab_size).len()
+    }
+
+    /// Screen (row within the line, column) of a byte position in a soft-wrapped line
+    pub fn wrapped_position(
+        &self,
+        row: usize,
+        col: usize,
+        width: usize,
+        tab_size: usize,
+    ) -> (usize, usize) {
+        let line = self.content.get(row).map_or("", |line| line.as_str());
+        let col = col.min(line.len());
+        let ranges = wrap_ranges(line, width, tab_size);
+        let visual_row = ranges
+            .iter()
+            .rposition(|range| range.start <= col)
+            .unwrap_or(0);
+        let row_start = visual_col(line, ranges[visual_row].start, tab_size);
+        (visual_row, visual_col(line, col, tab_size) - row_start)
+    }
+
+    /// Byte column at a screen position in a soft-wrapped line, clamped to
+    /// the line end
+    ///
+    /// A position past the end of a row gives the start of the next one.
+    pub fn col_at_wrapped_position(
+        &self,
+        row: usize,
+        visual_row: usize,
+        x: usize,
+        width: usize,
+        tab_size: usize,
+    ) -> usize {
+        let Some(line) = self.content.get(row) else {
+            return 0;
+        };
+        let Some(range) = wrap_ranges(line, width, tab_size).get(visual_row).cloned() else {
+            return line.len();
+        };
+        let row_start = visual_col(line, range.start, tab_size);
+        let mut col = row_start;
+        for (i, c) in line[range.clone()].char_indices() {
+            col += char_width(c, col, tab_size);
+            if col > row_start + x {
+                return range.start + i;
+            }
+        }
+        range.end
+    }
+}
@@ -0,0 +1,82 @@
+//! Soft limit on the number of open buffers
+//!
+//! Adding a buffer past `max_open_buffers` doesn't fail. Instead a warning
+//! toast suggests closing some, or with `close_lru` set the least recently
+//! used buffer is closed to make room, provided it has no unsaved changes and
+//! isn't shown in a pane. Buffers count as used when they're added, switched
+//! to, focused in a pane or left active by closing another.
+
+use crate::App;
+
+impl App {
+    /// Note the buffer at `index` as the most recently used one
+    pub(crate) fn note_buffer_used(&mut self, index: usize) {
+        let Some(id) = self.buffers.get(index).map(|buffer| buffer.id) else {
+            return;
+        };
+        self.buffer_use.retain(|&used| used != id);
+        self.buffer_use.insert(0, id);
+    }
+
+    /// Close the least recently used buffer or warn if more buffers are open
+    /// than `max_open_buffers` allows
+    pub(crate) fn enforce_buffer_limit(&mut self) {
+        let limit = self.config.editor.max_open_buffers;
+        if limit == 0 || self.buffers.len() <= limit {
+            return;
+        }
+
+        if self.config.editor.close_lru {
+            if let Some(index) = self.least_recently_used_buffer() {
+                let name = self.buffers[index].name.clone();
+                if let Err(e) = self.remember_cursor_position(index) {
+                    self.toast_manager
+                        .add_warning(format!("Could not save cursor positions: {}", e));
+                }
+                self.remove_inactive_buffer(index);
+                self.toast_manager.add_info(format!(
+                    "Closed {} to stay within {} open buffers",
+                    name, limit
+                ));
+                return;
+            }
+        }
+
+        self.toast_manager.add_warning(format!(
+            "{} buffers open, more than the {} configured; consider closing some",
+            self.buffers.len(),
+            limit
+        ));
+    }
+
+    /// Index of the buffer used longest ago that can be closed without
+    /// losing anything: not active, not shown in a pane and without unsaved
+    /// changes
+    fn least_recently_used_buffer(&self) -> Option<usize> {
+        self.buffers
+            .iter()
+            .enumerate()
+            .filter(|&(index, buffer)| {
+                index != self.active_buffer
+                    && !buffer.modified
+                    && !self.panes.iter().any(|pane| pane.buffer == index)
+            })
+            // Buffers never used count as the oldest
+            .max_by_key(|&(index, buffer)| {
+                let age = self.buffer_use.iter().position(|&id| id == buffer.id);
+                (age.unwrap_or(usize::MAX), std::cmp::Reverse(index))
+            })
+            .map(|(index, _)| index)
+    }
+
+    /// Remove a buffer other than the active one, keeping the same buffer
+    /// active
+    fn remove_inactive_buffer(&mut self, index: usize) {
+        let id = self.buffers.remove(index).id;
+        self.buffer_use.retain(|&used| used != id);
+        if self.active_buffer > index {
+            self.active_buffer -= 1;
+        }
+        self.remove_buffer_from_panes(index);
+    }
+}
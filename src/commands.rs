@@ -0,0 +1,355 @@
+//! Commands the palette runs
+//!
+//! A [`CommandRegistry`] maps the names and aliases of commands to their
+//! handlers, the built-in ones and any plugins add alike. Handlers run on a
+//! [`CommandContext`], which the input handling provides, so the registry
+//! doesn't depend on it. The fuzzy matching
+//! here turns what's been typed into ranked suggestions of their names. While
+//! the input is a `search_all_buffers` command, the suggestions are its
+//! matches instead, listed under the name of the buffer they're in.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::buffer::{Buffer, Position};
+use crate::plugins::PluginCommand;
+
+/// Most suggestions shown under the palette input
+pub const MAX_SUGGESTIONS: usize = 8;
+
+/// Most rows of `search_all_buffers` matches listed under the palette input,
+/// counting the header naming each buffer
+pub const MAX_BUFFER_MATCHES: usize = 12;
+
+/// Command that searches every open buffer, listing the matches as it's typed
+pub const SEARCH_ALL_BUFFERS: &str = "search_all_buffers";
+
+/// What a command is run with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandCall {
+    /// The command as typed, arguments included
+    pub input: String,
+
+    /// The name or alias it was run by
+    pub name: String,
+
+    /// The words after the name
+    pub args: Vec<String>,
+}
+
+impl CommandCall {
+    /// Split typed `input` into the name and arguments of a command
+    pub fn parse(input: &str) -> Self {
+        let mut words = input.split_whitespace().map(str::to_owned);
+        Self {
+            input: input.to_string(),
+            name: words.next().unwrap_or_default(),
+            args: words.collect(),
+        }
+    }
+}
+
+/// Future of a running command
+pub type CommandFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+/// Runs a command on the context `C` it was typed into
+pub type CommandHandler<C> = Arc<dyn Fn(C, CommandCall) -> CommandFuture + Send + Sync>;
+
+/// What commands are run on
+///
+/// Built-in commands can use all of the implementing type; plugin commands
+/// only need it to run them on the editor.
+pub trait CommandContext: Clone + Send + Sync + 'static {
+    /// Run a plugin command on the editor, with `args` as its arguments
+    fn run_plugin_command(
+        &self,
+        command: Arc<dyn PluginCommand>,
+        args: Vec<String>,
+    ) -> CommandFuture;
+}
+
+/// A command in the registry
+pub struct RegisteredCommand<C> {
+    name: String,
+    aliases: Vec<String>,
+
+    /// What the command does, shown in the help
+    description: String,
+
+    /// Keys that run the command by default, shown in the help
+    keys: Vec<String>,
+
+    handler: CommandHandler<C>,
+}
+
+impl<C> Clone for RegisteredCommand<C> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            aliases: self.aliases.clone(),
+            description: self.description.clone(),
+            keys: self.keys.clone(),
+            handler: self.handler.clone(),
+        }
+    }
+}
+
+impl<C> RegisteredCommand<C> {
+    /// Describe what the command does
+    pub fn describe(&mut self, description: &str) -> &mut Self {
+        self.description = description.to_string();
+        self
+    }
+
+    /// Note the keys that run the command by default, like `Ctrl+S`
+    pub fn keys(&mut self, keys: &[&str]) -> &mut Self {
+        self.keys = keys.iter().map(|key| key.to_string()).collect();
+        self
+    }
+
+    /// Full name of the command
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Other names the command runs by
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    /// What the command does, empty if it wasn't described
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Keys that run the command by default
+    pub fn default_keys(&self) -> &[String] {
+        &self.keys
+    }
+}
+
+/// Commands the palette can run, by name and alias
+///
+/// Built-in commands are registered when the app starts, and plugins add
+/// theirs the same way. The palette suggests commands by their full names,
+/// in the order they were registered.
+pub struct CommandRegistry<C> {
+    commands: Vec<RegisteredCommand<C>>,
+
+    /// Index into `commands` of every name and alias
+    lookup: HashMap<String, usize>,
+}
+
+impl<C> Clone for CommandRegistry<C> {
+    fn clone(&self) -> Self {
+        Self {
+            commands: self.commands.clone(),
+            lookup: self.lookup.clone(),
+        }
+    }
+}
+
+impl<C> Default for CommandRegistry<C> {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            lookup: HashMap::new(),
+        }
+    }
+}
+
+impl<C> CommandRegistry<C> {
+    /// Run `handler` for the command `name` and each of its `aliases`,
+    /// returning the command to describe it further
+    ///
+    /// Registering a name again replaces the command. Names and aliases of
+    /// an earlier command are taken over.
+    pub fn register<F, Fut>(
+        &mut self,
+        name: &str,
+        aliases: &[&str],
+        handler: F,
+    ) -> &mut RegisteredCommand<C>
+    where
+        F: Fn(C, CommandCall) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let command = RegisteredCommand {
+            name: name.to_string(),
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+            description: String::new(),
+            keys: Vec::new(),
+            handler: Arc::new(move |context, call| Box::pin(handler(context, call))),
+        };
+        let index = match self.commands.iter().position(|c| c.name == name) {
+            Some(index) => {
+                self.commands[index] = command;
+                index
+            }
+            None => {
+                self.commands.push(command);
+                self.commands.len() - 1
+            }
+        };
+        for key in std::iter::once(name).chain(aliases.iter().copied()) {
+            self.lookup.insert(key.to_string(), index);
+        }
+        &mut self.commands[index]
+    }
+
+    /// Handler of the command called `name`, or with `name` as an alias
+    pub fn get(&self, name: &str) -> Option<CommandHandler<C>> {
+        let index = *self.lookup.get(name)?;
+        Some(self.commands[index].handler.clone())
+    }
+
+    /// Full names of the commands, in the order they were registered
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.commands.iter().map(|command| command.name.as_str())
+    }
+
+    /// The commands, in the order they were registered
+    pub fn commands(&self) -> impl Iterator<Item = &RegisteredCommand<C>> {
+        self.commands.iter()
+    }
+}
+
+impl<C: CommandContext> CommandRegistry<C> {
+    /// Register a plugin command under its own name and `aliases`
+    ///
+    /// It runs on an [`EditorContext`](crate::plugins::EditorContext) for the
+    /// editor, with the words after the name as its arguments.
+    pub fn register_plugin_command(&mut self, command: Arc<dyn PluginCommand>, aliases: &[&str]) {
+        let name = command.name().to_string();
+        let description = command.description().to_string();
+        self.register(&name, aliases, move |context: C, call| {
+            context.run_plugin_command(command.clone(), call.args)
+        })
+        .describe(&description);
+    }
+}
+
+/// How well `query` fuzzy-matches `candidate`, or `None` if it doesn't
+///
+/// Every char of the query has to appear in the candidate in order, ignoring
+/// case. Chars that follow the previous match directly, or start the
+/// candidate or a `_`-separated word in it, score higher.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<usize> {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+    let mut chars = candidate.char_indices().peekable();
+    for wanted in query.chars().flat_map(char::to_lowercase) {
+        let (index, _) = chars.find(|(_, c)| c.to_lowercase().eq([wanted]))?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == index) {
+            score += 5;
+        }
+        if index == 0 || candidate[..index].ends_with('_') {
+            score += 10;
+        }
+        previous = Some(index);
+    }
+    Some(score)
+}
+
+/// Commands out of `names` matching `input`, best first
+///
+/// Only the command name is matched, so nothing is suggested once the input
+/// has arguments, or while it's empty. Equal scores go to the shorter name.
+pub fn suggest_commands<'a>(names: impl IntoIterator<Item = &'a str>, input: &str) -> Vec<String> {
+    if input.is_empty() || input.contains(char::is_whitespace) {
+        return Vec::new();
+    }
+
+    let mut ranked: Vec<(usize, &str)> = names
+        .into_iter()
+        .filter_map(|name| Some((fuzzy_score(input, name)?, name)))
+        .collect();
+    ranked.sort_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .cmp(a_score)
+            .then(a.len().cmp(&b.len()))
+            .then(a.cmp(b))
+    });
+    ranked
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// A match found by `search_all_buffers`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferMatch {
+    /// Index of the buffer the match is in
+    pub buffer: usize,
+
+    /// Where the match starts
+    pub position: Position,
+
+    /// What the palette lists under the buffer's name: line number and the
+    /// line's text
+    pub label: String,
+}
+
+/// The query typed after `search_all_buffers`, if there is one yet
+pub fn search_all_buffers_query(input: &str) -> Option<&str> {
+    let query = input
+        .strip_prefix(SEARCH_ALL_BUFFERS)?
+        .strip_prefix(char::is_whitespace)?
+        .trim_start();
+    (!query.is_empty()).then_some(query)
+}
+
+/// Matches of `query` in every buffer, grouped by buffer in tab order
+///
+/// Buffers without a match are left out. The search stops once the matches
+/// and their buffers' headers fill `MAX_BUFFER_MATCHES` rows, so it doesn't
+/// read every line of every buffer on each key typed.
+pub fn search_all_buffers(buffers: &[Buffer], query: &str) -> Vec<BufferMatch> {
+    let mut matches = Vec::new();
+    if query.is_empty() {
+        return matches;
+    }
+
+    let mut rows = 0;
+    for (index, buffer) in buffers.iter().enumerate() {
+        let mut header = 1;
+        for (row, line) in buffer.content.iter().enumerate() {
+            for (col, _) in line.match_indices(query) {
+                rows += 1 + std::mem::take(&mut header);
+                if rows > MAX_BUFFER_MATCHES {
+                    return matches;
+                }
+                matches.push(BufferMatch {
+                    buffer: index,
+                    position: Position::new(row, col),
+                    label: format!("{}: {}", row + 1, line.trim()),
+                });
+            }
+        }
+    }
+    matches
+}
+
+/// Rows the palette lists for `matches`: the name of each buffer, then its
+/// matches indented below, along with the row each match is on
+pub fn buffer_match_rows(buffers: &[Buffer], matches: &[BufferMatch]) -> (Vec<String>, Vec<usize>) {
+    let mut rows = Vec::new();
+    let mut match_rows = Vec::new();
+    for (i, found) in matches.iter().enumerate() {
+        if i == 0 || matches[i - 1].buffer != found.buffer {
+            let name = buffers
+                .get(found.buffer)
+                .map_or("", |buffer| buffer.name.as_str());
+            rows.push(name.to_string());
+        }
+        match_rows.push(rows.len());
+        rows.push(format!("  {}", found.label));
+    }
+    (rows, match_rows)
+}
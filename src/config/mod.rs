@@ -32,7 +32,8 @@ use std::path::{Path, PathBuf};
 
 pub use clipboard::{ClipboardContext, ClipboardProvider};
 
-use anyhow::{anyhow, Result};
+pub use crate::error::ConfigError;
+
 use serde::{Deserialize, Serialize};
 
 /// Editor configuration
@@ -46,7 +47,7 @@ pub struct Config {
     #[serde(default)]
     pub ui: UiConfig,
 
-    /// Keybindings
+    /// Key sequences bound to palette commands, like `"space f": "open "`
     #[serde(default)]
     pub keybindings: HashMap<String, String>,
 
@@ -56,12 +57,17 @@ pub struct Config {
 }
 
 /// Editor settings
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct EditorConfig {
     /// Tab size
     #[serde(default = "default_tab_size")]
     pub tab_size: usize,
 
+    /// Columns a tab char is drawn as, if different from `tab_size`. Only
+    /// changes how tabs look, not how much indenting inserts
+    #[serde(default)]
+    pub tab_display_width: Option<usize>,
+
     /// Use spaces instead of tabs
     #[serde(default = "default_use_spaces")]
     pub use_spaces: bool,
@@ -70,14 +76,55 @@ pub struct EditorConfig {
     #[serde(default = "default_show_line_numbers")]
     pub show_line_numbers: bool,
 
+    /// Number lines by their distance from the cursor line, which keeps its
+    /// absolute number. Turns the gutter on even if `show_line_numbers` is off
+    #[serde(default = "default_relative_line_numbers")]
+    pub relative_line_numbers: bool,
+
+    /// Spaces between the line numbers and the text, if different from the
+    /// theme's `gutter_gap`
+    #[serde(default)]
+    pub gutter_gap: Option<u16>,
+
     /// Highlight current line
     #[serde(default = "default_highlight_current_line")]
     pub highlight_current_line: bool,
 
+    /// Draw spaces and tabs as visible glyphs
+    #[serde(default = "default_render_whitespace")]
+    pub render_whitespace: bool,
+
     /// Word wrap
     #[serde(default = "default_word_wrap")]
     pub word_wrap: bool,
 
+    /// Strip trailing spaces and tabs from every line when saving
+    #[serde(default = "default_trim_trailing_whitespace")]
+    pub trim_trailing_whitespace: bool,
+
+    /// End the last line with a newline when saving; files that already
+    /// ended with one keep it either way
+    #[serde(default = "default_insert_final_newline")]
+    pub insert_final_newline: bool,
+
+    /// Strip trailing spaces and tabs from a line when Enter moves off it
+    #[serde(default = "default_trim_on_newline")]
+    pub trim_on_newline: bool,
+
+    /// Buffers that can be open before adding another warns; 0 for no limit
+    #[serde(default = "default_max_open_buffers")]
+    pub max_open_buffers: usize,
+
+    /// Close the least recently used buffer, if it has no unsaved changes,
+    /// instead of warning when `max_open_buffers` is exceeded
+    #[serde(default = "default_close_lru")]
+    pub close_lru: bool,
+
+    /// Close brackets and quotes as they're typed, and wrap the selection in
+    /// them
+    #[serde(default = "default_auto_pairs")]
+    pub auto_pairs: bool,
+
     /// Auto save
     #[serde(default = "default_auto_save")]
     pub auto_save: bool,
@@ -85,6 +132,16 @@ pub struct EditorConfig {
     /// Auto save delay in milliseconds
     #[serde(default = "default_auto_save_delay")]
     pub auto_save_delay: u64,
+
+    /// Lines the view can scroll past the end of the buffer, so the last
+    /// lines don't have to sit at the bottom of the screen
+    #[serde(default = "default_scroll_past_end")]
+    pub scroll_past_end: usize,
+
+    /// Lines kept visible above and below the cursor when scrolling, 0 to let
+    /// the cursor reach the edges. Capped at half the view's height
+    #[serde(default = "default_scroll_off")]
+    pub scroll_off: usize,
 }
 
 /// UI settings
@@ -113,6 +170,70 @@ pub struct UiConfig {
     /// Show minimap
     #[serde(default = "default_show_minimap")]
     pub show_minimap: bool,
+
+    /// Show a vertical scrollbar on the right edge of the editor
+    #[serde(default = "default_show_scrollbar")]
+    pub show_scrollbar: bool,
+
+    /// Show a tab bar listing the open buffers above the editor
+    #[serde(default = "default_show_tab_bar")]
+    pub show_tab_bar: bool,
+
+    /// Screen corner toasts stack up from
+    #[serde(default)]
+    pub toast_position: ToastPosition,
+
+    /// Most toasts shown at once; older ones are dropped to make room
+    #[serde(default = "default_max_toasts")]
+    pub max_toasts: usize,
+
+    /// Lines scrolled by one mouse wheel tick, up or down
+    #[serde(default = "default_scroll_lines_per_tick")]
+    pub scroll_lines_per_tick: usize,
+
+    /// Lines scrolled each time a drag selection reaches the top or bottom
+    /// of the editor; 0 turns auto-scrolling off
+    #[serde(default = "default_drag_scroll_lines")]
+    pub drag_scroll_lines: usize,
+
+    /// Blink the cursor while idle; off keeps it solid
+    #[serde(default = "default_cursor_blink_enabled")]
+    pub cursor_blink_enabled: bool,
+
+    /// Milliseconds between cursor blinks
+    #[serde(default = "default_cursor_blink_ms")]
+    pub cursor_blink_ms: u64,
+
+    /// Key that opens the command palette, named as in `keybindings`
+    #[serde(default = "default_command_palette_key")]
+    pub command_palette_key: String,
+
+    /// Where on the screen the command palette is shown
+    #[serde(default)]
+    pub command_palette_position: PalettePosition,
+}
+
+/// Screen corner toast notifications are shown in
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ToastPosition {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Height the command palette is shown at
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PalettePosition {
+    /// In the upper third of the screen
+    #[default]
+    Top,
+
+    /// Halfway down the screen
+    Center,
 }
 
 // Default values
@@ -125,18 +246,48 @@ fn default_use_spaces() -> bool {
 fn default_show_line_numbers() -> bool {
     true
 }
+fn default_relative_line_numbers() -> bool {
+    false
+}
 fn default_highlight_current_line() -> bool {
     true
 }
+fn default_render_whitespace() -> bool {
+    false
+}
 fn default_word_wrap() -> bool {
     false
 }
+fn default_trim_trailing_whitespace() -> bool {
+    false
+}
+fn default_insert_final_newline() -> bool {
+    true
+}
+fn default_trim_on_newline() -> bool {
+    false
+}
+fn default_max_open_buffers() -> usize {
+    20
+}
+fn default_close_lru() -> bool {
+    false
+}
+fn default_auto_pairs() -> bool {
+    true
+}
 fn default_auto_save() -> bool {
     false
 }
 fn default_auto_save_delay() -> u64 {
     1000
 }
+fn default_scroll_past_end() -> usize {
+    10
+}
+fn default_scroll_off() -> usize {
+    3
+}
 fn default_theme() -> String {
     "default".to_string()
 }
@@ -155,21 +306,64 @@ fn default_show_menu_bar() -> bool {
 fn default_show_minimap() -> bool {
     false
 }
+fn default_show_scrollbar() -> bool {
+    false
+}
+fn default_show_tab_bar() -> bool {
+    false
+}
+fn default_max_toasts() -> usize {
+    5
+}
+fn default_scroll_lines_per_tick() -> usize {
+    3
+}
+fn default_drag_scroll_lines() -> usize {
+    1
+}
+fn default_cursor_blink_enabled() -> bool {
+    true
+}
+fn default_cursor_blink_ms() -> u64 {
+    500
+}
+fn default_command_palette_key() -> String {
+    "alt+p".to_string()
+}
 
 impl Default for EditorConfig {
     fn default() -> Self {
         Self {
             tab_size: default_tab_size(),
+            tab_display_width: None,
             use_spaces: default_use_spaces(),
             show_line_numbers: default_show_line_numbers(),
+            relative_line_numbers: default_relative_line_numbers(),
+            gutter_gap: None,
             highlight_current_line: default_highlight_current_line(),
+            render_whitespace: default_render_whitespace(),
             word_wrap: default_word_wrap(),
+            trim_trailing_whitespace: default_trim_trailing_whitespace(),
+            insert_final_newline: default_insert_final_newline(),
+            trim_on_newline: default_trim_on_newline(),
+            max_open_buffers: default_max_open_buffers(),
+            close_lru: default_close_lru(),
+            auto_pairs: default_auto_pairs(),
             auto_save: default_auto_save(),
             auto_save_delay: default_auto_save_delay(),
+            scroll_past_end: default_scroll_past_end(),
+            scroll_off: default_scroll_off(),
         }
     }
 }
 
+impl EditorConfig {
+    /// Columns between the tab stops tabs are drawn to
+    pub fn display_tab_width(&self) -> usize {
+        self.tab_display_width.unwrap_or(self.tab_size).max(1)
+    }
+}
+
 impl Default for UiConfig {
     fn default() -> Self {
         Self {
@@ -179,6 +373,16 @@ impl Default for UiConfig {
             show_status_bar: default_show_status_bar(),
             show_menu_bar: default_show_menu_bar(),
             show_minimap: default_show_minimap(),
+            show_scrollbar: default_show_scrollbar(),
+            show_tab_bar: default_show_tab_bar(),
+            toast_position: ToastPosition::default(),
+            max_toasts: default_max_toasts(),
+            scroll_lines_per_tick: default_scroll_lines_per_tick(),
+            drag_scroll_lines: default_drag_scroll_lines(),
+            cursor_blink_enabled: default_cursor_blink_enabled(),
+            cursor_blink_ms: default_cursor_blink_ms(),
+            command_palette_key: default_command_palette_key(),
+            command_palette_position: PalettePosition::default(),
         }
     }
 }
@@ -204,29 +408,49 @@ impl ConfigManager {
     }
 
     /// Load the config
-    pub fn load(&mut self) -> Result<()> {
+    pub fn load(&mut self) -> Result<(), ConfigError> {
         // Create config directory if it doesn't exist
         if let Some(parent) = self.config_path.parent() {
             if !parent.exists() {
-                fs::create_dir_all(parent)?;
+                fs::create_dir_all(parent).map_err(|source| ConfigError::Io {
+                    path: parent.to_path_buf(),
+                    source,
+                })?;
             }
         }
 
         // Load config if it exists, otherwise use defaults
         if self.config_path.exists() {
-            let config_str = fs::read_to_string(&self.config_path)?;
-            self.config = serde_json::from_str(&config_str)
-                .map_err(|e| anyhow!("Failed to parse config: {}", e))?;
+            let config_str =
+                fs::read_to_string(&self.config_path).map_err(|source| ConfigError::Io {
+                    path: self.config_path.clone(),
+                    source,
+                })?;
+            self.config =
+                serde_json::from_str(&config_str).map_err(|source| ConfigError::Parse {
+                    path: self.config_path.clone(),
+                    source,
+                })?;
         }
 
         Ok(())
     }
 
     /// Save the config
-    pub fn save(&self) -> Result<()> {
-        let config_str = serde_json::to_string_pretty(&self.config)?;
-        fs::write(&self.config_path, config_str)?;
-        Ok(())
+    pub fn save(&self) -> Result<(), ConfigError> {
+        let config_str =
+            serde_json::to_string_pretty(&self.config).map_err(ConfigError::Serialize)?;
+        fs::write(&self.config_path, config_str).map_err(|source| ConfigError::Io {
+            path: self.config_path.clone(),
+            source,
+        })
+    }
+
+    /// Write `config` to the config file in `config_dir`
+    pub fn save_config(config_dir: &Path, config: Config) -> Result<(), ConfigError> {
+        let mut config_manager = Self::new(config_dir);
+        config_manager.config = config;
+        config_manager.save()
     }
 
     /// Get the config
@@ -240,51 +464,187 @@ impl ConfigManager {
     }
 
     /// Update a setting
-    pub fn update_setting(&mut self, path: &str, value: serde_json::Value) -> Result<()> {
+    pub fn update_setting(
+        &mut self,
+        path: &str,
+        value: serde_json::Value,
+    ) -> Result<(), ConfigError> {
         // Handle simple cases for common settings
         match path {
             "editor.tabSize" => {
-                self.config.editor.tab_size =
-                    value.as_u64().ok_or_else(|| anyhow!("Expected number"))? as usize;
+                self.config.editor.tab_size = value
+                    .as_u64()
+                    .ok_or_else(|| invalid_value(path, "number"))?
+                    as usize;
+            }
+            "editor.tabDisplayWidth" => {
+                self.config.editor.tab_display_width = match value {
+                    serde_json::Value::Null => None,
+                    value => Some(
+                        value
+                            .as_u64()
+                            .ok_or_else(|| invalid_value(path, "number or null"))?
+                            as usize,
+                    ),
+                };
             }
             "editor.useSpaces" => {
-                self.config.editor.use_spaces =
-                    value.as_bool().ok_or_else(|| anyhow!("Expected boolean"))?;
+                self.config.editor.use_spaces = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
             }
             "editor.showLineNumbers" => {
-                self.config.editor.show_line_numbers =
-                    value.as_bool().ok_or_else(|| anyhow!("Expected boolean"))?;
+                self.config.editor.show_line_numbers = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
+            }
+            "editor.relativeLineNumbers" => {
+                self.config.editor.relative_line_numbers = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
+            }
+            "editor.gutterGap" => {
+                self.config.editor.gutter_gap = match value {
+                    serde_json::Value::Null => None,
+                    value => Some(
+                        value
+                            .as_u64()
+                            .and_then(|gap| u16::try_from(gap).ok())
+                            .ok_or_else(|| invalid_value(path, "number or null"))?,
+                    ),
+                };
             }
             "editor.highlightCurrentLine" => {
-                self.config.editor.highlight_current_line =
-                    value.as_bool().ok_or_else(|| anyhow!("Expected boolean"))?;
+                self.config.editor.highlight_current_line = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
+            }
+            "editor.renderWhitespace" => {
+                self.config.editor.render_whitespace = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
             }
             "editor.wordWrap" => {
-                self.config.editor.word_wrap =
-                    value.as_bool().ok_or_else(|| anyhow!("Expected boolean"))?;
+                self.config.editor.word_wrap = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
+            }
+            "editor.trimTrailingWhitespace" => {
+                self.config.editor.trim_trailing_whitespace = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
+            }
+            "editor.insertFinalNewline" => {
+                self.config.editor.insert_final_newline = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
+            }
+            "editor.trimOnNewline" => {
+                self.config.editor.trim_on_newline = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
+            }
+            "editor.maxOpenBuffers" => {
+                self.config.editor.max_open_buffers = value
+                    .as_u64()
+                    .ok_or_else(|| invalid_value(path, "number"))?
+                    as usize;
+            }
+            "editor.closeLru" => {
+                self.config.editor.close_lru = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
+            }
+            "editor.autoPairs" => {
+                self.config.editor.auto_pairs = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
             }
             "editor.autoSave" => {
-                self.config.editor.auto_save =
-                    value.as_bool().ok_or_else(|| anyhow!("Expected boolean"))?;
+                self.config.editor.auto_save = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
             }
             "editor.autoSaveDelay" => {
-                self.config.editor.auto_save_delay =
-                    value.as_u64().ok_or_else(|| anyhow!("Expected number"))?;
+                self.config.editor.auto_save_delay = value
+                    .as_u64()
+                    .ok_or_else(|| invalid_value(path, "number"))?;
+            }
+            "editor.scrollPastEnd" => {
+                self.config.editor.scroll_past_end = value
+                    .as_u64()
+                    .ok_or_else(|| invalid_value(path, "number"))?
+                    as usize;
+            }
+            "editor.scrollOff" => {
+                self.config.editor.scroll_off = value
+                    .as_u64()
+                    .ok_or_else(|| invalid_value(path, "number"))?
+                    as usize;
             }
             "ui.theme" => {
                 self.config.ui.theme = value
                     .as_str()
-                    .ok_or_else(|| anyhow!("Expected string"))?
+                    .ok_or_else(|| invalid_value(path, "string"))?
                     .to_string();
             }
             "ui.fontSize" => {
-                self.config.ui.font_size =
-                    value.as_u64().ok_or_else(|| anyhow!("Expected number"))? as usize;
+                self.config.ui.font_size = value
+                    .as_u64()
+                    .ok_or_else(|| invalid_value(path, "number"))?
+                    as usize;
+            }
+            "ui.showMinimap" => {
+                self.config.ui.show_minimap = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
+            }
+            "ui.showTabBar" => {
+                self.config.ui.show_tab_bar = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
+            }
+            "ui.showScrollbar" => {
+                self.config.ui.show_scrollbar = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
+            }
+            "ui.scrollLinesPerTick" => {
+                self.config.ui.scroll_lines_per_tick = value
+                    .as_u64()
+                    .ok_or_else(|| invalid_value(path, "number"))?
+                    as usize;
+            }
+            "ui.dragScrollLines" => {
+                self.config.ui.drag_scroll_lines = value
+                    .as_u64()
+                    .ok_or_else(|| invalid_value(path, "number"))?
+                    as usize;
+            }
+            "ui.commandPaletteKey" => {
+                self.config.ui.command_palette_key = value
+                    .as_str()
+                    .ok_or_else(|| invalid_value(path, "string"))?
+                    .to_string();
+            }
+            "ui.commandPalettePosition" => {
+                self.config.ui.command_palette_position = serde_json::from_value(value)
+                    .map_err(|_| invalid_value(path, "\"top\" or \"center\""))?;
+            }
+            "ui.cursorBlinkEnabled" => {
+                self.config.ui.cursor_blink_enabled = value
+                    .as_bool()
+                    .ok_or_else(|| invalid_value(path, "boolean"))?;
+            }
+            "ui.cursorBlinkMs" => {
+                self.config.ui.cursor_blink_ms = value
+                    .as_u64()
+                    .ok_or_else(|| invalid_value(path, "number"))?;
             }
             _ => {
                 // For plugin settings or more complex paths, we would need
                 // a more sophisticated approach
-                return Err(anyhow!("Unsupported setting path: {}", path));
+                return Err(ConfigError::UnsupportedSetting(path.to_string()));
             }
         }
 
@@ -292,20 +652,59 @@ impl ConfigManager {
     }
 
     /// Get a setting by path
-    pub fn get_setting(&self, path: &str) -> Result<serde_json::Value> {
+    pub fn get_setting(&self, path: &str) -> Result<serde_json::Value, ConfigError> {
         match path {
             "editor.tabSize" => Ok(serde_json::json!(self.config.editor.tab_size)),
+            "editor.tabDisplayWidth" => Ok(serde_json::json!(self.config.editor.tab_display_width)),
             "editor.useSpaces" => Ok(serde_json::json!(self.config.editor.use_spaces)),
             "editor.showLineNumbers" => Ok(serde_json::json!(self.config.editor.show_line_numbers)),
+            "editor.relativeLineNumbers" => {
+                Ok(serde_json::json!(self.config.editor.relative_line_numbers))
+            }
+            "editor.gutterGap" => Ok(serde_json::json!(self.config.editor.gutter_gap)),
             "editor.highlightCurrentLine" => {
                 Ok(serde_json::json!(self.config.editor.highlight_current_line))
             }
+            "editor.renderWhitespace" => {
+                Ok(serde_json::json!(self.config.editor.render_whitespace))
+            }
             "editor.wordWrap" => Ok(serde_json::json!(self.config.editor.word_wrap)),
+            "editor.trimTrailingWhitespace" => Ok(serde_json::json!(
+                self.config.editor.trim_trailing_whitespace
+            )),
+            "editor.insertFinalNewline" => {
+                Ok(serde_json::json!(self.config.editor.insert_final_newline))
+            }
+            "editor.trimOnNewline" => Ok(serde_json::json!(self.config.editor.trim_on_newline)),
+            "editor.maxOpenBuffers" => Ok(serde_json::json!(self.config.editor.max_open_buffers)),
+            "editor.closeLru" => Ok(serde_json::json!(self.config.editor.close_lru)),
+            "editor.autoPairs" => Ok(serde_json::json!(self.config.editor.auto_pairs)),
             "editor.autoSave" => Ok(serde_json::json!(self.config.editor.auto_save)),
             "editor.autoSaveDelay" => Ok(serde_json::json!(self.config.editor.auto_save_delay)),
+            "editor.scrollPastEnd" => Ok(serde_json::json!(self.config.editor.scroll_past_end)),
+            "editor.scrollOff" => Ok(serde_json::json!(self.config.editor.scroll_off)),
             "ui.theme" => Ok(serde_json::json!(self.config.ui.theme)),
             "ui.fontSize" => Ok(serde_json::json!(self.config.ui.font_size)),
-            _ => Err(anyhow!("Unsupported setting path: {}", path)),
+            "ui.showMinimap" => Ok(serde_json::json!(self.config.ui.show_minimap)),
+            "ui.showTabBar" => Ok(serde_json::json!(self.config.ui.show_tab_bar)),
+            "ui.showScrollbar" => Ok(serde_json::json!(self.config.ui.show_scrollbar)),
+            "ui.scrollLinesPerTick" => Ok(serde_json::json!(self.config.ui.scroll_lines_per_tick)),
+            "ui.dragScrollLines" => Ok(serde_json::json!(self.config.ui.drag_scroll_lines)),
+            "ui.commandPaletteKey" => Ok(serde_json::json!(self.config.ui.command_palette_key)),
+            "ui.commandPalettePosition" => {
+                Ok(serde_json::json!(self.config.ui.command_palette_position))
+            }
+            "ui.cursorBlinkEnabled" => Ok(serde_json::json!(self.config.ui.cursor_blink_enabled)),
+            "ui.cursorBlinkMs" => Ok(serde_json::json!(self.config.ui.cursor_blink_ms)),
+            _ => Err(ConfigError::UnsupportedSetting(path.to_string())),
         }
     }
 }
+
+/// Error for a setting given a value of the wrong type
+fn invalid_value(setting: &str, expected: &'static str) -> ConfigError {
+    ConfigError::InvalidValue {
+        setting: setting.to_string(),
+        expected,
+    }
+}
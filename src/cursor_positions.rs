@@ -0,0 +1,130 @@
+//! Where the cursor was left in each file
+//!
+//! Saving or closing a file, or quitting, remembers its cursor position in a
+//! small map persisted under the user directory, and opening the file again
+//! puts the cursor back there. Unlike the session this covers every file
+//! edited lately, not just the ones open on quit. Paths are stored absolute,
+//! and positions are pulled back onto the text if the file got shorter.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+use crate::buffer::Position;
+use crate::App;
+
+/// File the cursor positions are persisted to, relative to the user directory
+const CURSOR_POSITIONS_FILE: &str = "cursor_positions.json";
+
+/// Most files whose cursor position is remembered
+pub const MAX_CURSOR_POSITIONS: usize = 200;
+
+/// Load the cursor positions from the user directory, most recent first,
+/// returning none if the file is missing
+pub fn load_cursor_positions(user_dir: &Path) -> Result<Vec<(PathBuf, Position)>> {
+    let path = user_dir.join(CURSOR_POSITIONS_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let positions_str = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&positions_str)
+        .map_err(|e| anyhow!("Failed to parse cursor positions: {}", e))
+}
+
+/// The absolute form of `path` positions are stored under
+fn position_key(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Write `positions` to the user directory without blocking the runtime,
+/// for callers that took them from the app and let go of it
+pub async fn write_cursor_positions(
+    user_dir: &Path,
+    positions: &[(PathBuf, Position)],
+) -> Result<()> {
+    let positions_str = serde_json::to_string_pretty(positions)?;
+    tokio::fs::write(user_dir.join(CURSOR_POSITIONS_FILE), positions_str).await?;
+    Ok(())
+}
+
+impl App {
+    /// Remember where the cursor is in the buffer at `index` and persist the
+    /// positions
+    ///
+    /// Buffers without a file have nothing to remember.
+    pub fn remember_cursor_position(&mut self, index: usize) -> Result<()> {
+        if self.record_cursor_position(index) {
+            self.save_cursor_positions()?;
+        }
+        Ok(())
+    }
+
+    /// Remember the cursor of every open file and persist the positions
+    pub fn remember_all_cursor_positions(&mut self) -> Result<()> {
+        let mut recorded = false;
+        for index in (0..self.buffers.len()).rev() {
+            recorded |= self.record_cursor_position(index);
+        }
+        if recorded {
+            self.save_cursor_positions()?;
+        }
+        Ok(())
+    }
+
+    /// Put the cursor of the buffer at `index` where it was last left in its
+    /// file, if that's remembered
+    pub fn restore_cursor_position(&mut self, index: usize) {
+        let Some(buffer) = self.buffers.get_mut(index) else {
+            return;
+        };
+        let Some(path) = &buffer.path else {
+            return;
+        };
+        let key = position_key(path);
+        if let Some(&(_, position)) = self.cursor_positions.iter().find(|(p, _)| *p == key) {
+            buffer.place_cursor(position);
+        }
+    }
+
+    /// Move the cursor position of the buffer at `index` to the front of the
+    /// remembered ones, returning false if it has no file
+    fn record_cursor_position(&mut self, index: usize) -> bool {
+        let Some((path, position)) = self.cursor_position_to_remember(index) else {
+            return false;
+        };
+        self.note_cursor_position(position_key(&path), position);
+        true
+    }
+
+    /// The file of the buffer at `index` and where its cursor is in it, or
+    /// `None` for a buffer without a file
+    pub fn cursor_position_to_remember(&self, index: usize) -> Option<(PathBuf, Position)> {
+        let buffer = self.buffers.get(index)?;
+        let path = buffer.path.clone()?;
+        Some((path, Position::from_tuple(buffer.cursor_pos)))
+    }
+
+    /// Put `position` in the file at the absolute `path` at the front of the
+    /// remembered positions, without persisting them
+    pub fn note_cursor_position(&mut self, path: PathBuf, position: Position) {
+        self.cursor_positions.retain(|(p, _)| *p != path);
+        self.cursor_positions.insert(0, (path, position));
+        self.cursor_positions.truncate(MAX_CURSOR_POSITIONS);
+    }
+
+    /// Load the cursor positions, keeping none if they can't be read
+    pub fn load_cursor_positions(&mut self) {
+        match load_cursor_positions(&self.user_dir) {
+            Ok(positions) => self.cursor_positions = positions,
+            Err(e) => eprintln!("Warning: Could not load cursor positions: {}", e),
+        }
+    }
+
+    /// Write the cursor positions to the user directory
+    pub fn save_cursor_positions(&self) -> Result<()> {
+        let positions_str = serde_json::to_string_pretty(&self.cursor_positions)?;
+        std::fs::write(self.user_dir.join(CURSOR_POSITIONS_FILE), positions_str)?;
+        Ok(())
+    }
+}
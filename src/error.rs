@@ -0,0 +1,128 @@
+//! Errors returned by the public API
+//!
+//! Inside the editor failures are mostly carried as `anyhow` errors and shown
+//! to the user. Functions that code embedding the editor calls directly return
+//! these enums instead, so callers can tell what went wrong by matching on
+//! them.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Why a file couldn't be opened
+#[derive(Debug)]
+pub enum OpenError {
+    /// There's no file at the path
+    NotFound { path: PathBuf },
+
+    /// The file exists but can't be read
+    PermissionDenied { path: PathBuf },
+
+    /// Any other I/O failure while opening the file or preparing the user
+    /// directory
+    Io { path: PathBuf, source: io::Error },
+}
+
+impl OpenError {
+    /// Classify an I/O error hit while opening `path`
+    pub fn from_io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        let path = path.into();
+        match source.kind() {
+            io::ErrorKind::NotFound => Self::NotFound { path },
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied { path },
+            _ => Self::Io { path, source },
+        }
+    }
+
+    /// The path that couldn't be opened
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            Self::NotFound { path } | Self::PermissionDenied { path } | Self::Io { path, .. } => {
+                path
+            }
+        }
+    }
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound { path } => write!(f, "'{}' does not exist", path.display()),
+            Self::PermissionDenied { path } => {
+                write!(f, "Permission denied opening '{}'", path.display())
+            }
+            Self::Io { path, source } => {
+                write!(f, "Failed to open '{}': {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OpenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Why the config couldn't be read, written or changed
+#[derive(Debug)]
+pub enum ConfigError {
+    /// Reading or writing the config file failed
+    Io { path: PathBuf, source: io::Error },
+
+    /// The config file isn't valid config JSON
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    /// The config couldn't be turned into JSON
+    Serialize(serde_json::Error),
+
+    /// No setting exists at this path
+    UnsupportedSetting(String),
+
+    /// The value given for a setting has the wrong type
+    InvalidValue {
+        setting: String,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => {
+                write!(
+                    f,
+                    "Failed to access config '{}': {}",
+                    path.display(),
+                    source
+                )
+            }
+            Self::Parse { path, source } => {
+                write!(f, "Failed to parse config '{}': {}", path.display(), source)
+            }
+            Self::Serialize(source) => write!(f, "Failed to serialize config: {}", source),
+            Self::UnsupportedSetting(setting) => {
+                write!(f, "Unsupported setting path: {}", setting)
+            }
+            Self::InvalidValue { setting, expected } => {
+                write!(f, "Expected {} for {}", expected, setting)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Parse { source, .. } | Self::Serialize(source) => Some(source),
+            Self::UnsupportedSetting(_) | Self::InvalidValue { .. } => None,
+        }
+    }
+}
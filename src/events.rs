@@ -1,7 +1,9 @@
 use anyhow::Result;
 use ratatui::crossterm::event::{KeyEvent, MouseEvent};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{mpsc, RwLock};
 
 /// All possible events in the application
@@ -13,11 +15,36 @@ pub enum AppEvent {
     /// Mouse input events
     MouseInput(MouseEvent),
 
+    /// Text pasted into the terminal
+    Paste(Arc<str>),
+
+    /// The keys of an unfinished key sequence may have waited long enough
+    ChordTimeout,
+
     /// Buffer-related events
+    ///
+    /// `buffer_id` is the buffer's index in `App::buffers` when the event was sent;
+    /// use `App::buffer_index_by_id` with `Buffer::id` to track a buffer across closes.
+    /// `BufferChanged` carries the whole text of a buffer that was just
+    /// loaded; edits are reported by `BufferEdited` instead.
     BufferChanged {
         buffer_id: usize,
         content: Arc<str>,
     },
+    /// An edit replaced `old_line_count` lines from `start_line` with
+    /// `new_line_count` lines, so subscribers can update just that region.
+    /// Only sent when the text actually changed.
+    BufferEdited {
+        buffer_id: usize,
+        start_line: usize,
+        old_line_count: usize,
+        new_line_count: usize,
+    },
+    /// A buffer turned from saved to modified, or was saved
+    BufferModifiedChanged {
+        buffer_id: usize,
+        modified: bool,
+    },
     BufferCursorMoved {
         buffer_id: usize,
         row: usize,
@@ -39,6 +66,8 @@ pub enum AppEvent {
     ToastMessage {
         message: Arc<str>,
         toast_type: Arc<str>,
+        /// How long the toast stays up, or `None` for the default
+        duration: Option<Duration>,
     },
     ShowCommandPalette,
     HideCommandPalette,
@@ -107,14 +136,24 @@ pub type AsyncEventHandler = Arc<
         + Sync,
 >;
 
+/// Token identifying a handler registered on the event bus, used to unsubscribe it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+/// Registered handlers keyed by event type, each tagged with its subscription id
+type HandlerRegistry<H> = Arc<RwLock<HashMap<String, Vec<(SubscriptionId, H)>>>>;
+
 /// Event bus for publishing and subscribing to events
 #[derive(Clone)]
 pub struct EventBus {
     /// Sync event handlers
-    handlers: Arc<RwLock<HashMap<String, Vec<EventHandler>>>>,
+    handlers: HandlerRegistry<EventHandler>,
 
     /// Async event handlers
-    async_handlers: Arc<RwLock<HashMap<String, Vec<AsyncEventHandler>>>>,
+    async_handlers: HandlerRegistry<AsyncEventHandler>,
+
+    /// Source of unique subscription ids, shared between clones
+    next_subscription_id: Arc<AtomicU64>,
 
     /// Channel for sending events
     sender: mpsc::UnboundedSender<AppEvent>,
@@ -131,6 +170,7 @@ impl EventBus {
         Self {
             handlers: Arc::new(RwLock::new(HashMap::new())),
             async_handlers: Arc::new(RwLock::new(HashMap::new())),
+            next_subscription_id: Arc::new(AtomicU64::new(0)),
             sender,
             receiver: Arc::new(RwLock::new(Some(receiver))),
         }
@@ -142,28 +182,65 @@ impl EventBus {
     }
 
     /// Subscribe to events with a sync handler
-    pub async fn subscribe<F>(&self, event_type: &str, handler: F)
+    pub async fn subscribe<F>(&self, event_type: &str, handler: F) -> SubscriptionId
     where
         F: Fn(&AppEvent) -> Result<()> + Send + Sync + 'static,
     {
+        let id = self.next_id();
         let mut handlers = self.handlers.write().await;
         handlers
             .entry(event_type.to_string())
             .or_insert_with(Vec::new)
-            .push(Arc::new(handler));
+            .push((id, Arc::new(handler)));
+        id
     }
 
     /// Subscribe to events with an async handler
-    pub async fn subscribe_async<F, Fut>(&self, event_type: &str, handler: F)
+    pub async fn subscribe_async<F, Fut>(&self, event_type: &str, handler: F) -> SubscriptionId
     where
         F: Fn(AppEvent) -> Fut + Send + Sync + 'static,
         Fut: std::future::Future<Output = Result<()>> + Send + 'static,
     {
+        let id = self.next_id();
         let mut async_handlers = self.async_handlers.write().await;
         async_handlers
             .entry(event_type.to_string())
             .or_insert_with(Vec::new)
-            .push(Arc::new(move |event| Box::pin(handler(event))));
+            .push((id, Arc::new(move |event| Box::pin(handler(event)))));
+        id
+    }
+
+    /// Remove a previously registered handler (sync or async)
+    ///
+    /// Returns whether a handler was removed. Safe to call from inside a handler:
+    /// events already being dispatched still reach it, later events don't.
+    pub async fn unsubscribe(&self, event_type: &str, id: SubscriptionId) -> bool {
+        let mut removed = false;
+
+        {
+            let mut handlers = self.handlers.write().await;
+            if let Some(event_handlers) = handlers.get_mut(event_type) {
+                let before = event_handlers.len();
+                event_handlers.retain(|(handler_id, _)| *handler_id != id);
+                removed |= event_handlers.len() != before;
+            }
+        }
+
+        {
+            let mut async_handlers = self.async_handlers.write().await;
+            if let Some(event_handlers) = async_handlers.get_mut(event_type) {
+                let before = event_handlers.len();
+                event_handlers.retain(|(handler_id, _)| *handler_id != id);
+                removed |= event_handlers.len() != before;
+            }
+        }
+
+        removed
+    }
+
+    /// Allocate a new subscription id
+    fn next_id(&self) -> SubscriptionId {
+        SubscriptionId(self.next_subscription_id.fetch_add(1, Ordering::Relaxed))
     }
 
     /// Publish an event
@@ -188,31 +265,59 @@ impl EventBus {
         Ok(())
     }
 
+    /// Handle every event published so far, and the ones they lead to, then
+    /// return
+    ///
+    /// For driving the editor without a running event loop; fails if
+    /// `start_processing` already took the events.
+    pub async fn process_pending(&self) -> Result<()> {
+        let mut receiver = {
+            let mut receiver_guard = self.receiver.write().await;
+            receiver_guard
+                .take()
+                .ok_or_else(|| anyhow::anyhow!("Event processor already started"))?
+        };
+
+        while let Ok(event) = receiver.try_recv() {
+            self.handle_event(event).await;
+        }
+
+        *self.receiver.write().await = Some(receiver);
+        Ok(())
+    }
+
     /// Handle a single event by calling all registered handlers
     async fn handle_event(&self, event: AppEvent) {
         let event_type = self.get_event_type(&event);
 
-        // Handle sync handlers
-        {
+        // Snapshot the handlers and release the locks before calling them, so a
+        // handler can subscribe or unsubscribe without deadlocking the bus
+        let sync_handlers: Vec<EventHandler> = {
             let handlers = self.handlers.read().await;
-            if let Some(event_handlers) = handlers.get(event_type) {
-                for handler in event_handlers {
-                    if let Err(e) = handler(&event) {
-                        eprintln!("Error in sync event handler for {}: {}", event_type, e);
-                    }
-                }
+            handlers
+                .get(event_type)
+                .map(|event_handlers| event_handlers.iter().map(|(_, h)| h.clone()).collect())
+                .unwrap_or_default()
+        };
+        let async_handlers: Vec<AsyncEventHandler> = {
+            let async_handlers = self.async_handlers.read().await;
+            async_handlers
+                .get(event_type)
+                .map(|event_handlers| event_handlers.iter().map(|(_, h)| h.clone()).collect())
+                .unwrap_or_default()
+        };
+
+        // Handle sync handlers
+        for handler in &sync_handlers {
+            if let Err(e) = handler(&event) {
+                eprintln!("Error in sync event handler for {}: {}", event_type, e);
             }
         }
 
         // Handle async handlers
-        {
-            let async_handlers = self.async_handlers.read().await;
-            if let Some(event_handlers) = async_handlers.get(event_type) {
-                for handler in event_handlers {
-                    if let Err(e) = handler(event.clone()).await {
-                        eprintln!("Error in async event handler for {}: {}", event_type, e);
-                    }
-                }
+        for handler in &async_handlers {
+            if let Err(e) = handler(event.clone()).await {
+                eprintln!("Error in async event handler for {}: {}", event_type, e);
             }
         }
     }
@@ -222,7 +327,11 @@ impl EventBus {
         match event {
             AppEvent::KeyInput(_) => "key_input",
             AppEvent::MouseInput(_) => "mouse_input",
+            AppEvent::Paste(_) => "paste",
+            AppEvent::ChordTimeout => "chord_timeout",
             AppEvent::BufferChanged { .. } => "buffer_changed",
+            AppEvent::BufferEdited { .. } => "buffer_edited",
+            AppEvent::BufferModifiedChanged { .. } => "buffer_modified_changed",
             AppEvent::BufferCursorMoved { .. } => "buffer_cursor_moved",
             AppEvent::BufferSelectionChanged { .. } => "buffer_selection_changed",
             AppEvent::ModeChanged { .. } => "mode_changed",
@@ -31,6 +31,39 @@ impl AppStateHandler {
             })
             .await;
 
+        // Subscribe to loaded buffers, to count their words
+        event_bus
+            .subscribe_async("buffer_changed", {
+                let handler = handler.clone();
+                move |event| {
+                    let handler = handler.clone();
+                    async move { handler.handle_buffer_changed(event).await }
+                }
+            })
+            .await;
+
+        // Subscribe to buffer edits, which restart the auto-save wait
+        event_bus
+            .subscribe_async("buffer_edited", {
+                let handler = handler.clone();
+                move |event| {
+                    let handler = handler.clone();
+                    async move { handler.handle_buffer_edited(event).await }
+                }
+            })
+            .await;
+
+        // Subscribe to buffers turning modified or saved
+        event_bus
+            .subscribe_async("buffer_modified_changed", {
+                let handler = handler.clone();
+                move |event| {
+                    let handler = handler.clone();
+                    async move { handler.handle_buffer_modified_changed(event).await }
+                }
+            })
+            .await;
+
         // Subscribe to status messages
         event_bus
             .subscribe_async("status_message", {
@@ -114,6 +147,45 @@ impl AppStateHandler {
             // Clear command input when switching to normal mode
             if app.command_mode == CommandMode::Normal {
                 app.command_input.clear();
+                app.update_command_suggestions();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle buffer load events
+    async fn handle_buffer_changed(&self, event: AppEvent) -> Result<()> {
+        if let AppEvent::BufferChanged { buffer_id, .. } = event {
+            let mut app = self.app_state.write().await;
+            app.update_word_count(buffer_id);
+        }
+
+        Ok(())
+    }
+
+    /// Handle buffer edit events
+    async fn handle_buffer_edited(&self, event: AppEvent) -> Result<()> {
+        if let AppEvent::BufferEdited { buffer_id, .. } = event {
+            let mut app = self.app_state.write().await;
+            app.note_edit();
+            app.update_word_count(buffer_id);
+        }
+
+        Ok(())
+    }
+
+    /// Handle a buffer turning modified or saved, updating the status bar
+    /// right away if it's the active one
+    async fn handle_buffer_modified_changed(&self, event: AppEvent) -> Result<()> {
+        if let AppEvent::BufferModifiedChanged {
+            buffer_id,
+            modified,
+        } = event
+        {
+            let mut app = self.app_state.write().await;
+            if buffer_id == app.active_buffer {
+                app.update_modified_status(modified);
             }
         }
 
@@ -124,7 +196,7 @@ impl AppStateHandler {
     async fn handle_status_message(&self, event: AppEvent) -> Result<()> {
         if let AppEvent::StatusMessage { message } = event {
             let mut app = self.app_state.write().await;
-            app.status_message = Some(message.to_string());
+            app.set_status_message(message.to_string());
         }
 
         Ok(())
@@ -136,6 +208,7 @@ impl AppStateHandler {
             let mut app = self.app_state.write().await;
             app.show_command_palette = true;
             app.command_input.clear();
+            app.update_command_suggestions();
         }
 
         Ok(())
@@ -168,16 +241,9 @@ impl AppStateHandler {
             // When cursor is moved programmatically (via keyboard), ensure it's visible
             // This is different from manual scrolling which shouldn't affect cursor visibility
 
-            // We need the terminal size to call ensure_cursor_visible
-            // For now, we'll use a reasonable default and improve this later
-            use ratatui::crossterm::terminal;
-            use ratatui::prelude::Rect;
-
-            // Get terminal size - use a reasonable default if not available
-            let terminal_size = terminal::size().unwrap_or((80, 24));
-            let editor_area = Rect::new(0, 0, terminal_size.0, terminal_size.1.saturating_sub(2)); // Leave space for status
-
+            // Keep the cursor inside the editor as it was last drawn
             let mut app = self.app_state.write().await;
+            let editor_area = app.editor_area();
             app.ensure_cursor_visible(editor_area);
         }
 
@@ -189,6 +255,7 @@ impl AppStateHandler {
         if let AppEvent::ToastMessage {
             message,
             toast_type,
+            duration,
         } = event
         {
             let mut app = self.app_state.write().await;
@@ -201,7 +268,10 @@ impl AppStateHandler {
                 _ => ToastType::Info,
             };
 
-            let toast = Toast::new(message.to_string(), toast_type);
+            let mut toast = Toast::new(message.to_string(), toast_type);
+            if let Some(duration) = duration {
+                toast = toast.with_duration(duration);
+            }
             app.toast_manager.add_toast(toast);
         }
 
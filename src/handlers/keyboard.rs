@@ -1,15 +1,51 @@
+use crate::buffer::{
+    toggle_case, write_save, Buffer, LineChange, LineEnding, PendingSave, Position, SearchQuery,
+};
+use crate::commands::{
+    CommandCall, CommandContext, CommandFuture, CommandRegistry, SEARCH_ALL_BUFFERS,
+};
+use crate::config::{Config, ConfigManager};
+use crate::cursor_positions::write_cursor_positions;
 use crate::events::{AppEvent, EventBus};
-use crate::{App, CommandMode};
+use crate::input::chords::{
+    is_key, match_chord, ChordMatch, PendingChord, CHORD_TIMEOUT, PANE_BINDINGS,
+};
+use crate::plugins::{EditorContext, PluginCommand};
+use crate::recent::write_recent_files;
+use crate::{App, CommandMode, UnsavedPrompt};
 use anyhow::Result;
 use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use regex::RegexBuilder;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::mpsc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
+
+/// A boolean setting a command can toggle
+type ConfigFlag = fn(&mut Config) -> &mut bool;
 
 /// Keyboard handler that processes keyboard events
 pub struct KeyboardHandler {
     app_state: Arc<RwLock<App>>,
     event_sender: mpsc::UnboundedSender<AppEvent>,
+    config_writes: Arc<ConfigWrites>,
+
+    /// Held while copying and writing the remembered cursor positions or
+    /// recent files, so the last copy taken is the last written
+    state_writes: Arc<Mutex<()>>,
+}
+
+/// Copies of the config taken to be written, numbered in the order they
+/// were taken
+#[derive(Default)]
+struct ConfigWrites {
+    /// Number of the latest copy taken
+    taken: AtomicU64,
+
+    /// Number of the latest copy written, held by the background task
+    /// writing one so writes take turns
+    written: Mutex<u64>,
 }
 
 impl KeyboardHandler {
@@ -18,6 +54,8 @@ impl KeyboardHandler {
         Self {
             app_state,
             event_sender,
+            config_writes: Arc::default(),
+            state_writes: Arc::default(),
         }
     }
 
@@ -26,7 +64,27 @@ impl KeyboardHandler {
         let handler = KeyboardHandler::new(self.app_state.clone(), self.event_sender.clone());
 
         event_bus
-            .subscribe_async("key_input", move |event| {
+            .subscribe_async("key_input", {
+                let handler = handler.clone();
+                move |event| {
+                    let handler = handler.clone();
+                    async move { handler.handle_key_event(event).await }
+                }
+            })
+            .await;
+
+        event_bus
+            .subscribe_async("paste", {
+                let handler = handler.clone();
+                move |event| {
+                    let handler = handler.clone();
+                    async move { handler.handle_paste_event(event).await }
+                }
+            })
+            .await;
+
+        event_bus
+            .subscribe_async("chord_timeout", move |event| {
                 let handler = handler.clone();
                 async move { handler.handle_key_event(event).await }
             })
@@ -35,15 +93,28 @@ impl KeyboardHandler {
         Ok(())
     }
 
-    /// Handle keyboard events
-    async fn handle_key_event(&self, event: AppEvent) -> Result<()> {
+    /// Handle keyboard events, and the timeouts of key sequences
+    pub async fn handle_key_event(&self, event: AppEvent) -> Result<()> {
+        if let AppEvent::ChordTimeout = event {
+            return self.flush_expired_chord().await;
+        }
+
         if let AppEvent::KeyInput(key) = event {
             let app = self.app_state.read().await;
             let command_mode = app.command_mode.clone();
+            let unsaved_prompt = app.unsaved_prompt;
+            let show_help = app.show_help;
             drop(app); // Release read lock early
 
+            if let Some(prompt) = unsaved_prompt {
+                return self.handle_unsaved_prompt_key(prompt, key).await;
+            }
+            if show_help {
+                return self.handle_help_key(key).await;
+            }
+
             match command_mode {
-                CommandMode::Normal => self.handle_normal_mode_key(key).await?,
+                CommandMode::Normal => self.handle_chord_key(key).await?,
                 CommandMode::Command => self.handle_command_mode_key(key).await?,
                 CommandMode::FileSearch => self.handle_file_search_key(key).await?,
                 CommandMode::TextSearch => self.handle_text_search_key(key).await?,
@@ -53,36 +124,183 @@ impl KeyboardHandler {
         Ok(())
     }
 
+    /// Handle a key in normal mode, holding it back while it could be part
+    /// of a bound key sequence
+    async fn handle_chord_key(&self, key: KeyEvent) -> Result<()> {
+        self.flush_expired_chord().await?;
+
+        let mut app = self.app_state.write().await;
+        let split = app.is_split();
+        if app.pending_chord.is_none() && app.config.keybindings.is_empty() && !split {
+            drop(app);
+            return self.handle_normal_mode_key(key).await;
+        }
+
+        let now = (app.clock)();
+        let mut pending = app.pending_chord.take().unwrap_or(PendingChord {
+            keys: Vec::new(),
+            started: now,
+        });
+        pending.keys.push(key);
+        let configured = app
+            .config
+            .keybindings
+            .iter()
+            .map(|(sequence, command)| (sequence.as_str(), command.as_str()));
+        let found = match match_chord(configured, &pending.keys) {
+            ChordMatch::None if split => match_chord(PANE_BINDINGS, &pending.keys),
+            found => found,
+        };
+        match found {
+            ChordMatch::Command(command) => {
+                let command = command.to_string();
+                drop(app);
+                self.run_bound_command(&command).await
+            }
+            ChordMatch::Prefix => {
+                app.pending_chord = Some(pending);
+
+                // Let the held keys through if nothing follows them in time
+                let event_sender = self.event_sender.clone();
+                app.background_tasks.spawn("chord timeout", async move {
+                    tokio::time::sleep(CHORD_TIMEOUT).await;
+                    let _ = event_sender.send(AppEvent::ChordTimeout);
+                });
+                Ok(())
+            }
+            ChordMatch::None => {
+                drop(app);
+                for key in pending.keys {
+                    self.handle_normal_mode_key(key).await?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Handle the held keys of a key sequence as normal input, if it timed
+    /// out unfinished
+    async fn flush_expired_chord(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let now = (app.clock)();
+        let Some(pending) = app.pending_chord.take_if(|pending| pending.expired(now)) else {
+            return Ok(());
+        };
+        drop(app);
+
+        for key in pending.keys {
+            self.handle_normal_mode_key(key).await?;
+        }
+        Ok(())
+    }
+
+    /// Run the palette command a key sequence is bound to, or open the
+    /// palette with it typed if it ends in whitespace
+    async fn run_bound_command(&self, command: &str) -> Result<()> {
+        if command.ends_with(char::is_whitespace) {
+            self.open_command_palette_with(command).await
+        } else {
+            self.execute_command(command).await
+        }
+    }
+
+    /// Handle text pasted into the terminal
+    ///
+    /// In the editor it replaces the selection; in a prompt only its first
+    /// line is taken.
+    pub async fn handle_paste_event(&self, event: AppEvent) -> Result<()> {
+        let AppEvent::Paste(text) = event else {
+            return Ok(());
+        };
+        // Some terminals send line breaks as a lone \r
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+        let mut app = self.app_state.write().await;
+        if app.unsaved_prompt.is_some() || app.show_help {
+            return Ok(());
+        }
+        if app.command_mode != CommandMode::Normal {
+            let first_line = text.lines().next().unwrap_or_default();
+            app.command_input.push_str(first_line);
+            app.update_command_suggestions();
+            return Ok(());
+        }
+
+        let active_buffer = app.active_buffer;
+        let Some(buffer) = app.buffers.get_mut(active_buffer) else {
+            return Ok(());
+        };
+        if buffer.visual_mode {
+            buffer.delete_selection();
+            buffer.clear_selection();
+        }
+        buffer.insert_str(&text);
+        let (row, col) = buffer.cursor_pos;
+        let change = buffer.take_change();
+        let became_modified = buffer.take_became_modified();
+        drop(app);
+
+        self.send_buffer_edited(active_buffer, change, became_modified)?;
+        self.event_sender.send(AppEvent::BufferCursorMoved {
+            buffer_id: active_buffer,
+            row,
+            col,
+        })?;
+        Ok(())
+    }
+
     /// Handle keyboard input in normal mode
     async fn handle_normal_mode_key(&self, key: KeyEvent) -> Result<()> {
-        // Check for key combinations first - prioritize command palette for immediate response
+        // Check the command palette key first for immediate response
+        let palette_key = self
+            .app_state
+            .read()
+            .await
+            .config
+            .ui
+            .command_palette_key
+            .clone();
+        if is_key(&palette_key, key) {
+            // Open command palette (Alt+P unless configured) - optimized for immediate response
+            self.event_sender.send(AppEvent::ModeChanged {
+                new_mode: "command".into(),
+            })?;
+            self.event_sender.send(AppEvent::ShowCommandPalette)?;
+            self.event_sender.send(AppEvent::CursorHide {
+                context: "editor".into(),
+            })?;
+            self.event_sender.send(AppEvent::CursorShow {
+                context: "command_palette".into(),
+            })?;
+            return Ok(()); // Exit immediately to minimize latency
+        }
+
         match (key.code, key.modifiers) {
-            (KeyCode::Char('p'), KeyModifiers::ALT) => {
-                // Open command palette with Alt+P - optimized for immediate response
-                self.event_sender.send(AppEvent::ModeChanged {
-                    new_mode: "command".into(),
-                })?;
-                self.event_sender.send(AppEvent::ShowCommandPalette)?;
-                self.event_sender.send(AppEvent::CursorHide {
-                    context: "editor".into(),
-                })?;
-                self.event_sender.send(AppEvent::CursorShow {
-                    context: "command_palette".into(),
-                })?;
-                return Ok(()); // Exit immediately to minimize latency
+            (KeyCode::Char('\''), KeyModifiers::ALT) => {
+                // Jump to a mark with Alt+'
+                self.open_command_palette_with("jump ").await?;
             }
             (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
                 // Quit with Ctrl+Q
-                self.event_sender.send(AppEvent::Quit)?;
+                self.handle_quit_request().await?;
             }
             (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
                 // Save with Ctrl+S
                 self.handle_save_command().await?;
             }
-            (KeyCode::Char('o'), KeyModifiers::CONTROL) => {
-                // Open file with Ctrl+O
+            (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+                // Open file with Ctrl+P
                 self.handle_open_command().await?;
             }
+            (KeyCode::Char('o'), KeyModifiers::CONTROL) => {
+                // Go back through the jump list with Ctrl+O
+                self.handle_jump_list(false).await?;
+            }
+            (KeyCode::Char('i'), KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                // Go forward through the jump list with Ctrl+I, or Alt+I in
+                // terminals that send Ctrl+I as Tab
+                self.handle_jump_list(true).await?;
+            }
             (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
                 // New buffer with Ctrl+N
                 self.handle_new_buffer().await?;
@@ -91,6 +309,12 @@ impl KeyboardHandler {
                 // Toggle visual mode with Ctrl+V
                 self.handle_toggle_visual_mode().await?;
             }
+            (KeyCode::Char('c' | 'C'), modifiers)
+                if modifiers == KeyModifiers::CONTROL | KeyModifiers::SHIFT =>
+            {
+                // Dismiss every toast with Ctrl+Shift+C
+                self.app_state.write().await.toast_manager.clear_all();
+            }
             (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
                 // Copy with Ctrl+C
                 self.handle_copy().await?;
@@ -99,13 +323,79 @@ impl KeyboardHandler {
                 // Cut with Ctrl+X
                 self.handle_cut().await?;
             }
+            (KeyCode::Char('k' | 'K'), modifiers)
+                if modifiers == KeyModifiers::CONTROL | KeyModifiers::SHIFT =>
+            {
+                // Delete the cursor line with Ctrl+Shift+K
+                self.edit_active_buffer(Buffer::delete_line).await?;
+            }
+            (KeyCode::Char('k'), KeyModifiers::CONTROL) => {
+                // Delete to the end of the line with Ctrl+K
+                self.edit_active_buffer(Buffer::delete_to_line_end).await?;
+            }
+            (KeyCode::Char('a'), KeyModifiers::CONTROL) => {
+                // Select the whole buffer with Ctrl+A
+                self.edit_active_buffer(|buffer| {
+                    buffer.select_all();
+                    true
+                })
+                .await?;
+            }
+            (KeyCode::Char('t'), KeyModifiers::CONTROL) => {
+                // Swap the chars around the cursor with Ctrl+T
+                self.edit_active_buffer(Buffer::transpose_chars).await?;
+            }
+            (KeyCode::Char('f'), KeyModifiers::CONTROL) => {
+                // Search the buffer with Ctrl+F
+                self.handle_open_text_search().await?;
+            }
+            (KeyCode::Char('d'), KeyModifiers::CONTROL) => {
+                // Add a cursor at the next occurrence with Ctrl+D
+                self.handle_add_cursor_at_next_occurrence().await?;
+            }
+            (KeyCode::Char('w'), KeyModifiers::CONTROL) => {
+                // Close the active buffer with Ctrl+W, unless `w` follows it
+                // in a split view
+                self.handle_close_request().await?;
+            }
+            (KeyCode::Char('l'), KeyModifiers::CONTROL) => {
+                // Center the cursor line in the view with Ctrl+L
+                self.handle_center_cursor().await?;
+            }
+            (KeyCode::Char(digit @ '1'..='9'), KeyModifiers::ALT) => {
+                // Switch to buffer N with Alt+N
+                let number = digit as usize - '0' as usize;
+                self.handle_switch_to_buffer(number).await?;
+            }
+            (KeyCode::Char('v'), KeyModifiers::ALT) => {
+                // Toggle linewise visual mode with Alt+V
+                self.handle_toggle_line_visual_mode().await?;
+            }
+            (KeyCode::Char('w'), KeyModifiers::ALT) => {
+                // Focus the next split pane with Alt+W, as with Ctrl+W w
+                self.handle_focus_next_pane().await?;
+            }
+            (KeyCode::Char('/' | '7'), KeyModifiers::CONTROL) => {
+                // Toggle line comments with Ctrl+/ (sent as Ctrl+7 by many
+                // terminals)
+                self.handle_toggle_comment().await?;
+            }
+            (KeyCode::F(1), KeyModifiers::NONE) => {
+                // List the commands with F1
+                self.handle_show_help().await?;
+            }
+            (KeyCode::F(10), KeyModifiers::NONE) => {
+                // Toggle the frame timing overlay with F10
+                self.handle_toggle_performance_overlay().await?;
+            }
             (KeyCode::Tab, KeyModifiers::NONE) => {
-                // Next buffer with Tab
-                self.handle_next_buffer().await?;
+                // Indent the selected lines, or go to the next buffer, with Tab
+                self.handle_tab(false).await?;
             }
-            (KeyCode::Tab, KeyModifiers::SHIFT) => {
-                // Previous buffer with Shift+Tab
-                self.handle_prev_buffer().await?;
+            (KeyCode::Tab, KeyModifiers::SHIFT) | (KeyCode::BackTab, _) => {
+                // Unindent the selected lines, or go to the previous buffer,
+                // with Shift+Tab
+                self.handle_tab(true).await?;
             }
             (KeyCode::Esc, _) => {
                 self.handle_escape().await?;
@@ -131,7 +421,7 @@ impl KeyboardHandler {
                 let movement = if modifiers.contains(KeyModifiers::CONTROL) {
                     crate::buffer::CursorMovement::BufferStart
                 } else {
-                    crate::buffer::CursorMovement::LineStart
+                    crate::buffer::CursorMovement::SmartLineStart
                 };
                 self.handle_cursor_movement(movement, modifiers).await?;
             }
@@ -144,12 +434,14 @@ impl KeyboardHandler {
                 self.handle_cursor_movement(movement, modifiers).await?;
             }
             (KeyCode::PageUp, modifiers) => {
-                self.handle_cursor_movement(crate::buffer::CursorMovement::PageUp, modifiers)
-                    .await?;
+                let page_size = self.page_size().await;
+                let movement = crate::buffer::CursorMovement::PageUp(page_size);
+                self.handle_cursor_movement(movement, modifiers).await?;
             }
             (KeyCode::PageDown, modifiers) => {
-                self.handle_cursor_movement(crate::buffer::CursorMovement::PageDown, modifiers)
-                    .await?;
+                let page_size = self.page_size().await;
+                let movement = crate::buffer::CursorMovement::PageDown(page_size);
+                self.handle_cursor_movement(movement, modifiers).await?;
             }
             // Text input
             (KeyCode::Char(c), KeyModifiers::NONE) => {
@@ -170,24 +462,57 @@ impl KeyboardHandler {
         Ok(())
     }
 
+    /// Lines Page Up and Page Down move by: the height of the editor
+    async fn page_size(&self) -> usize {
+        let app = self.app_state.read().await;
+        (app.editor_area().height as usize).max(1)
+    }
+
     /// Handle escape key
     async fn handle_escape(&self) -> Result<()> {
         let mut app = self.app_state.write().await;
         let active_buffer = app.active_buffer;
 
         if let Some(buffer) = app.buffers.get_mut(active_buffer) {
-            if buffer.visual_mode {
+            if buffer.has_multiple_cursors() {
+                buffer.clear_secondary_cursors();
+            } else if buffer.visual_mode {
                 buffer.clear_selection();
                 drop(app);
                 self.event_sender.send(AppEvent::StatusMessage {
                     message: "Selection cleared".into(),
                 })?;
+            } else {
+                app.search_query = None;
             }
         }
 
         Ok(())
     }
 
+    /// Add a cursor at the next occurrence of the word or selection (Ctrl+D)
+    async fn handle_add_cursor_at_next_occurrence(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let active_buffer = app.active_buffer;
+        let Some(buffer) = app.buffers.get_mut(active_buffer) else {
+            return Ok(());
+        };
+
+        if buffer.add_cursor_at_next_occurrence() {
+            let count = buffer.secondary_cursors.len() + 1;
+            drop(app);
+            self.event_sender.send(AppEvent::StatusMessage {
+                message: format!("{} cursors", count).into(),
+            })?;
+        } else {
+            drop(app);
+            self.event_sender.send(AppEvent::StatusMessage {
+                message: "No more occurrences".into(),
+            })?;
+        }
+        Ok(())
+    }
+
     /// Handle cursor movement
     async fn handle_cursor_movement(
         &self,
@@ -197,6 +522,9 @@ impl KeyboardHandler {
         let mut app = self.app_state.write().await;
         let active_buffer = app.active_buffer;
 
+        if movement.is_jump() {
+            app.record_jump();
+        }
         if let Some(buffer) = app.buffers.get_mut(active_buffer) {
             if modifiers.contains(KeyModifiers::SHIFT) && !buffer.visual_mode {
                 buffer.toggle_visual_mode();
@@ -206,7 +534,7 @@ impl KeyboardHandler {
             let (row, col) = buffer.cursor_pos;
             drop(app);
             self.event_sender.send(AppEvent::BufferCursorMoved {
-                buffer_id: 0,
+                buffer_id: active_buffer,
                 row,
                 col,
             })?;
@@ -219,27 +547,34 @@ impl KeyboardHandler {
     async fn handle_char_input(&self, c: char) -> Result<()> {
         let mut app = self.app_state.write().await;
         let active_buffer = app.active_buffer;
+        let auto_pairs = app.config.editor.auto_pairs;
 
         if let Some(buffer) = app.buffers.get_mut(active_buffer) {
-            if buffer.visual_mode {
-                buffer.delete_selection();
-                buffer.visual_mode = false;
-                buffer.selection_start = None;
-            }
+            if auto_pairs && buffer.visual_mode && buffer.wrap_selection(c) {
+                // The pair went around the selection instead of replacing it
+            } else {
+                if buffer.visual_mode {
+                    buffer.delete_selection();
+                    buffer.visual_mode = false;
+                    buffer.selection_start = None;
+                }
 
-            buffer.insert_char(c);
+                if auto_pairs {
+                    buffer.insert_char_paired(c);
+                } else {
+                    buffer.insert_char(c);
+                }
+            }
 
             let (row, col) = buffer.cursor_pos;
-            let content: Arc<str> = buffer.content_as_string().into();
+            let change = buffer.take_change();
+            let became_modified = buffer.take_became_modified();
             drop(app);
 
             // Send both buffer changed and cursor moved events to trigger scroll adjustment
-            self.event_sender.send(AppEvent::BufferChanged {
-                buffer_id: 0,
-                content,
-            })?;
+            self.send_buffer_edited(active_buffer, change, became_modified)?;
             self.event_sender.send(AppEvent::BufferCursorMoved {
-                buffer_id: 0,
+                buffer_id: active_buffer,
                 row,
                 col,
             })?;
@@ -252,6 +587,7 @@ impl KeyboardHandler {
     async fn handle_enter(&self) -> Result<()> {
         let mut app = self.app_state.write().await;
         let active_buffer = app.active_buffer;
+        let trim_on_newline = app.config.editor.trim_on_newline;
 
         if let Some(buffer) = app.buffers.get_mut(active_buffer) {
             if buffer.visual_mode {
@@ -259,19 +595,21 @@ impl KeyboardHandler {
                 buffer.visual_mode = false;
                 buffer.selection_start = None;
             }
-            buffer.insert_newline();
+            if trim_on_newline {
+                buffer.insert_newline_trimming();
+            } else {
+                buffer.insert_newline();
+            }
 
             let (row, col) = buffer.cursor_pos;
-            let content: Arc<str> = buffer.content_as_string().into();
+            let change = buffer.take_change();
+            let became_modified = buffer.take_became_modified();
             drop(app);
 
             // Send both events to ensure proper scroll adjustment
-            self.event_sender.send(AppEvent::BufferChanged {
-                buffer_id: 0,
-                content,
-            })?;
+            self.send_buffer_edited(active_buffer, change, became_modified)?;
             self.event_sender.send(AppEvent::BufferCursorMoved {
-                buffer_id: 0,
+                buffer_id: active_buffer,
                 row,
                 col,
             })?;
@@ -295,16 +633,14 @@ impl KeyboardHandler {
             }
 
             let (row, col) = buffer.cursor_pos;
-            let content: Arc<str> = buffer.content_as_string().into();
+            let change = buffer.take_change();
+            let became_modified = buffer.take_became_modified();
             drop(app);
 
             // Send both events to ensure proper scroll adjustment
-            self.event_sender.send(AppEvent::BufferChanged {
-                buffer_id: 0,
-                content,
-            })?;
+            self.send_buffer_edited(active_buffer, change, became_modified)?;
             self.event_sender.send(AppEvent::BufferCursorMoved {
-                buffer_id: 0,
+                buffer_id: active_buffer,
                 row,
                 col,
             })?;
@@ -328,16 +664,14 @@ impl KeyboardHandler {
             }
 
             let (row, col) = buffer.cursor_pos;
-            let content: Arc<str> = buffer.content_as_string().into();
+            let change = buffer.take_change();
+            let became_modified = buffer.take_became_modified();
             drop(app);
 
             // Send both events to ensure proper scroll adjustment
-            self.event_sender.send(AppEvent::BufferChanged {
-                buffer_id: 0,
-                content,
-            })?;
+            self.send_buffer_edited(active_buffer, change, became_modified)?;
             self.event_sender.send(AppEvent::BufferCursorMoved {
-                buffer_id: 0,
+                buffer_id: active_buffer,
                 row,
                 col,
             })?;
@@ -363,10 +697,15 @@ impl KeyboardHandler {
                 })?;
             }
             KeyCode::Enter => {
-                // Execute command
+                // Execute the picked suggestion, or the command as typed
                 let command = {
                     let app = self.app_state.read().await;
-                    app.command_input.clone()
+                    match app.selected_suggestion {
+                        Some(index) if index < app.command_suggestions.len() => {
+                            app.command_suggestions[index].clone()
+                        }
+                        _ => app.command_input.clone(),
+                    }
                 };
 
                 if !command.is_empty() {
@@ -390,11 +729,33 @@ impl KeyboardHandler {
                 // Add character to command input
                 let mut app = self.app_state.write().await;
                 app.command_input.push(c);
+                app.update_command_suggestions();
             }
             KeyCode::Backspace => {
                 // Remove character from command input
                 let mut app = self.app_state.write().await;
                 app.command_input.pop();
+                app.update_command_suggestions();
+            }
+            KeyCode::Up | KeyCode::Down => {
+                // Pick a suggestion while there are any, otherwise recall
+                // earlier commands
+                let delta = if key.code == KeyCode::Up { -1 } else { 1 };
+                let mut app = self.app_state.write().await;
+                if app.suggestion_count() == 0 || app.history_index.is_some() {
+                    app.recall_command_history(delta);
+                } else {
+                    app.select_suggestion(delta);
+                }
+            }
+            KeyCode::Tab => {
+                // Complete the input to the picked (or best) suggestion
+                let mut app = self.app_state.write().await;
+                let index = app.selected_suggestion.unwrap_or(0);
+                if let Some(name) = app.command_suggestions.get(index).cloned() {
+                    app.command_input = name;
+                    app.update_command_suggestions();
+                }
             }
             _ => {}
         }
@@ -431,9 +792,35 @@ impl KeyboardHandler {
     }
 
     /// Handle keyboard input in text search mode
+    ///
+    /// The cursor jumps to the nearest match as the query is typed. Alt+R
+    /// switches between literal and regex queries. Enter keeps the matches
+    /// highlighted after leaving the prompt; Esc clears them.
     async fn handle_text_search_key(&self, key: KeyEvent) -> Result<()> {
-        match key.code {
-            KeyCode::Esc => {
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('r'), KeyModifiers::ALT) => {
+                let mut app = self.app_state.write().await;
+                app.search_regex = !app.search_regex;
+                drop(app);
+                self.update_text_search().await?;
+            }
+            (KeyCode::Esc | KeyCode::Enter, _) => {
+                let mut app = self.app_state.write().await;
+                let invalid_regex = match key.code {
+                    KeyCode::Esc => {
+                        app.search_query = None;
+                        None
+                    }
+                    _ if app.search_regex => SearchQuery::regex(app.command_input.as_str()).err(),
+                    _ => None,
+                };
+                drop(app);
+
+                // Only report a bad pattern once it's submitted, not while
+                // it's half typed
+                if let Some(e) = invalid_regex {
+                    self.send_invalid_regex_toast(&e)?;
+                }
                 self.event_sender.send(AppEvent::ModeChanged {
                     new_mode: "normal".into(),
                 })?;
@@ -444,139 +831,480 @@ impl KeyboardHandler {
                     context: "editor".into(),
                 })?;
             }
-            _ => {
-                // TODO: Implement text search handling
+            (KeyCode::Char(c), _) => {
+                self.app_state.write().await.command_input.push(c);
+                self.update_text_search().await?;
             }
-        }
-
-        Ok(())
-    }
-
-    /// Handle save command (Ctrl+S)
-    async fn handle_save_command(&self) -> Result<()> {
-        let app = self.app_state.read().await;
-        let active_buffer = app.active_buffer;
-        if let Some(buffer) = app.buffers.get(active_buffer) {
-            if let Some(path) = &buffer.path {
-                let content = buffer.content_as_string();
-                let path = path.clone();
-                drop(app);
-
-                // Save asynchronously
-                if let Err(e) = tokio::fs::write(&path, content).await {
-                    let error_msg = format!("Error saving file: {}", e);
-                    self.event_sender.send(AppEvent::StatusMessage {
-                        message: error_msg.into(),
-                    })?;
-                } else {
-                    // Mark buffer as clean
-                    let mut app = self.app_state.write().await;
-                    if let Some(buffer) = app.buffers.get_mut(active_buffer) {
-                        buffer.modified = false;
-                    }
-                    drop(app);
-
-                    let success_message = format!("File saved: {}", path.display());
-                    let success_msg: Arc<str> = success_message.into();
-                    self.event_sender.send(AppEvent::ToastMessage {
-                        message: success_msg.clone(),
-                        toast_type: "success".into(),
-                    })?;
-                    self.event_sender.send(AppEvent::StatusMessage {
-                        message: success_msg,
-                    })?;
-                }
-            } else {
-                drop(app);
-                self.event_sender.send(AppEvent::StatusMessage {
-                    message: "No file path - use save as command".into(),
-                })?;
+            (KeyCode::Backspace, _) => {
+                self.app_state.write().await.command_input.pop();
+                self.update_text_search().await?;
             }
+            _ => {}
         }
+
         Ok(())
     }
 
-    /// Handle open command (Ctrl+O) - opens command palette with open command
-    async fn handle_open_command(&self) -> Result<()> {
-        // Switch to command mode and pre-fill with "open "
+    /// Open the search prompt (Ctrl+F)
+    async fn handle_open_text_search(&self) -> Result<()> {
         let mut app = self.app_state.write().await;
-        app.command_mode = CommandMode::Command;
-        app.command_input = "open ".to_string();
-        app.show_command_palette = true;
+        // Matches are jumped to as the query is typed, so the jump is from
+        // where the search started
+        app.record_jump();
+        app.command_mode = CommandMode::TextSearch;
+        app.command_input.clear();
         drop(app);
 
         self.event_sender.send(AppEvent::ModeChanged {
-            new_mode: "command".into(),
+            new_mode: "text_search".into(),
         })?;
-        self.event_sender.send(AppEvent::ShowCommandPalette)?;
         self.event_sender.send(AppEvent::CursorHide {
             context: "editor".into(),
         })?;
         self.event_sender.send(AppEvent::CursorShow {
-            context: "command_palette".into(),
+            context: "text_search".into(),
         })?;
         Ok(())
     }
 
-    /// Handle new buffer command (Ctrl+N)
-    async fn handle_new_buffer(&self) -> Result<()> {
+    /// Compile the typed query and move the cursor to the first match at or
+    /// after it
+    ///
+    /// A regex that doesn't compile (yet) clears the highlights.
+    async fn update_text_search(&self) -> Result<()> {
         let mut app = self.app_state.write().await;
-        let new_buffer = crate::buffer::Buffer::new();
-        app.add_buffer(new_buffer);
+        let pattern = app.command_input.clone();
+        let query = if app.search_regex {
+            match SearchQuery::regex(pattern) {
+                Ok(query) => query,
+                Err(_) => {
+                    app.search_query = None;
+                    return Ok(());
+                }
+            }
+        } else {
+            SearchQuery::new(pattern)
+        };
+        let active_buffer = app.active_buffer;
+        let found = app.buffers.get_mut(active_buffer).and_then(|buffer| {
+            let cursor = Position::from_tuple(buffer.cursor_pos);
+            let (start, _) = query.next_match(buffer, cursor)?;
+            buffer.cursor_pos = start.to_tuple();
+            Some(start)
+        });
+        let empty = query.is_empty();
+        app.search_query = Some(query);
         drop(app);
 
-        self.event_sender.send(AppEvent::StatusMessage {
-            message: "New buffer created".into(),
-        })?;
+        match found {
+            Some(start) => {
+                self.event_sender.send(AppEvent::BufferCursorMoved {
+                    buffer_id: active_buffer,
+                    row: start.row,
+                    col: start.col,
+                })?;
+            }
+            None if !empty => {
+                self.event_sender.send(AppEvent::StatusMessage {
+                    message: "Pattern not found".into(),
+                })?;
+            }
+            None => {}
+        }
         Ok(())
     }
 
-    /// Handle toggle visual mode (Ctrl+V)
-    async fn handle_toggle_visual_mode(&self) -> Result<()> {
+    /// Replace matches of a regex in the active buffer (`%s/pattern/replacement/flags`)
+    ///
+    /// `spec` is everything after `%s`. The `g` flag replaces every match on a
+    /// line instead of just the first; `i` ignores case. `$1` in the
+    /// replacement inserts the first capture group.
+    async fn handle_substitute(&self, spec: &str) -> Result<()> {
+        let Some((pattern, replacement, flags)) = parse_substitution(spec) else {
+            self.event_sender.send(AppEvent::StatusMessage {
+                message: "Usage: %s/pattern/replacement/[g][i]".into(),
+            })?;
+            return Ok(());
+        };
+
+        let regex = match RegexBuilder::new(&pattern)
+            .case_insensitive(flags.contains('i'))
+            .build()
+        {
+            Ok(regex) => regex,
+            Err(e) => return self.send_invalid_regex_toast(&e),
+        };
+        let limit = if flags.contains('g') { 0 } else { 1 };
+
         let mut app = self.app_state.write().await;
         let active_buffer = app.active_buffer;
+        let Some(buffer) = app.buffers.get_mut(active_buffer) else {
+            return Ok(());
+        };
+        let replaced = buffer.replace_regex(&regex, &replacement, limit);
+        let change = buffer.take_change();
+        let became_modified = buffer.take_became_modified();
+        drop(app);
 
-        if let Some(buffer) = app.buffers.get_mut(active_buffer) {
-            buffer.toggle_visual_mode();
-            let message = if buffer.visual_mode {
-                "Visual mode enabled"
-            } else {
-                "Visual mode disabled"
-            };
-            drop(app);
-
+        if replaced == 0 {
+            let not_found_msg = format!("Pattern not found: {}", pattern);
             self.event_sender.send(AppEvent::StatusMessage {
-                message: message.into(),
+                message: not_found_msg.into(),
             })?;
+            return Ok(());
         }
+
+        self.send_buffer_edited(active_buffer, change, became_modified)?;
+        let plural = if replaced == 1 { "" } else { "s" };
+        let replaced_msg = format!("Replaced {} occurrence{}", replaced, plural);
+        self.event_sender.send(AppEvent::ToastMessage {
+            message: replaced_msg.into(),
+            toast_type: "info".into(),
+            duration: None,
+        })?;
         Ok(())
     }
 
-    /// Handle copy command (Ctrl+C)
-    async fn handle_copy(&self) -> Result<()> {
-        let app = self.app_state.read().await;
-        if let Some(buffer) = app.buffers.get(app.active_buffer) {
-            if let Some(selected_text) = buffer.get_selected_text() {
-                drop(app);
-                // TODO: Implement clipboard integration
-                let copy_msg = format!("Copied {} characters", selected_text.len());
-                self.event_sender.send(AppEvent::StatusMessage {
-                    message: copy_msg.into(),
-                })?;
-            } else {
-                drop(app);
-                self.event_sender.send(AppEvent::StatusMessage {
-                    message: "No text selected".into(),
-                })?;
-            }
+    /// Tell subscribers which lines of a buffer changed, if any did, and
+    /// whether that made a saved buffer modified
+    fn send_buffer_edited(
+        &self,
+        buffer_id: usize,
+        change: Option<LineChange>,
+        became_modified: bool,
+    ) -> Result<()> {
+        if let Some(change) = change {
+            self.event_sender.send(AppEvent::BufferEdited {
+                buffer_id,
+                start_line: change.start,
+                old_line_count: change.old_count,
+                new_line_count: change.new_count,
+            })?;
+        }
+        if became_modified {
+            self.event_sender.send(AppEvent::BufferModifiedChanged {
+                buffer_id,
+                modified: true,
+            })?;
         }
         Ok(())
     }
 
-    /// Handle cut command (Ctrl+X)
-    async fn handle_cut(&self) -> Result<()> {
-        let mut app = self.app_state.write().await;
-        let active_buffer = app.active_buffer;
+    /// Remember a cursor position taken from the app and persist the
+    /// positions, touching the disk only while the app is free
+    async fn remember_cursor_position(&self, cursor: Option<(PathBuf, Position)>) -> Result<()> {
+        let Some((path, position)) = cursor else {
+            return Ok(());
+        };
+        let path = tokio::fs::canonicalize(&path).await.unwrap_or(path);
+
+        let _turn = self.state_writes.lock().await;
+        let mut app = self.app_state.write().await;
+        app.note_cursor_position(path, position);
+        let user_dir = app.user_dir.clone();
+        let positions = app.cursor_positions.clone();
+        drop(app);
+        write_cursor_positions(&user_dir, &positions).await
+    }
+
+    /// Put a file at the front of the recent files and persist them,
+    /// touching the disk only while the app is free
+    async fn remember_recent_file(&self, path: PathBuf) -> Result<()> {
+        let path = tokio::fs::canonicalize(&path).await.unwrap_or(path);
+
+        let _turn = self.state_writes.lock().await;
+        let mut app = self.app_state.write().await;
+        app.note_recent_file(path);
+        let user_dir = app.user_dir.clone();
+        let recent_files = app.recent_files.clone();
+        drop(app);
+        write_recent_files(&user_dir, &recent_files).await
+    }
+
+    /// Warn when the cursor positions couldn't be persisted
+    fn warn_if_not_remembered(&self, remembered: Result<()>) -> Result<()> {
+        if let Err(e) = remembered {
+            self.event_sender.send(AppEvent::ToastMessage {
+                message: format!("Could not save cursor positions: {}", e).into(),
+                toast_type: "warning".into(),
+                duration: None,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Tell subscribers a buffer was saved
+    fn send_buffer_saved(&self, buffer_id: usize) -> Result<()> {
+        self.event_sender.send(AppEvent::BufferModifiedChanged {
+            buffer_id,
+            modified: false,
+        })?;
+        Ok(())
+    }
+
+    /// Report a regex that failed to compile
+    fn send_invalid_regex_toast(&self, error: &regex::Error) -> Result<()> {
+        let error_msg = format!("Invalid regex: {}", error);
+        self.event_sender.send(AppEvent::ToastMessage {
+            message: error_msg.into(),
+            toast_type: "error".into(),
+            duration: None,
+        })?;
+        Ok(())
+    }
+
+    /// Handle save command (Ctrl+S)
+    async fn handle_save_command(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let active_buffer = app.active_buffer;
+        let save_options = app.save_options();
+        let Some(buffer) = app.buffers.get_mut(active_buffer) else {
+            return Ok(());
+        };
+        let Some(path) = buffer.path.clone() else {
+            drop(app);
+            self.event_sender.send(AppEvent::StatusMessage {
+                message: "No file path - use saveas <file_path>".into(),
+            })?;
+            return Ok(());
+        };
+
+        // Ask before writing over changes another program made
+        buffer.check_disk();
+        if buffer.changed_on_disk {
+            app.unsaved_prompt = Some(UnsavedPrompt::Overwrite);
+            return Ok(());
+        }
+
+        buffer.save_options = save_options;
+        let was_modified = buffer.modified;
+        let save = buffer.begin_save(path.clone());
+        drop(app);
+
+        // The file is written without holding the app, so a slow disk
+        // doesn't freeze the screen
+        let result = write_save(save).await;
+        let mut saved_index = None;
+        if let Ok((save, _)) = &result {
+            let mut app = self.app_state.write().await;
+            saved_index = app.finish_save(save);
+            let cursor = saved_index.and_then(|index| app.cursor_position_to_remember(index));
+            drop(app);
+            let remembered = self.remember_cursor_position(cursor).await;
+            self.warn_if_not_remembered(remembered)?;
+        }
+
+        match result {
+            Ok((_, written)) => {
+                if let Some(index) = saved_index.filter(|_| was_modified) {
+                    self.send_buffer_saved(index)?;
+                }
+                // Content matching the file isn't written again
+                let (success_msg, toast_type): (Arc<str>, _) = if written {
+                    (format!("File saved: {}", path.display()).into(), "success")
+                } else {
+                    (
+                        format!("No changes to save: {}", path.display()).into(),
+                        "info",
+                    )
+                };
+                self.event_sender.send(AppEvent::ToastMessage {
+                    message: success_msg.clone(),
+                    toast_type: toast_type.into(),
+                    duration: None,
+                })?;
+                self.event_sender.send(AppEvent::StatusMessage {
+                    message: success_msg,
+                })?;
+            }
+            Err(e) => {
+                self.event_sender.send(AppEvent::StatusMessage {
+                    message: format!("Error saving file: {}", e).into(),
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Save the active buffer to a new path and switch the buffer over to it
+    ///
+    /// Missing directories are created. An existing file other than the
+    /// buffer's own is only overwritten with `force`.
+    async fn handle_save_as(&self, file_path: &str, force: bool) -> Result<()> {
+        let path = PathBuf::from(file_path);
+
+        let mut app = self.app_state.write().await;
+        let active_buffer = app.active_buffer;
+        let save_options = app.save_options();
+        let Some(buffer) = app.buffers.get_mut(active_buffer) else {
+            return Ok(());
+        };
+
+        let is_own_file = buffer.path.as_ref() == Some(&path);
+        if is_own_file && !force {
+            // Ask before writing over changes another program made, as a
+            // plain save would
+            buffer.check_disk();
+            if buffer.changed_on_disk {
+                app.unsaved_prompt = Some(UnsavedPrompt::Overwrite);
+                return Ok(());
+            }
+        }
+        let was_modified = buffer.modified;
+        let mut saved_index = None;
+        let result = if path.exists() && !is_own_file && !force {
+            drop(app);
+            Err(anyhow::anyhow!(
+                "{} already exists - use saveas! to overwrite",
+                path.display()
+            ))
+        } else {
+            buffer.save_options = save_options;
+            let save = buffer.begin_save(path.clone());
+            drop(app);
+
+            // The file is written without holding the app
+            let result = write_to_new_path(save).await;
+            if let Ok(save) = &result {
+                saved_index = self.app_state.write().await.finish_save(save);
+            }
+            result.map(|_| ())
+        };
+
+        match result {
+            Ok(()) => {
+                if let Some(index) = saved_index.filter(|_| was_modified) {
+                    self.send_buffer_saved(index)?;
+                }
+                let success_msg: Arc<str> = format!("File saved: {}", path.display()).into();
+                self.event_sender.send(AppEvent::ToastMessage {
+                    message: success_msg.clone(),
+                    toast_type: "success".into(),
+                    duration: None,
+                })?;
+                self.event_sender.send(AppEvent::StatusMessage {
+                    message: success_msg,
+                })?;
+            }
+            Err(e) => {
+                let error_msg = format!("Error saving file: {}", e);
+                self.event_sender.send(AppEvent::ToastMessage {
+                    message: error_msg.into(),
+                    toast_type: "error".into(),
+                    duration: None,
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle open command (Ctrl+P) - opens command palette with open command
+    async fn handle_open_command(&self) -> Result<()> {
+        self.open_command_palette_with("open ").await
+    }
+
+    /// Open the command palette with `input` already typed
+    async fn open_command_palette_with(&self, input: &str) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        app.command_mode = CommandMode::Command;
+        app.command_input = input.to_string();
+        app.update_command_suggestions();
+        app.show_command_palette = true;
+        drop(app);
+
+        self.event_sender.send(AppEvent::ModeChanged {
+            new_mode: "command".into(),
+        })?;
+        self.event_sender.send(AppEvent::ShowCommandPalette)?;
+        self.event_sender.send(AppEvent::CursorHide {
+            context: "editor".into(),
+        })?;
+        self.event_sender.send(AppEvent::CursorShow {
+            context: "command_palette".into(),
+        })?;
+        Ok(())
+    }
+
+    /// Handle new buffer command (Ctrl+N)
+    async fn handle_new_buffer(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let new_buffer = crate::buffer::Buffer::new();
+        app.add_buffer(new_buffer);
+        drop(app);
+
+        self.event_sender.send(AppEvent::StatusMessage {
+            message: "New buffer created".into(),
+        })?;
+        Ok(())
+    }
+
+    /// Handle toggle visual mode (Ctrl+V)
+    async fn handle_toggle_visual_mode(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let active_buffer = app.active_buffer;
+
+        if let Some(buffer) = app.buffers.get_mut(active_buffer) {
+            buffer.toggle_visual_mode();
+            let message = if buffer.visual_mode {
+                "Visual mode enabled"
+            } else {
+                "Visual mode disabled"
+            };
+            drop(app);
+
+            self.event_sender.send(AppEvent::StatusMessage {
+                message: message.into(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Toggle selecting whole lines (Alt+V)
+    async fn handle_toggle_line_visual_mode(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let active_buffer = app.active_buffer;
+
+        if let Some(buffer) = app.buffers.get_mut(active_buffer) {
+            buffer.toggle_line_visual_mode();
+            let message = if buffer.visual_mode {
+                "Visual line mode enabled"
+            } else {
+                "Visual line mode disabled"
+            };
+            drop(app);
+
+            self.event_sender.send(AppEvent::StatusMessage {
+                message: message.into(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Handle copy command (Ctrl+C)
+    async fn handle_copy(&self) -> Result<()> {
+        let app = self.app_state.read().await;
+        if let Some(buffer) = app.buffers.get(app.active_buffer) {
+            if let Some(selected_text) = buffer.get_selected_text() {
+                drop(app);
+                // TODO: Implement clipboard integration
+                let copy_msg = format!("Copied {} characters", selected_text.len());
+                self.event_sender.send(AppEvent::StatusMessage {
+                    message: copy_msg.into(),
+                })?;
+            } else {
+                drop(app);
+                self.event_sender.send(AppEvent::StatusMessage {
+                    message: "No text selected".into(),
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle cut command (Ctrl+X)
+    async fn handle_cut(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let active_buffer = app.active_buffer;
 
         if let Some(buffer) = app.buffers.get_mut(active_buffer) {
             if let Some(selected_text) = buffer.get_selected_text() {
@@ -585,14 +1313,12 @@ impl KeyboardHandler {
                 buffer.visual_mode = false;
                 buffer.selection_start = None;
 
-                let content: Arc<str> = buffer.content_as_string().into();
+                let change = buffer.take_change();
+                let became_modified = buffer.take_became_modified();
                 drop(app);
 
                 // TODO: Implement clipboard integration
-                self.event_sender.send(AppEvent::BufferChanged {
-                    buffer_id: 0,
-                    content,
-                })?;
+                self.send_buffer_edited(active_buffer, change, became_modified)?;
                 let cut_msg = format!("Cut {} characters", selected_text.len());
                 self.event_sender.send(AppEvent::StatusMessage {
                     message: cut_msg.into(),
@@ -607,124 +1333,912 @@ impl KeyboardHandler {
         Ok(())
     }
 
-    /// Handle next buffer (Tab)
-    async fn handle_next_buffer(&self) -> Result<()> {
+    /// Comment or uncomment the selected lines, or the cursor's line, with
+    /// the comment prefix of the buffer's language (Ctrl+/)
+    async fn handle_toggle_comment(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let active_buffer = app.active_buffer;
+        let Some(buffer) = app.buffers.get_mut(active_buffer) else {
+            return Ok(());
+        };
+
+        let prefix = crate::syntax::comment_prefix_for_path(buffer.path.as_deref());
+        if buffer.toggle_line_comment(prefix) {
+            let (row, col) = buffer.cursor_pos;
+            let change = buffer.take_change();
+            let became_modified = buffer.take_became_modified();
+            drop(app);
+
+            self.send_buffer_edited(active_buffer, change, became_modified)?;
+            self.event_sender.send(AppEvent::BufferCursorMoved {
+                buffer_id: active_buffer,
+                row,
+                col,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Apply `edit` to the active buffer, reporting the lines it changed and
+    /// where the cursor ended up if it changed any
+    async fn edit_active_buffer(&self, edit: impl FnOnce(&mut Buffer) -> bool) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let active_buffer = app.active_buffer;
+        let Some(buffer) = app.buffers.get_mut(active_buffer) else {
+            return Ok(());
+        };
+        if !edit(buffer) {
+            return Ok(());
+        }
+
+        let (row, col) = buffer.cursor_pos;
+        let change = buffer.take_change();
+        let became_modified = buffer.take_became_modified();
+        drop(app);
+
+        self.send_buffer_edited(active_buffer, change, became_modified)?;
+        self.event_sender.send(AppEvent::BufferCursorMoved {
+            buffer_id: active_buffer,
+            row,
+            col,
+        })?;
+        Ok(())
+    }
+
+    /// Indent or unindent the selected lines when the selection spans
+    /// several of them, and switch buffers otherwise (Tab, Shift+Tab)
+    async fn handle_tab(&self, unindent: bool) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let use_spaces = app.config.editor.use_spaces;
+        let tab_size = app.config.editor.tab_size;
+        let active_buffer = app.active_buffer;
+        let Some(buffer) = app
+            .buffers
+            .get_mut(active_buffer)
+            .filter(|buffer| buffer.selected_rows().is_some())
+        else {
+            drop(app);
+            return if unindent {
+                self.handle_prev_buffer().await
+            } else {
+                self.handle_next_buffer().await
+            };
+        };
+
+        let changed = if unindent {
+            buffer.unindent_selection(tab_size)
+        } else {
+            buffer.indent_selection(use_spaces, tab_size)
+        };
+        if changed {
+            let (row, col) = buffer.cursor_pos;
+            let change = buffer.take_change();
+            let became_modified = buffer.take_became_modified();
+            drop(app);
+
+            self.send_buffer_edited(active_buffer, change, became_modified)?;
+            self.event_sender.send(AppEvent::BufferCursorMoved {
+                buffer_id: active_buffer,
+                row,
+                col,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Handle next buffer (Tab)
+    async fn handle_next_buffer(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let buffer_count = app.buffers.len();
+        if buffer_count > 1 {
+            let next = (app.active_buffer + 1) % buffer_count;
+            app.switch_to_buffer(next);
+            let buffer_name = app.buffers[app.active_buffer].name.clone();
+            drop(app);
+
+            let switch_msg = format!("Switched to buffer: {}", buffer_name);
+            self.event_sender.send(AppEvent::StatusMessage {
+                message: switch_msg.into(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Handle previous buffer (Shift+Tab)
+    async fn handle_prev_buffer(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let buffer_count = app.buffers.len();
+        if buffer_count > 1 {
+            let prev = if app.active_buffer == 0 {
+                buffer_count - 1
+            } else {
+                app.active_buffer - 1
+            };
+            app.switch_to_buffer(prev);
+            let buffer_name = app.buffers[app.active_buffer].name.clone();
+            drop(app);
+
+            let switch_msg = format!("Switched to buffer: {}", buffer_name);
+            self.event_sender.send(AppEvent::StatusMessage {
+                message: switch_msg.into(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Switch to the `number`th open buffer, counting from 1 (buffer N, Alt+N)
+    async fn handle_switch_to_buffer(&self, number: usize) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let buffer_count = app.buffers.len();
+        if !number
+            .checked_sub(1)
+            .is_some_and(|index| app.switch_to_buffer(index))
+        {
+            drop(app);
+            let error_msg = format!("No buffer {} ({} open)", number, buffer_count);
+            self.event_sender.send(AppEvent::ToastMessage {
+                message: error_msg.into(),
+                toast_type: "error".into(),
+                duration: None,
+            })?;
+            return Ok(());
+        }
+        let buffer_name = app.buffers[app.active_buffer].name.clone();
+        drop(app);
+
+        let switch_msg = format!("Switched to buffer: {}", buffer_name);
+        self.event_sender.send(AppEvent::StatusMessage {
+            message: switch_msg.into(),
+        })?;
+        Ok(())
+    }
+
+    /// Split the editor, showing the next buffer in a new pane to the right
+    async fn handle_split(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        app.split_horizontal();
+        let split_msg = format!(
+            "Split: pane {}/{} showing {}",
+            app.focused_pane + 1,
+            app.panes.len(),
+            app.buffers[app.active_buffer].name
+        );
+        drop(app);
+
+        self.event_sender.send(AppEvent::StatusMessage {
+            message: split_msg.into(),
+        })?;
+        Ok(())
+    }
+
+    /// Move focus to the next split pane (Alt+W)
+    async fn handle_focus_next_pane(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        if app.focus_next_pane() {
+            let active_buffer = app.active_buffer;
+            let (row, col) = app.buffers[active_buffer].cursor_pos;
+            drop(app);
+
+            self.event_sender.send(AppEvent::BufferCursorMoved {
+                buffer_id: active_buffer,
+                row,
+                col,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Close every split pane except the focused one
+    async fn handle_close_other_panes(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let message = if app.close_other_panes() {
+            "Closed other panes"
+        } else {
+            "No other panes"
+        };
+        drop(app);
+
+        self.event_sender.send(AppEvent::StatusMessage {
+            message: message.into(),
+        })?;
+        Ok(())
+    }
+
+    /// Quit, or ask what to do first if there are unsaved changes
+    async fn handle_quit_request(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        if app.has_unsaved_changes() {
+            app.unsaved_prompt = Some(UnsavedPrompt::Quit);
+        } else {
+            drop(app);
+            self.event_sender.send(AppEvent::Quit)?;
+        }
+        Ok(())
+    }
+
+    /// Close the active buffer, or ask what to do first if it's modified
+    async fn handle_close_request(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let dirty = app
+            .buffers
+            .get(app.active_buffer)
+            .is_some_and(|buffer| buffer.is_dirty());
+        if dirty {
+            app.unsaved_prompt = Some(UnsavedPrompt::CloseBuffer);
+            return Ok(());
+        }
+        drop(app);
+
+        self.close_active_buffer().await
+    }
+
+    /// Close the active buffer and report which buffer took its place
+    async fn close_active_buffer(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let cursor = app.cursor_position_to_remember(app.active_buffer);
+        if !app.close_current_buffer() {
+            return Ok(());
+        }
+        let active_buffer = app.active_buffer;
+        let buffer = &app.buffers[active_buffer];
+        let (row, col) = buffer.cursor_pos;
+        let close_msg = format!("Now editing {}", buffer.name);
+        drop(app);
+        let remembered = self.remember_cursor_position(cursor).await;
+        self.warn_if_not_remembered(remembered)?;
+
+        self.event_sender.send(AppEvent::ToastMessage {
+            message: close_msg.into(),
+            toast_type: "info".into(),
+            duration: None,
+        })?;
+        self.event_sender.send(AppEvent::BufferCursorMoved {
+            buffer_id: active_buffer,
+            row,
+            col,
+        })?;
+        Ok(())
+    }
+
+    /// Handle keyboard input while the unsaved-changes prompt is open
+    async fn handle_unsaved_prompt_key(&self, prompt: UnsavedPrompt, key: KeyEvent) -> Result<()> {
+        match key.code {
+            // Saving before reloading would only reload what was saved
+            KeyCode::Char('s') if prompt != UnsavedPrompt::Reload => {
+                let mut app = self.app_state.write().await;
+                app.unsaved_prompt = None;
+                let unsaved = app.unsaved_buffers_for(prompt);
+
+                // Stop at the first file another program changed, asking
+                // before writing over it
+                if prompt != UnsavedPrompt::Overwrite {
+                    let changed = unsaved.iter().copied().find(|&index| {
+                        let buffer = &mut app.buffers[index];
+                        buffer.check_disk();
+                        buffer.changed_on_disk
+                    });
+                    if let Some(index) = changed {
+                        app.switch_to_buffer(index);
+                        app.unsaved_prompt = Some(UnsavedPrompt::Overwrite);
+                        return Ok(());
+                    }
+                }
+
+                let save_options = app.save_options();
+                let mut errors = Vec::new();
+                let mut saves = Vec::new();
+                for index in unsaved {
+                    let buffer = &mut app.buffers[index];
+                    buffer.save_options = save_options;
+                    let Some(path) = buffer.path.clone() else {
+                        errors.push(format!(
+                            "{}: No file path associated with buffer",
+                            buffer.name
+                        ));
+                        continue;
+                    };
+                    saves.push((buffer.name.clone(), buffer.begin_save(path)));
+                }
+                drop(app);
+
+                // Write the files without holding the app
+                let mut written = Vec::new();
+                for (name, save) in saves {
+                    match write_save(save).await {
+                        Ok((save, _)) => written.push(save),
+                        Err(e) => errors.push(format!("{}: {}", name, e)),
+                    }
+                }
+                let mut app = self.app_state.write().await;
+                let saved: Vec<usize> = written
+                    .iter()
+                    .filter_map(|save| app.finish_save(save))
+                    .collect();
+                drop(app);
+
+                for index in saved {
+                    self.send_buffer_saved(index)?;
+                }
+
+                if errors.is_empty() {
+                    // Saving over the changed file was all there was to do
+                    if prompt != UnsavedPrompt::Overwrite {
+                        self.finish_unsaved_prompt(prompt).await?;
+                    }
+                } else {
+                    let error_msg = format!("Error saving {}", errors.join(", "));
+                    self.event_sender.send(AppEvent::ToastMessage {
+                        message: error_msg.into(),
+                        toast_type: "error".into(),
+                        duration: None,
+                    })?;
+                }
+            }
+            KeyCode::Char('d') => {
+                self.app_state.write().await.unsaved_prompt = None;
+                self.finish_unsaved_prompt(prompt).await?;
+            }
+            KeyCode::Esc | KeyCode::Char('c') => {
+                self.app_state.write().await.unsaved_prompt = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Carry out the action the unsaved-changes prompt was holding back
+    async fn finish_unsaved_prompt(&self, prompt: UnsavedPrompt) -> Result<()> {
+        match prompt {
+            UnsavedPrompt::Quit => {
+                self.event_sender.send(AppEvent::Quit)?;
+                Ok(())
+            }
+            UnsavedPrompt::CloseBuffer => self.close_active_buffer().await,
+            UnsavedPrompt::Reload | UnsavedPrompt::Overwrite => self.reload_active_buffer().await,
+        }
+    }
+
+    /// Reload the active buffer from disk, asking first if it has unsaved
+    /// changes unless `force` is set
+    async fn handle_reload_request(&self, force: bool) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let Some(buffer) = app.buffers.get(app.active_buffer) else {
+            return Ok(());
+        };
+        if buffer.path.is_none() {
+            drop(app);
+            self.event_sender.send(AppEvent::ToastMessage {
+                message: "No file to reload - the buffer was never saved".into(),
+                toast_type: "error".into(),
+                duration: None,
+            })?;
+            return Ok(());
+        }
+        if buffer.is_dirty() && !force {
+            app.unsaved_prompt = Some(UnsavedPrompt::Reload);
+            return Ok(());
+        }
+        drop(app);
+
+        self.reload_active_buffer().await
+    }
+
+    /// Replace the active buffer's content with its file on disk
+    async fn reload_active_buffer(&self) -> Result<()> {
+        let app = self.app_state.read().await;
+        let Some(buffer) = app.buffers.get(app.active_buffer) else {
+            return Ok(());
+        };
+        let (id, path, name) = (buffer.id, buffer.path.clone(), buffer.name.clone());
+        drop(app);
+
+        // Read the file without holding the app
+        let result = match path {
+            Some(path) => tokio::fs::read(path).await,
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "No file path associated with buffer",
+            )),
+        };
+        let bytes = match result {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.event_sender.send(AppEvent::ToastMessage {
+                    message: format!("Failed to reload {}: {}", name, e).into(),
+                    toast_type: "error".into(),
+                    duration: None,
+                })?;
+                return Ok(());
+            }
+        };
+
+        let mut app = self.app_state.write().await;
+        let Some(active_buffer) = app.buffers.iter().position(|buffer| buffer.id == id) else {
+            return Ok(());
+        };
+        let buffer = &mut app.buffers[active_buffer];
+        let was_modified = buffer.modified;
+        buffer.reload_from(&bytes);
+        let change = buffer.take_change();
+        let (row, col) = buffer.cursor_pos;
+        drop(app);
+
+        self.send_buffer_edited(active_buffer, change, false)?;
+        if was_modified {
+            self.send_buffer_saved(active_buffer)?;
+        }
+        self.event_sender.send(AppEvent::BufferCursorMoved {
+            buffer_id: active_buffer,
+            row,
+            col,
+        })?;
+        self.event_sender.send(AppEvent::StatusMessage {
+            message: format!("Reloaded {}", name).into(),
+        })?;
+        Ok(())
+    }
+
+    /// Scroll the cursor line to the middle of the editor (Ctrl+L)
+    async fn handle_center_cursor(&self) -> Result<()> {
         let mut app = self.app_state.write().await;
-        let buffer_count = app.buffers.len();
-        if buffer_count > 1 {
-            app.active_buffer = (app.active_buffer + 1) % buffer_count;
-            app.scroll_offset = (0, 0);
-            let buffer_name = app.buffers[app.active_buffer].name.clone();
-            drop(app);
+        let editor_area = app.editor_area();
+        app.center_cursor(editor_area);
+        Ok(())
+    }
 
-            let switch_msg = format!("Switched to buffer: {}", buffer_name);
+    /// Show or hide the frame timing overlay (F10)
+    async fn handle_toggle_performance_overlay(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        app.show_performance_overlay = !app.show_performance_overlay;
+        Ok(())
+    }
+
+    /// Show the help listing the commands (F1)
+    async fn handle_show_help(&self) -> Result<()> {
+        self.app_state.write().await.open_help();
+        Ok(())
+    }
+
+    /// Scroll the help with the arrow and page keys, or close it with Esc,
+    /// `q` or F1
+    async fn handle_help_key(&self, key: KeyEvent) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let page = app.editor_area().height.max(1) as isize;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::F(1) => app.show_help = false,
+            KeyCode::Up | KeyCode::Char('k') => app.scroll_help(-1),
+            KeyCode::Down | KeyCode::Char('j') => app.scroll_help(1),
+            KeyCode::PageUp => app.scroll_help(-page),
+            KeyCode::PageDown => app.scroll_help(page),
+            KeyCode::Home => app.help_scroll = 0,
+            KeyCode::End => app.scroll_help(isize::MAX),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Execute a command from the command palette, looked up by name or alias
+    /// in the app's command registry
+    async fn execute_command(&self, command: &str) -> Result<()> {
+        // Substitutions keep their spaces, so they're handled before splitting
+        if let Some(spec) = command.trim_start().strip_prefix("%s") {
+            self.handle_substitute(spec).await?;
+            let mut app = self.app_state.write().await;
+            app.command_input.clear();
+            app.push_command_history(command);
+            return Ok(());
+        }
+
+        let call = CommandCall::parse(command);
+        if call.name.is_empty() {
+            return Ok(());
+        }
+
+        let run = self.app_state.read().await.commands.get(&call.name);
+        let recognized = if let Some(mark) = call.name.strip_prefix('\'') {
+            // `'a` jumps to mark `a`
+            match single_char(mark) {
+                Some(name) => self.handle_jump_to_mark(name).await?,
+                None => self.send_usage("'<a-z|A-Z>")?,
+            }
+            true
+        } else if let Some(run) = run {
+            run(self.clone(), call).await?;
+            true
+        } else {
+            let unknown_cmd_msg = format!("Unknown command: {}", call.name);
             self.event_sender.send(AppEvent::StatusMessage {
-                message: switch_msg.into(),
+                message: unknown_cmd_msg.into(),
             })?;
+            false
+        };
+
+        // Clear command input, remembering it if it ran
+        let mut app = self.app_state.write().await;
+        app.command_input.clear();
+        app.update_command_suggestions();
+        if recognized {
+            app.push_command_history(command);
         }
+
         Ok(())
     }
 
-    /// Handle previous buffer (Shift+Tab)
-    async fn handle_prev_buffer(&self) -> Result<()> {
+    /// Show how a command is used in the status bar
+    fn send_usage(&self, usage: &str) -> Result<()> {
+        self.event_sender.send(AppEvent::StatusMessage {
+            message: format!("Usage: {}", usage).into(),
+        })?;
+        Ok(())
+    }
+
+    /// Run a plugin command on an [`EditorContext`], reporting what it did
+    /// to the active buffer like any other edit, and toasting if it fails
+    async fn execute_plugin_command(
+        &self,
+        command: &dyn PluginCommand,
+        args: &[String],
+    ) -> Result<()> {
         let mut app = self.app_state.write().await;
-        let buffer_count = app.buffers.len();
-        if buffer_count > 1 {
-            app.active_buffer = if app.active_buffer == 0 {
-                buffer_count - 1
-            } else {
-                app.active_buffer - 1
-            };
-            app.scroll_offset = (0, 0);
-            let buffer_name = app.buffers[app.active_buffer].name.clone();
-            drop(app);
+        let result = command.execute(&mut EditorContext::new(&mut app), args);
+        let active_buffer = app.active_buffer;
+        let edit = app.buffers.get_mut(active_buffer).map(|buffer| {
+            let change = buffer.take_change();
+            let became_modified = buffer.take_became_modified();
+            (change, became_modified, buffer.cursor_pos)
+        });
+        drop(app);
 
-            let switch_msg = format!("Switched to buffer: {}", buffer_name);
-            self.event_sender.send(AppEvent::StatusMessage {
-                message: switch_msg.into(),
+        if let Some((change, became_modified, (row, col))) = edit {
+            self.send_buffer_edited(active_buffer, change, became_modified)?;
+            self.event_sender.send(AppEvent::BufferCursorMoved {
+                buffer_id: active_buffer,
+                row,
+                col,
+            })?;
+        }
+        if let Err(e) = result {
+            let error_msg = format!("Error running {}: {}", command.name(), e);
+            self.event_sender.send(AppEvent::ToastMessage {
+                message: error_msg.into(),
+                toast_type: "error".into(),
+                duration: None,
             })?;
         }
         Ok(())
     }
 
-    /// Execute a command from the command palette
-    async fn execute_command(&self, command: &str) -> Result<()> {
-        let parts: Vec<&str> = command.split_whitespace().collect();
-        if parts.is_empty() {
+    /// Flip a boolean setting, save the config and report the new state
+    async fn toggle_config_flag(&self, label: &str, flag: ConfigFlag) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let enabled = {
+            let value = flag(&mut app.config);
+            *value = !*value;
+            *value
+        };
+        self.spawn_config_save(&mut app);
+        drop(app);
+
+        let status = if enabled { "enabled" } else { "disabled" };
+        let status_msg = format!("{} {}", label, status);
+        self.event_sender.send(AppEvent::ToastMessage {
+            message: status_msg.into(),
+            toast_type: "info".into(),
+            duration: None,
+        })?;
+        Ok(())
+    }
+
+    /// Write the config of `app` to its user directory on one of its
+    /// background tasks, so a slow disk doesn't hold up the keys after the
+    /// command, toasting if it fails
+    ///
+    /// The config is copied now, while the caller holds the app, so the task
+    /// writes the settings the command left. Writes take turns, and one
+    /// whose copy is older than what was already written is skipped, so the
+    /// newest settings stay on disk.
+    fn spawn_config_save(&self, app: &mut App) {
+        let config = app.config.clone();
+        let user_dir = app.user_dir.clone();
+        let event_sender = self.event_sender.clone();
+        let config_writes = self.config_writes.clone();
+        let generation = config_writes.taken.fetch_add(1, Ordering::Relaxed) + 1;
+        app.background_tasks.spawn("config save", async move {
+            let mut written = config_writes.written.lock().await;
+            if *written > generation {
+                return;
+            }
+            *written = generation;
+            let result =
+                tokio::task::spawn_blocking(move || ConfigManager::save_config(&user_dir, config))
+                    .await;
+
+            let error = match result {
+                Ok(Ok(())) => return,
+                Ok(Err(e)) => e.to_string(),
+                Err(e) => e.to_string(),
+            };
+            let _ = event_sender.send(AppEvent::ToastMessage {
+                message: format!("Error saving config: {}", error).into(),
+                toast_type: "error".into(),
+                duration: None,
+            });
+        });
+    }
+
+    /// Show the line, word, char and byte counts of the selection or the
+    /// whole buffer
+    async fn handle_word_count(&self) -> Result<()> {
+        let app = self.app_state.read().await;
+        let Some(buffer) = app.buffers.get(app.active_buffer) else {
             return Ok(());
+        };
+        let stats = buffer.stats();
+        let scope = if buffer.get_selection_range().is_some() {
+            "Selection"
+        } else {
+            "Buffer"
+        };
+        drop(app);
+
+        let message = format!(
+            "{}: {} lines, {} words, {} chars, {} bytes",
+            scope, stats.lines, stats.words, stats.chars, stats.bytes
+        );
+        self.event_sender.send(AppEvent::ToastMessage {
+            message: message.into(),
+            toast_type: "info".into(),
+            duration: None,
+        })?;
+        Ok(())
+    }
+
+    /// Convert the active buffer's indentation to the configured style
+    async fn handle_retab(&self) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let use_spaces = app.config.editor.use_spaces;
+        let tab_size = app.config.editor.tab_size;
+        let active_buffer = app.active_buffer;
+        let Some(buffer) = app.buffers.get_mut(active_buffer) else {
+            return Ok(());
+        };
+
+        let changed = buffer.retab(use_spaces, tab_size);
+        let change = buffer.take_change();
+        let became_modified = buffer.take_became_modified();
+        drop(app);
+
+        self.send_buffer_edited(active_buffer, change, became_modified)?;
+        let style = if use_spaces { "spaces" } else { "tabs" };
+        self.event_sender.send(AppEvent::StatusMessage {
+            message: format!("Reindented {} lines with {}", changed, style).into(),
+        })?;
+        Ok(())
+    }
+
+    /// Remove repeated adjacent lines and report how many went
+    async fn handle_uniq(&self) -> Result<()> {
+        let mut removed = 0;
+        self.edit_active_buffer(|buffer| {
+            removed = buffer.dedup_lines();
+            removed > 0
+        })
+        .await?;
+
+        let message = match removed {
+            0 => "No duplicate lines".to_string(),
+            1 => "Removed 1 duplicate line".to_string(),
+            _ => format!("Removed {} duplicate lines", removed),
+        };
+        self.event_sender.send(AppEvent::ToastMessage {
+            message: message.into(),
+            toast_type: "info".into(),
+            duration: None,
+        })?;
+        Ok(())
+    }
+
+    /// End the active buffer's lines with `line_ending` when it's next saved
+    async fn handle_set_line_ending(&self, line_ending: LineEnding) -> Result<()> {
+        let mut converted = false;
+        self.edit_active_buffer(|buffer| {
+            converted = buffer.set_line_ending(line_ending);
+            converted
+        })
+        .await?;
+
+        let message = if converted {
+            format!("Converted line endings to {}", line_ending.name())
+        } else {
+            format!("Line endings are already {}", line_ending.name())
+        };
+        self.event_sender.send(AppEvent::StatusMessage {
+            message: message.into(),
+        })?;
+        Ok(())
+    }
+
+    /// Switch to another theme and save it as the configured one
+    async fn handle_set_theme(&self, name: &str) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let result = app.set_theme(name);
+        if result.is_ok() {
+            self.spawn_config_save(&mut app);
         }
+        drop(app);
 
-        match parts[0] {
-            "quit" | "q" => {
-                self.event_sender.send(AppEvent::Quit)?;
-            }
-            "save" | "w" => {
-                self.handle_save_command().await?;
-            }
-            "new" => {
-                self.handle_new_buffer().await?;
+        let (message, toast_type) = match result {
+            Ok(()) => (format!("Theme: {}", name), "info"),
+            Err(e) => (format!("Error switching theme: {}", e), "error"),
+        };
+        self.event_sender.send(AppEvent::ToastMessage {
+            message: message.into(),
+            toast_type: toast_type.into(),
+            duration: None,
+        })?;
+        Ok(())
+    }
+
+    /// Handle setting a local or global mark at the cursor
+    async fn handle_set_mark(&self, name: char) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let result = app.set_mark(name);
+        drop(app);
+
+        let message = match result {
+            Ok(()) => format!("Mark '{}' set", name),
+            Err(e) => format!("Error setting mark: {}", e),
+        };
+        self.event_sender.send(AppEvent::StatusMessage {
+            message: message.into(),
+        })?;
+        Ok(())
+    }
+
+    /// Move the cursor to the start of a line, counting from 1
+    async fn handle_goto_line(&self, line: usize) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let active_buffer = app.active_buffer;
+        app.record_jump();
+        let Some(buffer) = app.buffers.get_mut(active_buffer) else {
+            return Ok(());
+        };
+        buffer.place_cursor(Position::new(line.saturating_sub(1), 0));
+        let (row, col) = buffer.cursor_pos;
+        drop(app);
+
+        self.event_sender.send(AppEvent::BufferCursorMoved {
+            buffer_id: active_buffer,
+            row,
+            col,
+        })?;
+        Ok(())
+    }
+
+    /// Go back or forward through the jump list (Ctrl+O, Ctrl+I)
+    async fn handle_jump_list(&self, forward: bool) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let moved = if forward {
+            app.jump_forward()
+        } else {
+            app.jump_back()
+        };
+        let buffer_id = app.active_buffer;
+        let cursor = app.get_active_buffer().map(|buffer| buffer.cursor_pos);
+        drop(app);
+
+        match cursor {
+            Some((row, col)) if moved => {
+                self.event_sender.send(AppEvent::BufferCursorMoved {
+                    buffer_id,
+                    row,
+                    col,
+                })?;
             }
-            "open" | "o" => {
-                if parts.len() > 1 {
-                    let file_path = parts[1..].join(" ");
-                    self.handle_open_file(&file_path).await?;
+            _ => {
+                let message = if forward {
+                    "Already at the newest jump"
                 } else {
-                    self.event_sender.send(AppEvent::StatusMessage {
-                        message: "Usage: open <file_path>".into(),
-                    })?;
-                }
+                    "Already at the oldest jump"
+                };
+                self.event_sender.send(AppEvent::StatusMessage {
+                    message: message.into(),
+                })?;
             }
-            "next" | "n" => {
-                self.handle_next_buffer().await?;
+        }
+        Ok(())
+    }
+
+    /// Handle jumping to a local or global mark, switching buffers if needed
+    async fn handle_jump_to_mark(&self, name: char) -> Result<()> {
+        let app = self.app_state.read().await;
+        let unopened = app.unopened_mark_file(name);
+        drop(app);
+
+        // Read the file of a global mark without holding the app
+        let opened = match unopened {
+            Some(path) => crate::marks::read_marked_file(path).await.map(Some),
+            None => Ok(None),
+        };
+
+        let mut app = self.app_state.write().await;
+        match opened.and_then(|opened| app.jump_to_read_mark(name, opened)) {
+            Ok(()) => {
+                let buffer_id = app.active_buffer;
+                let (row, col) = app.buffers[buffer_id].cursor_pos;
+                drop(app);
+
+                self.event_sender.send(AppEvent::BufferCursorMoved {
+                    buffer_id,
+                    row,
+                    col,
+                })?;
             }
-            "prev" | "p" => {
-                self.handle_prev_buffer().await?;
+            Err(e) => {
+                drop(app);
+                let error_msg = format!("Error jumping to mark: {}", e);
+                self.event_sender.send(AppEvent::ToastMessage {
+                    message: error_msg.into(),
+                    toast_type: "error".into(),
+                    duration: None,
+                })?;
             }
-            "toggle_line_numbers" | "line_numbers" => {
-                // Toggle line numbers in the config
-                let app = self.app_state.read().await;
-                let config_dir = app.user_dir.clone();
-                let current_setting = app.get_line_numbers_setting();
+        }
+        Ok(())
+    }
+
+    /// Jump to the `search_all_buffers` match picked in the palette, or the
+    /// first one
+    async fn handle_search_all_buffers(&self, command: &str) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let query = crate::commands::search_all_buffers_query(command).unwrap_or_default();
+        let matches = crate::commands::search_all_buffers(&app.buffers, query);
+        let index = app.selected_suggestion.unwrap_or(0);
+        match matches.get(index).or(matches.first()) {
+            Some(found) => {
+                app.jump_to_buffer_match(found);
+                let buffer_id = app.active_buffer;
+                let (row, col) = app.buffers[buffer_id].cursor_pos;
                 drop(app);
 
-                // Update the config file
-                let mut config_manager = crate::config::ConfigManager::new(&config_dir);
-                if config_manager.load().is_ok() {
-                    // Toggle the setting
-                    config_manager.get_config_mut().editor.show_line_numbers = !current_setting;
-                    if let Err(e) = config_manager.save() {
-                        let error_msg = format!("Error saving config: {}", e);
-                        self.event_sender.send(AppEvent::ToastMessage {
-                            message: error_msg.into(),
-                            toast_type: "error".into(),
-                        })?;
-                    } else {
-                        let status = if !current_setting {
-                            "enabled"
-                        } else {
-                            "disabled"
-                        };
-                        let line_status_msg = format!("Line numbers {}", status);
-                        self.event_sender.send(AppEvent::ToastMessage {
-                            message: line_status_msg.into(),
-                            toast_type: "info".into(),
-                        })?;
-                    }
-                }
+                self.event_sender.send(AppEvent::BufferCursorMoved {
+                    buffer_id,
+                    row,
+                    col,
+                })?;
             }
-            _ => {
-                let unknown_cmd_msg = format!("Unknown command: {}", parts[0]);
+            None => {
+                drop(app);
+                let not_found_msg = format!("No buffer contains: {}", query);
                 self.event_sender.send(AppEvent::StatusMessage {
-                    message: unknown_cmd_msg.into(),
+                    message: not_found_msg.into(),
                 })?;
             }
         }
+        Ok(())
+    }
 
-        // Clear command input
+    /// Open the recent file picked in the palette, or the most recent one,
+    /// switching to it if it's already open
+    async fn handle_open_recent(&self, command: &str) -> Result<()> {
         let mut app = self.app_state.write().await;
-        app.command_input.clear();
+        let query = crate::recent::open_recent_query(command).unwrap_or_default();
+        let matches = app.recent_file_matches(query);
+        let index = app.selected_suggestion.unwrap_or(0);
+        let Some(path) = matches.get(index).or(matches.first()).cloned() else {
+            drop(app);
+            self.event_sender.send(AppEvent::StatusMessage {
+                message: "No recent files".into(),
+            })?;
+            return Ok(());
+        };
 
-        Ok(())
+        if let Some(index) = app.find_buffer_by_path(&path) {
+            app.switch_to_buffer(index);
+            return Ok(());
+        }
+        drop(app);
+        self.handle_open_file(&path.to_string_lossy()).await
     }
 
     /// Handle opening a file
@@ -733,15 +2247,45 @@ impl KeyboardHandler {
 
         match crate::buffer::Buffer::from_path_async(path.clone()).await {
             Ok(buffer) => {
+                let encoding_warning = buffer.encoding_warning();
+                let content: Arc<str> = buffer.content_as_string().into();
                 let mut app = self.app_state.write().await;
-                app.add_buffer(buffer);
+                let buffer_id = app.add_buffer(buffer);
+                app.restore_cursor_position(buffer_id);
+                let (row, col) = app.buffers[buffer_id].cursor_pos;
                 drop(app);
+                let remembered = self.remember_recent_file(path).await;
+
+                self.event_sender
+                    .send(AppEvent::BufferChanged { buffer_id, content })?;
+                self.event_sender.send(AppEvent::BufferCursorMoved {
+                    buffer_id,
+                    row,
+                    col,
+                })?;
+
+                if let Err(e) = remembered {
+                    self.event_sender.send(AppEvent::ToastMessage {
+                        message: format!("Could not save recent files: {}", e).into(),
+                        toast_type: "warning".into(),
+                        duration: None,
+                    })?;
+                }
+
+                if let Some(warning) = encoding_warning {
+                    self.event_sender.send(AppEvent::ToastMessage {
+                        message: warning.into(),
+                        toast_type: "warning".into(),
+                        duration: None,
+                    })?;
+                }
 
                 let success_message = format!("Opened file: {}", file_path);
                 let success_msg: Arc<str> = success_message.into();
                 self.event_sender.send(AppEvent::ToastMessage {
                     message: success_msg.clone(),
                     toast_type: "success".into(),
+                    duration: None,
                 })?;
                 self.event_sender.send(AppEvent::StatusMessage {
                     message: success_msg,
@@ -753,6 +2297,7 @@ impl KeyboardHandler {
                 self.event_sender.send(AppEvent::ToastMessage {
                     message: error_msg.clone(),
                     toast_type: "error".into(),
+                    duration: None,
                 })?;
                 self.event_sender
                     .send(AppEvent::StatusMessage { message: error_msg })?;
@@ -763,11 +2308,383 @@ impl KeyboardHandler {
     }
 }
 
+/// Write a save begun under the app lock to its path, creating any missing
+/// directories on the way
+async fn write_to_new_path(save: std::io::Result<PendingSave>) -> Result<PendingSave> {
+    let save = save?;
+    if let Some(parent) = save
+        .path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    save.write().await?;
+    Ok(save)
+}
+
+/// Split the `/pattern/replacement/flags` part of a `%s` command
+///
+/// The first char is the delimiter, usually `/`. A delimiter escaped with a
+/// backslash is taken literally; other escapes are left for the regex. The
+/// trailing delimiter and flags are optional.
+fn parse_substitution(spec: &str) -> Option<(String, String, String)> {
+    let mut chars = spec.chars();
+    let delimiter = chars
+        .next()
+        .filter(|c| !c.is_alphanumeric() && !c.is_whitespace())?;
+
+    let mut fields = vec![String::new()];
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut()?;
+        match c {
+            '\\' => match chars.next() {
+                Some(next) if next == delimiter => field.push(next),
+                Some(next) => {
+                    field.push('\\');
+                    field.push(next);
+                }
+                None => field.push('\\'),
+            },
+            c if c == delimiter => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+
+    let mut fields = fields.into_iter();
+    let pattern = fields.next().filter(|pattern| !pattern.is_empty())?;
+    let replacement = fields.next()?;
+    let flags = fields.next().unwrap_or_default();
+    let valid_flags = flags.chars().all(|flag| matches!(flag, 'g' | 'i'));
+    (valid_flags && fields.next().is_none()).then_some((pattern, replacement, flags))
+}
+
+impl CommandContext for KeyboardHandler {
+    fn run_plugin_command(
+        &self,
+        command: Arc<dyn PluginCommand>,
+        args: Vec<String>,
+    ) -> CommandFuture {
+        let keyboard = self.clone();
+        Box::pin(async move {
+            keyboard
+                .execute_plugin_command(command.as_ref(), &args)
+                .await
+        })
+    }
+}
+
+impl CommandRegistry<KeyboardHandler> {
+    /// Registry holding every built-in command
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::default();
+        register_builtin_commands(&mut registry);
+        registry
+    }
+}
+
+/// Register the commands the editor comes with
+pub fn register_builtin_commands(registry: &mut CommandRegistry<KeyboardHandler>) {
+    registry
+        .register("quit", &["q", "quit!", "q!"], |keyboard, call| async move {
+            if call.name.ends_with('!') {
+                keyboard.event_sender.send(AppEvent::Quit)?;
+                Ok(())
+            } else {
+                keyboard.handle_quit_request().await
+            }
+        })
+        .describe("Quit, asking about unsaved changes; quit! discards them")
+        .keys(&["Ctrl+Q"]);
+    registry
+        .register("save", &["w"], |keyboard, call| async move {
+            if call.args.is_empty() {
+                keyboard.handle_save_command().await
+            } else {
+                keyboard.handle_save_as(&call.args.join(" "), false).await
+            }
+        })
+        .describe("Save the active buffer, or save it to the given file")
+        .keys(&["Ctrl+S"]);
+    registry
+        .register("saveas", &["saveas!", "w!"], |keyboard, call| async move {
+            if call.args.is_empty() {
+                return keyboard.send_usage("saveas <file_path>");
+            }
+            let force = call.name.ends_with('!');
+            keyboard.handle_save_as(&call.args.join(" "), force).await
+        })
+        .describe("Save the active buffer as a new file; saveas! overwrites one");
+    registry
+        .register("open", &["o"], |keyboard, call| async move {
+            if crate::recent::open_recent_query(&call.input).is_some() {
+                keyboard.handle_open_recent(&call.input).await
+            } else if call.args.is_empty() {
+                keyboard.send_usage("open <file_path>")
+            } else {
+                keyboard.handle_open_file(&call.args.join(" ")).await
+            }
+        })
+        .describe("Open a file")
+        .keys(&["Ctrl+P"]);
+    registry
+        .register("new", &[], |keyboard, _| async move {
+            keyboard.handle_new_buffer().await
+        })
+        .describe("Open a new empty buffer")
+        .keys(&["Ctrl+N"]);
+    registry
+        .register("reload", &["reload!", "e!"], |keyboard, call| async move {
+            keyboard.handle_reload_request(call.name != "reload").await
+        })
+        .describe("Reload the active buffer from disk; reload! discards changes");
+    registry
+        .register("close", &["bd"], |keyboard, _| async move {
+            keyboard.handle_close_request().await
+        })
+        .describe("Close the active buffer")
+        .keys(&["Ctrl+W"]);
+    registry
+        .register("next", &["n"], |keyboard, _| async move {
+            keyboard.handle_next_buffer().await
+        })
+        .describe("Go to the next buffer")
+        .keys(&["Tab"]);
+    registry
+        .register("prev", &["p"], |keyboard, _| async move {
+            keyboard.handle_prev_buffer().await
+        })
+        .describe("Go to the previous buffer")
+        .keys(&["Shift+Tab"]);
+    registry
+        .register("buffer", &["b"], |keyboard, call| async move {
+            match call.args.first().and_then(|n| n.parse().ok()) {
+                Some(number) => keyboard.handle_switch_to_buffer(number).await,
+                None => keyboard.send_usage("buffer <number>"),
+            }
+        })
+        .describe("Switch to the buffer with the given number")
+        .keys(&["Alt+1-9"]);
+    registry
+        .register("goto", &["g"], |keyboard, call| async move {
+            match call.args.first().and_then(|n| n.parse().ok()) {
+                Some(line) => keyboard.handle_goto_line(line).await,
+                None => keyboard.send_usage("goto <line>"),
+            }
+        })
+        .describe("Go to the given line");
+    registry
+        .register("vsplit", &["vs"], |keyboard, _| async move {
+            keyboard.handle_split().await
+        })
+        .describe("Split the view side by side");
+    registry
+        .register("focus", &[], |keyboard, _| async move {
+            keyboard.handle_focus_next_pane().await
+        })
+        .describe("Focus the next pane")
+        .keys(&["Ctrl+W w", "Alt+W"]);
+    registry
+        .register("only", &[], |keyboard, _| async move {
+            keyboard.handle_close_other_panes().await
+        })
+        .describe("Close every pane but the focused one");
+    let mark_commands: [(&str, &str, &[&str]); 2] = [
+        ("mark", "Set the given mark at the cursor", &[]),
+        (
+            "jump",
+            "Jump to the given mark, or 'a for mark a",
+            &["Alt+'"],
+        ),
+    ];
+    for (name, description, keys) in mark_commands {
+        registry
+            .register(name, &[], |keyboard, call| async move {
+                match call.args.first().and_then(|name| single_char(name)) {
+                    Some(name) if call.name == "mark" => keyboard.handle_set_mark(name).await,
+                    Some(name) => keyboard.handle_jump_to_mark(name).await,
+                    None => keyboard.send_usage(&format!("{} <a-z|A-Z>", call.name)),
+                }
+            })
+            .describe(description)
+            .keys(keys);
+    }
+    registry
+        .register("back", &[], |keyboard, _| async move {
+            keyboard.handle_jump_list(false).await
+        })
+        .describe("Go back to where the cursor was before the last jump")
+        .keys(&["Ctrl+O"]);
+    registry
+        .register("forward", &[], |keyboard, _| async move {
+            keyboard.handle_jump_list(true).await
+        })
+        .describe("Go forward again through the jumps gone back over")
+        .keys(&["Ctrl+I", "Alt+I"]);
+    registry
+        .register("perf", &[], |keyboard, _| async move {
+            keyboard.handle_toggle_performance_overlay().await
+        })
+        .describe("Show or hide frame timings")
+        .keys(&["F10"]);
+    registry
+        .register("help", &[], |keyboard, _| async move {
+            keyboard.handle_show_help().await
+        })
+        .describe("List the commands and the keys that run them")
+        .keys(&["F1"]);
+    registry
+        .register(SEARCH_ALL_BUFFERS, &[], |keyboard, call| async move {
+            if call.args.is_empty() {
+                keyboard.send_usage("search_all_buffers <text>")
+            } else {
+                keyboard.handle_search_all_buffers(&call.input).await
+            }
+        })
+        .describe("Search every open buffer for text");
+    registry
+        .register("theme", &[], |keyboard, call| async move {
+            match call.args.first() {
+                Some(name) => keyboard.handle_set_theme(name).await,
+                None => keyboard.send_usage("theme <name>"),
+            }
+        })
+        .describe("Switch to the theme with the given name");
+    registry
+        .register("wc", &[], |keyboard, _| async move {
+            keyboard.handle_word_count().await
+        })
+        .describe("Count the lines, words and chars of the selection or buffer");
+    registry
+        .register("retab", &[], |keyboard, _| async move {
+            keyboard.handle_retab().await
+        })
+        .describe("Convert the indentation to the configured style");
+    registry
+        .register("upper", &[], |keyboard, _| async move {
+            keyboard
+                .edit_active_buffer(|buffer| buffer.transform_selection(str::to_uppercase))
+                .await
+        })
+        .describe("Uppercase the selection");
+    registry
+        .register("lower", &[], |keyboard, _| async move {
+            keyboard
+                .edit_active_buffer(|buffer| buffer.transform_selection(str::to_lowercase))
+                .await
+        })
+        .describe("Lowercase the selection");
+    registry
+        .register("togglecase", &[], |keyboard, _| async move {
+            keyboard
+                .edit_active_buffer(|buffer| buffer.transform_selection(toggle_case))
+                .await
+        })
+        .describe("Toggle the case of the selection");
+    registry
+        .register("sort", &[], |keyboard, call| async move {
+            let flags: Vec<&str> = call.args.iter().map(String::as_str).collect();
+            match parse_sort_flags(&flags) {
+                Some((reverse, ignore_case)) => {
+                    keyboard
+                        .edit_active_buffer(|buffer| buffer.sort_lines(reverse, ignore_case))
+                        .await
+                }
+                None => keyboard.send_usage("sort [-r] [-i]"),
+            }
+        })
+        .describe("Sort the selected lines, or all; -r reverses, -i ignores case");
+    registry
+        .register("uniq", &[], |keyboard, _| async move {
+            keyboard.handle_uniq().await
+        })
+        .describe("Remove repeated adjacent lines");
+    registry
+        .register("eol", &[], |keyboard, call| async move {
+            match call
+                .args
+                .first()
+                .and_then(|name| LineEnding::from_name(name))
+            {
+                Some(line_ending) => keyboard.handle_set_line_ending(line_ending).await,
+                None => keyboard.send_usage("eol <lf|crlf>"),
+            }
+        })
+        .describe("Set the line ending to lf or crlf");
+
+    // Settings with a command each to toggle them: name, alias and label
+    let flags: [(&str, &str, &str, ConfigFlag); 7] = [
+        (
+            "toggle_line_numbers",
+            "line_numbers",
+            "Line numbers",
+            |config| &mut config.editor.show_line_numbers,
+        ),
+        (
+            "toggle_relative_line_numbers",
+            "relative",
+            "Relative line numbers",
+            |config| &mut config.editor.relative_line_numbers,
+        ),
+        ("toggle_word_wrap", "wrap", "Word wrap", |config| {
+            &mut config.editor.word_wrap
+        }),
+        ("toggle_whitespace", "whitespace", "Whitespace", |config| {
+            &mut config.editor.render_whitespace
+        }),
+        ("toggle_minimap", "minimap", "Minimap", |config| {
+            &mut config.ui.show_minimap
+        }),
+        ("toggle_tab_bar", "tabs", "Tab bar", |config| {
+            &mut config.ui.show_tab_bar
+        }),
+        ("toggle_scrollbar", "scrollbar", "Scrollbar", |config| {
+            &mut config.ui.show_scrollbar
+        }),
+    ];
+    for (name, alias, label, flag) in flags {
+        registry
+            .register(name, &[alias], move |keyboard, _| async move {
+                keyboard.toggle_config_flag(label, flag).await
+            })
+            .describe(&format!("Toggle {}", label.to_lowercase()));
+    }
+}
+
+/// Parse a command argument that must be exactly one character
+fn single_char(arg: &str) -> Option<char> {
+    let mut chars = arg.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// Parse the flags of `sort` into whether to reverse the order and whether
+/// to ignore case, accepting `-r` and `-i` on their own or together as `-ri`
+fn parse_sort_flags(args: &[&str]) -> Option<(bool, bool)> {
+    let mut reverse = false;
+    let mut ignore_case = false;
+    for arg in args {
+        let flags = arg.strip_prefix('-').filter(|flags| !flags.is_empty())?;
+        for flag in flags.chars() {
+            match flag {
+                'r' => reverse = true,
+                'i' => ignore_case = true,
+                _ => return None,
+            }
+        }
+    }
+    Some((reverse, ignore_case))
+}
+
 impl Clone for KeyboardHandler {
     fn clone(&self) -> Self {
         Self {
             app_state: self.app_state.clone(),
             event_sender: self.event_sender.clone(),
+            config_writes: self.config_writes.clone(),
+            state_writes: self.state_writes.clone(),
         }
     }
 }
@@ -1,8 +1,10 @@
 /// Mouse input handlers that subscribe to mouse events
 use crate::events::{AppEvent, EventBus};
+use crate::input::clicks::ClickState;
 use crate::{App, CommandMode};
 use anyhow::Result;
-use ratatui::crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Position;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio::sync::RwLock;
@@ -37,7 +39,7 @@ impl MouseHandler {
     }
 
     /// Handle mouse events
-    async fn handle_mouse_event(&self, event: AppEvent) -> Result<()> {
+    pub async fn handle_mouse_event(&self, event: AppEvent) -> Result<()> {
         if let AppEvent::MouseInput(mouse) = event {
             let app = self.app_state.read().await;
             let command_mode = app.command_mode.clone();
@@ -58,19 +60,35 @@ impl MouseHandler {
     async fn handle_normal_mode_mouse(&self, mouse: MouseEvent) -> Result<()> {
         match mouse.kind {
             MouseEventKind::Down(MouseButton::Left) => {
-                self.handle_click(mouse.column, mouse.row).await?;
+                let extend = mouse.modifiers.contains(KeyModifiers::SHIFT);
+                self.handle_click(mouse.column, mouse.row, extend).await?;
             }
             MouseEventKind::Drag(MouseButton::Left) => {
-                self.handle_drag(mouse.column, mouse.row).await?;
+                let block = mouse.modifiers.contains(KeyModifiers::ALT);
+                self.handle_drag(mouse.column, mouse.row, block).await?;
             }
             MouseEventKind::Up(MouseButton::Left) => {
                 self.handle_release(mouse.column, mouse.row).await?;
             }
+            MouseEventKind::ScrollUp if mouse.modifiers.contains(KeyModifiers::SHIFT) => {
+                self.handle_horizontal_scroll(-8).await?; // Scroll 8 columns left
+            }
+            MouseEventKind::ScrollDown if mouse.modifiers.contains(KeyModifiers::SHIFT) => {
+                self.handle_horizontal_scroll(8).await?; // Scroll 8 columns right
+            }
+            MouseEventKind::ScrollLeft => {
+                self.handle_horizontal_scroll(-8).await?;
+            }
+            MouseEventKind::ScrollRight => {
+                self.handle_horizontal_scroll(8).await?;
+            }
             MouseEventKind::ScrollUp => {
-                self.handle_scroll(-8).await?; // Scroll 8 lines up
+                let page = mouse.modifiers.contains(KeyModifiers::CONTROL);
+                self.handle_scroll(-1, page).await?;
             }
             MouseEventKind::ScrollDown => {
-                self.handle_scroll(8).await?; // Scroll 8 lines down
+                let page = mouse.modifiers.contains(KeyModifiers::CONTROL);
+                self.handle_scroll(1, page).await?;
             }
             MouseEventKind::Down(MouseButton::Right) => {
                 static RIGHT_CLICK_MSG: &str = "Right click detected";
@@ -91,45 +109,125 @@ impl MouseHandler {
     }
 
     /// Handle mouse click to position cursor
-    async fn handle_click(&self, mouse_x: u16, mouse_y: u16) -> Result<()> {
+    ///
+    /// With `extend` (Shift held) the selection is kept and stretched from its
+    /// anchor to the click, starting one at the cursor if there's none.
+    async fn handle_click(&self, mouse_x: u16, mouse_y: u16, extend: bool) -> Result<()> {
         let mut app = self.app_state.write().await;
 
-        // Get actual terminal size
-        let (terminal_width, terminal_height) =
-            if let Ok((w, h)) = ratatui::crossterm::terminal::size() {
-                (w, h)
-            } else {
-                (120, 30) // Fallback
+        let terminal_area = crate::input::coordinates::terminal_area();
+        let layout = app.layout(terminal_area);
+
+        // Clicking a toast dismisses it, without reaching the editor below
+        if app
+            .toast_manager
+            .dismiss_at(terminal_area, Position::new(mouse_x, mouse_y))
+        {
+            return Ok(());
+        }
+
+        // Clicking the encoding or line ending on the status line changes it
+        if layout.status.contains(Position::new(mouse_x, mouse_y)) {
+            let slot = app
+                .status_bar
+                .slot_at(layout.status.width, mouse_x - layout.status.x)
+                .map(str::to_owned);
+            drop(app);
+            return match slot {
+                Some(slot) => self.handle_status_slot_click(&slot).await,
+                None => Ok(()),
             };
+        }
 
-        let editor_area = ratatui::layout::Rect {
-            x: 0,
-            y: 0,
-            width: terminal_width,
-            height: terminal_height.saturating_sub(1), // -1 for status line
-        };
+        // Clicking a tab switches to its buffer
+        if let Some(tab_bar) = layout.tab_bar {
+            if tab_bar.contains(Position::new(mouse_x, mouse_y)) {
+                let tabs = crate::widgets::tab_bar::TabBar::new(&app.buffers, app.active_buffer);
+                if let Some(index) = tabs.tab_at(mouse_x - tab_bar.x, tab_bar.width) {
+                    app.switch_to_buffer(index);
+                }
+                return Ok(());
+            }
+        }
+
+        // Clicking the scrollbar jumps there and starts dragging the thumb
+        if let Some(track) = layout.scrollbar {
+            if track.contains(Position::new(mouse_x, mouse_y)) {
+                app.scroll_to_scrollbar_position(mouse_y, track);
+                app.scrollbar_drag = true;
+                return Ok(());
+            }
+        }
+
+        // Clicking the minimap scrolls to the lines it shows there
+        if let Some(minimap) = layout.minimap {
+            if minimap.contains(Position::new(mouse_x, mouse_y)) {
+                app.scroll_to_minimap_position(mouse_y, minimap, layout.editor);
+                return Ok(());
+            }
+        }
+
+        // Clicking another split pane moves focus there before placing the cursor
+        if let Some(index) = layout
+            .panes
+            .iter()
+            .position(|pane| pane.contains(Position::new(mouse_x, mouse_y)))
+        {
+            if layout.panes[index] != layout.editor {
+                app.focus_pane(index);
+                // The editor is drawn in the clicked pane from now on
+                app.last_editor_area = Some(layout.panes[index]);
+            }
+        }
 
-        // Check if click is within editor area
-        if mouse_y >= editor_area.height {
-            // Click is in status line or below - ignore
+        // Clicks outside the editor, like on the status line, are ignored
+        if !app.editor_area().contains(Position::new(mouse_x, mouse_y)) {
             return Ok(());
         }
 
         // Convert screen coordinates to buffer coordinates using proper conversion
-        if let Some((buffer_row, buffer_col)) =
-            crate::input::coordinates::screen_to_buffer_coords(&app, mouse_x, mouse_y)
+        if let Some(clicked) =
+            crate::input::coordinates::screen_to_clicked_position(&app, mouse_x, mouse_y)
         {
             let active_buffer = app.active_buffer;
+            let (buffer_row, buffer_col) = (clicked.row, clicked.col);
+
+            // Double and triple clicks select the word or line under the
+            // pointer; past the end of the line there's no word to select
+            let now = (app.clock)();
+            let click = ClickState::next(app.last_click, mouse_x, mouse_y, now);
+            app.last_click = Some(click);
+
+            // Clicking the gutter selects the whole line, and dragging on
+            // from there extends the selection a line at a time
+            if clicked.in_gutter && !extend {
+                drop(app);
+                return self.select_clicked_line(active_buffer, buffer_row).await;
+            }
+            if click.count > 1 && !extend && !(click.count == 2 && clicked.past_line_end) {
+                drop(app);
+                return self
+                    .select_clicked(active_buffer, buffer_row, buffer_col, click.count)
+                    .await;
+            }
 
             if let Some(buffer) = app.buffers.get_mut(active_buffer) {
-                // Clear any existing selection
-                buffer.clear_selection();
+                let anchor = if extend {
+                    // Keep the selection, anchored where it started
+                    let anchor = *buffer.selection_start.get_or_insert(buffer.cursor_pos);
+                    buffer.visual_mode = true;
+                    anchor
+                } else {
+                    // Clear any existing selection
+                    buffer.clear_selection();
+                    (buffer_row, buffer_col)
+                };
 
                 // Position cursor at click location
                 buffer.cursor_pos = (buffer_row, buffer_col);
 
                 // Start potential drag selection
-                app.mouse_drag_start = Some((buffer_row, buffer_col));
+                app.mouse_drag_start = Some(anchor);
 
                 // Ensure clicked position is visible
                 // (scroll will be adjusted in the render cycle)
@@ -137,7 +235,7 @@ impl MouseHandler {
 
             // Publish cursor moved event
             self.event_sender.send(AppEvent::BufferCursorMoved {
-                buffer_id: 0,
+                buffer_id: active_buffer,
                 row: buffer_row,
                 col: buffer_col,
             })?;
@@ -157,10 +255,139 @@ impl MouseHandler {
         Ok(())
     }
 
+    /// Select the word (double click) or line (triple click) at a clicked
+    /// position
+    ///
+    /// Words are delimited like Ctrl+D finds them; a line is selected up to the
+    /// start of the next one, so it's taken along with its line break.
+    async fn select_clicked(
+        &self,
+        buffer_id: usize,
+        row: usize,
+        col: usize,
+        count: u8,
+    ) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        app.mouse_drag_start = None;
+        let Some(buffer) = app.buffers.get_mut(buffer_id) else {
+            return Ok(());
+        };
+
+        let (start, end) = if count == 2 {
+            match buffer.word_at(crate::buffer::Position::new(row, col)) {
+                Some(word) => ((row, word.start), (row, word.end)),
+                None => return Ok(()),
+            }
+        } else if row + 1 < buffer.content.len() {
+            ((row, 0), (row + 1, 0))
+        } else {
+            ((row, 0), (row, buffer.content[row].len()))
+        };
+
+        buffer.clear_selection();
+        buffer.visual_mode = true;
+        buffer.selection_start = Some(start);
+        buffer.cursor_pos = end;
+        drop(app);
+
+        self.event_sender.send(AppEvent::BufferSelectionChanged {
+            buffer_id,
+            start: Some(start),
+            end: Some(end),
+        })?;
+        Ok(())
+    }
+
+    /// Cycle the active buffer's encoding, or switch its line ending, when
+    /// that status bar slot is clicked
+    async fn handle_status_slot_click(&self, slot: &str) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        let active_buffer = app.active_buffer;
+        let Some(buffer) = app.buffers.get_mut(active_buffer) else {
+            return Ok(());
+        };
+
+        let message = match slot {
+            "encoding" => {
+                let encoding = buffer.encoding.cycled();
+                buffer.set_encoding(encoding);
+                format!("Saving as {}", encoding.label())
+            }
+            "line_ending" => {
+                let line_ending = buffer.line_ending.toggled();
+                buffer.set_line_ending(line_ending);
+                format!("Saving with {} line endings", line_ending.name())
+            }
+            _ => return Ok(()),
+        };
+        let became_modified = buffer.take_became_modified();
+        drop(app);
+
+        if became_modified {
+            self.event_sender.send(AppEvent::BufferModifiedChanged {
+                buffer_id: active_buffer,
+                modified: true,
+            })?;
+        }
+        self.event_sender.send(AppEvent::StatusMessage {
+            message: message.into(),
+        })?;
+        Ok(())
+    }
+
+    /// Select the line at `row` in linewise visual mode, starting a drag
+    /// there
+    async fn select_clicked_line(&self, buffer_id: usize, row: usize) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        app.mouse_drag_start = Some((row, 0));
+        let Some(buffer) = app.buffers.get_mut(buffer_id) else {
+            return Ok(());
+        };
+
+        buffer.clear_selection();
+        buffer.cursor_pos = (row, 0);
+        buffer.toggle_line_visual_mode();
+        let (start, end) = match buffer.get_selection_range() {
+            Some((start, end)) => (start.to_tuple(), end.to_tuple()),
+            None => return Ok(()),
+        };
+        drop(app);
+
+        self.event_sender.send(AppEvent::BufferCursorMoved {
+            buffer_id,
+            row,
+            col: 0,
+        })?;
+        self.event_sender.send(AppEvent::BufferSelectionChanged {
+            buffer_id,
+            start: Some(start),
+            end: Some(end),
+        })?;
+        Ok(())
+    }
+
     /// Handle mouse drag for text selection
-    async fn handle_drag(&self, mouse_x: u16, mouse_y: u16) -> Result<()> {
+    ///
+    /// With `block` (Alt held) the drag selects a rectangle instead of a range.
+    /// Dragging to the top or bottom row of the editor scrolls it, so the
+    /// selection can reach past what was visible.
+    async fn handle_drag(&self, mouse_x: u16, mouse_y: u16, block: bool) -> Result<()> {
         let mut app = self.app_state.write().await;
 
+        if app.scrollbar_drag {
+            if let Some(track) = crate::input::coordinates::scrollbar_area(&app) {
+                app.scroll_to_scrollbar_position(mouse_y, track);
+            }
+            return Ok(());
+        }
+
+        let mouse_y = if app.mouse_drag_start.is_some() {
+            let editor_area = app.editor_area();
+            app.scroll_for_drag(mouse_y, editor_area)
+        } else {
+            mouse_y
+        };
+
         // Convert screen coordinates to buffer coordinates using proper conversion
         if let Some((buffer_row, buffer_col)) =
             crate::input::coordinates::screen_to_buffer_coords(&app, mouse_x, mouse_y)
@@ -171,7 +398,9 @@ impl MouseHandler {
             if let Some(buffer) = app.buffers.get_mut(active_buffer) {
                 if let Some(start_pos) = mouse_drag_start {
                     // Enable visual mode if not already enabled
-                    if !buffer.visual_mode {
+                    if block && buffer.block_selection().is_none() {
+                        buffer.start_block_selection(start_pos);
+                    } else if !buffer.visual_mode {
                         buffer.visual_mode = true;
                         buffer.selection_start = Some(start_pos);
                     }
@@ -183,7 +412,7 @@ impl MouseHandler {
 
             // Publish selection changed event
             self.event_sender.send(AppEvent::BufferSelectionChanged {
-                buffer_id: 0,
+                buffer_id: active_buffer,
                 start: mouse_drag_start,
                 end: Some((buffer_row, buffer_col)),
             })?;
@@ -198,6 +427,7 @@ impl MouseHandler {
 
         // Clear drag start - selection is finalized
         app.mouse_drag_start = None;
+        app.scrollbar_drag = false;
 
         // Show selection info if we have one
         if let Some(buffer) = app.buffers.get(app.active_buffer) {
@@ -223,38 +453,31 @@ impl MouseHandler {
         Ok(())
     }
 
-    /// Handle scroll events
-    async fn handle_scroll(&self, delta: i32) -> Result<()> {
+    /// Scroll one wheel tick down (`direction` > 0) or up
+    ///
+    /// A tick moves `scroll_lines_per_tick` lines, or a whole page of the
+    /// editor with `page` set (Ctrl held).
+    async fn handle_scroll(&self, direction: i32, page: bool) -> Result<()> {
         let mut app = self.app_state.write().await;
 
         let (current_row, current_col) = app.scroll_offset;
 
-        // Get terminal dimensions
-        let term_height = if let Ok((_, h)) = ratatui::crossterm::terminal::size() {
-            h
+        // Scroll no further than the lines allowed past the end of the buffer
+        let editor_area = app.editor_area();
+        let max_scroll_row = app.get_max_scroll_row_past_end(editor_area);
+        let lines = if page {
+            editor_area.height as usize
         } else {
-            30 // Fallback size
+            app.config.ui.scroll_lines_per_tick
         };
 
-        // Calculate visible rows in editor (terminal height minus status bar)
-        let visible_rows = term_height.saturating_sub(1) as usize;
-
-        // Get buffer size and calculate maximum scroll position
-        let max_scroll_row = if let Some(buffer) = app.buffers.get(app.active_buffer) {
-            // Allow scrolling to show the last line at the bottom of the editor
-            // This means max scroll is buffer size minus visible rows
-            buffer.content.len().saturating_sub(visible_rows / 2) // Allows more scrolling past the end
-        } else {
-            0
-        };
-
-        if delta > 0 {
+        if direction > 0 {
             // Scroll down - don't scroll past the calculated maximum
-            let new_row = (current_row + delta as usize).min(max_scroll_row);
+            let new_row = (current_row + lines).min(max_scroll_row);
             app.scroll_offset = (new_row, current_col);
         } else {
             // Scroll up - don't scroll above the beginning (line 0)
-            let new_row = current_row.saturating_sub((-delta) as usize);
+            let new_row = current_row.saturating_sub(lines);
             app.scroll_offset = (new_row, current_col);
         }
 
@@ -270,6 +493,35 @@ impl MouseHandler {
         Ok(())
     }
 
+    /// Scroll long lines sideways (Shift+wheel or a horizontal wheel)
+    ///
+    /// Stops once the longest line's end is in view. Wrapped lines never need
+    /// it, so nothing happens with word wrap on.
+    async fn handle_horizontal_scroll(&self, delta: i32) -> Result<()> {
+        let mut app = self.app_state.write().await;
+        if app.config.editor.word_wrap {
+            return Ok(());
+        }
+
+        let editor_area = app.editor_area();
+        let max_scroll_col = app.get_max_scroll_col(editor_area);
+        let (current_row, current_col) = app.scroll_offset;
+        let new_col = if delta > 0 {
+            (current_col + delta as usize).min(max_scroll_col.max(current_col))
+        } else {
+            current_col.saturating_sub((-delta) as usize)
+        };
+        app.scroll_offset = (current_row, new_col);
+        app.cursor_manager.notify_activity_for_active();
+
+        let scroll_msg = format!("Scrolled to column {}", new_col + 1);
+        self.event_sender.send(AppEvent::StatusMessage {
+            message: scroll_msg.into(),
+        })?;
+
+        Ok(())
+    }
+
     /// Handle mouse events in command palette mode
     async fn handle_command_mode_mouse(&self, mouse: MouseEvent) -> Result<()> {
         if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
@@ -0,0 +1,108 @@
+//! Driving the editor without a terminal
+//!
+//! Input goes through the same event bus and handlers as in [`App::run`], so
+//! code embedding the editor, or its tests, can script it and see what a user
+//! would. A [`HeadlessEditor`] shares the app with its handlers for as long as
+//! it lives, like the terminal loop does. Each call handles its input and
+//! every event that follows from it before returning.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::events::EventBus;
+use crate::input_system::InputSystem;
+use crate::App;
+
+/// An editor taking scripted input instead of terminal input
+pub struct HeadlessEditor {
+    app_state: Arc<RwLock<App>>,
+    event_bus: EventBus,
+    input_system: InputSystem,
+}
+
+impl HeadlessEditor {
+    /// Drive `app`, with its handlers and the hooks registered so far
+    /// subscribed to a bus of its own
+    pub async fn new(app: App) -> Result<Self> {
+        let app_state = Arc::new(RwLock::new(app));
+        let event_bus = EventBus::new();
+        let input_system = InputSystem::new(event_bus.clone());
+        App::subscribe_handlers(&app_state, &event_bus).await?;
+        Ok(Self {
+            app_state,
+            event_bus,
+            input_system,
+        })
+    }
+
+    /// The app, to look at between inputs
+    pub async fn app(&self) -> RwLockReadGuard<'_, App> {
+        self.app_state.read().await
+    }
+
+    /// The app, to change between inputs
+    pub async fn app_mut(&self) -> RwLockWriteGuard<'_, App> {
+        self.app_state.write().await
+    }
+
+    /// The app as it was left, once done driving it
+    pub async fn into_app(self) -> App {
+        std::mem::take(&mut *self.app_state.write().await)
+    }
+
+    /// Handle a key press as if it had been typed in the terminal
+    pub async fn press_key(&self, key: KeyEvent) -> Result<()> {
+        self.input_system.handle_key_input(key)?;
+        self.event_bus.process_pending().await
+    }
+
+    /// Handle `text` as if it had been pasted into the terminal
+    pub async fn paste(&self, text: &str) -> Result<()> {
+        self.input_system.handle_paste(text.to_string())?;
+        self.event_bus.process_pending().await
+    }
+
+    /// Type `text` a key at a time, with Enter for each newline
+    ///
+    /// Tab switches buffers rather than typing one, so each tab is typed as
+    /// the indent the config asks for: `tab_size` spaces, or a tab char.
+    pub async fn type_text(&self, text: &str) -> Result<()> {
+        let tab = {
+            let editor_config = &self.app().await.config.editor;
+            if editor_config.use_spaces {
+                " ".repeat(editor_config.tab_size)
+            } else {
+                "\t".to_string()
+            }
+        };
+        for c in text.chars() {
+            let codes: Vec<KeyCode> = match c {
+                '\n' => vec![KeyCode::Enter],
+                '\t' => tab.chars().map(KeyCode::Char).collect(),
+                c => vec![KeyCode::Char(c)],
+            };
+            for code in codes {
+                self.press_key(KeyEvent::new(code, KeyModifiers::NONE))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Text of the active buffer, lines joined with `\n`
+    pub async fn active_content(&self) -> String {
+        self.app().await.active_content()
+    }
+}
+
+impl App {
+    /// Text of the active buffer, lines joined with `\n`
+    pub fn active_content(&self) -> String {
+        self.get_active_buffer()
+            .map(|buffer| buffer.content_as_string())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,61 @@
+//! Help listing the commands
+//!
+//! `help` or F1 opens a modal with every command in the registry: its name
+//! and aliases, the keys that run it, and what it does. Keys come from the
+//! defaults noted when the command was registered and from the sequences in
+//! `keybindings` bound to it. The list scrolls when it's taller than the
+//! screen.
+
+use crate::App;
+
+impl App {
+    /// Show the help from its first line
+    pub fn open_help(&mut self) {
+        self.show_help = true;
+        self.help_scroll = 0;
+    }
+
+    /// Move the help `lines` down, or up when negative
+    ///
+    /// How far it can go down depends on the screen, so that's left to the
+    /// rendering.
+    pub fn scroll_help(&mut self, lines: isize) {
+        self.help_scroll = self.help_scroll.saturating_add_signed(lines);
+    }
+
+    /// Lines of the help: each command with its aliases and keys, then what
+    /// it does indented below
+    pub fn help_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for command in self.commands.commands() {
+            let mut heading = command.name().to_string();
+            if !command.aliases().is_empty() {
+                heading.push_str(&format!(" ({})", command.aliases().join(", ")));
+            }
+
+            let mut keys = command.default_keys().to_vec();
+            let mut bound: Vec<String> = self
+                .config
+                .keybindings
+                .iter()
+                .filter(|(_, bound)| {
+                    bound.split_whitespace().next().is_some_and(|name| {
+                        name == command.name() || command.aliases().iter().any(|a| a == name)
+                    })
+                })
+                .map(|(sequence, _)| sequence.clone())
+                .collect();
+            bound.sort();
+            keys.extend(bound);
+            if !keys.is_empty() {
+                heading.push_str(&format!("  [{}]", keys.join(", ")));
+            }
+
+            lines.push(heading);
+            if !command.description().is_empty() {
+                lines.push(format!("    {}", command.description()));
+            }
+        }
+        lines
+    }
+}
@@ -0,0 +1,130 @@
+//! Key sequence bindings
+//!
+//! `keybindings` in the config maps key sequences to palette commands, like
+//! `"space f": "open "`. A sequence is key names separated by spaces: a single
+//! char, `space`, `tab`, `enter`, `esc`, `backspace`, or any of those after
+//! `ctrl+` or `alt+`. A command ending in whitespace opens the palette with it
+//! typed instead of running it.
+//!
+//! Keys that start a bound sequence are held back while it's being typed.
+//! Once a key no sequence continues with arrives, or `CHORD_TIMEOUT` passes
+//! without one, the held keys are handled as normal input.
+//!
+//! While the view is split, `PANE_BINDINGS` adds vim's `ctrl+w w` for moving
+//! between panes, so Ctrl+W on its own closes the buffer once it times out.
+//! Sequences in `keybindings` take precedence over it.
+
+use std::time::{Duration, Instant};
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// How long the keys of an unfinished sequence are held back
+pub const CHORD_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Sequences bound while the view is split, checked after `keybindings`
+pub const PANE_BINDINGS: [(&str, &str); 2] = [("ctrl+w w", "focus"), ("ctrl+w ctrl+w", "focus")];
+
+/// Keys typed so far of a sequence that isn't finished yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingChord {
+    /// The keys, in the order they were typed
+    pub keys: Vec<KeyEvent>,
+
+    /// When the first of them was typed
+    pub started: Instant,
+}
+
+impl PendingChord {
+    /// Whether `CHORD_TIMEOUT` has passed since the sequence started
+    pub fn expired(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.started) >= CHORD_TIMEOUT
+    }
+}
+
+/// How the keys typed so far relate to the bound sequences
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChordMatch<'a> {
+    /// They're a whole sequence, bound to this command
+    Command(&'a str),
+
+    /// They start at least one longer sequence
+    Prefix,
+
+    /// No sequence starts with them
+    None,
+}
+
+/// Look `keys` up in `bindings` of sequences to commands, ignoring
+/// sequences that don't parse
+pub fn match_chord<'a>(
+    bindings: impl IntoIterator<Item = (&'a str, &'a str)>,
+    keys: &[KeyEvent],
+) -> ChordMatch<'a> {
+    let keys: Vec<_> = keys.iter().map(|key| normalize(*key)).collect();
+    let mut result = ChordMatch::None;
+    for (sequence, command) in bindings {
+        let Some(sequence) = parse_sequence(sequence) else {
+            continue;
+        };
+        if sequence == keys {
+            return ChordMatch::Command(command);
+        }
+        if sequence.len() > keys.len() && sequence.starts_with(&keys) {
+            result = ChordMatch::Prefix;
+        }
+    }
+    result
+}
+
+/// Whether `key` is the one key `name` stands for, like `"alt+p"`
+pub fn is_key(name: &str, key: KeyEvent) -> bool {
+    parse_sequence(name).is_some_and(|keys| keys == [normalize(key)])
+}
+
+/// The keys of a sequence like `"ctrl+k s"`, or `None` if a name isn't known
+pub fn parse_sequence(sequence: &str) -> Option<Vec<(KeyCode, KeyModifiers)>> {
+    sequence.split_whitespace().map(parse_key).collect()
+}
+
+/// One key name, with its `ctrl+` and `alt+` prefixes
+fn parse_key(name: &str) -> Option<(KeyCode, KeyModifiers)> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut name = name;
+    loop {
+        if let Some(rest) = name.strip_prefix("ctrl+") {
+            modifiers |= KeyModifiers::CONTROL;
+            name = rest;
+        } else if let Some(rest) = name.strip_prefix("alt+") {
+            modifiers |= KeyModifiers::ALT;
+            name = rest;
+        } else {
+            break;
+        }
+    }
+
+    let code = match name {
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        _ => {
+            let mut chars = name.chars();
+            let c = chars.next()?;
+            if chars.next().is_some() {
+                return None;
+            }
+            KeyCode::Char(c)
+        }
+    };
+    Some((code, modifiers))
+}
+
+/// A key as written in a sequence, dropping the Shift that comes with
+/// uppercase and symbol chars
+fn normalize(key: KeyEvent) -> (KeyCode, KeyModifiers) {
+    match key.code {
+        KeyCode::Char(_) => (key.code, key.modifiers - KeyModifiers::SHIFT),
+        _ => (key.code, key.modifiers),
+    }
+}
@@ -0,0 +1,49 @@
+//! Multi-click detection
+//!
+//! Clicks that land on the same cell within `MULTI_CLICK_INTERVAL` of the one
+//! before count up, so the mouse handler can tell single, double and triple
+//! clicks apart. A fourth click starts over as a single click.
+
+use std::time::{Duration, Instant};
+
+/// Longest gap between two clicks that still makes them a double click
+pub const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// The last click in the editor, remembered to count repeated clicks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClickState {
+    /// When the click happened
+    pub at: Instant,
+
+    /// Screen column clicked
+    pub column: u16,
+
+    /// Screen row clicked
+    pub row: u16,
+
+    /// 1 for a single click, 2 for a double click, 3 for a triple click
+    pub count: u8,
+}
+
+impl ClickState {
+    /// A click at (`column`, `row`) at time `at`, counting on from `previous`
+    /// if it repeats that click
+    pub fn next(previous: Option<ClickState>, column: u16, row: u16, at: Instant) -> Self {
+        let count = match previous {
+            Some(previous)
+                if previous.column == column
+                    && previous.row == row
+                    && at.saturating_duration_since(previous.at) <= MULTI_CLICK_INTERVAL =>
+            {
+                previous.count % 3 + 1
+            }
+            _ => 1,
+        };
+        Self {
+            at,
+            column,
+            row,
+            count,
+        }
+    }
+}
@@ -1,15 +1,74 @@
 // Coordinate conversion and screen layout management
 
+use crate::buffer::{byte_col_at_visual, visual_col};
 use crate::App;
 use ratatui::layout::Rect;
 
+/// Buffer position a screen cell maps to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClickedPosition {
+    pub row: usize,
+
+    /// Byte column, clamped to the end of the line
+    pub col: usize,
+
+    /// Whether the cell is in the empty space after the end of the line, or
+    /// below the last line
+    pub past_line_end: bool,
+
+    /// Whether the cell is in the line number gutter, left of the text
+    pub in_gutter: bool,
+}
+
+impl ClickedPosition {
+    fn new(row: usize, col: usize, past_line_end: bool) -> Self {
+        Self {
+            row,
+            col,
+            past_line_end,
+            in_gutter: false,
+        }
+    }
+}
+
 /// Convert screen coordinates to buffer coordinates
 /// Takes into account the current editor layout, scroll offset, and line numbers
 pub fn screen_to_buffer_coords(app: &App, mouse_x: u16, mouse_y: u16) -> Option<(usize, usize)> {
-    // Get the actual editor area - this should be passed from the UI layer
-    // For now, we'll calculate it based on the application state
-    let editor_area = get_editor_area();
+    screen_to_clicked_position(app, mouse_x, mouse_y).map(|clicked| (clicked.row, clicked.col))
+}
+
+/// Convert screen coordinates to buffer coordinates for an editor drawn in `editor_area`
+pub fn screen_to_buffer_coords_in_area(
+    app: &App,
+    editor_area: Rect,
+    mouse_x: u16,
+    mouse_y: u16,
+) -> Option<(usize, usize)> {
+    screen_to_clicked_position_in_area(app, editor_area, mouse_x, mouse_y)
+        .map(|clicked| (clicked.row, clicked.col))
+}
+
+/// Buffer position of a screen cell, noting whether it's past the end of
+/// the line
+pub fn screen_to_clicked_position(
+    app: &App,
+    mouse_x: u16,
+    mouse_y: u16,
+) -> Option<ClickedPosition> {
+    // Map onto the editor where it was last drawn
+    screen_to_clicked_position_in_area(app, app.editor_area(), mouse_x, mouse_y)
+}
 
+/// Buffer position of a screen cell for an editor drawn in `editor_area`
+///
+/// Tabs take up to `display_tab_width` screen columns, so the clicked column is
+/// mapped back to the byte offset of the char drawn there.
+pub fn screen_to_clicked_position_in_area(
+    app: &App,
+    editor_area: Rect,
+    mouse_x: u16,
+    mouse_y: u16,
+) -> Option<ClickedPosition> {
     // Check if click is within editor area
     if mouse_x < editor_area.x
         || mouse_x >= editor_area.x + editor_area.width
@@ -24,25 +83,26 @@ pub fn screen_to_buffer_coords(app: &App, mouse_x: u16, mouse_y: u16) -> Option<
     let relative_y = mouse_y - editor_area.y;
 
     // Account for line numbers if enabled
-    let line_number_width = if app.get_line_numbers_setting() {
-        if let Some(buffer) = app.buffers.get(app.active_buffer) {
-            buffer.line_number_width()
-        } else {
-            0
-        }
-    } else {
-        0
-    };
+    let line_number_width = app.gutter_width();
+
+    let text_width = (editor_area.width as usize).saturating_sub(line_number_width);
 
     // Check if click is in line number area
     if relative_x < line_number_width as u16 {
         // Click is in line number area - position cursor at beginning of line
-        let (scroll_row, _) = app.scroll_offset;
-        let buffer_row = scroll_row + relative_y as usize;
+        let buffer_row = if app.config.editor.word_wrap {
+            wrapped_row_at(app, relative_y as usize, text_width).map(|(row, _)| row)
+        } else {
+            let (scroll_row, _) = app.scroll_offset;
+            Some(scroll_row + relative_y as usize)
+        };
 
         if let Some(buffer) = app.buffers.get(app.active_buffer) {
-            if buffer_row < buffer.content.len() {
-                return Some((buffer_row, 0));
+            if let Some(buffer_row) = buffer_row.filter(|&row| row < buffer.content.len()) {
+                return Some(ClickedPosition {
+                    in_gutter: true,
+                    ..ClickedPosition::new(buffer_row, 0, false)
+                });
             }
         }
         return None;
@@ -50,10 +110,18 @@ pub fn screen_to_buffer_coords(app: &App, mouse_x: u16, mouse_y: u16) -> Option<
 
     let text_relative_x = relative_x - line_number_width as u16;
 
+    if app.config.editor.word_wrap {
+        return wrapped_screen_to_buffer_coords(
+            app,
+            text_relative_x as usize,
+            relative_y as usize,
+            text_width,
+        );
+    }
+
     // Apply scroll offset
     let (scroll_row, scroll_col) = app.scroll_offset;
     let buffer_row = scroll_row + relative_y as usize;
-    let buffer_col = scroll_col + text_relative_x as usize;
 
     // Validate coordinates against buffer content
     if let Some(buffer) = app.buffers.get(app.active_buffer) {
@@ -65,38 +133,80 @@ pub fn screen_to_buffer_coords(app: &App, mouse_x: u16, mouse_y: u16) -> Option<
                 .get(last_row)
                 .map(|line| line.len())
                 .unwrap_or(0);
-            return Some((last_row, last_col));
+            return Some(ClickedPosition::new(last_row, last_col, true));
         }
 
         let line = &buffer.content[buffer_row];
-        let adjusted_col = buffer_col.min(line.len());
-        return Some((buffer_row, adjusted_col));
+        let tab_size = app.config.editor.display_tab_width();
+        let x = visual_col(line, scroll_col, tab_size) + text_relative_x as usize;
+        let past_line_end = x >= visual_col(line, line.len(), tab_size);
+        return Some(ClickedPosition::new(
+            buffer_row,
+            byte_col_at_visual(line, x, tab_size),
+            past_line_end,
+        ));
     }
 
     None
 }
 
-/// Get the editor area bounds
-/// This should eventually be passed from the UI rendering layer
-/// For now, we'll use a reasonable approximation
-fn get_editor_area() -> Rect {
-    // Try to get actual terminal size
-    if let Ok((width, height)) = ratatui::crossterm::terminal::size() {
-        Rect {
-            x: 0,
-            y: 0,
-            width,
-            height: height.saturating_sub(1), // -1 for status line
-        }
-    } else {
-        // Fallback to default size
-        Rect {
-            x: 0,
-            y: 0,
-            width: 120,
-            height: 29,
+/// Buffer row, and screen row within it, shown `y` rows below the top of
+/// the editor when lines are soft-wrapped, or `None` below the last line
+fn wrapped_row_at(app: &App, y: usize, text_width: usize) -> Option<(usize, usize)> {
+    let buffer = app.buffers.get(app.active_buffer)?;
+    let tab_size = app.config.editor.display_tab_width();
+
+    // Walk down from the first visible line until we reach the screen row
+    let mut rows_above = 0;
+    for row in app.scroll_offset.0..buffer.content.len() {
+        let rows = buffer.visual_rows(row, text_width, tab_size);
+        if y < rows_above + rows {
+            return Some((row, y - rows_above));
         }
+        rows_above += rows;
     }
+    None
+}
+
+/// Map a position in the text area to buffer coordinates when lines are soft-wrapped
+fn wrapped_screen_to_buffer_coords(
+    app: &App,
+    x: usize,
+    y: usize,
+    text_width: usize,
+) -> Option<ClickedPosition> {
+    let buffer = app.buffers.get(app.active_buffer)?;
+    let tab_size = app.config.editor.display_tab_width();
+
+    if let Some((row, visual_row)) = wrapped_row_at(app, y, text_width) {
+        // Past the text of a row is its end, which is only the line's end on
+        // the last row
+        let col = buffer.col_at_wrapped_position(row, visual_row, x, text_width, tab_size);
+        return Some(ClickedPosition::new(
+            row,
+            col,
+            col == buffer.content[row].len(),
+        ));
+    }
+
+    // Click is beyond buffer content - position at end of last line
+    let last_row = buffer.content.len().saturating_sub(1);
+    Some(ClickedPosition::new(
+        last_row,
+        buffer.content.get(last_row).map_or(0, |line| line.len()),
+        true,
+    ))
+}
+
+/// Size of the terminal, with a fallback when it can't be queried
+pub fn terminal_area() -> Rect {
+    let (width, height) = ratatui::crossterm::terminal::size().unwrap_or((120, 30));
+    Rect::new(0, 0, width, height)
+}
+
+/// The scrollbar column, if the scrollbar is enabled
+pub fn scrollbar_area(app: &App) -> Option<Rect> {
+    app.layout(terminal_area()).scrollbar
 }
 
 /// Calculate the editor area based on terminal size and UI layout
@@ -133,15 +243,7 @@ pub fn buffer_to_screen_coords(
     }
 
     // Account for line numbers with better width calculation
-    let line_number_width = if app.get_line_numbers_setting() {
-        if let Some(buffer) = app.buffers.get(app.active_buffer) {
-            buffer.line_number_width()
-        } else {
-            0
-        }
-    } else {
-        0
-    };
+    let line_number_width = app.gutter_width();
 
     let screen_col = relative_col + line_number_width;
 
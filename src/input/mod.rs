@@ -1,3 +1,5 @@
+pub mod chords;
+pub mod clicks;
 pub mod coordinates;
 pub mod scroll;
 
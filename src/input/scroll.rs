@@ -1,4 +1,5 @@
 /// Scroll handling module
+use crate::widgets::editor::scroll_margin;
 use crate::App;
 use ratatui::prelude::Rect;
 
@@ -16,20 +17,8 @@ impl App {
             scroll_row.saturating_sub((-delta) as usize)
         };
 
-        // Limit scroll to buffer content with proper bounds checking
-        if let Some(buffer) = self.buffers.get(self.active_buffer) {
-            let editor_height = editor_area.height as usize;
-
-            // Allow scrolling past the end of buffer to see final lines comfortably
-            // Add half the editor height as extra scrollable space
-            let max_scroll = if buffer.content.len() > editor_height {
-                buffer.content.len() + (editor_height / 2) - editor_height
-            } else {
-                0
-            };
-
-            self.scroll_offset.0 = new_scroll_row.min(max_scroll);
-        }
+        // Limit scroll to buffer content, plus the lines allowed past its end
+        self.scroll_offset.0 = new_scroll_row.min(self.get_max_scroll_row_past_end(editor_area));
 
         // Manual scroll shouldn't move the cursor - we're just changing the view
 
@@ -62,9 +51,9 @@ impl App {
             let (row, col) = buffer.cursor_pos;
             let (scroll_row, scroll_col) = self.scroll_offset;
 
-            // Define scroll margins - keep cursor at least 3 lines from edges when possible
-            let scroll_margin = 3;
+            // Keep the cursor `scroll_off` lines from the edges when possible
             let visible_rows = area.height as usize;
+            let scroll_margin = scroll_margin(self.config.editor.scroll_off, visible_rows);
 
             // Adjust vertical scroll with margin consideration
             if row < scroll_row + scroll_margin {
@@ -77,11 +66,7 @@ impl App {
             }
 
             // Adjust horizontal scroll if needed (account for line numbers)
-            let line_number_width = if self.get_line_numbers_setting() {
-                buffer.line_number_width()
-            } else {
-                0
-            };
+            let line_number_width = self.gutter_width();
             let visible_cols = area.width as usize - line_number_width;
 
             if col < scroll_col {
@@ -92,6 +77,62 @@ impl App {
         }
     }
 
+    /// Scroll so the scrollbar thumb sits at screen row `y` of the scrollbar `track`
+    ///
+    /// The top of the track shows the start of the buffer and the bottom shows
+    /// the last full page.
+    pub fn scroll_to_scrollbar_position(&mut self, y: u16, track: Rect) {
+        let max_scroll = self.get_max_scroll_row(track);
+        let offset = y
+            .saturating_sub(track.y)
+            .min(track.height.saturating_sub(1)) as usize;
+        let last_offset = track.height.saturating_sub(1).max(1) as usize;
+
+        self.scroll_offset.0 = (offset * max_scroll + last_offset / 2) / last_offset;
+        self.cursor_manager.notify_activity_for_active();
+    }
+
+    /// Scroll so the lines under minimap row `y` are centered in the editor
+    pub fn scroll_to_minimap_position(&mut self, y: u16, minimap: Rect, editor_area: Rect) {
+        let Some(buffer) = self.buffers.get(self.active_buffer) else {
+            return;
+        };
+        let row = y
+            .saturating_sub(minimap.y)
+            .min(minimap.height.saturating_sub(1));
+        let line = crate::widgets::minimap::line_at_row(buffer.content.len(), minimap.height, row);
+        let top = line.saturating_sub(editor_area.height as usize / 2);
+
+        self.scroll_offset.0 = top.min(self.get_max_scroll_row(editor_area));
+        self.cursor_manager.notify_activity_for_active();
+    }
+
+    /// Scroll `drag_scroll_lines` further while a drag selection is on the
+    /// top or bottom row of the editor, or past it
+    ///
+    /// Returns the row the drag is taken to be on, pulled into the editor so
+    /// the selection keeps extending with the pointer outside it.
+    pub fn scroll_for_drag(&mut self, y: u16, editor_area: Rect) -> u16 {
+        if editor_area.height == 0 {
+            return y;
+        }
+        let top = editor_area.y;
+        let bottom = editor_area.y + editor_area.height - 1;
+        let lines = self.config.ui.drag_scroll_lines;
+
+        if y <= top {
+            self.scroll_offset.0 = self.scroll_offset.0.saturating_sub(lines);
+        } else if y >= bottom {
+            // Stop once the last line is in view, without undoing a scroll
+            // past the end
+            let max_scroll_row = self
+                .get_max_scroll_row(editor_area)
+                .max(self.scroll_offset.0);
+            self.scroll_offset.0 = (self.scroll_offset.0 + lines).min(max_scroll_row);
+        }
+        y.clamp(top, bottom)
+    }
+
     /// Get the maximum scroll position for the current buffer
     pub fn get_max_scroll_row(&self, editor_area: Rect) -> usize {
         if let Some(buffer) = self.buffers.get(self.active_buffer) {
@@ -106,15 +147,38 @@ impl App {
         }
     }
 
+    /// Get the maximum scroll position when scrolling freely, which goes up
+    /// to `scroll_past_end` lines beyond the last full page but always keeps
+    /// the last line in view
+    pub fn get_max_scroll_row_past_end(&self, editor_area: Rect) -> usize {
+        let Some(buffer) = self.buffers.get(self.active_buffer) else {
+            return 0;
+        };
+        let past_end = self.get_max_scroll_row(editor_area) + self.config.editor.scroll_past_end;
+        past_end.min(buffer.content.len().saturating_sub(1))
+    }
+
+    /// Scroll so the cursor line sits in the middle of the editor (Ctrl+L)
+    ///
+    /// Near the end of the buffer this may scroll past it, as far as
+    /// `scroll_past_end` allows.
+    pub fn center_cursor(&mut self, editor_area: Rect) {
+        let Some(buffer) = self.buffers.get(self.active_buffer) else {
+            return;
+        };
+        let top = buffer
+            .cursor_pos
+            .0
+            .saturating_sub(editor_area.height as usize / 2);
+        self.scroll_offset.0 = top.min(self.get_max_scroll_row_past_end(editor_area));
+        self.cursor_manager.notify_activity_for_active();
+    }
+
     /// Get the maximum horizontal scroll position for the current buffer
     pub fn get_max_scroll_col(&self, editor_area: Rect) -> usize {
         if let Some(buffer) = self.buffers.get(self.active_buffer) {
-            let line_number_width = if self.get_line_numbers_setting() {
-                buffer.line_number_width()
-            } else {
-                0
-            };
-            let visible_cols = editor_area.width as usize - line_number_width;
+            let line_number_width = self.gutter_width();
+            let visible_cols = (editor_area.width as usize).saturating_sub(line_number_width);
 
             // Find the longest line in the buffer
             let max_line_length = buffer
@@ -28,6 +28,13 @@ impl InputSystem {
             .context("Failed to publish mouse input event")
     }
 
+    /// Handle text pasted into the terminal by publishing a paste event
+    pub fn handle_paste(&self, text: String) -> Result<()> {
+        self.event_bus
+            .publish(AppEvent::Paste(text.into()))
+            .context("Failed to publish paste event")
+    }
+
     /// Get the event bus sender for direct event publishing
     pub fn event_sender(&self) -> mpsc::UnboundedSender<AppEvent> {
         self.event_bus.sender()
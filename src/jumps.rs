@@ -0,0 +1,119 @@
+//! History of cursor jumps for going back and forward
+//!
+//! Movements that can land far from the cursor (`goto`, searches, mark
+//! jumps, page moves) record where it was before them, while single steps
+//! like the arrow keys don't. Ctrl+O walks back through those locations,
+//! switching buffers as needed, and Ctrl+I walks forward again. Recording a
+//! jump after going back drops the locations ahead, like a browser history.
+//! Locations are kept by buffer id, so ones in buffers closed since are
+//! skipped.
+
+use crate::buffer::Position;
+use crate::App;
+
+/// Most locations the jump list keeps
+pub const MAX_JUMPS: usize = 100;
+
+/// A location in the jump list: the id of a buffer and a position in it
+pub type JumpLocation = (usize, Position);
+
+/// Locations jumped away from, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct JumpList {
+    locations: Vec<JumpLocation>,
+
+    /// Entry the cursor was last sent to by going back or forward, or
+    /// `locations.len()` when it isn't in the history
+    index: usize,
+}
+
+impl JumpList {
+    /// Record a location the cursor is jumping away from
+    pub fn push(&mut self, location: JumpLocation) {
+        self.locations.truncate(self.index);
+        if self.locations.last() != Some(&location) {
+            self.locations.push(location);
+        }
+        if self.locations.len() > MAX_JUMPS {
+            self.locations.remove(0);
+        }
+        self.index = self.locations.len();
+    }
+
+    /// Step back from `current`, returning the location to go to
+    ///
+    /// The first step back records `current`, so going forward can return
+    /// to it.
+    pub fn back(&mut self, current: JumpLocation) -> Option<JumpLocation> {
+        if self.index == self.locations.len() {
+            self.push(current);
+            self.index = self.locations.len() - 1;
+        }
+        self.index = self.index.checked_sub(1)?;
+        Some(self.locations[self.index])
+    }
+
+    /// Step forward again, returning the location to go to
+    pub fn forward(&mut self) -> Option<JumpLocation> {
+        if self.index + 1 >= self.locations.len() {
+            return None;
+        }
+        self.index += 1;
+        Some(self.locations[self.index])
+    }
+}
+
+impl App {
+    /// Record the active buffer's cursor as a location jumped away from
+    pub fn record_jump(&mut self) {
+        if let Some(location) = self.current_jump_location() {
+            self.jump_list.push(location);
+        }
+    }
+
+    /// Go back to the previous location in the jump list, returning false if
+    /// there's none
+    pub fn jump_back(&mut self) -> bool {
+        let Some(current) = self.current_jump_location() else {
+            return false;
+        };
+        while let Some(location) = self.jump_list.back(current) {
+            if location != current && self.go_to_jump_location(location) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Go forward to the next location in the jump list, returning false if
+    /// there's none
+    pub fn jump_forward(&mut self) -> bool {
+        let current = self.current_jump_location();
+        while let Some(location) = self.jump_list.forward() {
+            if Some(location) != current && self.go_to_jump_location(location) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// The active buffer's id and cursor position
+    fn current_jump_location(&self) -> Option<JumpLocation> {
+        let buffer = self.get_active_buffer()?;
+        Some((buffer.id, Position::from_tuple(buffer.cursor_pos)))
+    }
+
+    /// Switch to the buffer of `location` and put the cursor there,
+    /// returning false if the buffer has been closed
+    fn go_to_jump_location(&mut self, (id, position): JumpLocation) -> bool {
+        let Some(index) = self.buffers.iter().position(|buffer| buffer.id == id) else {
+            return false;
+        };
+        if index != self.active_buffer {
+            self.switch_to_buffer(index);
+        }
+        // The text may have changed since the jump was recorded
+        self.buffers[index].place_cursor(position);
+        true
+    }
+}
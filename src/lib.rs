@@ -1,17 +1,33 @@
 //! Editor library for testing purposes
 
 pub mod app;
+pub mod autosave;
 pub mod buffer;
+pub mod buffer_limit;
+pub mod commands;
 pub mod config;
+pub mod cursor_positions;
+pub mod error;
 pub mod events;
 pub mod handlers;
+pub mod headless;
+pub mod help;
 pub mod input;
 pub mod input_system;
+pub mod jumps;
+pub mod marks;
+pub mod panes;
 pub mod performance;
 pub mod plugins;
+pub mod recent;
+pub mod session;
+pub mod syntax;
+pub mod theme;
 pub mod ui;
 pub mod widgets;
 
 // Re-export main types for convenience
-pub use app::{App, CommandMode};
+pub use app::{App, CommandMode, UnsavedPrompt};
 pub use buffer::Buffer;
+pub use error::{ConfigError, OpenError};
+pub use headless::HeadlessEditor;
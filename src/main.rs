@@ -2,7 +2,10 @@ use anyhow::Result;
 use ratatui::{
     backend::CrosstermBackend,
     crossterm::{
-        event::{DisableMouseCapture, EnableMouseCapture},
+        event::{
+            DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+            EnableFocusChange, EnableMouseCapture,
+        },
         execute,
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     },
@@ -11,19 +14,33 @@ use ratatui::{
 use std::io::stdout;
 
 pub mod app;
+pub mod autosave;
 pub mod buffer;
+pub mod buffer_limit;
+pub mod commands;
 pub mod config;
+pub mod cursor_positions;
+pub mod error;
 pub mod events;
 pub mod handlers;
+pub mod headless;
+pub mod help;
 pub mod input;
 pub mod input_system;
+pub mod jumps;
+pub mod marks;
+pub mod panes;
 pub mod performance;
 pub mod plugins;
+pub mod recent;
+pub mod session;
+pub mod syntax;
+pub mod theme;
 pub mod ui;
 pub mod widgets;
 
 // Re-export main types for easier imports
-pub use app::{App, CommandMode};
+pub use app::{App, CommandMode, UnsavedPrompt};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -33,7 +50,13 @@ async fn main() -> Result<()> {
     // Setup terminal - disable mouse events to prevent OS text selection
     enable_raw_mode()?;
     let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableFocusChange,
+        EnableBracketedPaste
+    )?;
 
     // Create backend without mouse events
     let backend = CrosstermBackend::new(stdout);
@@ -43,7 +66,12 @@ async fn main() -> Result<()> {
     let mut app = if args.len() > 1 {
         App::with_file(&args[1]).await?
     } else {
-        App::new().await
+        let mut app = App::new().await;
+        if let Err(e) = app.restore_session().await {
+            app.toast_manager
+                .add_warning(format!("Could not restore the last session: {}", e));
+        }
+        app
     };
     let result = app.run(&mut terminal).await;
 
@@ -53,6 +81,8 @@ async fn main() -> Result<()> {
         terminal.backend_mut(),
         LeaveAlternateScreen,
         DisableMouseCapture,
+        DisableFocusChange,
+        DisableBracketedPaste,
         ratatui::crossterm::cursor::Show
     )?;
 
@@ -0,0 +1,197 @@
+//! Marks for jumping back to saved cursor locations
+//!
+//! Local marks (`a`-`z`) remember an open buffer and a position in it, and
+//! last as long as the session. Jumping to one whose buffer has been closed
+//! fails. Global marks (`A`-`Z`) remember a file path and a position, so
+//! jumping to one can switch buffers or reopen the file. They're persisted
+//! under the user directory and survive restarts.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::buffer::{Buffer, Position};
+use crate::App;
+
+/// File the global marks are persisted to, relative to the user directory
+const GLOBAL_MARKS_FILE: &str = "global_marks.json";
+
+/// A mark that refers to a location in a file rather than an open buffer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlobalMark {
+    /// Path of the file the mark points into
+    pub path: PathBuf,
+
+    /// Cursor position inside that file
+    pub position: Position,
+}
+
+/// Whether a mark name refers to a global mark (`A`-`Z`)
+pub fn is_global_mark(name: char) -> bool {
+    name.is_ascii_uppercase()
+}
+
+/// Whether a mark name refers to a local mark (`a`-`z`)
+pub fn is_local_mark(name: char) -> bool {
+    name.is_ascii_lowercase()
+}
+
+/// Load global marks from the user directory, returning no marks if the file is missing
+pub fn load_global_marks(user_dir: &Path) -> Result<HashMap<char, GlobalMark>> {
+    let path = user_dir.join(GLOBAL_MARKS_FILE);
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let marks_str = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&marks_str).map_err(|e| anyhow!("Failed to parse global marks: {}", e))
+}
+
+/// Read the file of a global mark, returned by
+/// [`App::unopened_mark_file`], into a buffer
+pub async fn read_marked_file(path: PathBuf) -> Result<Buffer> {
+    Buffer::from_path_async(path.clone())
+        .await
+        .map_err(|e| anyhow!("Failed to open '{}': {}", path.display(), e))
+}
+
+impl App {
+    /// Record a local or global mark at the active buffer's cursor
+    pub fn set_mark(&mut self, name: char) -> Result<()> {
+        if is_global_mark(name) {
+            return self.set_global_mark(name);
+        }
+        if !is_local_mark(name) {
+            return Err(anyhow!("Marks must be a-z or A-Z, got '{}'", name));
+        }
+
+        let buffer = self
+            .get_active_buffer()
+            .ok_or_else(|| anyhow!("No active buffer"))?;
+        let mark = (buffer.id, Position::from_tuple(buffer.cursor_pos));
+        self.marks.insert(name, mark);
+        Ok(())
+    }
+
+    /// Jump to a local or global mark, switching buffers if needed
+    pub async fn jump_to_mark(&mut self, name: char) -> Result<()> {
+        let opened = match self.unopened_mark_file(name) {
+            Some(path) => Some(read_marked_file(path).await?),
+            None => None,
+        };
+        self.jump_to_read_mark(name, opened)
+    }
+
+    /// Jump to a mark, given the file of a global mark as read with
+    /// [`read_marked_file`] if no buffer had it open
+    pub fn jump_to_read_mark(&mut self, name: char, opened: Option<Buffer>) -> Result<()> {
+        if !is_local_mark(name) {
+            return self.jump_to_read_global_mark(name, opened);
+        }
+
+        let (id, position) = *self
+            .marks
+            .get(&name)
+            .ok_or_else(|| anyhow!("Mark '{}' is not set", name))?;
+        let index = self
+            .buffers
+            .iter()
+            .position(|buffer| buffer.id == id)
+            .ok_or_else(|| anyhow!("The buffer of mark '{}' has been closed", name))?;
+        self.record_jump();
+        if index != self.active_buffer {
+            self.switch_to_buffer(index);
+        }
+
+        // The text may have changed since the mark was set
+        self.buffers[index].place_cursor(position);
+        Ok(())
+    }
+
+    /// Record a global mark at the active buffer's cursor and persist it
+    pub fn set_global_mark(&mut self, name: char) -> Result<()> {
+        if !is_global_mark(name) {
+            return Err(anyhow!("Global marks must be A-Z, got '{}'", name));
+        }
+
+        let buffer = self
+            .get_active_buffer()
+            .ok_or_else(|| anyhow!("No active buffer"))?;
+        let path = buffer
+            .path
+            .clone()
+            .ok_or_else(|| anyhow!("Buffer has no file path - save it before marking"))?;
+        let position = Position::from_tuple(buffer.cursor_pos);
+
+        self.global_marks
+            .insert(name, GlobalMark { path, position });
+        self.save_global_marks()
+    }
+
+    /// Jump to a global mark, switching to (or opening) its file
+    pub async fn jump_to_global_mark(&mut self, name: char) -> Result<()> {
+        let opened = match self.unopened_mark_file(name) {
+            Some(path) => Some(read_marked_file(path).await?),
+            None => None,
+        };
+        self.jump_to_read_global_mark(name, opened)
+    }
+
+    /// File of the global mark `name` if no buffer has it open, which has
+    /// to be read before jumping there
+    pub fn unopened_mark_file(&self, name: char) -> Option<PathBuf> {
+        let mark = self.global_marks.get(&name)?;
+        match self.find_buffer_by_path(&mark.path) {
+            Some(_) => None,
+            None => Some(mark.path.clone()),
+        }
+    }
+
+    fn jump_to_read_global_mark(&mut self, name: char, opened: Option<Buffer>) -> Result<()> {
+        let mark = self
+            .global_marks
+            .get(&name)
+            .cloned()
+            .ok_or_else(|| anyhow!("Mark '{}' is not set", name))?;
+
+        let index = match (self.find_buffer_by_path(&mark.path), opened) {
+            (Some(index), _) => {
+                self.record_jump();
+                self.switch_to_buffer(index);
+                index
+            }
+            (None, Some(buffer)) => {
+                self.record_jump();
+                self.add_buffer(buffer)
+            }
+            (None, None) => {
+                return Err(anyhow!("'{}' isn't open", mark.path.display()));
+            }
+        };
+
+        // The file may have changed since the mark was set
+        self.buffers[index].place_cursor(mark.position);
+        Ok(())
+    }
+
+    /// Find the index of the open buffer backed by `path`, if any
+    pub fn find_buffer_by_path(&self, path: &Path) -> Option<usize> {
+        let canonical = path.canonicalize().ok();
+        self.buffers.iter().position(|buffer| match &buffer.path {
+            Some(buffer_path) => {
+                buffer_path == path
+                    || (canonical.is_some() && buffer_path.canonicalize().ok() == canonical)
+            }
+            None => false,
+        })
+    }
+
+    /// Write the global marks to the user directory
+    pub fn save_global_marks(&self) -> Result<()> {
+        let marks_str = serde_json::to_string_pretty(&self.global_marks)?;
+        std::fs::write(self.user_dir.join(GLOBAL_MARKS_FILE), marks_str)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+//! Split editor panes
+//!
+//! Splitting the editor shows several buffers side by side. Each pane is a
+//! viewport with its own buffer index and scroll position, and exactly one of
+//! them has focus and receives input.
+//!
+//! The focused pane's state lives in `App::active_buffer` and
+//! `App::scroll_offset` like it does without a split, so everything that edits
+//! or scrolls the active buffer keeps working unchanged. The stored entry for
+//! the focused pane is only brought up to date when focus moves away from it.
+
+use crate::App;
+
+/// A viewport onto one of the open buffers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Pane {
+    /// Index of the buffer shown in this pane
+    pub buffer: usize,
+
+    /// Scroll position of this pane
+    pub scroll_offset: (usize, usize),
+}
+
+impl App {
+    /// Whether the editor is split into more than one pane
+    pub fn is_split(&self) -> bool {
+        self.panes.len() > 1
+    }
+
+    /// The panes to draw, left to right, with the focused pane's live state
+    ///
+    /// Without a split this is the single active editor.
+    pub fn pane_views(&self) -> Vec<Pane> {
+        let focused = self.focused_view();
+        if !self.is_split() {
+            return vec![focused];
+        }
+
+        let mut panes = self.panes.clone();
+        panes[self.focused_pane] = focused;
+        panes
+    }
+
+    /// Split the focused pane in two, side by side
+    ///
+    /// The new pane opens to the right showing the next buffer (or the same
+    /// one if there's only one) and takes focus.
+    pub fn split_horizontal(&mut self) {
+        if self.panes.is_empty() {
+            self.panes.push(self.focused_view());
+            self.focused_pane = 0;
+        }
+        self.store_focused_pane();
+
+        let buffer = if self.buffers.is_empty() {
+            0
+        } else {
+            (self.active_buffer + 1) % self.buffers.len()
+        };
+        let index = self.focused_pane + 1;
+        self.panes.insert(
+            index,
+            Pane {
+                buffer,
+                scroll_offset: (0, 0),
+            },
+        );
+        self.load_pane(index);
+    }
+
+    /// Move focus to the pane at `index`
+    pub fn focus_pane(&mut self, index: usize) -> bool {
+        if index >= self.panes.len() {
+            return false;
+        }
+
+        self.store_focused_pane();
+        self.load_pane(index);
+        true
+    }
+
+    /// Move focus to the next pane, wrapping around to the first
+    pub fn focus_next_pane(&mut self) -> bool {
+        if !self.is_split() {
+            return false;
+        }
+
+        self.focus_pane((self.focused_pane + 1) % self.panes.len())
+    }
+
+    /// Close every pane except the focused one
+    pub fn close_other_panes(&mut self) -> bool {
+        if !self.is_split() {
+            return false;
+        }
+
+        self.panes.clear();
+        self.focused_pane = 0;
+        true
+    }
+
+    /// Fix up the panes' buffer indices after the buffer at `index` was removed
+    ///
+    /// Panes showing the removed buffer move to the active buffer.
+    pub(crate) fn remove_buffer_from_panes(&mut self, index: usize) {
+        let active_buffer = self.active_buffer;
+        for (i, pane) in self.panes.iter_mut().enumerate() {
+            if i == self.focused_pane {
+                continue;
+            }
+            if pane.buffer == index {
+                *pane = Pane {
+                    buffer: active_buffer,
+                    scroll_offset: (0, 0),
+                };
+            } else if pane.buffer > index {
+                pane.buffer -= 1;
+            }
+        }
+    }
+
+    fn focused_view(&self) -> Pane {
+        Pane {
+            buffer: self.active_buffer,
+            scroll_offset: self.scroll_offset,
+        }
+    }
+
+    fn store_focused_pane(&mut self) {
+        let focused = self.focused_view();
+        if let Some(pane) = self.panes.get_mut(self.focused_pane) {
+            *pane = focused;
+        }
+    }
+
+    fn load_pane(&mut self, index: usize) {
+        let pane = self.panes[index];
+        self.focused_pane = index;
+        self.active_buffer = pane.buffer;
+        self.scroll_offset = pane.scroll_offset;
+        self.note_buffer_used(pane.buffer);
+    }
+}
@@ -9,6 +9,7 @@ pub struct PerformanceMonitor {
     event_times: VecDeque<Duration>,
     max_samples: usize,
     last_frame_start: Option<Instant>,
+    degraded_streak: usize,
 }
 
 impl PerformanceMonitor {
@@ -19,6 +20,7 @@ impl PerformanceMonitor {
             event_times: VecDeque::new(),
             max_samples,
             last_frame_start: None,
+            degraded_streak: 0,
         }
     }
 
@@ -41,6 +43,12 @@ impl PerformanceMonitor {
         if self.frame_times.len() > self.max_samples {
             self.frame_times.pop_front();
         }
+
+        if self.is_performance_degraded() {
+            self.degraded_streak += 1;
+        } else {
+            self.degraded_streak = 0;
+        }
     }
 
     /// Add an event processing time measurement
@@ -80,12 +88,12 @@ impl PerformanceMonitor {
     pub fn stats_string(&self) -> String {
         let avg_frame = self
             .average_frame_time()
-            .map(|d| format!("{:.2}ms", d.as_millis()))
+            .map(|d| format!("{:.2}ms", d.as_secs_f64() * 1000.0))
             .unwrap_or_else(|| "N/A".to_string());
 
         let avg_event = self
             .average_event_time()
-            .map(|d| format!("{:.2}ms", d.as_millis()))
+            .map(|d| format!("{:.2}ms", d.as_secs_f64() * 1000.0))
             .unwrap_or_else(|| "N/A".to_string());
 
         let fps = self
@@ -113,6 +121,11 @@ impl PerformanceMonitor {
             false
         }
     }
+
+    /// Number of frames in a row that ended with performance degraded
+    pub fn degraded_streak(&self) -> usize {
+        self.degraded_streak
+    }
 }
 
 impl Default for PerformanceMonitor {
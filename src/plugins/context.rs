@@ -0,0 +1,86 @@
+//! What plugin commands can see and change
+//!
+//! A command gets an [`EditorContext`] for the editor it runs in instead of
+//! the whole app: the active buffer's text, cursor and selection, plus the
+//! status message and toasts to report back through. Edits made through it
+//! are recorded like any other, so whoever ran the command can report them.
+
+use crate::buffer::{Buffer, Position};
+use crate::App;
+
+/// Handle a plugin command works on the editor through
+pub struct EditorContext<'a> {
+    app: &'a mut App,
+}
+
+impl<'a> EditorContext<'a> {
+    /// Context for commands run in `app`
+    pub fn new(app: &'a mut App) -> Self {
+        Self { app }
+    }
+
+    fn buffer(&self) -> Option<&Buffer> {
+        self.app.get_active_buffer()
+    }
+
+    fn buffer_mut(&mut self) -> Option<&mut Buffer> {
+        let active_buffer = self.app.active_buffer;
+        self.app.buffers.get_mut(active_buffer)
+    }
+
+    /// Text of the active buffer, lines joined with `\n`
+    pub fn text(&self) -> String {
+        self.buffer()
+            .map(Buffer::content_as_string)
+            .unwrap_or_default()
+    }
+
+    /// Where the cursor is in the active buffer
+    pub fn cursor(&self) -> Position {
+        self.buffer().map_or(Position::new(0, 0), |buffer| {
+            Position::from_tuple(buffer.cursor_pos)
+        })
+    }
+
+    /// Move the cursor of the active buffer, clamped to its text
+    pub fn set_cursor(&mut self, position: Position) {
+        if let Some(buffer) = self.buffer_mut() {
+            buffer.place_cursor(position);
+        }
+    }
+
+    /// The selected text in the active buffer, if anything is selected
+    pub fn selected_text(&self) -> Option<String> {
+        self.buffer()?.get_selected_text()
+    }
+
+    /// Replace the selected text with `text`, keeping the new text selected
+    ///
+    /// Does nothing if nothing is selected.
+    pub fn replace_selection(&mut self, text: &str) {
+        let Some(buffer) = self.buffer_mut() else {
+            return;
+        };
+        let Some((start, _)) = buffer.get_selection_range() else {
+            return;
+        };
+
+        buffer.clear_secondary_cursors();
+        buffer.delete_selection();
+        buffer.clear_selection();
+        buffer.cursor_pos = start.to_tuple();
+        buffer.insert_str(text);
+        buffer.selection_start = Some(start.to_tuple());
+        buffer.visual_mode = true;
+    }
+
+    /// Show `message` in the status bar
+    pub fn set_status(&mut self, message: impl Into<String>) {
+        self.app.set_status_message(message.into());
+    }
+
+    /// Show `message` as an info toast
+    pub fn show_toast(&mut self, message: impl Into<String>) {
+        self.app.toast_manager.add_info(message.into());
+    }
+}
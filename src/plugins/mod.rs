@@ -1,6 +1,13 @@
 //! Plugin system for the editor
 //!
 //! Makes the editor hackable - you can add your own features and commands.
+//! Commands work on the editor through an [`EditorContext`].
+mod context;
+mod sample;
+
+pub use context::EditorContext;
+pub use sample::UppercaseSelection;
+
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -34,8 +41,8 @@ pub struct Plugin {
 
 /// A command provided by a plugin
 pub trait PluginCommand: Send + Sync {
-    /// Execute the command
-    fn execute(&self, args: &[String]) -> Result<()>;
+    /// Execute the command on the editor `context` gives access to
+    fn execute(&self, context: &mut EditorContext<'_>, args: &[String]) -> Result<()>;
 
     /// Get the name of the command
     fn name(&self) -> &str;
@@ -0,0 +1,30 @@
+//! A sample plugin command
+//!
+//! Small enough to read in one go, it shows how a command reads the editor
+//! through its [`EditorContext`] and reports back to the user.
+
+use anyhow::Result;
+
+use super::{EditorContext, PluginCommand};
+
+/// Uppercases the selected text
+pub struct UppercaseSelection;
+
+impl PluginCommand for UppercaseSelection {
+    fn execute(&self, context: &mut EditorContext<'_>, _args: &[String]) -> Result<()> {
+        let Some(text) = context.selected_text() else {
+            context.set_status("Nothing selected to uppercase");
+            return Ok(());
+        };
+        context.replace_selection(&text.to_uppercase());
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "uppercase"
+    }
+
+    fn description(&self) -> &str {
+        "Uppercase the selected text"
+    }
+}
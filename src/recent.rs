@@ -0,0 +1,90 @@
+//! Recently opened files
+//!
+//! Every file opened with the `open` command goes to the front of a short
+//! list persisted under the user directory, and `open recent` lists them in
+//! the command palette to reopen one. Paths are stored absolute, so the list
+//! works from any directory.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+use crate::App;
+
+/// File the recent files are persisted to, relative to the user directory
+const RECENT_FILES_FILE: &str = "recent_files.json";
+
+/// Most files remembered
+pub const MAX_RECENT_FILES: usize = 20;
+
+/// Command that lists the recent files, optionally followed by a filter
+pub const OPEN_RECENT: &str = "open recent";
+
+/// Load the recent files from the user directory, returning none if the file
+/// is missing
+pub fn load_recent_files(user_dir: &Path) -> Result<Vec<PathBuf>> {
+    let path = user_dir.join(RECENT_FILES_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let recent_str = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&recent_str).map_err(|e| anyhow!("Failed to parse recent files: {}", e))
+}
+
+/// Write `recent_files` to the user directory without blocking the runtime,
+/// for callers that took them from the app and let go of it
+pub async fn write_recent_files(user_dir: &Path, recent_files: &[PathBuf]) -> Result<()> {
+    let recent_str = serde_json::to_string_pretty(recent_files)?;
+    tokio::fs::write(user_dir.join(RECENT_FILES_FILE), recent_str).await?;
+    Ok(())
+}
+
+/// The filter typed after `open recent`, empty if there's none yet, or
+/// `None` if the input isn't that command
+pub fn open_recent_query(input: &str) -> Option<&str> {
+    let rest = input.trim_start().strip_prefix(OPEN_RECENT)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim())
+}
+
+impl App {
+    /// Put the absolute `path` at the front of the recent files, without
+    /// persisting them
+    pub fn note_recent_file(&mut self, path: PathBuf) {
+        self.recent_files.retain(|recent| *recent != path);
+        self.recent_files.insert(0, path);
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+
+    /// Recent files that still exist and fuzzy-match `query`, most recent
+    /// first
+    pub fn recent_file_matches(&self, query: &str) -> Vec<PathBuf> {
+        self.recent_files
+            .iter()
+            .filter(|path| path.exists())
+            .filter(|path| {
+                query.is_empty()
+                    || crate::commands::fuzzy_score(query, &path.to_string_lossy()).is_some()
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Load the recent files, keeping none if they can't be read
+    pub fn load_recent_files(&mut self) {
+        match load_recent_files(&self.user_dir) {
+            Ok(recent_files) => self.recent_files = recent_files,
+            Err(e) => eprintln!("Warning: Could not load recent files: {}", e),
+        }
+    }
+
+    /// Write the recent files to the user directory
+    pub fn save_recent_files(&self) -> Result<()> {
+        let recent_str = serde_json::to_string_pretty(&self.recent_files)?;
+        std::fs::write(self.user_dir.join(RECENT_FILES_FILE), recent_str)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+//! Open files remembered between runs
+//!
+//! On quit the editor writes which files were open, which one was active and
+//! where each cursor was to a session file under the user directory. Starting
+//! without a file argument reopens them. Buffers that were never saved have
+//! nothing to reopen and are left out.
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::buffer::{Buffer, Position};
+use crate::App;
+
+/// File the session is persisted to, relative to the user directory
+const SESSION_FILE: &str = "session.json";
+
+/// The open files and the active one, as saved on quit
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// Every open file, in tab order
+    pub files: Vec<SessionFile>,
+
+    /// Index into `files` of the active buffer
+    pub active: usize,
+}
+
+/// An open file and where its cursor was
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionFile {
+    pub path: PathBuf,
+    pub cursor: Position,
+}
+
+impl App {
+    /// The open files, active buffer and cursors, ready to be saved
+    pub fn session(&self) -> Session {
+        let mut session = Session::default();
+        for (index, buffer) in self.buffers.iter().enumerate() {
+            let Some(path) = &buffer.path else {
+                continue;
+            };
+            if index == self.active_buffer {
+                session.active = session.files.len();
+            }
+            session.files.push(SessionFile {
+                path: path.clone(),
+                cursor: Position::from_tuple(buffer.cursor_pos),
+            });
+        }
+        session
+    }
+
+    /// Write the session to the user directory
+    pub fn save_session(&self) -> Result<()> {
+        let session_str = serde_json::to_string_pretty(&self.session())?;
+        std::fs::write(self.user_dir.join(SESSION_FILE), session_str)?;
+        Ok(())
+    }
+
+    /// Reopen the files of the last session in place of the open buffers
+    ///
+    /// Files that no longer exist are skipped with a warning toast. Nothing
+    /// changes if there's no session or none of its files could be opened.
+    /// Returns how many files were reopened.
+    pub async fn restore_session(&mut self) -> Result<usize> {
+        let path = self.user_dir.join(SESSION_FILE);
+        if !path.exists() {
+            return Ok(0);
+        }
+        let session_str = std::fs::read_to_string(&path)?;
+        let session: Session = serde_json::from_str(&session_str)
+            .map_err(|e| anyhow!("Failed to parse session: {}", e))?;
+
+        let mut buffers = Vec::with_capacity(session.files.len());
+        let mut active = 0;
+        for (index, file) in session.files.into_iter().enumerate() {
+            match Buffer::from_path_async(file.path.clone()).await {
+                Ok(mut buffer) => {
+                    buffer.place_cursor(file.cursor);
+                    if index <= session.active {
+                        active = buffers.len();
+                    }
+                    buffers.push(buffer);
+                }
+                Err(e) => self.toast_manager.add_warning(format!(
+                    "Skipped '{}' from the last session: {}",
+                    file.path.display(),
+                    e
+                )),
+            }
+        }
+
+        let restored = buffers.len();
+        if restored > 0 {
+            self.buffers = buffers;
+            self.active_buffer = active;
+            self.scroll_offset = (0, 0);
+            self.buffer_use.clear();
+            self.note_buffer_used(active);
+        }
+        Ok(restored)
+    }
+}
@@ -0,0 +1,41 @@
+//! JSON syntax highlighting
+
+use super::{
+    ident_end, number_end, string_end, HighlightKind, HighlightSpan, HighlightState, Highlighter,
+};
+
+/// Highlights JSON strings, numbers and the `true`/`false`/`null` literals
+pub struct JsonHighlighter;
+
+impl Highlighter for JsonHighlighter {
+    fn highlight_line(&self, line: &str, state: &mut HighlightState) -> Vec<HighlightSpan> {
+        // JSON has no multiline tokens, so every line starts fresh
+        *state = HighlightState::Normal;
+
+        let mut spans = Vec::new();
+        let mut pos = 0;
+
+        while let Some(c) = line[pos..].chars().next() {
+            let start = pos;
+
+            if c == '"' {
+                pos = string_end(line, start + 1, '"').unwrap_or(line.len());
+                spans.push(HighlightSpan::new(start..pos, HighlightKind::String));
+            } else if c.is_ascii_digit()
+                || (c == '-' && line[start + 1..].starts_with(|n: char| n.is_ascii_digit()))
+            {
+                pos = number_end(line, start + 1);
+                spans.push(HighlightSpan::new(start..pos, HighlightKind::Number));
+            } else if c.is_alphabetic() {
+                pos = ident_end(line, start);
+                if matches!(&line[start..pos], "true" | "false" | "null") {
+                    spans.push(HighlightSpan::new(start..pos, HighlightKind::Keyword));
+                }
+            } else {
+                pos += c.len_utf8();
+            }
+        }
+
+        spans
+    }
+}
@@ -0,0 +1,143 @@
+//! # Syntax highlighting
+//!
+//! Line-based highlighters that turn source text into colored ranges.
+//!
+//! A [`Highlighter`] looks at one line at a time and returns the byte ranges of
+//! keywords, strings, comments and numbers in it. Constructs that can span
+//! lines (block comments, multiline strings) are tracked with a small
+//! [`HighlightState`] passed from one line to the next.
+//!
+//! The highlighter for a buffer is picked from its file extension with
+//! [`highlighter_for_path`].
+
+mod json;
+mod rust;
+
+use std::ops::Range;
+use std::path::Path;
+
+pub use json::JsonHighlighter;
+pub use rust::RustHighlighter;
+
+/// Kinds of tokens a highlighter can mark
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HighlightKind {
+    Keyword,
+    String,
+    Comment,
+    Number,
+}
+
+/// A highlighted byte range within a line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightSpan {
+    pub range: Range<usize>,
+    pub kind: HighlightKind,
+}
+
+impl HighlightSpan {
+    pub fn new(range: Range<usize>, kind: HighlightKind) -> Self {
+        Self { range, kind }
+    }
+}
+
+/// Highlighting state carried over from the end of the previous line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HighlightState {
+    /// Not inside any multiline construct
+    #[default]
+    Normal,
+
+    /// Inside a block comment that hasn't been closed yet
+    BlockComment,
+
+    /// Inside a string literal that hasn't been closed yet
+    String,
+}
+
+/// Produces highlighted ranges for one line at a time
+pub trait Highlighter: Send + Sync {
+    /// Highlight `line`, starting in `state` and leaving it as it is at the end of the line
+    ///
+    /// Returned spans are sorted, don't overlap, and fall on char boundaries.
+    fn highlight_line(&self, line: &str, state: &mut HighlightState) -> Vec<HighlightSpan>;
+}
+
+/// Pick a highlighter from a file's extension
+pub fn highlighter_for_path(path: &Path) -> Option<&'static dyn Highlighter> {
+    match path.extension()?.to_str()? {
+        "rs" => Some(&RustHighlighter),
+        "json" => Some(&JsonHighlighter),
+        _ => None,
+    }
+}
+
+/// Line comment prefix used when a file's language isn't known
+pub const DEFAULT_COMMENT_PREFIX: &str = "//";
+
+/// Pick the line comment prefix from a file's extension, falling back to
+/// [`DEFAULT_COMMENT_PREFIX`]
+pub fn comment_prefix_for_path(path: Option<&Path>) -> &'static str {
+    let extension = path
+        .and_then(Path::extension)
+        .and_then(|extension| extension.to_str());
+    match extension {
+        Some("py" | "sh" | "bash" | "zsh" | "rb" | "pl" | "toml" | "yaml" | "yml" | "r") => "#",
+        Some("lua" | "sql" | "hs") => "--",
+        Some("vim") => "\"",
+        Some("lisp" | "el" | "clj" | "scm" | "asm" | "ini") => ";",
+        Some("tex" | "erl") => "%",
+        _ => DEFAULT_COMMENT_PREFIX,
+    }
+}
+
+/// Find the end of a string literal that starts at `start` (just after the opening quote)
+///
+/// Returns the byte index just past the closing quote, or `None` if the line
+/// ends first.
+fn string_end(line: &str, start: usize, quote: char) -> Option<usize> {
+    let mut chars = line[start..].char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == quote {
+            return Some(start + i + c.len_utf8());
+        }
+    }
+    None
+}
+
+/// Find the end of a number literal starting at `start`
+///
+/// Accepts digits, letters (for hex digits, exponents and type suffixes),
+/// underscores and a decimal point followed by a digit.
+fn number_end(line: &str, start: usize) -> usize {
+    let bytes = line.as_bytes();
+    let mut end = start;
+    while end < bytes.len() {
+        let b = bytes[end];
+        let is_decimal_point =
+            b == b'.' && bytes.get(end + 1).is_some_and(|next| next.is_ascii_digit());
+        let is_exponent_sign = (b == b'+' || b == b'-')
+            && matches!(bytes[end - 1], b'e' | b'E')
+            && !line[start..end].starts_with("0x");
+        if b.is_ascii_alphanumeric() || b == b'_' || is_decimal_point || is_exponent_sign {
+            end += 1;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+/// Find the end of an identifier starting at `start`
+fn ident_end(line: &str, start: usize) -> usize {
+    line[start..]
+        .char_indices()
+        .find(|(_, c)| !is_ident_char(*c))
+        .map_or(line.len(), |(i, _)| start + i)
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
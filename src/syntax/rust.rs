@@ -0,0 +1,103 @@
+//! Rust syntax highlighting
+
+use super::{
+    ident_end, is_ident_char, number_end, string_end, HighlightKind, HighlightSpan, HighlightState,
+    Highlighter,
+};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+/// Highlights Rust keywords, strings, chars, comments and numbers
+pub struct RustHighlighter;
+
+impl Highlighter for RustHighlighter {
+    fn highlight_line(&self, line: &str, state: &mut HighlightState) -> Vec<HighlightSpan> {
+        let mut spans = Vec::new();
+
+        // Finish whatever the previous line left open
+        let (carried_kind, carried_end) = match *state {
+            HighlightState::BlockComment => {
+                (HighlightKind::Comment, line.find("*/").map(|i| i + 2))
+            }
+            HighlightState::String => (HighlightKind::String, string_end(line, 0, '"')),
+            HighlightState::Normal => (HighlightKind::Comment, Some(0)),
+        };
+        let mut pos = match carried_end {
+            Some(end) => {
+                *state = HighlightState::Normal;
+                end
+            }
+            None => line.len(),
+        };
+        if pos > 0 {
+            spans.push(HighlightSpan::new(0..pos, carried_kind));
+        }
+
+        while let Some(c) = line[pos..].chars().next() {
+            let rest = &line[pos..];
+            let start = pos;
+
+            if rest.starts_with("//") {
+                spans.push(HighlightSpan::new(
+                    start..line.len(),
+                    HighlightKind::Comment,
+                ));
+                break;
+            } else if rest.starts_with("/*") {
+                pos = match line[start + 2..].find("*/") {
+                    Some(i) => start + 2 + i + 2,
+                    None => {
+                        *state = HighlightState::BlockComment;
+                        line.len()
+                    }
+                };
+                spans.push(HighlightSpan::new(start..pos, HighlightKind::Comment));
+            } else if c == '"' {
+                pos = string_end(line, start + 1, '"').unwrap_or_else(|| {
+                    *state = HighlightState::String;
+                    line.len()
+                });
+                spans.push(HighlightSpan::new(start..pos, HighlightKind::String));
+            } else if c == '\'' {
+                // Char literal, or a lifetime if there's no closing quote right after
+                match char_literal_end(line, start) {
+                    Some(end) => {
+                        spans.push(HighlightSpan::new(start..end, HighlightKind::String));
+                        pos = end;
+                    }
+                    None => pos = ident_end(line, start + 1),
+                }
+            } else if c.is_ascii_digit() {
+                pos = number_end(line, start);
+                spans.push(HighlightSpan::new(start..pos, HighlightKind::Number));
+            } else if is_ident_char(c) {
+                pos = ident_end(line, start);
+                if KEYWORDS.contains(&&line[start..pos]) {
+                    spans.push(HighlightSpan::new(start..pos, HighlightKind::Keyword));
+                }
+            } else {
+                pos += c.len_utf8();
+            }
+        }
+
+        spans
+    }
+}
+
+/// Find the end of a char literal like `'a'` or `'\n'` starting at `start`
+fn char_literal_end(line: &str, start: usize) -> Option<usize> {
+    let mut chars = line[start + 1..].char_indices();
+    let (_, first) = chars.next()?;
+    if first == '\\' {
+        return string_end(line, start + 1, '\'');
+    }
+    match chars.next()? {
+        (i, '\'') => Some(start + 1 + i + 1),
+        _ => None,
+    }
+}
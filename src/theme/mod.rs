@@ -0,0 +1,303 @@
+//! # Themes
+//!
+//! Colors and spacing used when drawing the editor.
+//!
+//! A theme maps named roles (editor text, gutter, selection, ...) to colors so
+//! widgets don't have to hardcode them. Everything has a sensible default that
+//! matches the editor's original look.
+
+mod named;
+
+pub use named::{BUILTIN_THEMES, THEMES_DIR};
+
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::buffer::LineStatus;
+use crate::syntax::HighlightKind;
+
+/// Color palette and spacing for the editor UI
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// Foreground of regular buffer text
+    pub editor_fg: Color,
+
+    /// Background of the text area
+    pub editor_bg: Color,
+
+    /// Foreground of line numbers
+    pub line_number: Color,
+
+    /// Background of the line-number gutter, including the gap after the numbers
+    pub gutter_bg: Color,
+
+    /// Number of spaces between the line numbers and the text
+    pub gutter_gap: u16,
+
+    /// Foreground of selected text
+    pub selection_fg: Color,
+
+    /// Background of selected text
+    pub selection_bg: Color,
+
+    /// Background of the line the cursor is on
+    pub current_line_bg: Color,
+
+    /// Background of the bracket at the cursor and its match
+    pub matching_bracket_bg: Color,
+
+    /// Background of the extra cursors when editing with several at once
+    pub secondary_cursor_bg: Color,
+
+    /// Background of search matches
+    pub search_match_bg: Color,
+
+    /// Background of the search match the cursor is on
+    pub active_search_match_bg: Color,
+
+    /// Gutter marker of lines added since the file was saved
+    pub diff_added: Color,
+
+    /// Gutter marker of lines modified since the file was saved
+    pub diff_modified: Color,
+
+    /// Gutter marker where lines were removed since the file was saved
+    pub diff_removed: Color,
+
+    /// Foreground of spaces and tabs when whitespace is rendered
+    pub whitespace: Color,
+
+    /// Foreground of language keywords
+    pub keyword: Color,
+
+    /// Foreground of string and character literals
+    pub string: Color,
+
+    /// Foreground of comments
+    pub comment: Color,
+
+    /// Foreground of numeric literals
+    pub number: Color,
+
+    /// Scrollbar track
+    pub scrollbar_track: Color,
+
+    /// Scrollbar thumb showing the visible part of the buffer
+    pub scrollbar_thumb: Color,
+
+    /// Line blocks in the minimap
+    pub minimap_fg: Color,
+
+    /// Background of the minimap band covering the visible lines
+    pub minimap_viewport_bg: Color,
+
+    /// Foreground of inactive tabs in the tab bar
+    pub tab_fg: Color,
+
+    /// Background of the tab bar and inactive tabs
+    pub tab_bg: Color,
+
+    /// Foreground of the active buffer's tab
+    pub active_tab_fg: Color,
+
+    /// Background of the active buffer's tab
+    pub active_tab_bg: Color,
+
+    /// Foreground of the status bar
+    pub status_bar_fg: Color,
+
+    /// Background of the status bar
+    pub status_bar_bg: Color,
+
+    /// Foreground of the less important status bar slots
+    pub status_bar_dim_fg: Color,
+
+    /// Background of the mode indicator in the status bar
+    pub status_bar_mode_bg: Color,
+
+    /// Foreground of the character under the cursor
+    pub cursor_fg: Color,
+
+    /// Background of the cell the cursor is on
+    pub cursor_bg: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            editor_fg: Color::White,
+            editor_bg: Color::Black,
+            line_number: Color::Rgb(100, 100, 120),
+            gutter_bg: Color::Black,
+            gutter_gap: 1,
+            selection_fg: Color::White,
+            selection_bg: Color::DarkGray,
+            current_line_bg: Color::Rgb(30, 30, 40),
+            matching_bracket_bg: Color::Rgb(75, 75, 100),
+            secondary_cursor_bg: Color::Rgb(200, 200, 210),
+            search_match_bg: Color::Rgb(90, 80, 40),
+            active_search_match_bg: Color::Rgb(220, 170, 60),
+            diff_added: Color::Rgb(110, 180, 90),
+            diff_modified: Color::Rgb(80, 140, 220),
+            diff_removed: Color::Rgb(210, 80, 80),
+            whitespace: Color::Rgb(70, 70, 85),
+            keyword: Color::Rgb(198, 120, 221),
+            string: Color::Rgb(152, 195, 121),
+            comment: Color::Rgb(92, 99, 112),
+            number: Color::Rgb(209, 154, 102),
+            scrollbar_track: Color::Rgb(60, 60, 70),
+            scrollbar_thumb: Color::Rgb(130, 130, 150),
+            minimap_fg: Color::Rgb(80, 80, 95),
+            minimap_viewport_bg: Color::Rgb(35, 35, 45),
+            tab_fg: Color::Gray,
+            tab_bg: Color::Rgb(30, 30, 40),
+            active_tab_fg: Color::White,
+            active_tab_bg: Color::LightBlue,
+            status_bar_fg: Color::White,
+            status_bar_bg: Color::LightBlue,
+            status_bar_dim_fg: Color::Gray,
+            status_bar_mode_bg: Color::DarkGray,
+            cursor_fg: Color::Black,
+            cursor_bg: Color::White,
+        }
+    }
+}
+
+impl Theme {
+    /// Style for regular buffer text
+    pub fn editor_style(&self) -> Style {
+        Style::default().fg(self.editor_fg).bg(self.editor_bg)
+    }
+
+    /// Style for the line numbers in the gutter
+    pub fn line_number_style(&self) -> Style {
+        Style::default().fg(self.line_number).bg(self.gutter_bg)
+    }
+
+    /// Style for the gap between the line numbers and the text
+    pub fn gutter_gap_style(&self) -> Style {
+        Style::default().bg(self.gutter_bg)
+    }
+
+    /// Style for selected text
+    pub fn selection_style(&self) -> Style {
+        Style::default().fg(self.selection_fg).bg(self.selection_bg)
+    }
+
+    /// Background for the line the cursor is on, under any text styling
+    pub fn current_line_style(&self) -> Style {
+        Style::default().bg(self.current_line_bg)
+    }
+
+    /// Style for the bracket at the cursor and its match
+    pub fn matching_bracket_style(&self) -> Style {
+        Style::default()
+            .bg(self.matching_bracket_bg)
+            .add_modifier(Modifier::BOLD)
+    }
+
+    /// Style for the cells under the extra cursors
+    pub fn secondary_cursor_style(&self) -> Style {
+        Style::default()
+            .fg(self.editor_bg)
+            .bg(self.secondary_cursor_bg)
+    }
+
+    /// Background for every search match in view
+    pub fn search_match_style(&self) -> Style {
+        Style::default().bg(self.search_match_bg)
+    }
+
+    /// Style for the search match the cursor is on, stronger than the rest
+    pub fn active_search_match_style(&self) -> Style {
+        Style::default()
+            .fg(self.editor_bg)
+            .bg(self.active_search_match_bg)
+            .add_modifier(Modifier::BOLD)
+    }
+
+    /// Style for the gutter marker of a changed line
+    pub fn diff_marker_style(&self, status: LineStatus) -> Style {
+        let color = match status {
+            LineStatus::Added => self.diff_added,
+            LineStatus::Modified => self.diff_modified,
+            LineStatus::Removed => self.diff_removed,
+        };
+        Style::default().fg(color).bg(self.gutter_bg)
+    }
+
+    /// Foreground for the glyphs drawn in place of spaces and tabs
+    pub fn whitespace_style(&self) -> Style {
+        Style::default().fg(self.whitespace)
+    }
+
+    /// Style for the scrollbar track
+    pub fn scrollbar_track_style(&self) -> Style {
+        Style::default().fg(self.scrollbar_track).bg(self.editor_bg)
+    }
+
+    /// Style for the scrollbar thumb
+    pub fn scrollbar_thumb_style(&self) -> Style {
+        Style::default().fg(self.scrollbar_thumb).bg(self.editor_bg)
+    }
+
+    /// Style for the minimap outside the visible band
+    pub fn minimap_style(&self) -> Style {
+        Style::default().fg(self.minimap_fg).bg(self.editor_bg)
+    }
+
+    /// Style for the minimap rows covering the visible lines
+    pub fn minimap_viewport_style(&self) -> Style {
+        Style::default()
+            .fg(self.minimap_fg)
+            .bg(self.minimap_viewport_bg)
+    }
+
+    /// Style for the tab bar and inactive tabs
+    pub fn tab_style(&self) -> Style {
+        Style::default().fg(self.tab_fg).bg(self.tab_bg)
+    }
+
+    /// Style for the active buffer's tab
+    pub fn active_tab_style(&self) -> Style {
+        Style::default()
+            .fg(self.active_tab_fg)
+            .bg(self.active_tab_bg)
+            .add_modifier(Modifier::BOLD)
+    }
+
+    /// Style for the status bar and most of its slots
+    pub fn status_bar_style(&self) -> Style {
+        Style::default()
+            .fg(self.status_bar_fg)
+            .bg(self.status_bar_bg)
+    }
+
+    /// Style for the less important status bar slots
+    pub fn status_bar_dim_style(&self) -> Style {
+        Style::default()
+            .fg(self.status_bar_dim_fg)
+            .bg(self.status_bar_bg)
+    }
+
+    /// Style for the mode indicator in the status bar
+    pub fn status_bar_mode_style(&self) -> Style {
+        Style::default()
+            .fg(self.status_bar_fg)
+            .bg(self.status_bar_mode_bg)
+    }
+
+    /// Style for the cell under the cursor
+    pub fn cursor_style(&self) -> Style {
+        Style::default().fg(self.cursor_fg).bg(self.cursor_bg)
+    }
+
+    /// Foreground color for a kind of highlighted token
+    pub fn syntax_color(&self, kind: HighlightKind) -> Color {
+        match kind {
+            HighlightKind::Keyword => self.keyword,
+            HighlightKind::String => self.string,
+            HighlightKind::Comment => self.comment,
+            HighlightKind::Number => self.number,
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! Themes picked by name
+//!
+//! "default" and "dark" are built in. Any other name is looked up as
+//! `<name>.json` in the themes directory: a JSON object mapping role names to
+//! colors, plus an optional `"base"` naming the built-in theme the roles not
+//! listed are taken from. Colors are anything ratatui parses, like `"red"`,
+//! `"#1e1e2e"` or an ANSI index such as `"236"`.
+//!
+//! ```json
+//! { "base": "dark", "editor_bg": "#1e1e2e", "selection_bg": "#45475a" }
+//! ```
+
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use ratatui::style::Color;
+use serde_json::{Map, Value};
+
+use super::Theme;
+
+/// Directory under the user directory holding theme files
+pub const THEMES_DIR: &str = "themes";
+
+/// Names of the themes that don't need a file
+pub const BUILTIN_THEMES: &[&str] = &["default", "dark"];
+
+impl Theme {
+    /// A darker theme with softer, true-color backgrounds
+    pub fn dark() -> Self {
+        Self {
+            editor_fg: Color::Rgb(205, 214, 244),
+            editor_bg: Color::Rgb(24, 24, 32),
+            line_number: Color::Rgb(88, 91, 112),
+            gutter_bg: Color::Rgb(24, 24, 32),
+            selection_fg: Color::Rgb(205, 214, 244),
+            selection_bg: Color::Rgb(69, 71, 90),
+            current_line_bg: Color::Rgb(36, 36, 48),
+            whitespace: Color::Rgb(60, 62, 78),
+            scrollbar_track: Color::Rgb(40, 40, 52),
+            minimap_viewport_bg: Color::Rgb(40, 40, 52),
+            tab_bg: Color::Rgb(30, 30, 40),
+            active_tab_fg: Color::Rgb(24, 24, 32),
+            active_tab_bg: Color::Rgb(137, 180, 250),
+            status_bar_fg: Color::Rgb(24, 24, 32),
+            status_bar_bg: Color::Rgb(137, 180, 250),
+            status_bar_dim_fg: Color::Rgb(49, 50, 68),
+            status_bar_mode_bg: Color::Rgb(69, 71, 90),
+            cursor_fg: Color::Rgb(24, 24, 32),
+            cursor_bg: Color::Rgb(245, 224, 220),
+            ..Self::default()
+        }
+    }
+
+    /// One of the built-in themes, by name
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "dark" => Some(Self::dark()),
+            _ => None,
+        }
+    }
+
+    /// Theme called `name`, either built in or read from
+    /// `<themes_dir>/<name>.json`
+    pub fn load(themes_dir: &Path, name: &str) -> Result<Self> {
+        if let Some(theme) = Self::builtin(name) {
+            return Ok(theme);
+        }
+        // Only files right in the themes directory are themes
+        if name.is_empty() || name.contains("..") || name.chars().any(std::path::is_separator) {
+            bail!("'{}' is not a theme name", name);
+        }
+
+        let path = themes_dir.join(format!("{}.json", name));
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Failed to read theme '{}': {}", path.display(), e))?;
+        let roles: Map<String, Value> = serde_json::from_str(&text)
+            .map_err(|e| anyhow!("Failed to parse theme '{}': {}", path.display(), e))?;
+        Self::from_roles(&roles).map_err(|e| anyhow!("Invalid theme '{}': {}", name, e))
+    }
+
+    /// Theme built from a role → color map, starting from its `base`
+    fn from_roles(roles: &Map<String, Value>) -> Result<Self> {
+        let mut theme = match roles.get("base") {
+            None => Self::default(),
+            Some(Value::String(base)) => {
+                Self::builtin(base).ok_or_else(|| anyhow!("'{}' is not a built-in theme", base))?
+            }
+            Some(_) => bail!("'base' must be a theme name"),
+        };
+        for (role, value) in roles {
+            if role != "base" {
+                theme.set_role(role, value)?;
+            }
+        }
+        Ok(theme)
+    }
+
+    /// Set the color (or, for `gutter_gap`, the width) of one role
+    fn set_role(&mut self, role: &str, value: &Value) -> Result<()> {
+        if role == "gutter_gap" {
+            self.gutter_gap = value
+                .as_u64()
+                .and_then(|gap| u16::try_from(gap).ok())
+                .ok_or_else(|| anyhow!("'gutter_gap' must be a number"))?;
+            return Ok(());
+        }
+
+        let slot = match role {
+            "editor_fg" => &mut self.editor_fg,
+            "editor_bg" => &mut self.editor_bg,
+            "line_number" => &mut self.line_number,
+            "gutter_bg" => &mut self.gutter_bg,
+            "selection_fg" => &mut self.selection_fg,
+            "selection_bg" => &mut self.selection_bg,
+            "current_line_bg" => &mut self.current_line_bg,
+            "matching_bracket_bg" => &mut self.matching_bracket_bg,
+            "secondary_cursor_bg" => &mut self.secondary_cursor_bg,
+            "search_match_bg" => &mut self.search_match_bg,
+            "active_search_match_bg" => &mut self.active_search_match_bg,
+            "diff_added" => &mut self.diff_added,
+            "diff_modified" => &mut self.diff_modified,
+            "diff_removed" => &mut self.diff_removed,
+            "whitespace" => &mut self.whitespace,
+            "keyword" => &mut self.keyword,
+            "string" => &mut self.string,
+            "comment" => &mut self.comment,
+            "number" => &mut self.number,
+            "scrollbar_track" => &mut self.scrollbar_track,
+            "scrollbar_thumb" => &mut self.scrollbar_thumb,
+            "minimap_fg" => &mut self.minimap_fg,
+            "minimap_viewport_bg" => &mut self.minimap_viewport_bg,
+            "tab_fg" => &mut self.tab_fg,
+            "tab_bg" => &mut self.tab_bg,
+            "active_tab_fg" => &mut self.active_tab_fg,
+            "active_tab_bg" => &mut self.active_tab_bg,
+            "status_bar_fg" => &mut self.status_bar_fg,
+            "status_bar_bg" => &mut self.status_bar_bg,
+            "status_bar_dim_fg" => &mut self.status_bar_dim_fg,
+            "status_bar_mode_bg" => &mut self.status_bar_mode_bg,
+            "cursor_fg" => &mut self.cursor_fg,
+            "cursor_bg" => &mut self.cursor_bg,
+            _ => bail!("unknown role '{}'", role),
+        };
+        let color = value
+            .as_str()
+            .and_then(|color| Color::from_str(color).ok())
+            .ok_or_else(|| anyhow!("'{}' is not a color: {}", role, value))?;
+        *slot = color;
+        Ok(())
+    }
+}
@@ -1,26 +1,236 @@
+use crate::buffer::{visual_col, Buffer};
+use crate::config::EditorConfig;
+use crate::panes::Pane;
+use crate::theme::Theme;
 use crate::widgets::cursor::CursorSupport;
-use crate::widgets::editor::Editor;
-use crate::widgets::modal::CommandPalette;
-use crate::App;
+use crate::widgets::editor::{scroll_col_for_cursor, scroll_margin, Editor};
+use crate::widgets::minimap::{Minimap, MINIMAP_WIDTH};
+use crate::widgets::modal::{CommandPalette, Modal};
+use crate::widgets::tab_bar::TabBar;
+use crate::{App, CommandMode, UnsavedPrompt};
+use ratatui::buffer::Buffer as TuiBuffer;
 use ratatui::prelude::*;
+use ratatui::widgets::{Clear, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState};
+
+/// Everything the editor region's output depends on
+#[derive(Clone, PartialEq)]
+struct EditorCacheKey {
+    area: Rect,
+    buffer_id: usize,
+
+    /// The file's extension picks the highlighter
+    path: Option<std::path::PathBuf>,
+    version: u64,
+    modified: bool,
+    cursor_pos: (usize, usize),
+    secondary_cursors: Vec<crate::buffer::Position>,
+    selection_start: Option<(usize, usize)>,
+    visual_mode: bool,
+    selection_mode: crate::buffer::SelectionMode,
+    search_query: Option<crate::buffer::SearchQuery>,
+    scroll_offset: (usize, usize),
+    editor_config: EditorConfig,
+    theme: Theme,
+}
+
+/// Last rendered editor region, reused while nothing it depends on has changed
+///
+/// Frames triggered only by animations (cursor blink, toasts) copy these cells
+/// instead of rebuilding the editor's spans. Input marks the cache dirty so the
+/// next frame always rebuilds, even if the key didn't change.
+#[derive(Clone, Default)]
+pub struct EditorRenderCache {
+    key: Option<EditorCacheKey>,
+    cells: TuiBuffer,
+    dirty: bool,
+    builds: usize,
+}
+
+impl EditorRenderCache {
+    /// Force the next frame to rebuild the editor region
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Number of times the editor region has been rebuilt
+    pub fn builds(&self) -> usize {
+        self.builds
+    }
+
+    fn is_stale(&self, key: &EditorCacheKey) -> bool {
+        self.dirty || self.key.as_ref() != Some(key)
+    }
+
+    fn store(&mut self, key: EditorCacheKey, cells: TuiBuffer) {
+        self.key = Some(key);
+        self.cells = cells;
+        self.dirty = false;
+        self.builds += 1;
+    }
+}
+
+/// Screen regions of the main UI for a given terminal size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppLayout {
+    /// Row above the editor listing the open buffers, if enabled
+    pub tab_bar: Option<Rect>,
+
+    /// Text area of the focused editor pane, including the gutter
+    pub editor: Rect,
+
+    /// Text areas of every editor pane, left to right, with a separator
+    /// column between neighbours
+    pub panes: Vec<Rect>,
+
+    /// Column to the right of the text showing the minimap, if enabled
+    pub minimap: Option<Rect>,
+
+    /// Column on the editor's right edge reserved for the scrollbar, if enabled
+    pub scrollbar: Option<Rect>,
+
+    /// Status line at the bottom
+    pub status: Rect,
+}
+
+/// Divide the editor region into `count` panes of equal width
+///
+/// Every pane after the first gives up its leftmost column to a separator.
+fn split_panes(area: Rect, count: usize) -> Vec<Rect> {
+    if count <= 1 {
+        return vec![area];
+    }
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Ratio(1, count as u32); count])
+        .split(area)
+        .iter()
+        .enumerate()
+        .map(|(index, &pane)| {
+            if index > 0 && pane.width > 1 {
+                Rect::new(pane.x + 1, pane.y, pane.width - 1, pane.height)
+            } else {
+                pane
+            }
+        })
+        .collect()
+}
 
 impl App {
-    /// Main render function for the application UI
-    pub fn render(&mut self, f: &mut Frame) {
-        // Create layout
+    /// Split the screen into the UI regions
+    ///
+    /// Rendering and mouse handling both go through this so they agree on
+    /// where everything is.
+    pub fn layout(&self, area: Rect) -> AppLayout {
+        let tab_bar_height = u16::from(self.config.ui.show_tab_bar);
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Min(1),    // Editor area
-                Constraint::Length(1), // Status line
+                Constraint::Length(tab_bar_height), // Tab bar
+                Constraint::Min(1),                 // Editor area
+                Constraint::Length(1),              // Status line
             ])
-            .split(f.area());
+            .split(area);
+
+        let tab_bar = self.config.ui.show_tab_bar.then_some(chunks[0]);
+        let mut editor = chunks[1];
+        let scrollbar = if self.config.ui.show_scrollbar && editor.width > 1 {
+            editor.width -= 1;
+            Some(Rect::new(editor.right(), editor.y, 1, editor.height))
+        } else {
+            None
+        };
+        // Only show the minimap when it leaves the text most of the width
+        let minimap = if self.config.ui.show_minimap && editor.width >= MINIMAP_WIDTH * 3 {
+            editor.width -= MINIMAP_WIDTH;
+            Some(Rect::new(
+                editor.right(),
+                editor.y,
+                MINIMAP_WIDTH,
+                editor.height,
+            ))
+        } else {
+            None
+        };
+
+        let panes = split_panes(editor, self.pane_views().len());
+        let editor = panes.get(self.focused_pane).copied().unwrap_or(panes[0]);
+
+        AppLayout {
+            tab_bar,
+            editor,
+            panes,
+            minimap,
+            scrollbar,
+            status: chunks[2],
+        }
+    }
+
+    /// Where the focused editor is drawn
+    ///
+    /// That's the area from the last frame, so mouse positions map onto what's
+    /// actually on screen. Before the first frame it's laid out for the
+    /// current terminal size.
+    pub fn editor_area(&self) -> Rect {
+        self.last_editor_area.unwrap_or_else(|| {
+            self.layout(crate::input::coordinates::terminal_area())
+                .editor
+        })
+    }
+
+    /// Render a frame `width` by `height` cells in memory, without a
+    /// terminal, and return its cells
+    ///
+    /// Goes through `render` like a real frame, so it updates the same state,
+    /// like the editor area clicks are mapped onto.
+    pub fn render_to_test_buffer(&mut self, width: u16, height: u16) -> TuiBuffer {
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(width, height))
+            .expect("creating an in-memory terminal can't fail");
+        terminal
+            .draw(|f| self.render(f))
+            .expect("drawing to an in-memory terminal can't fail");
+        terminal.backend().buffer().clone()
+    }
+
+    /// Main render function for the application UI
+    pub fn render(&mut self, f: &mut Frame) {
+        let layout = self.layout(f.area());
+        self.last_editor_area = Some(layout.editor);
+
+        if let Some(tab_bar_area) = layout.tab_bar {
+            let tab_bar =
+                TabBar::new(&self.buffers, self.active_buffer).with_theme(self.theme.clone());
+            f.render_widget(tab_bar, tab_bar_area);
+        }
 
-        // Render the editor
-        self.render_editor(f, chunks[0]);
+        // Render the editor panes, with the focused one going through the cache
+        let views = self.pane_views();
+        for (index, (view, &area)) in views.iter().zip(&layout.panes).enumerate() {
+            if index > 0 {
+                self.render_pane_separator(f, area);
+            }
+            if area == layout.editor {
+                self.render_editor(f, area);
+            } else {
+                self.render_unfocused_pane(f, area, view);
+            }
+        }
+        if let Some(minimap_area) = layout.minimap {
+            self.render_minimap(f, minimap_area, layout.editor.height);
+        }
+        if let Some(scrollbar_area) = layout.scrollbar {
+            self.render_scrollbar(f, scrollbar_area);
+        }
 
-        // Render status line
-        self.render_status_line(f, chunks[1]);
+        // Render status line, or the search prompt in its place
+        self.render_status_line(f, layout.status);
+        if self.command_mode == CommandMode::TextSearch {
+            self.render_search_prompt(f, layout.status);
+        }
+
+        if self.show_performance_overlay {
+            self.render_performance_overlay(f, layout.editor);
+        }
 
         // Render command line (only in normal mode, modal handles command input)
         if !self.show_command_palette {
@@ -38,6 +248,19 @@ impl App {
             self.render_command_palette(f, f.area());
         }
 
+        if self.show_help {
+            self.render_help(f, f.area());
+        }
+
+        // Render the unsaved-changes prompt over everything, without a cursor
+        if let Some(prompt) = self.unsaved_prompt {
+            self.render_unsaved_prompt(f, f.area(), prompt);
+            return;
+        }
+        if self.show_help {
+            return;
+        }
+
         // Render the active cursor last
         self.render_active_cursor(f);
     }
@@ -51,18 +274,118 @@ impl App {
         // Get configuration for line numbers
         let show_line_numbers = self.get_line_numbers_setting();
 
-        let editor = Editor {
-            buffer: &self.buffers[self.active_buffer],
+        let buffer = &self.buffers[self.active_buffer];
+        let key = EditorCacheKey {
+            area,
+            buffer_id: buffer.id,
+            path: buffer.path.clone(),
+            version: buffer.version(),
+            modified: buffer.modified,
+            cursor_pos: buffer.cursor_pos,
+            secondary_cursors: buffer.secondary_cursors.clone(),
+            selection_start: buffer.selection_start,
+            visual_mode: buffer.visual_mode,
+            selection_mode: buffer.selection_mode,
+            search_query: self.search_query.clone(),
             scroll_offset: self.scroll_offset,
-            show_line_numbers,
+            editor_config: self.config.editor.clone(),
+            theme: self.theme.clone(),
         };
 
-        f.render_widget(editor, area);
+        if self.editor_cache.is_stale(&key) {
+            let editor = self.editor_widget(buffer, self.scroll_offset);
+
+            let mut cells = TuiBuffer::empty(area);
+            Widget::render(editor, area, &mut cells);
+            self.editor_cache.store(key, cells);
+        }
+
+        let frame_buffer = f.buffer_mut();
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                frame_buffer[(x, y)] = self.editor_cache.cells[(x, y)].clone();
+            }
+        }
 
         // Update cursor manager for editor context (but don't force cursor visibility)
         self.update_editor_cursor(area, show_line_numbers);
     }
 
+    /// Render a pane that doesn't have focus, without caching or a cursor
+    fn render_unfocused_pane(&self, f: &mut Frame, area: Rect, pane: &Pane) {
+        let Some(buffer) = self.buffers.get(pane.buffer) else {
+            return;
+        };
+
+        f.render_widget(self.editor_widget(buffer, pane.scroll_offset), area);
+    }
+
+    /// Build the editor widget for a buffer with the current settings
+    fn editor_widget<'a>(
+        &'a self,
+        buffer: &'a Buffer,
+        scroll_offset: (usize, usize),
+    ) -> Editor<'a> {
+        Editor {
+            scroll_offset,
+            show_line_numbers: self.get_line_numbers_setting(),
+            relative_line_numbers: self.config.editor.relative_line_numbers,
+            highlight_current_line: self.config.editor.highlight_current_line,
+            render_whitespace: self.config.editor.render_whitespace,
+            tab_size: self.config.editor.display_tab_width(),
+            theme: Theme {
+                gutter_gap: self.gutter_gap(),
+                ..self.theme.clone()
+            },
+            word_wrap: self.config.editor.word_wrap,
+            search: self.search_query.as_ref(),
+            scroll_off: self.config.editor.scroll_off,
+            ..Editor::new(buffer)
+        }
+    }
+
+    /// Draw the separator column on the left of a pane
+    fn render_pane_separator(&self, f: &mut Frame, pane: Rect) {
+        let Some(x) = pane.x.checked_sub(1) else {
+            return;
+        };
+        let style = self.theme.line_number_style();
+        let buffer = f.buffer_mut();
+        for y in pane.top()..pane.bottom() {
+            buffer[(x, y)].set_symbol("│").set_style(style);
+        }
+    }
+
+    /// Render the minimap for the active buffer
+    fn render_minimap(&self, f: &mut Frame, area: Rect, viewport_height: u16) {
+        if let Some(buffer) = self.buffers.get(self.active_buffer) {
+            let minimap = Minimap::new(buffer, self.scroll_offset.0, viewport_height as usize)
+                .with_theme(self.theme.clone());
+            f.render_widget(minimap, area);
+        }
+    }
+
+    /// Render the vertical scrollbar, only once the buffer doesn't fit the viewport
+    fn render_scrollbar(&self, f: &mut Frame, area: Rect) {
+        let Some(buffer) = self.buffers.get(self.active_buffer) else {
+            return;
+        };
+        let viewport = area.height as usize;
+        if buffer.content.len() <= viewport {
+            return;
+        }
+
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_style(self.theme.scrollbar_track_style())
+            .thumb_style(self.theme.scrollbar_thumb_style());
+        let mut state = ScrollbarState::new(buffer.content.len().saturating_sub(viewport))
+            .position(self.scroll_offset.0)
+            .viewport_content_length(viewport);
+        f.render_stateful_widget(scrollbar, area, &mut state);
+    }
+
     /// Render the status line using the new StatusBar widget
     fn render_status_line(&mut self, f: &mut Frame, area: Rect) {
         // Update status bar content before rendering
@@ -72,6 +395,43 @@ impl App {
         f.render_widget(self.status_bar.clone(), area);
     }
 
+    /// Render the text search prompt over the status line, labelled by
+    /// whether the query is a regex
+    fn render_search_prompt(&mut self, f: &mut Frame, area: Rect) {
+        let label = if self.search_regex {
+            "Regex search"
+        } else {
+            "Search"
+        };
+        let prompt = format!("{}: {}", label, self.command_input);
+        let cursor_x = area.x + (prompt.chars().count() as u16).min(area.width.saturating_sub(1));
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(prompt).style(self.theme.editor_style()),
+            area,
+        );
+
+        self.cursor_manager.hide_cursor("editor");
+        self.cursor_manager
+            .update_cursor_position("text_search", cursor_x, area.y);
+        self.cursor_manager.set_active_context("text_search");
+    }
+
+    /// Render frame timings in the top right corner of the editor
+    fn render_performance_overlay(&self, f: &mut Frame, editor: Rect) {
+        let stats = format!(" {} ", self.performance.stats_string());
+        let width = (stats.chars().count() as u16).min(editor.width);
+        let area = Rect::new(
+            editor.right() - width,
+            editor.y,
+            width,
+            1.min(editor.height),
+        );
+        f.render_widget(Clear, area);
+        let style = Style::default().fg(Color::White).bg(Color::DarkGray);
+        f.render_widget(Paragraph::new(stats).style(style), area);
+    }
+
     /// Render toast notifications
     fn render_toasts(&self, f: &mut Frame, area: Rect) {
         use crate::widgets::toast::ToastWidget;
@@ -79,9 +439,94 @@ impl App {
         f.render_widget(toast_widget, area);
     }
 
+    /// Render the prompt listing the buffers an action would lose changes in
+    fn render_unsaved_prompt(&self, f: &mut Frame, area: Rect, prompt: UnsavedPrompt) {
+        let heading = match prompt {
+            UnsavedPrompt::Overwrite => "Changed on disk since it was opened:",
+            _ => "Unsaved changes in:",
+        };
+        let mut lines = vec![Line::from(heading)];
+        for index in self.unsaved_buffers_for(prompt) {
+            lines.push(Line::from(format!("  {}", self.buffers[index].name)));
+        }
+        lines.push(Line::from(""));
+        let (title, options) = match prompt {
+            UnsavedPrompt::Quit => (
+                "Quit",
+                "[s] Save all and quit  [d] Discard and quit  [Esc] Cancel",
+            ),
+            UnsavedPrompt::CloseBuffer => (
+                "Close Buffer",
+                "[s] Save and close  [d] Discard and close  [Esc] Cancel",
+            ),
+            UnsavedPrompt::Reload => ("Reload", "[d] Discard and reload  [Esc] Cancel"),
+            UnsavedPrompt::Overwrite => (
+                "Overwrite",
+                "[s] Save over it  [d] Discard mine and reload  [Esc] Cancel",
+            ),
+        };
+        lines.push(Line::from(options));
+
+        let modal = Modal::new(title)
+            .content(lines.clone())
+            .width(62.min(area.width))
+            .height((lines.len() as u16 + 2).min(area.height));
+        f.render_widget(modal, area);
+    }
+
+    /// Render the help listing the commands, keeping its scroll position
+    /// within the lines that don't fit
+    fn render_help(&mut self, f: &mut Frame, area: Rect) {
+        let lines: Vec<Line> = self
+            .help_lines()
+            .into_iter()
+            .map(|line| {
+                if line.starts_with(' ') {
+                    Line::from(line)
+                } else {
+                    Line::styled(line, Style::default().fg(Color::Cyan))
+                }
+            })
+            .collect();
+
+        let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(2));
+        let visible = height.saturating_sub(2) as usize;
+        self.help_scroll = self.help_scroll.min(lines.len().saturating_sub(visible));
+
+        let modal = Modal::new("Help  (Up/Down to scroll, Esc to close)")
+            .content(lines)
+            .width(72.min(area.width))
+            .height(height)
+            .scroll(self.help_scroll as u16);
+        f.render_widget(modal, area);
+    }
+
     /// Render command palette modal
     fn render_command_palette(&mut self, f: &mut Frame, area: Rect) {
-        let palette = CommandPalette::new(&self.command_input);
+        let recent_labels: Vec<String> = self
+            .recent_matches
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        let (match_rows, rows_of_matches) =
+            crate::commands::buffer_match_rows(&self.buffers, &self.buffer_matches);
+        let mut selected = self.selected_suggestion;
+        let suggestions = if !self.buffer_matches.is_empty() {
+            // Headers aren't picked, so the selection skips over them
+            selected = selected.and_then(|index| rows_of_matches.get(index).copied());
+            match_rows.iter().map(String::as_str).collect()
+        } else if !recent_labels.is_empty() {
+            recent_labels.iter().map(String::as_str).collect()
+        } else {
+            self.command_suggestions
+                .iter()
+                .map(String::as_str)
+                .collect()
+        };
+        let palette = CommandPalette::new(&self.command_input)
+            .suggestions(suggestions)
+            .selected(selected)
+            .position(self.config.ui.command_palette_position);
 
         // Use the CursorSupport trait to calculate proper cursor position before rendering
         let cursor_position = palette.calculate_cursor_position(
@@ -107,29 +552,45 @@ impl App {
     }
 
     /// Get line numbers setting from config
+    ///
+    /// Relative line numbers turn the gutter on even if absolute ones are off.
     pub fn get_line_numbers_setting(&self) -> bool {
-        let config_dir = &self.user_dir;
-        if config_dir.exists() {
-            let mut config_manager = crate::config::ConfigManager::new(config_dir);
-            if config_manager.load().is_ok() {
-                config_manager.get_config().editor.show_line_numbers
-            } else {
-                true // Default to showing line numbers if config can't be loaded
+        self.config.editor.show_line_numbers || self.config.editor.relative_line_numbers
+    }
+
+    /// Spaces between the line numbers and the text: the editor setting if
+    /// there is one, else the theme's
+    pub fn gutter_gap(&self) -> u16 {
+        self.config
+            .editor
+            .gutter_gap
+            .unwrap_or(self.theme.gutter_gap)
+    }
+
+    /// Width of the line-number gutter for the active buffer (0 when line numbers are off)
+    pub fn gutter_width(&self) -> usize {
+        match self.buffers.get(self.active_buffer) {
+            Some(buffer) if self.get_line_numbers_setting() => {
+                buffer.gutter_width(self.gutter_gap() as usize)
             }
-        } else {
-            true // Default to showing line numbers if config directory doesn't exist
+            _ => 0,
         }
     }
 
     /// Ensure cursor is visible within the editor area (only call when cursor moves programmatically)
     pub fn ensure_cursor_visible(&mut self, area: Rect) {
+        if self.config.editor.word_wrap {
+            self.ensure_cursor_visible_wrapped(area);
+            return;
+        }
+
         if let Some(buffer) = self.buffers.get(self.active_buffer) {
             let (row, col) = buffer.cursor_pos;
             let (scroll_row, scroll_col) = self.scroll_offset;
 
-            // Define scroll margins - keep cursor at least 3 lines from edges when possible
-            let scroll_margin = 3;
+            // Keep the cursor `scroll_off` lines from the edges when possible
             let visible_rows = area.height as usize;
+            let scroll_margin = scroll_margin(self.config.editor.scroll_off, visible_rows);
 
             // Adjust vertical scroll with margin consideration
             if row < scroll_row + scroll_margin {
@@ -142,21 +603,58 @@ impl App {
             }
 
             // Adjust horizontal scroll if needed (account for line numbers)
-            let line_number_width = if self.get_line_numbers_setting() {
-                buffer.line_number_width()
-            } else {
-                0
-            };
-            let visible_cols = area.width as usize - line_number_width;
+            let line_number_width = self.gutter_width();
+            let visible_cols = (area.width as usize).saturating_sub(line_number_width);
+            let line = buffer.content.get(row).map_or("", |line| line.as_str());
+            self.scroll_offset.1 = scroll_col_for_cursor(
+                line,
+                col,
+                scroll_col,
+                visible_cols,
+                self.config.editor.display_tab_width(),
+            );
+        }
+    }
+
+    /// Vertical-only version of `ensure_cursor_visible` for soft-wrapped lines
+    ///
+    /// The scroll offset is still a buffer row, but the margin and viewport
+    /// height are counted in screen rows since long lines take several.
+    fn ensure_cursor_visible_wrapped(&mut self, area: Rect) {
+        let text_width = self.editor_text_width(area);
+        let visible_rows = area.height as usize;
+        let scroll_margin = scroll_margin(self.config.editor.scroll_off, visible_rows);
+        self.scroll_offset.1 = 0;
+
+        if let Some(buffer) = self.buffers.get(self.active_buffer) {
+            let (row, col) = buffer.cursor_pos;
 
-            if col < scroll_col {
-                self.scroll_offset.1 = col;
-            } else if col >= scroll_col + visible_cols {
-                self.scroll_offset.1 = col.saturating_sub(visible_cols) + 1;
+            if row < self.scroll_offset.0 + scroll_margin {
+                self.scroll_offset.0 = row.saturating_sub(scroll_margin);
+                return;
+            }
+
+            // Scroll down one buffer row at a time until the cursor's screen row
+            // is clear of the bottom margin
+            let max_cursor_row = visible_rows.saturating_sub(scroll_margin + 1);
+            let tab_size = self.config.editor.display_tab_width();
+            let cursor_row = |scroll_row: usize| {
+                let rows_above: usize = (scroll_row..row)
+                    .map(|r| buffer.visual_rows(r, text_width, tab_size))
+                    .sum();
+                rows_above + buffer.wrapped_position(row, col, text_width, tab_size).0
+            };
+            while self.scroll_offset.0 < row && cursor_row(self.scroll_offset.0) > max_cursor_row {
+                self.scroll_offset.0 += 1;
             }
         }
     }
 
+    /// Width of the editor's text area, excluding the gutter
+    pub fn editor_text_width(&self, area: Rect) -> usize {
+        (area.width as usize).saturating_sub(self.gutter_width())
+    }
+
     /// Update cursor position for the editor context
     fn update_editor_cursor(&mut self, area: Rect, show_line_numbers: bool) {
         // Don't update editor cursor if command palette is open
@@ -168,16 +666,36 @@ impl App {
         if let Some(buffer) = self.buffers.get(self.active_buffer) {
             let (row, col) = buffer.cursor_pos;
             let (scroll_row, scroll_col) = self.scroll_offset;
+            let line = buffer.content.get(row).map_or("", |line| line.as_str());
+            let tab_size = self.config.editor.display_tab_width();
 
             // Calculate line number width for cursor positioning
             let line_number_width = if show_line_numbers {
-                buffer.line_number_width() as u16
+                buffer.gutter_width(self.gutter_gap() as usize) as u16
             } else {
                 0
             };
 
-            let cursor_x = (col.saturating_sub(scroll_col)) as u16 + line_number_width;
-            let cursor_y = (row.saturating_sub(scroll_row)) as u16;
+            let (cursor_x, cursor_y) = if self.config.editor.word_wrap {
+                // Count the screen rows taken by the lines above the cursor
+                let text_width = (area.width - line_number_width.min(area.width)) as usize;
+                let rows_above: usize = (scroll_row..row)
+                    .map(|r| buffer.visual_rows(r, text_width, tab_size))
+                    .sum();
+                let (visual_row, x) = buffer.wrapped_position(row, col, text_width, tab_size);
+                (
+                    x as u16 + line_number_width,
+                    (rows_above + visual_row).min(u16::MAX as usize) as u16,
+                )
+            } else {
+                // Tabs before the cursor take more than one screen column
+                let x = visual_col(line, col, tab_size)
+                    .saturating_sub(visual_col(line, scroll_col, tab_size));
+                (
+                    x.min(u16::MAX as usize) as u16 + line_number_width,
+                    (row.saturating_sub(scroll_row)) as u16,
+                )
+            };
 
             // Always update cursor position, but clip it to the visible area
             // This ensures the scroll logic can work properly
@@ -235,7 +753,7 @@ impl App {
 
                 let cursor = Cursor::new(active_context.clone())
                     .with_position(position.x, position.y)
-                    .with_style(Style::default().bg(Color::White).fg(Color::Black))
+                    .with_style(self.theme.cursor_style())
                     .active(true);
 
                 // Get the cursor state from the manager
@@ -1,5 +1,12 @@
+use std::time::Duration;
+
 use ratatui::{prelude::*, widgets::StatefulWidget};
 
+use crate::config::UiConfig;
+
+/// Time between blinks unless the config says otherwise
+const DEFAULT_BLINK_INTERVAL: Duration = Duration::from_millis(500);
+
 /// A cursor widget that can render and manage cursor state independently
 #[derive(Debug, Clone)]
 pub struct Cursor {
@@ -13,6 +20,35 @@ pub struct Cursor {
     pub id: String,
 }
 
+/// How the cursor is drawn in its cell
+///
+/// Cells can't be split, so the bar and underline are drawn as glyphs when
+/// the cell is blank. Over text, the bar falls back to a block and the
+/// underline underlines the character in the cursor color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CursorShape {
+    /// The whole cell, for editing text
+    #[default]
+    Block,
+
+    /// A thin vertical bar, for typing into prompts
+    Bar,
+
+    /// A line under the cell, for overwriting text
+    Underline,
+}
+
+impl CursorShape {
+    /// Shape of the cursor in an input context: a block in the editor and a
+    /// bar in the command palette and search prompts
+    pub fn for_context(context: &str) -> Self {
+        match context {
+            "editor" => Self::Block,
+            _ => Self::Bar,
+        }
+    }
+}
+
 /// State for the cursor widget
 #[derive(Debug, Clone)]
 pub struct CursorState {
@@ -28,6 +64,12 @@ pub struct CursorState {
     pub last_activity: std::time::Instant,
     /// Duration to keep cursor solid after activity before starting to blink
     pub activity_timeout: std::time::Duration,
+    /// How the cursor is drawn
+    pub shape: CursorShape,
+    /// Whether the cursor blinks at all; when off it stays solid
+    pub blink_enabled: bool,
+    /// Time between blinks
+    pub blink_interval: Duration,
 }
 
 impl Default for CursorState {
@@ -39,6 +81,27 @@ impl Default for CursorState {
             blink_on: true,
             last_activity: std::time::Instant::now(),
             activity_timeout: std::time::Duration::from_millis(500), // 500ms before blinking starts
+            shape: CursorShape::Block,
+            blink_enabled: true,
+            blink_interval: DEFAULT_BLINK_INTERVAL,
+        }
+    }
+}
+
+impl CursorState {
+    /// A cursor that blinks as `ui` configures
+    pub fn from_config(ui: &UiConfig) -> Self {
+        let mut state = Self::default();
+        state.apply_config(ui);
+        state
+    }
+
+    /// Blink as `ui` configures from now on
+    pub fn apply_config(&mut self, ui: &UiConfig) {
+        self.blink_enabled = ui.cursor_blink_enabled;
+        self.blink_interval = Duration::from_millis(ui.cursor_blink_ms.max(1));
+        if !self.blink_enabled {
+            self.blink_on = true;
         }
     }
 }
@@ -48,7 +111,7 @@ impl Cursor {
         Self {
             position: Position::new(0, 0),
             active: true,
-            style: Style::default().bg(Color::White).fg(Color::Black),
+            style: crate::theme::Theme::default().cursor_style(),
             id: id.into(),
         }
     }
@@ -83,7 +146,7 @@ impl StatefulWidget for Cursor {
         let in_activity_period = now.duration_since(state.last_activity) < state.activity_timeout;
 
         // Determine cursor visibility - during activity period always show, otherwise use blink state
-        let should_show_cursor = if in_activity_period {
+        let should_show_cursor = if in_activity_period || !state.blink_enabled {
             true // During activity period, always show cursor (no blinking)
         } else {
             state.blink_on // Use the centrally managed blink state
@@ -97,10 +160,27 @@ impl StatefulWidget for Cursor {
             // Ensure cursor is within bounds
             if cursor_x < area.width && cursor_y < area.height {
                 if let Some(cell) = buf.cell_mut(Position::new(cursor_x, cursor_y)) {
-                    // Set cursor by changing the background color of the cell
-                    // This works for any character including spaces and empty cells
-                    cell.set_bg(Color::White);
-                    cell.set_fg(Color::Black);
+                    let blank = cell.symbol().trim().is_empty();
+                    let color = self.style.bg.unwrap_or(Color::White);
+                    match state.shape {
+                        // Invert the cell, which works for any character
+                        // including spaces and empty cells
+                        CursorShape::Block => {
+                            cell.set_style(self.style);
+                        }
+                        CursorShape::Bar if blank => {
+                            cell.set_symbol("▏").set_fg(color);
+                        }
+                        CursorShape::Bar => {
+                            cell.set_style(self.style);
+                        }
+                        CursorShape::Underline if blank => {
+                            cell.set_symbol("▁").set_fg(color);
+                        }
+                        CursorShape::Underline => {
+                            cell.set_fg(color).modifier.insert(Modifier::UNDERLINED);
+                        }
+                    }
                 }
             }
         }
@@ -117,6 +197,8 @@ pub struct CursorManager {
     cursors: std::collections::HashMap<String, CursorState>,
     /// Currently active cursor context
     active_context: Option<String>,
+    /// Blink settings new cursor states are created with, if configured
+    ui: Option<UiConfig>,
 }
 
 impl CursorManager {
@@ -124,9 +206,21 @@ impl CursorManager {
         Self::default()
     }
 
+    /// Blink every cursor, and every one created later, as `ui` configures
+    pub fn configure(&mut self, ui: &UiConfig) {
+        for cursor_state in self.cursors.values_mut() {
+            cursor_state.apply_config(ui);
+        }
+        self.ui = Some(ui.clone());
+    }
+
     /// Get or create a cursor state for a given context
     pub fn get_or_create_cursor(&mut self, context: &str) -> &mut CursorState {
-        self.cursors.entry(context.to_string()).or_default()
+        let ui = &self.ui;
+        self.cursors.entry(context.to_string()).or_insert_with(|| {
+            ui.as_ref()
+                .map_or_else(CursorState::default, CursorState::from_config)
+        })
     }
 
     /// Set the active cursor context - ONLY ONE CURSOR ACTIVE AT A TIME
@@ -139,9 +233,21 @@ impl CursorManager {
         // Set new active context
         self.active_context = Some(context.to_string());
 
-        // Show ONLY the active cursor
+        // Show ONLY the active cursor, shaped for its context
         let cursor_state = self.get_or_create_cursor(context);
         cursor_state.visible = true;
+        cursor_state.shape = CursorShape::for_context(context);
+    }
+
+    /// Draw the cursor of a context with another shape, until the context is
+    /// activated again
+    pub fn set_cursor_shape(&mut self, context: &str, shape: CursorShape) {
+        self.get_or_create_cursor(context).shape = shape;
+    }
+
+    /// Shape the cursor of a context is drawn with
+    pub fn get_cursor_shape(&self, context: &str) -> Option<CursorShape> {
+        self.cursors.get(context).map(|state| state.shape)
     }
 
     /// Get the active cursor context
@@ -191,15 +297,12 @@ impl CursorManager {
         }
     }
 
-    /// Get the current cursor position for a context, if visible
+    /// Get the last known cursor position for a context
+    ///
+    /// Positions are tracked for every context, not just the active one, so
+    /// switching back to a context restores its cursor where it was left.
     pub fn get_cursor_position(&self, context: &str) -> Option<Position> {
-        self.cursors.get(context).and_then(|state| {
-            if state.visible && self.active_context.as_deref() == Some(context) {
-                Some(state.position)
-            } else {
-                None
-            }
-        })
+        self.cursors.get(context).map(|state| state.position)
     }
 
     /// Get a mutable reference to a specific cursor state
@@ -251,13 +354,12 @@ impl CursorManager {
                 now.duration_since(cursor_state.last_activity) < cursor_state.activity_timeout;
 
             // Only blink if we're past the activity period
-            if !in_activity_period {
-                if now.duration_since(cursor_state.last_blink)
-                    > std::time::Duration::from_millis(500)
-                {
-                    cursor_state.blink_on = !cursor_state.blink_on;
-                    cursor_state.last_blink = now;
-                }
+            if cursor_state.blink_enabled
+                && !in_activity_period
+                && now.duration_since(cursor_state.last_blink) > cursor_state.blink_interval
+            {
+                cursor_state.blink_on = !cursor_state.blink_on;
+                cursor_state.last_blink = now;
             }
         }
     }
@@ -1,59 +1,328 @@
 use ratatui::{
     buffer::Buffer as TuiBuffer,
     layout::Rect,
-    style::{Color, Style},
+    style::Style,
     text::{Line, Span},
     widgets::{Paragraph, StatefulWidget, Widget},
 };
 
-use crate::buffer::Buffer;
+use std::ops::Range;
+
+use crate::buffer::{
+    char_width, visual_col, wrap_ranges, Buffer, LineProvider, LineStatus, Position, SearchQuery,
+};
+use crate::syntax::{highlighter_for_path, HighlightSpan, HighlightState};
+use crate::theme::Theme;
 
 pub struct Editor<'a> {
-    pub buffer: &'a Buffer,
+    pub lines: &'a dyn LineProvider, // Text to draw; a buffer also brings its cursor and selection
     pub scroll_offset: (usize, usize), // (row, col) offset for viewport scrolling
-    pub show_line_numbers: bool,       // Whether to display line numbers
+    pub show_line_numbers: bool,     // Whether to display line numbers
+    pub relative_line_numbers: bool, // Number lines by distance from the cursor line
+    pub highlight_current_line: bool, // Paint the cursor's line with a background
+    pub render_whitespace: bool,     // Draw spaces and tabs as visible glyphs
+    pub tab_size: usize,             // Columns between tab stops
+    pub theme: Theme,                // Colors and gutter spacing
+    pub word_wrap: bool,             // Soft-wrap long lines instead of scrolling horizontally
+    pub search: Option<&'a SearchQuery>, // Query whose matches in view are highlighted
+    pub scroll_off: usize,           // Lines kept visible above and below the cursor
 }
 
 impl<'a> Editor<'a> {
-    pub fn new(buffer: &'a Buffer) -> Self {
+    pub fn new(lines: &'a dyn LineProvider) -> Self {
         Self {
-            buffer,
+            lines,
             scroll_offset: (0, 0),
             show_line_numbers: true, // Enable line numbers by default
+            relative_line_numbers: false,
+            highlight_current_line: false,
+            render_whitespace: false,
+            tab_size: 4,
+            theme: Theme::default(),
+            word_wrap: false,
+            search: None,
+            scroll_off: 3,
+        }
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// The buffer being drawn, if the lines come from one
+    fn buffer(&self) -> Option<&'a Buffer> {
+        self.lines.as_buffer()
+    }
+
+    /// Position of the primary cursor, if the lines come from a buffer
+    fn cursor_pos(&self) -> Option<(usize, usize)> {
+        self.buffer().map(|buffer| buffer.cursor_pos)
+    }
+
+    /// Width of the column marking changed lines, 0 without a diff base
+    fn diff_marker_width(&self) -> usize {
+        self.buffer().map_or(0, Buffer::diff_marker_width)
+    }
+
+    /// Whether the gutter is shown; relative numbering implies line numbers
+    fn has_gutter(&self) -> bool {
+        self.show_line_numbers || self.relative_line_numbers
+    }
+
+    /// Width of the gutter (line numbers plus gap), or 0 when line numbers are hidden
+    fn gutter_width(&self) -> usize {
+        if self.has_gutter() {
+            self.diff_marker_width()
+                + Buffer::number_column_width(self.lines.line_count())
+                + self.theme.gutter_gap as usize
+        } else {
+            0
+        }
+    }
+
+    /// Number shown in the gutter for a buffer row
+    ///
+    /// With relative numbering every line but the cursor's shows its distance
+    /// from the cursor line; the cursor line keeps its absolute number.
+    fn line_number(&self, row: usize) -> usize {
+        match self.cursor_pos() {
+            Some((cursor_row, _)) if self.relative_line_numbers && row != cursor_row => {
+                row.abs_diff(cursor_row)
+            }
+            _ => row + 1,
+        }
+    }
+
+    /// Base style for unselected text on a row
+    ///
+    /// The cursor's line gets the current-line background when enabled.
+    fn row_style(&self, row: usize) -> Style {
+        if self.highlight_current_line && self.cursor_pos().is_some_and(|(r, _)| r == row) {
+            self.theme.current_line_style()
+        } else {
+            Style::default()
+        }
+    }
+
+    /// Build the gutter spans for a screen row
+    ///
+    /// `row` is the buffer row whose number to show, or `None` for the
+    /// continuation rows of a wrapped line. When the buffer tracks its changes
+    /// the number is preceded by a bar in the color of the line's `status`.
+    /// The gap after the number takes the selection background when the row
+    /// continues a selection from the row above, so there's no seam between
+    /// them.
+    fn gutter_spans(
+        &self,
+        row: Option<usize>,
+        status: Option<LineStatus>,
+        row_style: Style,
+        continues_selection: bool,
+    ) -> Vec<Span<'static>> {
+        let gap = self.theme.gutter_gap as usize;
+        let marker_width = self.diff_marker_width();
+        let number_width = self.gutter_width() - gap - marker_width;
+        let line_num_str = match row {
+            Some(row) => format!("{:>width$}", self.line_number(row), width = number_width),
+            None => " ".repeat(number_width),
+        };
+
+        let gap_style = if continues_selection {
+            self.theme.gutter_gap_style().bg(self.theme.selection_bg)
+        } else {
+            self.theme.gutter_gap_style().patch(row_style)
+        };
+
+        let mut spans = Vec::with_capacity(3);
+        if marker_width > 0 {
+            spans.push(match status {
+                // Removed lines are marked once, not on every wrapped row
+                Some(LineStatus::Removed) if row.is_some() => {
+                    Span::styled("▁", self.theme.diff_marker_style(LineStatus::Removed))
+                }
+                Some(status @ (LineStatus::Added | LineStatus::Modified)) => {
+                    Span::styled("▎", self.theme.diff_marker_style(status))
+                }
+                _ => Span::styled(" ", self.theme.gutter_gap_style()),
+            });
+        }
+        spans.push(Span::styled(
+            line_num_str,
+            self.theme.line_number_style().patch(row_style),
+        ));
+        spans.push(Span::styled(" ".repeat(gap), gap_style));
+        spans
+    }
+
+    /// Build the spans for the `visible` byte range of a line
+    ///
+    /// The line is cut at every syntax highlight, selection, search match and
+    /// matched bracket boundary. Selected pieces take the selection style with
+    /// the syntax color kept as foreground; the rest sit on the row's base
+    /// style, with search `matches` highlighted and the one at the cursor
+    /// stronger still. Matched brackets are highlighted on top of any of these.
+    fn content_spans<'l>(
+        &self,
+        row: usize,
+        line: &'l str,
+        visible: Range<usize>,
+        highlights: &[HighlightSpan],
+        matches: &[Range<usize>],
+        brackets: Option<(Position, Position)>,
+    ) -> Vec<Span<'l>> {
+        if visible.start >= visible.end {
+            return vec![Span::raw("")];
+        }
+
+        let selected = self.buffer().and_then(|buffer| buffer.selected_cols(row));
+        let is_selected = |col: usize| selected.as_ref().is_some_and(|cols| cols.contains(&col));
+        let match_style = |col: usize| {
+            let found = matches.iter().find(|m| m.contains(&col))?;
+            if self.cursor_pos() == Some((row, found.start)) {
+                Some(self.theme.active_search_match_style())
+            } else {
+                Some(self.theme.search_match_style())
+            }
+        };
+
+        let mut boundaries = vec![visible.start, visible.end];
+        for span in highlights {
+            boundaries.push(span.range.start);
+            boundaries.push(span.range.end);
+        }
+        if let Some(cols) = &selected {
+            boundaries.push(cols.start);
+            boundaries.push(cols.end);
+        }
+        for found in matches {
+            boundaries.push(found.start);
+            boundaries.push(found.end);
+        }
+        let brackets = brackets
+            .into_iter()
+            .flat_map(|(a, b)| [a, b])
+            .filter(|bracket| bracket.row == row);
+        for bracket in brackets.clone() {
+            boundaries.push(bracket.col);
+            boundaries.push(bracket.col + 1);
+        }
+        boundaries
+            .retain(|&b| (visible.start..=visible.end).contains(&b) && line.is_char_boundary(b));
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        let row_style = self.row_style(row);
+        let mut highlights = highlights.iter().peekable();
+        let mut spans = Vec::new();
+        let mut col = boundaries
+            .first()
+            .map_or(0, |&start| visual_col(line, start, self.tab_size));
+        for piece in boundaries.windows(2) {
+            let (start, end) = (piece[0], piece[1]);
+            while highlights.next_if(|h| h.range.end <= start).is_some() {}
+            let kind = highlights
+                .peek()
+                .filter(|h| h.range.start <= start)
+                .map(|h| h.kind);
+
+            let style = match (is_selected(start), kind) {
+                (true, Some(kind)) => self
+                    .theme
+                    .selection_style()
+                    .fg(self.theme.syntax_color(kind)),
+                (true, None) => self.theme.selection_style(),
+                (false, Some(kind)) => row_style.fg(self.theme.syntax_color(kind)),
+                (false, None) => row_style,
+            };
+            let style = match match_style(start) {
+                Some(highlight) if !is_selected(start) => style.patch(highlight),
+                _ => style,
+            };
+            let style = if brackets.clone().any(|bracket| bracket.col == start) {
+                style.patch(self.theme.matching_bracket_style())
+            } else {
+                style
+            };
+            self.push_piece(&mut spans, &line[start..end], style, &mut col);
+        }
+        spans
+    }
+
+    /// Push the spans for a piece of a line that starts at visual column `col`
+    ///
+    /// Tabs stretch to the next tab stop. With `render_whitespace` on, spaces
+    /// and tabs are drawn as dim glyphs in spans of their own.
+    fn push_piece<'l>(
+        &self,
+        spans: &mut Vec<Span<'l>>,
+        text: &'l str,
+        style: Style,
+        col: &mut usize,
+    ) {
+        let has_whitespace_glyphs = self.render_whitespace && text.contains(' ');
+        if !has_whitespace_glyphs && !text.contains('\t') {
+            *col += text
+                .chars()
+                .map(|ch| char_width(ch, 0, self.tab_size))
+                .sum::<usize>();
+            spans.push(Span::styled(text, style));
+            return;
+        }
+
+        let whitespace_style = style.patch(self.theme.whitespace_style());
+        let mut plain = String::new();
+        for ch in text.chars() {
+            let width = char_width(ch, *col, self.tab_size);
+            *col += width;
+            let glyph = match ch {
+                '\t' if self.render_whitespace => format!("→{}", " ".repeat(width - 1)),
+                ' ' if self.render_whitespace => "·".to_string(),
+                '\t' => {
+                    plain.push_str(&" ".repeat(width));
+                    continue;
+                }
+                _ => {
+                    plain.push(ch);
+                    continue;
+                }
+            };
+            if !plain.is_empty() {
+                spans.push(Span::styled(std::mem::take(&mut plain), style));
+            }
+            spans.push(Span::styled(glyph, whitespace_style));
+        }
+        if !plain.is_empty() {
+            spans.push(Span::styled(plain, style));
         }
     }
 
     pub fn ensure_cursor_visible(&mut self, area: Rect) {
-        let (row, col) = self.buffer.cursor_pos;
+        let Some((row, col)) = self.cursor_pos() else {
+            return;
+        };
         let (scroll_row, scroll_col) = self.scroll_offset;
 
-        // Define scroll margins - keep cursor at least 3 lines from edges when possible
-        let scroll_margin = 3;
+        // Keep the cursor `scroll_off` lines from the edges when possible
         let visible_rows = area.height as usize;
+        let scroll_margin = scroll_margin(self.scroll_off, visible_rows);
 
         // Adjust vertical scroll with margin consideration
         if row < scroll_row + scroll_margin {
             // Cursor is too close to the top, scroll up
             self.scroll_offset.0 = row.saturating_sub(scroll_margin);
         } else if row >= scroll_row + visible_rows - scroll_margin {
-            // Cursor is too close to the bottom, scroll down  
+            // Cursor is too close to the bottom, scroll down
             let new_scroll = row.saturating_sub(visible_rows.saturating_sub(scroll_margin + 1));
             self.scroll_offset.0 = new_scroll;
         }
 
         // Adjust horizontal scroll if needed (account for line numbers)
-        let line_number_width = if self.show_line_numbers {
-            self.buffer.line_number_width()
-        } else {
-            0
-        };
-        let visible_cols = area.width as usize - line_number_width;
-
-        if col < scroll_col {
-            self.scroll_offset.1 = col;
-        } else if col >= scroll_col + visible_cols {
-            self.scroll_offset.1 = col.saturating_sub(visible_cols) + 1;
-        }
+        let line_number_width = self.gutter_width();
+        let visible_cols = (area.width as usize).saturating_sub(line_number_width);
+        let line = self.lines.line(row).unwrap_or_default();
+        self.scroll_offset.1 =
+            scroll_col_for_cursor(&line, col, scroll_col, visible_cols, self.tab_size);
     }
 }
 
@@ -64,158 +333,153 @@ impl Widget for Editor<'_> {
 
         // Determine visible portion of the buffer
         let start_row = self.scroll_offset.0;
-        let end_row = (start_row + inner_area.height as usize).min(self.buffer.content.len());
-        let h_offset = self.scroll_offset.1;
-
-        // Calculate line number width (if enabled)
-        let line_number_width = if self.show_line_numbers {
-            self.buffer.line_number_width()
-        } else {
+        let end_row = (start_row + inner_area.height as usize).min(self.lines.line_count());
+        let h_offset = if self.word_wrap {
             0
+        } else {
+            self.scroll_offset.1
         };
 
+        // Calculate line number width (if enabled)
+        let line_number_width = self.gutter_width();
+        let text_width = (inner_area.width as usize).saturating_sub(line_number_width);
+
         // Render visible lines
         let mut lines = Vec::new();
 
-        // Get selection range for rendering highlighting
-        let selection_range = self.buffer.get_selection_range();
+        // Screen rows of the cursor's line and how much of each the text fills,
+        // so the current-line background can be extended to the right edge
+        let mut current_rows = Vec::new();
+
+        // Screen cells under the secondary cursors
+        let mut secondary_cells = Vec::new();
+
+        // Get selection range for rendering highlighting; a block selection
+        // never carries over from one row to the next
+        let buffer = self.buffer();
+        let selection_range = buffer
+            .filter(|buffer| buffer.block_selection().is_none())
+            .and_then(Buffer::get_selection_range);
+        let brackets = buffer.and_then(Buffer::bracket_pair_at_cursor);
+        let line_statuses = buffer.map(Buffer::line_statuses).unwrap_or_default();
+        let search_matches = match (buffer, self.search) {
+            (Some(buffer), Some(query)) if !query.is_empty() => {
+                query.matches_in(buffer, start_row..end_row)
+            }
+            _ => Vec::new(),
+        };
 
-        for i in start_row..end_row {
-            if let Some(line) = self.buffer.content.get(i) {
-                // Extract the visible portion of the line without cloning
-                let visible_content = if h_offset < line.len() {
-                    &line[h_offset..]
-                } else {
-                    ""
-                };
+        // Start from the state the lines above the viewport leave, so
+        // multiline comments and strings carry into the first visible line
+        let highlighter = buffer
+            .and_then(|buffer| buffer.path.as_deref())
+            .and_then(highlighter_for_path);
+        let mut highlight_state = match highlighter.zip(buffer) {
+            Some((highlighter, buffer)) => buffer.highlight_state_at(highlighter, start_row),
+            None => HighlightState::default(),
+        };
 
-                // Create spans for the line content, with highlighting for selection
-                let content_spans = if let Some((start, end)) = selection_range {
-                    let mut spans = Vec::new();
-
-                    // Check if this line is within selection
-                    if i < start.row || i > end.row {
-                        // Line is completely outside selection
-                        spans.push(Span::raw(visible_content));
-                    } else if i == start.row && i == end.row {
-                        // Selection starts and ends on this line
-                        let start_col = start.col.saturating_sub(h_offset);
-                        let end_col = end.col.saturating_sub(h_offset);
-
-                        // Text before selection
-                        if start_col > 0 && start_col <= visible_content.len() {
-                            spans.push(Span::raw(&visible_content[..start_col]));
-                        }
-
-                        // Selected text
-                        if start_col < visible_content.len() && end_col > 0 {
-                            let sel_start = start_col;
-                            let sel_end = end_col.min(visible_content.len());
-                            if sel_end > sel_start {
-                                spans.push(Span::styled(
-                                    &visible_content[sel_start..sel_end],
-                                    Style::default().bg(Color::DarkGray).fg(Color::White),
-                                ));
-                            }
-                        }
-
-                        // Text after selection
-                        if end_col < visible_content.len() {
-                            spans.push(Span::raw(&visible_content[end_col..]));
-                        }
-                    } else if i == start.row {
-                        // First line of multi-line selection
-                        let start_col = start.col.saturating_sub(h_offset);
-
-                        // Text before selection
-                        if start_col > 0 && start_col <= visible_content.len() {
-                            spans.push(Span::raw(&visible_content[..start_col]));
-                        }
-
-                        // Selected text to end of line
-                        if start_col < visible_content.len() {
-                            spans.push(Span::styled(
-                                &visible_content[start_col..],
-                                Style::default().bg(Color::DarkGray).fg(Color::White),
-                            ));
-                        }
-                    } else if i == end.row {
-                        // Last line of multi-line selection
-                        let end_col = end.col.saturating_sub(h_offset);
-
-                        // Selected text from start of line to end of selection
-                        if end_col > 0 {
-                            let sel_end = end_col.min(visible_content.len());
-                            spans.push(Span::styled(
-                                &visible_content[..sel_end],
-                                Style::default().bg(Color::DarkGray).fg(Color::White),
-                            ));
-                        }
-
-                        // Text after selection
-                        if end_col < visible_content.len() {
-                            spans.push(Span::raw(&visible_content[end_col..]));
-                        }
-                    } else {
-                        // Middle line of multi-line selection - whole line is selected
-                        spans.push(Span::styled(
-                            visible_content,
-                            Style::default().bg(Color::DarkGray).fg(Color::White),
-                        ));
-                    }
+        // Only the lines in view are requested from the provider
+        let visible_lines: Vec<_> = (start_row..end_row)
+            .map_while(|i| self.lines.line(i))
+            .collect();
+
+        for (i, line) in (start_row..).zip(&visible_lines) {
+            let line: &str = line;
+
+            let matches: Vec<Range<usize>> = search_matches
+                .iter()
+                .filter(|(start, _)| start.row == i)
+                .map(|(start, end)| start.col..end.col)
+                .collect();
+            let highlights = match highlighter {
+                Some(highlighter) => highlighter.highlight_line(line, &mut highlight_state),
+                None => Vec::new(),
+            };
+
+            // Each piece of the line that gets its own screen row
+            let visual_rows = if self.word_wrap {
+                wrap_ranges(line, text_width, self.tab_size)
+            } else {
+                let visible = h_offset.min(line.len())..line.len();
+                vec![visible]
+            };
 
-                    spans
-                } else {
-                    // No selection, just show the regular text
-                    vec![Span::raw(visible_content)]
-                };
+            for visible in visual_rows {
+                if lines.len() >= inner_area.height as usize {
+                    break;
+                }
 
-                if self.show_line_numbers {
-                    // Create line with line number
-                    let line_num = i + 1; // 1-indexed line numbers
-                    let line_num_str =
-                        format!("{:>width$}", line_num, width = line_number_width - 1);
+                let first_row = visible.start == 0 || !self.word_wrap;
+                let content_spans =
+                    self.content_spans(i, line, visible.clone(), &highlights, &matches, brackets);
 
+                let screen_line = if self.has_gutter() {
                     // Combine line number with content spans
-                    let mut line_spans = vec![
-                        Span::styled(line_num_str, Style::default().fg(Color::Rgb(100, 100, 120))),
-                        Span::raw(" "), // Separator
-                    ];
+                    let row_start = Position::new(i, if first_row { 0 } else { visible.start });
+                    let continues_selection = selection_range
+                        .is_some_and(|(start, end)| start < row_start && row_start <= end);
+                    let number = first_row.then_some(i);
+                    let status = line_statuses.get(i).copied().flatten();
+                    let mut line_spans =
+                        self.gutter_spans(number, status, self.row_style(i), continues_selection);
                     line_spans.extend(content_spans);
 
-                    lines.push(Line::from(line_spans));
+                    Line::from(line_spans)
                 } else {
-                    lines.push(Line::from(content_spans));
+                    Line::from(content_spans)
+                };
+
+                for cursor in buffer.iter().flat_map(|buffer| &buffer.secondary_cursors) {
+                    let on_piece = cursor.row == i
+                        && visible.start <= cursor.col
+                        && (cursor.col < visible.end
+                            || cursor.col == line.len() && visible.end == line.len());
+                    if on_piece {
+                        let x = visual_col(line, cursor.col, self.tab_size)
+                            - visual_col(line, visible.start, self.tab_size)
+                            + line_number_width;
+                        secondary_cells.push((x, lines.len()));
+                    }
                 }
-            } else {
-                // For empty lines, still need to maintain line numbers if enabled
-                if self.show_line_numbers {
-                    let line_num = i + 1; // 1-indexed line numbers
-                    let line_num_str =
-                        format!("{:>width$}", line_num, width = line_number_width - 1);
-
-                    lines.push(Line::from(vec![
-                        Span::styled(line_num_str, Style::default().fg(Color::Rgb(100, 100, 120))),
-                        Span::raw(" "), // Separator
-                        Span::raw(""),
-                    ]));
-                } else {
-                    lines.push(Line::from(""));
+
+                if self.highlight_current_line && self.cursor_pos().is_some_and(|(r, _)| r == i) {
+                    current_rows.push((lines.len() as u16, screen_line.width() as u16));
                 }
+                lines.push(screen_line);
             }
         }
 
         // Create paragraph with all visible lines (no block, just content)
-        let paragraph =
-            Paragraph::new(lines).style(Style::default().fg(Color::White).bg(Color::Black));
+        let paragraph = Paragraph::new(lines).style(self.theme.editor_style());
         paragraph.render(inner_area, buf);
 
+        for (y, text_width) in current_rows {
+            if text_width < inner_area.width {
+                let rest = Rect::new(
+                    inner_area.x + text_width,
+                    inner_area.y + y,
+                    inner_area.width - text_width,
+                    1,
+                );
+                buf.set_style(rest, self.theme.current_line_style());
+            }
+        }
+
+        for (x, y) in secondary_cells {
+            if x < inner_area.width as usize {
+                let cell = &mut buf[(inner_area.x + x as u16, inner_area.y + y as u16)];
+                cell.set_style(self.theme.secondary_cursor_style());
+            }
+        }
+
         // Position cursor
-        let cursor_row = self.buffer.cursor_pos.0.saturating_sub(start_row) as u16;
-        let cursor_col = self.buffer.cursor_pos.1.saturating_sub(h_offset) as u16;
+        let (cursor_row, cursor_col) = self.cursor_pos().unwrap_or_default();
+        let cursor_row = cursor_row.saturating_sub(start_row) as u16;
+        let cursor_col = cursor_col.saturating_sub(h_offset) as u16;
 
         // For cursor positioning, we need to consider line number width when show_line_numbers is true
-        let effective_cursor_col = if self.show_line_numbers {
+        let effective_cursor_col = if self.has_gutter() {
             cursor_col + line_number_width as u16
         } else {
             cursor_col
@@ -227,6 +491,43 @@ impl Widget for Editor<'_> {
     }
 }
 
+/// Lines to keep between the cursor and the top or bottom of a view
+/// `visible_rows` high
+///
+/// `scroll_off` is capped so the margins above and below the cursor always
+/// leave it a row to sit on.
+pub fn scroll_margin(scroll_off: usize, visible_rows: usize) -> usize {
+    scroll_off.min(visible_rows.saturating_sub(1) / 2)
+}
+
+/// Horizontal scroll offset (a byte column) that keeps `col` of `line` on screen
+///
+/// Works in visual columns so a cursor after tabs isn't scrolled off the
+/// right edge. Leaves `scroll_col` alone when the cursor is already visible.
+pub fn scroll_col_for_cursor(
+    line: &str,
+    col: usize,
+    scroll_col: usize,
+    visible_cols: usize,
+    tab_size: usize,
+) -> usize {
+    let cursor_x = visual_col(line, col, tab_size);
+    let scroll_x = visual_col(line, scroll_col, tab_size);
+
+    if col < scroll_col {
+        col
+    } else if cursor_x >= scroll_x + visible_cols {
+        // First char that starts far enough right to bring the cursor into view
+        let min_x = cursor_x + 1 - visible_cols.max(1);
+        line.char_indices()
+            .map(|(i, _)| i)
+            .find(|&i| visual_col(line, i, tab_size) >= min_x)
+            .unwrap_or(col)
+    } else {
+        scroll_col
+    }
+}
+
 // Implementation for a stateful widget version if needed later
 impl StatefulWidget for Editor<'_> {
     type State = ();
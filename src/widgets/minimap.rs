@@ -0,0 +1,90 @@
+use ratatui::{buffer::Buffer as TuiBuffer, layout::Rect, widgets::Widget};
+
+use crate::buffer::Buffer;
+use crate::theme::Theme;
+
+/// Width of the minimap column, in cells
+pub const MINIMAP_WIDTH: u16 = 12;
+
+/// Number of text columns each minimap cell stands for
+const CHARS_PER_CELL: usize = 4;
+
+/// Condensed overview of a whole buffer
+///
+/// Every row stands for one or more buffer lines. Only the first line of each
+/// row is looked at, so drawing costs the same for a 100k-line file as for a
+/// short one. The rows covering the visible part of the buffer are highlighted.
+pub struct Minimap<'a> {
+    pub buffer: &'a Buffer,
+    pub scroll_row: usize,      // First buffer row shown in the editor
+    pub viewport_height: usize, // Number of buffer rows the editor shows
+    pub theme: Theme,           // Colors for the line blocks and viewport band
+}
+
+impl<'a> Minimap<'a> {
+    pub fn new(buffer: &'a Buffer, scroll_row: usize, viewport_height: usize) -> Self {
+        Self {
+            buffer,
+            scroll_row,
+            viewport_height,
+            theme: Theme::default(),
+        }
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+/// Number of buffer lines each minimap row covers
+pub fn lines_per_row(line_count: usize, height: u16) -> usize {
+    line_count.div_ceil(height.max(1) as usize).max(1)
+}
+
+/// First buffer line covered by a minimap row
+pub fn line_at_row(line_count: usize, height: u16, row: u16) -> usize {
+    (row as usize * lines_per_row(line_count, height)).min(line_count.saturating_sub(1))
+}
+
+impl Widget for Minimap<'_> {
+    fn render(self, area: Rect, buf: &mut TuiBuffer) {
+        let line_count = self.buffer.content.len();
+        let per_row = lines_per_row(line_count, area.height);
+        let viewport = self.scroll_row..self.scroll_row + self.viewport_height.max(1);
+
+        for y in 0..area.height {
+            let first_line = y as usize * per_row;
+            let rows = first_line..first_line + per_row;
+            let in_viewport = rows.start < viewport.end && viewport.start < rows.end;
+            let style = if in_viewport {
+                self.theme.minimap_viewport_style()
+            } else {
+                self.theme.minimap_style()
+            };
+
+            // Sample the first line of the row and draw its shape: blank up to
+            // the indent, then a block for each cell that has text. Lines of a
+            // lazily opened file that haven't been read yet are drawn blank.
+            let sample = self
+                .buffer
+                .content
+                .get_loaded(first_line)
+                .map(String::as_str);
+            let (indent, len) = sample.map_or((0, 0), |line| {
+                let trimmed = line.trim_start();
+                (
+                    (line.len() - trimmed.len()) / CHARS_PER_CELL,
+                    line.trim_end().len().div_ceil(CHARS_PER_CELL),
+                )
+            });
+
+            for x in 0..area.width {
+                let cell = &mut buf[(area.x + x, area.y + y)];
+                let has_text = (indent..len).contains(&(x as usize));
+                cell.set_symbol(if has_text { "▬" } else { " " });
+                cell.set_style(style);
+            }
+        }
+    }
+}
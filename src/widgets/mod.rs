@@ -1,8 +1,36 @@
 pub mod cursor;
 pub mod editor;
+pub mod minimap;
 pub mod modal;
 pub mod status_bar;
+pub mod tab_bar;
 pub mod toast;
 
-pub use cursor::{Cursor, CursorManager, CursorState, CursorSupport};
+pub use cursor::{Cursor, CursorManager, CursorShape, CursorState, CursorSupport};
 pub use status_bar::{SlotAlignment, StatusBar, StatusSlot};
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// `text` cut down to `max_width` columns, ending in "..." if anything was
+/// cut
+///
+/// Cuts fall between chars, so multibyte text is never split.
+pub fn truncate_to_width(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    let ellipsis = &"..."[..max_width.min(3)];
+    let room = max_width - ellipsis.len();
+
+    let mut truncated = String::with_capacity(max_width);
+    let mut used = 0;
+    for c in text.chars() {
+        used += c.width().unwrap_or(0);
+        if used > room {
+            break;
+        }
+        truncated.push(c);
+    }
+    truncated.push_str(ellipsis);
+    truncated
+}
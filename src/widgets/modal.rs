@@ -1,3 +1,4 @@
+use crate::config::PalettePosition;
 use crate::widgets::cursor::CursorSupport;
 use ratatui::prelude::Position;
 use ratatui::{
@@ -15,6 +16,7 @@ pub struct Modal<'a> {
     width: u16,
     height: u16,
     focused: bool,
+    scroll: u16,
 }
 
 impl<'a> Modal<'a> {
@@ -25,6 +27,7 @@ impl<'a> Modal<'a> {
             width: 60,
             height: 20,
             focused: true,
+            scroll: 0,
         }
     }
 
@@ -48,6 +51,13 @@ impl<'a> Modal<'a> {
         self
     }
 
+    /// Start the content `scroll` lines down, for content taller than the
+    /// modal
+    pub fn scroll(mut self, scroll: u16) -> Self {
+        self.scroll = scroll;
+        self
+    }
+
     /// Calculate the centered area for the modal
     fn centered_rect(&self, area: Rect) -> Rect {
         let popup_layout = Layout::default()
@@ -108,7 +118,8 @@ impl Widget for Modal<'_> {
         // Render content
         let paragraph = Paragraph::new(self.content)
             .style(Style::default().fg(Color::White))
-            .alignment(Alignment::Left);
+            .alignment(Alignment::Left)
+            .scroll((self.scroll, 0));
 
         paragraph.render(inner_area, buf);
     }
@@ -118,8 +129,9 @@ impl Widget for Modal<'_> {
 pub struct CommandPalette<'a> {
     input: &'a str,
     suggestions: Vec<&'a str>,
-    selected: usize,
+    selected: Option<usize>,
     focused: bool,
+    position: PalettePosition,
 }
 
 impl<'a> CommandPalette<'a> {
@@ -127,8 +139,9 @@ impl<'a> CommandPalette<'a> {
         Self {
             input,
             suggestions: Vec::new(),
-            selected: 0,
+            selected: None,
             focused: true,
+            position: PalettePosition::default(),
         }
     }
 
@@ -137,8 +150,9 @@ impl<'a> CommandPalette<'a> {
         self
     }
 
-    pub fn selected(mut self, selected: usize) -> Self {
-        self.selected = selected.min(self.suggestions.len().saturating_sub(1));
+    /// Highlight the suggestion at `selected`, if any
+    pub fn selected(mut self, selected: Option<usize>) -> Self {
+        self.selected = selected.filter(|&index| index < self.suggestions.len());
         self
     }
 
@@ -147,15 +161,25 @@ impl<'a> CommandPalette<'a> {
         self
     }
 
+    /// Show the palette at `position` instead of the upper third
+    pub fn position(mut self, position: PalettePosition) -> Self {
+        self.position = position;
+        self
+    }
+
     /// Calculate the centered area for the command palette
     fn centered_rect(&self, area: Rect) -> Rect {
         let height = (self.suggestions.len() as u16 + 3).min(15); // +3 for input and borders
         let width = 80.min(area.width.saturating_sub(4));
+        let top = match self.position {
+            PalettePosition::Top => area.height.saturating_sub(height) / 3,
+            PalettePosition::Center => area.height.saturating_sub(height) / 2,
+        };
 
         let popup_layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length((area.height.saturating_sub(height)) / 3), // Position in upper third
+                Constraint::Length(top),
                 Constraint::Length(height),
                 Constraint::Min(0),
             ])
@@ -233,7 +257,7 @@ impl Widget for CommandPalette<'_> {
                 .iter()
                 .enumerate()
                 .map(|(i, suggestion)| {
-                    if i == self.selected {
+                    if self.selected == Some(i) {
                         Line::from(Span::styled(
                             format!("  {} ", suggestion),
                             Style::default()
@@ -1,11 +1,15 @@
 use ratatui::{
     buffer::Buffer as TuiBuffer,
     layout::Rect,
-    style::{Color, Style},
+    style::Style,
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Widget},
 };
 use std::collections::HashMap;
+use unicode_width::UnicodeWidthStr;
+
+use super::truncate_to_width;
+use crate::theme::Theme;
 
 /// Represents the alignment of a status bar slot
 #[derive(Debug, Clone, PartialEq)]
@@ -35,7 +39,7 @@ impl StatusSlot {
             content: content.into(),
             alignment: SlotAlignment::Left,
             priority: 50, // Default medium priority
-            style: Style::default().fg(Color::White).bg(Color::LightBlue),
+            style: Theme::default().status_bar_style(),
             visible: true,
             min_width: None,
             max_width: None,
@@ -102,7 +106,7 @@ impl StatusBar {
     pub fn new() -> Self {
         Self {
             slots: HashMap::new(),
-            background_style: Style::default().bg(Color::LightBlue).fg(Color::White),
+            background_style: Theme::default().status_bar_style(),
             separator: " | ".to_string(),
             show_separators: true,
         }
@@ -144,6 +148,16 @@ impl StatusBar {
         }
     }
 
+    /// Give the slots styled with the first style of a pair in `styles`
+    /// the second
+    pub fn restyle_slots(&mut self, styles: &[(Style, Style)]) {
+        for slot in self.slots.values_mut() {
+            if let Some((_, new)) = styles.iter().find(|(old, _)| *old == slot.style) {
+                slot.style = *new;
+            }
+        }
+    }
+
     /// Set the background style for the entire status bar
     pub fn with_background_style(mut self, style: Style) -> Self {
         self.background_style = style;
@@ -187,66 +201,52 @@ impl StatusBar {
         }
 
         // Sort by priority (higher priority first)
-        left_slots.sort_by(|a, b| b.priority.cmp(&a.priority));
-        center_slots.sort_by(|a, b| b.priority.cmp(&a.priority));
-        right_slots.sort_by(|a, b| b.priority.cmp(&a.priority));
+        left_slots.sort_by_key(|slot| std::cmp::Reverse(slot.priority));
+        center_slots.sort_by_key(|slot| std::cmp::Reverse(slot.priority));
+        right_slots.sort_by_key(|slot| std::cmp::Reverse(slot.priority));
 
         (left_slots, center_slots, right_slots)
     }
 
-    /// Create spans for a group of slots
-    fn create_spans_for_slots(&self, slots: &[&StatusSlot]) -> Vec<Span> {
+    /// Create spans for a group of slots, each with the id of the slot it
+    /// shows, or `None` for a separator
+    fn create_spans_for_slots<'a>(
+        &'a self,
+        slots: &[&'a StatusSlot],
+    ) -> Vec<(Option<&'a str>, Span<'a>)> {
         let mut spans = Vec::new();
 
         for (i, slot) in slots.iter().enumerate() {
             // Add separator before slot (except for first slot)
             if i > 0 && self.show_separators && !self.separator.is_empty() {
-                spans.push(Span::styled(&self.separator, self.background_style));
+                spans.push((None, Span::styled(&self.separator, self.background_style)));
             }
 
-            // Add the slot content
-            let mut content = slot.content.clone();
-
-            // Apply width constraints if specified
-            if let Some(max_width) = slot.max_width {
-                if content.len() > max_width as usize {
-                    content.truncate(max_width as usize - 3);
-                    content.push_str("...");
-                }
-            }
+            // Add the slot content, applying width constraints if specified
+            let mut content = match slot.max_width {
+                Some(max_width) => truncate_to_width(&slot.content, max_width as usize),
+                None => slot.content.clone(),
+            };
 
             if let Some(min_width) = slot.min_width {
-                if content.len() < min_width as usize {
-                    content = format!("{:width$}", content, width = min_width as usize);
-                }
+                let padding = (min_width as usize).saturating_sub(content.width());
+                content.push_str(&" ".repeat(padding));
             }
 
-            spans.push(Span::styled(content, slot.style));
+            spans.push((Some(slot.id.as_str()), Span::styled(content, slot.style)));
         }
 
         spans
     }
 
     /// Calculate the width needed for a group of spans
-    fn calculate_spans_width(&self, spans: &[Span]) -> u16 {
-        spans.iter().map(|span| span.content.len() as u16).sum()
+    fn calculate_spans_width(&self, spans: &[(Option<&str>, Span)]) -> u16 {
+        spans.iter().map(|(_, span)| span_width(span)).sum()
     }
-}
-
-impl Widget for StatusBar {
-    fn render(self, area: Rect, buf: &mut TuiBuffer) {
-        // Create the background block
-        let block = Block::default()
-            .style(self.background_style)
-            .borders(Borders::NONE);
-
-        let inner_area = block.inner(area);
-        block.render(area, buf);
-
-        if inner_area.width == 0 {
-            return;
-        }
 
+    /// Spans filling a status bar `available_width` columns wide, left to
+    /// right, each with the id of the slot it shows, if any
+    fn layout_spans(&self, available_width: u16) -> Vec<(Option<&str>, Span<'_>)> {
         // Get organized slots
         let (left_slots, center_slots, right_slots) = self.get_organized_slots();
 
@@ -259,10 +259,15 @@ impl Widget for StatusBar {
         let left_width = self.calculate_spans_width(&left_spans);
         let center_width = self.calculate_spans_width(&center_spans);
         let right_width = self.calculate_spans_width(&right_spans);
+        let padding = |width: u16| {
+            (
+                None,
+                Span::styled(" ".repeat(width as usize), self.background_style),
+            )
+        };
 
         // Calculate layout
         let total_content_width = left_width + center_width + right_width;
-        let available_width = inner_area.width;
 
         if total_content_width <= available_width {
             // We have enough space for all content
@@ -278,10 +283,7 @@ impl Widget for StatusBar {
 
                 // Add padding before center content
                 if center_padding > 0 {
-                    all_spans.push(Span::styled(
-                        " ".repeat(center_padding as usize),
-                        self.background_style,
-                    ));
+                    all_spans.push(padding(center_padding));
                 }
 
                 // Add center content
@@ -290,28 +292,19 @@ impl Widget for StatusBar {
                 // Add padding after center content to push right content to the right
                 let remaining_padding = remaining_width - center_width - center_padding;
                 if remaining_padding > 0 {
-                    all_spans.push(Span::styled(
-                        " ".repeat(remaining_padding as usize),
-                        self.background_style,
-                    ));
+                    all_spans.push(padding(remaining_padding));
                 }
             } else if center_width == 0 {
                 // No center content, pad to push right content to the right
-                let padding = available_width - left_width - right_width;
-                if padding > 0 {
-                    all_spans.push(Span::styled(
-                        " ".repeat(padding as usize),
-                        self.background_style,
-                    ));
+                let padding_width = available_width - left_width - right_width;
+                if padding_width > 0 {
+                    all_spans.push(padding(padding_width));
                 }
             }
 
             // Add right-aligned content
             all_spans.extend(right_spans);
-
-            let line = Line::from(all_spans);
-            let paragraph = Paragraph::new(line).style(self.background_style);
-            paragraph.render(inner_area, buf);
+            all_spans
         } else {
             // Not enough space, prioritize left content, then right, then center
             let mut truncated_spans = Vec::new();
@@ -319,9 +312,9 @@ impl Widget for StatusBar {
 
             // Add left content first (highest priority)
             for span in left_spans {
-                let span_width = span.content.len() as u16;
-                if used_width + span_width <= available_width {
-                    used_width += span_width;
+                let width = span_width(&span.1);
+                if used_width + width <= available_width {
+                    used_width += width;
                     truncated_spans.push(span);
                 } else {
                     break;
@@ -334,9 +327,9 @@ impl Widget for StatusBar {
             let mut right_spans_to_add = Vec::new();
 
             for span in right_spans_rev {
-                let span_width = span.content.len() as u16;
-                if used_width + span_width <= available_width {
-                    used_width += span_width;
+                let width = span_width(&span.1);
+                if used_width + width <= available_width {
+                    used_width += width;
                     right_spans_to_add.push(span);
                 } else {
                     break;
@@ -347,25 +340,64 @@ impl Widget for StatusBar {
             // Fill remaining space with padding
             let remaining_width = available_width - used_width;
             if remaining_width > 0 {
-                truncated_spans.push(Span::styled(
-                    " ".repeat(remaining_width as usize),
-                    self.background_style,
-                ));
+                truncated_spans.push(padding(remaining_width));
             }
 
             // Add right spans
             truncated_spans.extend(right_spans_to_add);
+            truncated_spans
+        }
+    }
 
-            let line = Line::from(truncated_spans);
-            let paragraph = Paragraph::new(line).style(self.background_style);
-            paragraph.render(inner_area, buf);
+    /// Id of the slot drawn at column `x` of a status bar `width` columns
+    /// wide, if any
+    pub fn slot_at(&self, width: u16, x: u16) -> Option<&str> {
+        let mut start = 0;
+        for (id, span) in self.layout_spans(width) {
+            let end = start + span_width(&span);
+            if x < end {
+                return id;
+            }
+            start = end;
         }
+        None
+    }
+}
+
+/// Columns a span takes up on screen, with wide chars like CJK counting
+/// two and combining marks none
+fn span_width(span: &Span) -> u16 {
+    span.content.width() as u16
+}
+
+impl Widget for StatusBar {
+    fn render(self, area: Rect, buf: &mut TuiBuffer) {
+        // Create the background block
+        let block = Block::default()
+            .style(self.background_style)
+            .borders(Borders::NONE);
+
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+
+        if inner_area.width == 0 {
+            return;
+        }
+
+        let spans: Vec<Span> = self
+            .layout_spans(inner_area.width)
+            .into_iter()
+            .map(|(_, span)| span)
+            .collect();
+        let paragraph = Paragraph::new(Line::from(spans)).style(self.background_style);
+        paragraph.render(inner_area, buf);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ratatui::style::Color;
 
     #[test]
     fn test_status_slot_creation() {
@@ -0,0 +1,137 @@
+use std::ops::Range;
+
+use ratatui::{buffer::Buffer as TuiBuffer, layout::Rect, widgets::Widget};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::buffer::Buffer;
+use crate::theme::Theme;
+
+/// Marker shown after the name of a buffer with unsaved changes
+const MODIFIED_MARKER: &str = " ●";
+
+/// Shortest a name gets truncated to before tabs start falling off the end
+const MIN_NAME_WIDTH: usize = 3;
+
+/// One-line bar listing the open buffers, with the active one highlighted
+pub struct TabBar<'a> {
+    pub buffers: &'a [Buffer],
+    pub active: usize, // Index of the active buffer
+    pub theme: Theme,  // Colors for active and inactive tabs
+}
+
+impl<'a> TabBar<'a> {
+    pub fn new(buffers: &'a [Buffer], active: usize) -> Self {
+        Self {
+            buffers,
+            active,
+            theme: Theme::default(),
+        }
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Label for each tab, with names shortened so all tabs fit in `width` if possible
+    fn labels(&self, width: u16) -> Vec<String> {
+        if self.buffers.is_empty() {
+            return Vec::new();
+        }
+
+        // Each tab has a space of padding on both sides plus the modified marker
+        let chrome = |buffer: &Buffer| {
+            2 + if buffer.modified {
+                MODIFIED_MARKER.width()
+            } else {
+                0
+            }
+        };
+        let full_width: usize = self
+            .buffers
+            .iter()
+            .map(|buffer| buffer.name.width() + chrome(buffer))
+            .sum();
+
+        let max_name = if full_width <= width as usize {
+            usize::MAX
+        } else {
+            let chrome_total: usize = self.buffers.iter().map(chrome).sum();
+            ((width as usize).saturating_sub(chrome_total) / self.buffers.len()).max(MIN_NAME_WIDTH)
+        };
+
+        self.buffers
+            .iter()
+            .map(|buffer| {
+                let name = truncate_name(&buffer.name, max_name);
+                let marker = if buffer.modified { MODIFIED_MARKER } else { "" };
+                format!(" {}{} ", name, marker)
+            })
+            .collect()
+    }
+
+    /// Column range of each tab that is at least partly visible in `width` columns
+    pub fn tab_ranges(&self, width: u16) -> Vec<(usize, Range<u16>)> {
+        let mut ranges = Vec::new();
+        let mut x = 0u16;
+        for (index, label) in self.labels(width).iter().enumerate() {
+            if x >= width {
+                break;
+            }
+            let end = x.saturating_add(label.width() as u16).min(width);
+            ranges.push((index, x..end));
+            x = end;
+        }
+        ranges
+    }
+
+    /// Index of the buffer whose tab is at column `x` of a bar `width` columns wide
+    pub fn tab_at(&self, x: u16, width: u16) -> Option<usize> {
+        self.tab_ranges(width)
+            .into_iter()
+            .find(|(_, range)| range.contains(&x))
+            .map(|(index, _)| index)
+    }
+}
+
+/// Shorten a name to `max` columns, ending with an ellipsis when cut
+fn truncate_name(name: &str, max: usize) -> String {
+    if name.width() <= max {
+        return name.to_string();
+    }
+
+    let room = max.saturating_sub(1);
+    let mut short = String::new();
+    let mut used = 0;
+    for c in name.chars() {
+        used += c.width().unwrap_or(0);
+        if used > room {
+            break;
+        }
+        short.push(c);
+    }
+    short.push('…');
+    short
+}
+
+impl Widget for TabBar<'_> {
+    fn render(self, area: Rect, buf: &mut TuiBuffer) {
+        buf.set_style(area, self.theme.tab_style());
+
+        let labels = self.labels(area.width);
+        for (index, range) in self.tab_ranges(area.width) {
+            let style = if index == self.active {
+                self.theme.active_tab_style()
+            } else {
+                self.theme.tab_style()
+            };
+            buf.set_stringn(
+                area.x + range.start,
+                area.y,
+                &labels[index],
+                (range.end - range.start) as usize,
+                style,
+            );
+        }
+    }
+}
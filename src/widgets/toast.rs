@@ -1,12 +1,21 @@
 use ratatui::{
     buffer::Buffer as TuiBuffer,
-    layout::Rect,
+    layout::{Position, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph, Widget},
 };
 use std::time::{Duration, Instant};
 
+use super::truncate_to_width;
+use crate::config::ToastPosition;
+
+/// Rows taken by each toast: its message, progress bar and border
+const TOAST_HEIGHT: u16 = 3;
+
+/// Gap between the toasts and the edges of the screen
+const TOAST_MARGIN: u16 = 2;
+
 /// Type of toast notification
 #[derive(Debug, Clone, PartialEq)]
 pub enum ToastType {
@@ -80,6 +89,7 @@ impl Toast {
 pub struct ToastManager {
     toasts: Vec<Toast>,
     max_toasts: usize,
+    position: ToastPosition,
 }
 
 impl ToastManager {
@@ -87,16 +97,26 @@ impl ToastManager {
         Self {
             toasts: Vec::new(),
             max_toasts: 5,
+            position: ToastPosition::default(),
         }
     }
 
+    /// Set the corner toasts stack up from and how many are kept at once
+    pub fn configure(&mut self, position: ToastPosition, max_toasts: usize) {
+        self.position = position;
+        self.max_toasts = max_toasts;
+        self.drop_overflow();
+    }
+
     pub fn add_toast(&mut self, toast: Toast) {
         self.toasts.push(toast);
+        self.drop_overflow();
+    }
 
-        // Remove oldest toasts if we exceed the maximum
-        while self.toasts.len() > self.max_toasts {
-            self.toasts.remove(0);
-        }
+    /// Remove oldest toasts if we exceed the maximum
+    fn drop_overflow(&mut self) {
+        let overflow = self.toasts.len().saturating_sub(self.max_toasts);
+        self.toasts.drain(..overflow);
     }
 
     pub fn add_info(&mut self, message: String) {
@@ -124,37 +144,66 @@ impl ToastManager {
         !self.toasts.is_empty()
     }
 
-    pub fn render(&self, area: Rect, buf: &mut TuiBuffer) {
-        if self.toasts.is_empty() {
-            return;
-        }
+    /// The toasts being shown, oldest first
+    pub fn toasts(&self) -> &[Toast] {
+        &self.toasts
+    }
 
-        // Calculate toast area (top-right corner)
-        let toast_width = 40.min(area.width / 3);
-        let toast_height = (self.toasts.len() as u16 * 3).min(area.height / 2); // 3 lines per toast
+    /// Dismiss the toast drawn at `point` when rendering over `area`
+    ///
+    /// Returns whether there was a toast there, so the click can be kept from
+    /// reaching whatever is underneath.
+    pub fn dismiss_at(&mut self, area: Rect, point: Position) -> bool {
+        self.update();
+        let index = self
+            .toast_areas(area)
+            .iter()
+            .position(|toast_area| toast_area.contains(point));
+        if let Some(index) = index {
+            self.toasts.remove(index);
+        }
+        index.is_some()
+    }
 
-        let toast_area = Rect {
-            x: area.width.saturating_sub(toast_width + 2),
-            y: 2,
-            width: toast_width,
-            height: toast_height,
-        };
+    /// Dismiss every toast
+    pub fn clear_all(&mut self) {
+        self.toasts.clear();
+    }
 
-        // Render each toast
-        for (i, toast) in self.toasts.iter().enumerate() {
-            let y_offset = i as u16 * 3;
-            if y_offset >= toast_area.height {
-                break;
+    /// Screen area of each toast, oldest first, when drawn over `area`
+    ///
+    /// The oldest toast sits in the configured corner and newer ones stack
+    /// away from it, using at most half the height. Toasts that don't fit
+    /// aren't given an area.
+    pub fn toast_areas(&self, area: Rect) -> Vec<Rect> {
+        let width = 40.min(area.width / 3);
+        let fits = (area.height / 2 / TOAST_HEIGHT) as usize;
+        let x = match self.position {
+            ToastPosition::TopLeft | ToastPosition::BottomLeft => area.x + TOAST_MARGIN,
+            ToastPosition::TopRight | ToastPosition::BottomRight => {
+                area.right().saturating_sub(width + TOAST_MARGIN)
             }
+        };
 
-            let individual_toast_area = Rect {
-                x: toast_area.x,
-                y: toast_area.y + y_offset,
-                width: toast_area.width,
-                height: 3.min(toast_area.height - y_offset),
-            };
+        (0..self.toasts.len().min(fits) as u16)
+            .map(|i| {
+                let offset = i * TOAST_HEIGHT;
+                let y = match self.position {
+                    ToastPosition::TopLeft | ToastPosition::TopRight => {
+                        area.y + TOAST_MARGIN + offset
+                    }
+                    ToastPosition::BottomLeft | ToastPosition::BottomRight => area
+                        .bottom()
+                        .saturating_sub(TOAST_MARGIN + TOAST_HEIGHT + offset),
+                };
+                Rect::new(x, y, width, TOAST_HEIGHT)
+            })
+            .collect()
+    }
 
-            self.render_single_toast(toast, individual_toast_area, buf);
+    pub fn render(&self, area: Rect, buf: &mut TuiBuffer) {
+        for (toast, toast_area) in self.toasts.iter().zip(self.toast_areas(area)) {
+            self.render_single_toast(toast, toast_area, buf);
         }
     }
 
@@ -190,17 +239,11 @@ impl ToastManager {
 
         // Create the toast content
         let icon = toast.toast_type.icon();
-        let message = if toast.message.len() > (inner_area.width as usize).saturating_sub(4) {
-            // Truncate long messages with pre-allocated string
-            let max_len = (inner_area.width as usize).saturating_sub(7); // Leave space for "..."
-            let truncate_len = max_len.min(toast.message.len());
-            let mut truncated = String::with_capacity(truncate_len + 3);
-            truncated.push_str(&toast.message[..truncate_len]);
-            truncated.push_str("...");
-            truncated
-        } else {
-            toast.message.clone()
-        };
+        // Long messages are cut short, leaving room for the icon
+        let message = truncate_to_width(
+            &toast.message,
+            (inner_area.width as usize).saturating_sub(4),
+        );
 
         let mut icon_text = String::with_capacity(icon.len() + 1);
         icon_text.push_str(icon);
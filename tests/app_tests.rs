@@ -5,7 +5,7 @@
 use std::fs;
 use tempfile::TempDir;
 
-use editor::{buffer::Buffer, App};
+use editor::{buffer::Buffer, App, HeadlessEditor};
 
 #[tokio::test]
 async fn test_app_creation() {
@@ -267,3 +267,1154 @@ async fn test_app_multiple_buffers() {
     assert!(buffer_count_slot.is_some());
     assert!(buffer_count_slot.unwrap().content.contains("3"));
 }
+
+#[tokio::test]
+async fn test_toast_only_frame_reuses_editor_region() {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let mut app = App::default();
+    for ch in "fn main() {}".chars() {
+        app.buffers[0].insert_char(ch);
+    }
+
+    let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+    terminal.draw(|f| app.render(f)).unwrap();
+    assert_eq!(app.editor_cache.builds(), 1);
+
+    // A frame that only animates a toast copies the cached editor cells
+    app.toast_manager.add_info("Saved".to_string());
+    terminal.draw(|f| app.render(f)).unwrap();
+    assert_eq!(app.editor_cache.builds(), 1);
+    assert_eq!(terminal.backend().buffer()[(5, 0)].symbol(), "f");
+
+    // Editing the buffer invalidates the cache
+    app.buffers[0].insert_char('!');
+    terminal.draw(|f| app.render(f)).unwrap();
+    assert_eq!(app.editor_cache.builds(), 2);
+
+    // So does input, even when nothing the editor depends on changed
+    app.editor_cache.mark_dirty();
+    terminal.draw(|f| app.render(f)).unwrap();
+    assert_eq!(app.editor_cache.builds(), 3);
+
+    // Saving under a new name can change the highlighting
+    app.buffers[0].path = Some("main.rs".into());
+    terminal.draw(|f| app.render(f)).unwrap();
+    assert_eq!(app.editor_cache.builds(), 4);
+}
+
+#[tokio::test]
+async fn test_word_wrap_cursor_position() {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let mut app = App::default();
+    app.config.editor.word_wrap = true;
+    for _ in 0..200 {
+        app.buffers[0].insert_char('x');
+    }
+
+    let mut terminal = Terminal::new(TestBackend::new(80, 10)).unwrap();
+    terminal.draw(|f| app.render(f)).unwrap();
+
+    // Column 200 of a line wrapped at 75 columns is on the third screen row
+    let position = app.cursor_manager.get_cursor_position("editor").unwrap();
+    assert_eq!((position.x, position.y), (5 + 50, 2));
+}
+
+#[tokio::test]
+async fn test_word_wrap_cursor_and_clicks_count_wide_chars_as_two_columns() {
+    use editor::input::coordinates::screen_to_buffer_coords_in_area;
+    use ratatui::{backend::TestBackend, layout::Rect, Terminal};
+
+    let mut app = App::default();
+    app.config.editor.word_wrap = true;
+    app.buffers[0].content = vec!["日".repeat(40)].into();
+    app.buffers[0].cursor_pos = (0, 38 * "日".len());
+
+    let mut terminal = Terminal::new(TestBackend::new(80, 10)).unwrap();
+    terminal.draw(|f| app.render(f)).unwrap();
+
+    // 37 wide chars fill 74 of the 75 text columns, so the 39th char is the
+    // second one on the next row
+    let position = app.cursor_manager.get_cursor_position("editor").unwrap();
+    assert_eq!((position.x, position.y), (5 + 2, 1));
+    assert_eq!(terminal.backend().buffer()[(5 + 2, 1)].symbol(), "日");
+
+    // Either column of that char clicks on it
+    let area = Rect::new(0, 0, 80, 9);
+    let col = 38 * "日".len();
+    assert_eq!(screen_to_buffer_coords_in_area(&app, area, 5 + 2, 1), Some((0, col)));
+    assert_eq!(screen_to_buffer_coords_in_area(&app, area, 5 + 3, 1), Some((0, col)));
+}
+
+#[tokio::test]
+async fn test_cursor_position_after_tab() {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let mut app = App::default();
+    app.config.editor.tab_size = 4;
+    app.buffers[0].content = vec!["a\tb".to_string()].into();
+    app.buffers[0].cursor_pos = (0, 2);
+
+    let mut terminal = Terminal::new(TestBackend::new(40, 5)).unwrap();
+    terminal.draw(|f| app.render(f)).unwrap();
+
+    // The cursor on 'b' sits after the expanded tab, past the 5-column gutter
+    let position = app.cursor_manager.get_cursor_position("editor").unwrap();
+    assert_eq!((position.x, position.y), (5 + 4, 0));
+    assert_eq!(terminal.backend().buffer()[(9, 0)].symbol(), "b");
+}
+
+#[tokio::test]
+async fn test_word_wrap_scrolls_by_screen_rows() {
+    use ratatui::layout::Rect;
+
+    let mut app = App::default();
+    app.config.editor.word_wrap = true;
+    let long_line = "y".repeat(300);
+    for ch in long_line.chars() {
+        app.buffers[0].insert_char(ch);
+    }
+    for _ in 0..3 {
+        app.buffers[0].insert_newline();
+        for ch in long_line.chars() {
+            app.buffers[0].insert_char(ch);
+        }
+    }
+
+    // Each line takes 5 screen rows at 75 columns, so the last line's cursor
+    // is far below a 10-row viewport starting at the top
+    app.ensure_cursor_visible(Rect::new(0, 0, 80, 10));
+    assert_eq!(app.scroll_offset, (3, 0));
+}
+
+#[tokio::test]
+async fn test_scrollbar_rendered_when_buffer_exceeds_viewport() {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let mut app = App::default();
+    app.config.ui.show_scrollbar = true;
+    for _ in 0..5 {
+        app.buffers[0].insert_newline();
+    }
+
+    // Fits in the 9-row editor: the column is reserved but left blank
+    let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+    terminal.draw(|f| app.render(f)).unwrap();
+    let column: Vec<String> = (0..9)
+        .map(|y| terminal.backend().buffer()[(39, y)].symbol().to_string())
+        .collect();
+    assert!(column.iter().all(|cell| cell == " "));
+
+    for _ in 0..45 {
+        app.buffers[0].insert_newline();
+    }
+    terminal.draw(|f| app.render(f)).unwrap();
+    let buf = terminal.backend().buffer();
+
+    // Thumb at the top, track below it, nothing drawn over the status line
+    assert_eq!(buf[(39, 0)].symbol(), "█");
+    assert_eq!(buf[(39, 0)].fg, app.theme.scrollbar_thumb);
+    assert_eq!(buf[(39, 8)].symbol(), "║");
+    assert_ne!(buf[(39, 9)].symbol(), "█");
+    assert_ne!(buf[(39, 9)].symbol(), "║");
+}
+
+#[tokio::test]
+async fn test_scrollbar_click_jumps_scroll_position() {
+    use ratatui::layout::Rect;
+
+    let mut app = App::default();
+    app.config.ui.show_scrollbar = true;
+    for _ in 0..99 {
+        app.buffers[0].insert_newline();
+    }
+
+    let layout = app.layout(Rect::new(0, 0, 40, 11));
+    let track = layout.scrollbar.unwrap();
+    assert_eq!(track, Rect::new(39, 0, 1, 10));
+    assert_eq!(layout.editor.width, 39);
+
+    // 100 lines in a 10-row viewport: the bottom of the track is the last page
+    app.scroll_to_scrollbar_position(9, track);
+    assert_eq!(app.scroll_offset.0, 90);
+    app.scroll_to_scrollbar_position(0, track);
+    assert_eq!(app.scroll_offset.0, 0);
+    app.scroll_to_scrollbar_position(5, track);
+    assert_eq!(app.scroll_offset.0, 50);
+}
+
+#[tokio::test]
+async fn test_minimap_click_scrolls_to_region() {
+    use ratatui::layout::Rect;
+
+    let mut app = App::default();
+    app.config.ui.show_minimap = true;
+    for _ in 0..999 {
+        app.buffers[0].insert_newline();
+    }
+
+    let layout = app.layout(Rect::new(0, 0, 80, 11));
+    let minimap = layout.minimap.unwrap();
+    assert_eq!(minimap, Rect::new(68, 0, 12, 10));
+    assert_eq!(layout.editor.width, 68);
+
+    // Row 5 of 10 covers lines 500..600; line 500 ends up centered
+    app.scroll_to_minimap_position(5, minimap, layout.editor);
+    assert_eq!(app.scroll_offset.0, 495);
+}
+
+#[tokio::test]
+async fn test_tab_bar_sits_above_editor() {
+    use ratatui::{backend::TestBackend, layout::Rect, Terminal};
+
+    let mut app = App::default();
+    app.config.ui.show_tab_bar = true;
+    app.add_buffer(Buffer::new());
+
+    let layout = app.layout(Rect::new(0, 0, 40, 10));
+    assert_eq!(layout.tab_bar, Some(Rect::new(0, 0, 40, 1)));
+    assert_eq!(layout.editor, Rect::new(0, 1, 40, 8));
+
+    let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+    terminal.draw(|f| app.render(f)).unwrap();
+    let top: String = (0..40)
+        .map(|x| terminal.backend().buffer()[(x, 0)].symbol())
+        .collect();
+    assert!(top.starts_with(" untitled  untitled "));
+    assert_eq!(terminal.backend().buffer()[(3, 1)].symbol(), "1");
+}
+
+#[tokio::test]
+async fn test_split_renders_two_editor_regions() {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let mut app = App::default();
+    app.buffers[0].content = vec!["left side".to_string()].into();
+    let mut right = Buffer::new();
+    right.content = vec!["right side".to_string()].into();
+    app.add_buffer(right);
+    app.switch_to_buffer(0);
+
+    app.split_horizontal();
+    assert!(app.is_split());
+    assert_eq!(app.focused_pane, 1);
+    assert_eq!(app.active_buffer, 1);
+
+    let mut terminal = Terminal::new(TestBackend::new(41, 6)).unwrap();
+    terminal.draw(|f| app.render(f)).unwrap();
+
+    let layout = app.layout(ratatui::layout::Rect::new(0, 0, 41, 6));
+    assert_eq!(layout.panes.len(), 2);
+    assert_eq!(layout.editor, layout.panes[1]);
+
+    let buffer = terminal.backend().buffer();
+    let row = |pane: ratatui::layout::Rect| -> String {
+        (pane.left()..pane.right())
+            .map(|x| buffer[(x, pane.y)].symbol())
+            .collect()
+    };
+    assert!(row(layout.panes[0]).contains("left side"));
+    assert!(row(layout.panes[1]).contains("right side"));
+    assert_eq!(buffer[(layout.panes[1].x - 1, 0)].symbol(), "│");
+
+    // Ctrl+W-style focus switching restores each pane's own scroll position
+    app.scroll_offset = (3, 0);
+    assert!(app.focus_next_pane());
+    assert_eq!((app.focused_pane, app.active_buffer), (0, 0));
+    assert_eq!(app.scroll_offset, (0, 0));
+    assert!(app.focus_next_pane());
+    assert_eq!(app.scroll_offset, (3, 0));
+}
+
+#[test]
+fn test_click_mapping_over_leading_tabs() {
+    use editor::input::coordinates::screen_to_buffer_coords_in_area;
+    use ratatui::layout::Rect;
+
+    let mut app = App::default();
+    app.config.editor.tab_size = 4;
+    app.buffers[0].content = vec!["\t\tfoo".to_string(), "\tx = 1".to_string()].into();
+    let area = Rect::new(0, 0, 40, 10);
+    let click = |x: u16, y: u16| screen_to_buffer_coords_in_area(&app, area, 5 + x, y);
+
+    // Columns inside an expanded tab map to the tab itself
+    assert_eq!(click(0, 0), Some((0, 0)));
+    assert_eq!(click(3, 0), Some((0, 0)));
+    assert_eq!(click(5, 0), Some((0, 1)));
+    // Text after the tabs starts at the second tab stop
+    assert_eq!(click(8, 0), Some((0, 2)));
+    assert_eq!(click(10, 0), Some((0, 4)));
+    assert_eq!(click(30, 0), Some((0, 5)));
+
+    assert_eq!(click(4, 1), Some((1, 1)));
+    assert_eq!(click(6, 1), Some((1, 3)));
+}
+
+#[tokio::test]
+async fn test_status_message_expires() {
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock = {
+        let now = now.clone();
+        Arc::new(move || *now.lock().unwrap())
+    };
+    let mut app = App {
+        clock,
+        ..App::default()
+    };
+
+    app.set_status_message_with_timeout("Saved".to_string(), Duration::from_secs(2));
+    *now.lock().unwrap() += Duration::from_secs(1);
+    app.update_status_bar();
+    assert_eq!(app.status_message.as_deref(), Some("Saved"));
+
+    *now.lock().unwrap() += Duration::from_secs(2);
+    app.update_status_bar();
+    assert_eq!(app.status_message, None);
+}
+
+#[tokio::test]
+async fn test_auto_save_writes_dirty_buffer_after_delay() {
+    use editor::autosave::auto_save_if_idle;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("auto.txt");
+    fs::write(&file_path, "Hello").unwrap();
+
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let mut app = App::with_file(file_path.to_str().unwrap()).await.unwrap();
+    app.clock = {
+        let now = now.clone();
+        Arc::new(move || *now.lock().unwrap())
+    };
+    app.config.editor.auto_save = true;
+    app.config.editor.auto_save_delay = 100;
+    app.add_buffer(Buffer::new());
+
+    app.active_buffer = 0;
+    app.buffers[0].cursor_pos = (0, 5);
+    app.buffers[0].insert_char('!');
+    app.buffers[1].insert_char('x');
+    app.note_edit();
+
+    // Still within the delay, nothing is saved
+    let app_state = tokio::sync::RwLock::new(app);
+    *now.lock().unwrap() += Duration::from_millis(50);
+    assert!(!auto_save_if_idle(&app_state).await);
+    assert!(app_state.read().await.buffers[0].is_dirty());
+
+    *now.lock().unwrap() += Duration::from_millis(50);
+    assert!(auto_save_if_idle(&app_state).await);
+    let app = app_state.read().await;
+    assert!(!app.buffers[0].is_dirty());
+    assert_eq!(fs::read_to_string(&file_path).unwrap().trim_end(), "Hello!");
+
+    // The untitled buffer is left alone
+    assert!(app.buffers[1].is_dirty());
+}
+
+#[tokio::test]
+async fn test_abort_all_stops_background_tasks() {
+    use editor::app::BackgroundTasks;
+    use std::time::Duration;
+
+    let mut tasks = BackgroundTasks::default();
+    let (done_tx, done_rx) = tokio::sync::oneshot::channel::<()>();
+    tasks.spawn("sleeper", async move {
+        tokio::time::sleep(Duration::from_secs(3600)).await;
+        let _ = done_tx.send(());
+    });
+    assert_eq!(tasks.running(), vec!["sleeper"]);
+
+    tasks.abort_all();
+
+    // The aborted task drops its sender without ever sending
+    assert!(done_rx.await.is_err());
+    assert!(tasks.running().is_empty());
+}
+
+#[tokio::test]
+async fn test_on_event_handler_receives_buffer_changes() {
+    use editor::events::{AppEvent, EventBus};
+    use editor::handlers::KeyboardHandler;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::{mpsc, RwLock};
+
+    let (seen_tx, mut seen_rx) = mpsc::unbounded_channel();
+    let mut app = App::default();
+    app.on_event("buffer_edited", move |event| {
+        if let AppEvent::BufferEdited {
+            start_line,
+            new_line_count,
+            ..
+        } = event
+        {
+            let _ = seen_tx.send(*start_line..start_line + new_line_count);
+        }
+        Ok(())
+    });
+
+    // Wire up the bus the way `run` does
+    let event_bus = EventBus::new();
+    app.subscribe_event_hooks(&event_bus).await;
+    let handler = KeyboardHandler::new(Arc::new(RwLock::new(app)), event_bus.sender());
+    let processor = event_bus.clone();
+    tokio::spawn(async move { processor.start_processing().await });
+
+    handler
+        .handle_key_event(AppEvent::KeyInput(KeyEvent::new(
+            KeyCode::Char('x'),
+            KeyModifiers::NONE,
+        )))
+        .await
+        .unwrap();
+
+    let lines = tokio::time::timeout(Duration::from_secs(1), seen_rx.recv())
+        .await
+        .expect("handler was not called")
+        .unwrap();
+    assert_eq!(lines, 0..1);
+}
+
+#[tokio::test]
+async fn test_type_text_types_tabs_as_the_configured_indent() {
+    let mut app = App::default();
+    app.buffers.push(Buffer::new());
+    app.config.editor.use_spaces = true;
+    app.config.editor.tab_size = 4;
+    let editor = HeadlessEditor::new(app).await.unwrap();
+
+    editor.type_text("\tx").await.unwrap();
+    assert_eq!(editor.app().await.active_buffer, 0);
+    assert_eq!(editor.active_content().await, "    x");
+
+    editor.app_mut().await.config.editor.use_spaces = false;
+    editor.type_text("\ty").await.unwrap();
+    assert_eq!(editor.app().await.active_buffer, 0);
+    assert_eq!(editor.active_content().await, "    x\ty");
+}
+
+#[tokio::test]
+async fn test_type_text_edits_like_real_input() {
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    let editor = HeadlessEditor::new(App::default()).await.unwrap();
+    editor.type_text("hi\nbye").await.unwrap();
+    {
+        let app = editor.app().await;
+        assert_eq!(app.buffers[0].content, vec!["hi", "bye"]);
+        assert_eq!(app.active_content(), "hi\nbye");
+        assert!(app.buffers[0].modified);
+    }
+
+    editor
+        .press_key(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE))
+        .await
+        .unwrap();
+    assert_eq!(editor.active_content().await, "hi\nby");
+}
+
+#[tokio::test]
+async fn test_opening_a_missing_file_starts_it_empty() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("new.txt");
+
+    let app = App::with_file(path.to_str().unwrap()).await.unwrap();
+    let buffer = &app.buffers[0];
+    assert_eq!(buffer.name, "new.txt");
+    assert_eq!(buffer.path.as_ref(), Some(&path));
+    assert_eq!(app.active_content(), "");
+    assert!(!buffer.modified);
+    assert!(!path.exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_opening_an_unreadable_file_is_an_error() {
+    use std::os::unix::fs::PermissionsExt;
+
+    use editor::OpenError;
+
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("secret.txt");
+    fs::write(&path, "hidden").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+    if fs::read(&path).is_ok() {
+        // Permissions don't stop root, so there's nothing to test
+        eprintln!("skipping: running as root, {} is readable", path.display());
+        return;
+    }
+
+    let Err(error) = App::with_file(path.to_str().unwrap()).await else {
+        panic!("opened a file that can't be read");
+    };
+    assert!(
+        matches!(&error, OpenError::PermissionDenied { path: denied } if *denied == path),
+        "{}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn test_structured_errors_for_unopenable_path_and_bad_config() {
+    use editor::config::ConfigManager;
+    use editor::{ConfigError, OpenError};
+
+    let temp_dir = TempDir::new().unwrap();
+
+    // A missing file opens as a new one, but a path through a file can't
+    let missing = temp_dir.path().join("missing.txt");
+    let app = App::with_file(missing.to_str().unwrap()).await.unwrap();
+    assert_eq!(app.buffers[0].path.as_ref(), Some(&missing));
+    fs::write(temp_dir.path().join("plain.txt"), "text").unwrap();
+    let inside = temp_dir.path().join("plain.txt/inside.txt");
+    let Err(error) = App::with_file(inside.to_str().unwrap()).await else {
+        panic!("opened a file inside a file");
+    };
+    assert!(
+        matches!(&error, OpenError::Io { path, .. } if *path == inside),
+        "{}",
+        error
+    );
+    assert_eq!(error.path(), &inside);
+
+    fs::write(temp_dir.path().join("config.json"), "{ not json").unwrap();
+    let mut config_manager = ConfigManager::new(temp_dir.path());
+    assert!(matches!(
+        config_manager.load(),
+        Err(ConfigError::Parse { .. })
+    ));
+    assert!(matches!(
+        config_manager.update_setting("editor.tabSize", serde_json::json!("wide")),
+        Err(ConfigError::InvalidValue {
+            expected: "number",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_command_palette_position_setting_round_trips() {
+    use editor::config::{ConfigManager, PalettePosition};
+    use editor::ConfigError;
+
+    let temp_dir = TempDir::new().unwrap();
+    let mut config_manager = ConfigManager::new(temp_dir.path());
+    let setting = "ui.commandPalettePosition";
+    assert_eq!(config_manager.get_setting(setting).unwrap(), "top");
+
+    config_manager
+        .update_setting(setting, serde_json::json!("center"))
+        .unwrap();
+    assert_eq!(
+        config_manager.get_config().ui.command_palette_position,
+        PalettePosition::Center
+    );
+    assert_eq!(config_manager.get_setting(setting).unwrap(), "center");
+
+    assert!(matches!(
+        config_manager.update_setting(setting, serde_json::json!("bottom")),
+        Err(ConfigError::InvalidValue { .. })
+    ));
+    assert_eq!(config_manager.get_setting(setting).unwrap(), "center");
+}
+
+#[tokio::test]
+async fn test_loading_a_theme_changes_the_editor_background() {
+    use editor::app::WORD_COUNT_SLOT;
+    use ratatui::{backend::TestBackend, style::Color, Terminal};
+
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir(temp_dir.path().join("themes")).unwrap();
+    fs::write(
+        temp_dir.path().join("themes/ocean.json"),
+        r##"{ "base": "dark", "editor_bg": "#102030" }"##,
+    )
+    .unwrap();
+
+    let mut app = App {
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    };
+
+    // Row 2 is below the text, away from the current line highlight
+    let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+    terminal.draw(|f| app.render(f)).unwrap();
+    assert_eq!(terminal.backend().buffer()[(10, 2)].bg, Color::Black);
+
+    app.set_theme("ocean").unwrap();
+    assert_eq!(app.config.ui.theme, "ocean");
+    terminal.draw(|f| app.render(f)).unwrap();
+    assert_eq!(
+        terminal.backend().buffer()[(10, 2)].bg,
+        Color::Rgb(0x10, 0x20, 0x30)
+    );
+
+    // Unknown themes leave the current one in place
+    assert!(app.set_theme("missing").is_err());
+    assert_eq!(app.theme.editor_bg, Color::Rgb(0x10, 0x20, 0x30));
+
+    // Names can't reach outside the themes directory
+    fs::write(temp_dir.path().join("outside.json"), "{}").unwrap();
+    assert!(app.set_theme("../outside").is_err());
+    assert!(app.set_theme("themes/../../outside").is_err());
+    assert_eq!(app.config.ui.theme, "ocean");
+
+    // Added slots follow the theme along with the built-in ones
+    app.update_word_count(0);
+    app.set_theme("default").unwrap();
+    assert_eq!(
+        app.status_bar.get_slot(WORD_COUNT_SLOT).unwrap().style,
+        app.theme.status_bar_dim_style()
+    );
+}
+
+#[tokio::test]
+async fn test_custom_status_slots_and_word_count() {
+    use editor::app::WORD_COUNT_SLOT;
+    use editor::widgets::{SlotAlignment, StatusSlot};
+
+    let mut app = App::default();
+    app.set_status_slot(
+        StatusSlot::new("git_branch", "main").with_alignment(SlotAlignment::Right),
+    );
+    assert!(app.update_status_slot("git_branch", "feature"));
+    assert!(!app.update_status_slot("missing", "ignored"));
+
+    // Custom slots survive the per-frame refresh of the built-in ones
+    app.update_status_bar();
+    let slot = app.status_bar.get_slot("git_branch").unwrap();
+    assert_eq!(slot.content, "feature");
+    assert_eq!(slot.alignment, SlotAlignment::Right);
+
+    app.remove_status_slot("git_branch");
+    assert!(app.status_bar.get_slot("git_branch").is_none());
+
+    // The word-count slot shows up once the buffer changes
+    assert!(app.status_bar.get_slot(WORD_COUNT_SLOT).is_none());
+    let editor = HeadlessEditor::new(app).await.unwrap();
+    editor.type_text("hello world\nbye").await.unwrap();
+    let app = editor.into_app().await;
+    assert_eq!(
+        app.status_bar.get_slot(WORD_COUNT_SLOT).unwrap().content,
+        "3 words, 15 chars"
+    );
+}
+
+#[tokio::test]
+async fn test_word_count_recounts_only_edited_lines_and_catches_up() {
+    use editor::app::WORD_COUNT_SLOT;
+    use editor::buffer::BufferStats;
+
+    let editor = HeadlessEditor::new(App::default()).await.unwrap();
+    editor
+        .type_text("one two\nthree\nfour five six")
+        .await
+        .unwrap();
+    let mut app = editor.into_app().await;
+
+    // Several edits before the count catches up, one of them joining lines
+    let buffer = &mut app.buffers[0];
+    buffer.cursor_pos = (1, 0);
+    buffer.backspace();
+    buffer.insert_str(" and ");
+    buffer.cursor_pos = (1, 0);
+    buffer.insert_newline();
+    app.update_word_count(0);
+
+    let expected = BufferStats::of(&app.buffers[0].content_as_string());
+    assert_eq!(
+        app.status_bar.get_slot(WORD_COUNT_SLOT).unwrap().content,
+        format!("{} words, {} chars", expected.words, expected.chars)
+    );
+    assert_eq!(expected.words, 7);
+}
+
+#[tokio::test]
+async fn test_center_cursor_scrolls_the_line_to_mid_view() {
+    use ratatui::layout::Rect;
+
+    let mut app = App::default();
+    app.buffers[0].content = vec![String::new(); 100].into();
+    let editor_area = Rect::new(0, 0, 80, 20);
+
+    app.buffers[0].cursor_pos = (50, 0);
+    app.center_cursor(editor_area);
+    assert_eq!(app.scroll_offset.0, 40);
+
+    // Near the top there's nothing to scroll above the first line
+    app.buffers[0].cursor_pos = (3, 0);
+    app.center_cursor(editor_area);
+    assert_eq!(app.scroll_offset.0, 0);
+
+    // The last line can only be centered as far as scroll_past_end allows
+    app.buffers[0].cursor_pos = (99, 0);
+    app.center_cursor(editor_area);
+    assert_eq!(app.scroll_offset.0, 89);
+    app.config.editor.scroll_past_end = 5;
+    app.center_cursor(editor_area);
+    assert_eq!(app.scroll_offset.0, 85);
+}
+
+#[tokio::test]
+async fn test_session_reopens_files_with_their_cursors() {
+    let temp_dir = TempDir::new().unwrap();
+    let first = temp_dir.path().join("first.txt");
+    let second = temp_dir.path().join("second.txt");
+    let gone = temp_dir.path().join("gone.txt");
+    fs::write(&first, "one\ntwo\nthree\n").unwrap();
+    fs::write(&second, "alpha beta\n").unwrap();
+    fs::write(&gone, "soon deleted\n").unwrap();
+
+    let mut app = App {
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    };
+    for (path, cursor) in [(&first, (2, 3)), (&gone, (0, 0)), (&second, (0, 6))] {
+        let mut buffer = Buffer::from_path(path.clone()).unwrap();
+        buffer.cursor_pos = cursor;
+        app.add_buffer(buffer);
+    }
+    app.save_session().unwrap();
+    fs::remove_file(&gone).unwrap();
+
+    let mut restored = App {
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    };
+    assert_eq!(restored.restore_session().await.unwrap(), 2);
+
+    let paths: Vec<_> = restored.buffers.iter().map(|b| b.path.clone().unwrap()).collect();
+    assert_eq!(paths, vec![first, second]);
+    assert_eq!(restored.buffers[0].cursor_pos, (2, 3));
+    assert_eq!(restored.buffers[1].cursor_pos, (0, 6));
+    assert_eq!(restored.active_buffer, 1);
+    assert_eq!(restored.toast_manager.toasts().len(), 1);
+}
+
+#[test]
+fn test_scroll_off_sets_how_early_the_view_follows_the_cursor() {
+    use editor::config::EditorConfig;
+    use ratatui::layout::Rect;
+
+    // Row the cursor first makes a 20-row view scroll down when moving down
+    // from the top
+    fn first_scrolling_row(scroll_off: usize) -> usize {
+        let mut app = App::default();
+        app.config.editor.scroll_off = scroll_off;
+        for _ in 0..100 {
+            app.buffers[0].insert_newline();
+        }
+        let area = Rect::new(0, 0, 80, 20);
+        for row in 0..100 {
+            app.buffers[0].cursor_pos = (row, 0);
+            app.ensure_cursor_visible(area);
+            if app.scroll_offset.0 > 0 {
+                return row;
+            }
+        }
+        unreachable!("the view never scrolled");
+    }
+
+    let default = first_scrolling_row(EditorConfig::default().scroll_off);
+    assert_eq!(default, 17);
+    assert_eq!(first_scrolling_row(5), 15);
+    assert_eq!(first_scrolling_row(0), 20);
+
+    // A margin larger than half the view is capped so the cursor can still
+    // sit in the middle row
+    assert_eq!(first_scrolling_row(50), 11);
+}
+
+#[test]
+fn test_clicks_map_onto_the_editor_area_of_the_last_frame() {
+    use editor::input::coordinates::screen_to_buffer_coords;
+    use ratatui::{backend::TestBackend, layout::Rect, Terminal};
+
+    let mut app = App::default();
+    app.buffers[0].content = (0..20).map(|i| format!("line {}", i)).collect();
+    let text_x = app.gutter_width() as u16;
+
+    app.last_editor_area = Some(Rect::new(0, 0, 40, 10));
+    assert_eq!(screen_to_buffer_coords(&app, text_x + 2, 3), Some((3, 2)));
+
+    // With a tab bar above it the same cell is one line higher in the text
+    app.last_editor_area = Some(Rect::new(0, 1, 40, 10));
+    assert_eq!(screen_to_buffer_coords(&app, text_x + 2, 3), Some((2, 2)));
+    assert_eq!(screen_to_buffer_coords(&app, text_x + 2, 0), None);
+
+    // Rendering stores where the editor was actually drawn
+    app.config.ui.show_tab_bar = true;
+    let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+    terminal.draw(|f| app.render(f)).unwrap();
+    assert_eq!(app.last_editor_area, Some(app.layout(Rect::new(0, 0, 40, 12)).editor));
+    assert_eq!(app.editor_area().y, 1);
+}
+
+#[tokio::test]
+async fn test_gutter_gap_setting_overrides_the_theme() {
+    use editor::config::ConfigManager;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let mut app = App::default();
+    app.theme.gutter_gap = 2;
+    app.buffers[0].content = vec!["x".to_string()].into();
+    let themed = app.gutter_width();
+
+    let temp_dir = TempDir::new().unwrap();
+    let mut config_manager = ConfigManager::new(temp_dir.path());
+    config_manager
+        .update_setting("editor.gutterGap", serde_json::json!(4))
+        .unwrap();
+    assert_eq!(config_manager.get_setting("editor.gutterGap").unwrap(), 4);
+    app.config = config_manager.get_config().clone();
+    assert_eq!(app.gutter_width(), themed + 2);
+
+    // The text is drawn after the wider gap
+    let mut terminal = Terminal::new(TestBackend::new(20, 3)).unwrap();
+    terminal.draw(|f| app.render(f)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert_eq!(buffer[(themed as u16 + 1, 0)].symbol(), " ");
+    assert_eq!(buffer[(themed as u16 + 2, 0)].symbol(), "x");
+
+    // Null goes back to the theme's gap
+    config_manager
+        .update_setting("editor.gutterGap", serde_json::Value::Null)
+        .unwrap();
+    app.config = config_manager.get_config().clone();
+    assert_eq!(app.gutter_width(), themed);
+}
+
+#[tokio::test]
+async fn test_tab_display_width_only_changes_how_tabs_are_drawn() {
+    use editor::input::coordinates::screen_to_buffer_coords_in_area;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::{backend::TestBackend, layout::Rect, Terminal};
+
+    let mut app = App::default();
+    app.config.editor.tab_size = 4;
+    app.config.editor.tab_display_width = Some(8);
+    app.buffers[0].content = vec!["\tx".to_string(), "y".to_string()].into();
+    let text_x = app.gutter_width() as u16;
+
+    let mut terminal = Terminal::new(TestBackend::new(40, 5)).unwrap();
+    terminal.draw(|f| app.render(f)).unwrap();
+    assert_eq!(terminal.backend().buffer()[(text_x + 8, 0)].symbol(), "x");
+
+    // Clicks map through the displayed width too
+    let area = Rect::new(0, 0, 40, 4);
+    assert_eq!(screen_to_buffer_coords_in_area(&app, area, text_x + 8, 0), Some((0, 1)));
+
+    // Indenting still inserts tab_size spaces
+    app.buffers[0].selection_start = Some((0, 0));
+    app.buffers[0].cursor_pos = (1, 1);
+    let editor = HeadlessEditor::new(app).await.unwrap();
+    editor
+        .press_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE))
+        .await
+        .unwrap();
+    assert_eq!(editor.app().await.buffers[0].content, vec!["    \tx", "    y"]);
+}
+
+#[tokio::test]
+async fn test_brackets_and_quotes_are_closed_as_they_are_typed() {
+    let editor = HeadlessEditor::new(App::default()).await.unwrap();
+
+    editor.type_text("(").await.unwrap();
+    assert_eq!(editor.active_content().await, "()");
+    assert_eq!(editor.app().await.buffers[0].cursor_pos, (0, 1));
+
+    // Typing the closing char steps over it
+    editor.type_text("x)").await.unwrap();
+    assert_eq!(editor.active_content().await, "(x)");
+    assert_eq!(editor.app().await.buffers[0].cursor_pos, (0, 3));
+
+    // An apostrophe inside a word stays single
+    editor.type_text(" don't").await.unwrap();
+    assert_eq!(editor.active_content().await, "(x) don't");
+
+    // Turned off, nothing is closed
+    editor.app_mut().await.config.editor.auto_pairs = false;
+    editor.type_text(" [").await.unwrap();
+    assert_eq!(editor.active_content().await, "(x) don't [");
+}
+
+#[tokio::test]
+async fn test_pairs_typed_at_several_cursors_keep_each_cursor_inside_its_own() {
+    use editor::buffer::Position;
+
+    let mut app = App::default();
+    app.buffers[0].content = vec![", , ,".to_string()].into();
+    app.buffers[0].cursor_pos = (0, 1);
+    app.buffers[0].add_cursor(Position::new(0, 3));
+    app.buffers[0].add_cursor(Position::new(0, 5));
+
+    let editor = HeadlessEditor::new(app).await.unwrap();
+    editor.type_text("(").await.unwrap();
+    assert_eq!(editor.active_content().await, ",() ,() ,()");
+    assert_eq!(
+        editor.app().await.buffers[0].all_cursors(),
+        vec![Position::new(0, 2), Position::new(0, 6), Position::new(0, 10)]
+    );
+
+    editor.type_text("x)").await.unwrap();
+    assert_eq!(editor.active_content().await, ",(x) ,(x) ,(x)");
+}
+
+#[tokio::test]
+async fn test_typing_a_quote_wraps_the_selection() {
+    let mut app = App::default();
+    app.buffers[0].content = vec!["say hello now".to_string()].into();
+    app.buffers[0].visual_mode = true;
+    app.buffers[0].selection_start = Some((0, 4));
+    app.buffers[0].cursor_pos = (0, 9);
+
+    let editor = HeadlessEditor::new(app).await.unwrap();
+    editor.type_text("\"").await.unwrap();
+    let app = editor.into_app().await;
+    assert_eq!(app.active_content(), "say \"hello\" now");
+    assert_eq!(app.buffers[0].get_selected_text().as_deref(), Some("hello"));
+}
+
+#[tokio::test]
+async fn test_plugin_commands_edit_the_active_buffer_through_the_context() {
+    use editor::plugins::{EditorContext, PluginCommand, UppercaseSelection};
+
+    let mut app = App::default();
+    app.buffers[0].content = vec!["say hello now".to_string()].into();
+    app.buffers[0].selection_start = Some((0, 4));
+    app.buffers[0].cursor_pos = (0, 9);
+
+    let mut context = EditorContext::new(&mut app);
+    UppercaseSelection.execute(&mut context, &[]).unwrap();
+    assert_eq!(context.text(), "say HELLO now");
+    assert_eq!(context.selected_text().as_deref(), Some("HELLO"));
+    assert!(app.buffers[0].modified);
+    assert!(app.buffers[0].take_change().is_some());
+
+    // With nothing selected it only says so
+    app.buffers[0].clear_selection();
+    let mut context = EditorContext::new(&mut app);
+    UppercaseSelection.execute(&mut context, &[]).unwrap();
+    assert_eq!(context.text(), "say HELLO now");
+    assert!(app.status_message.is_some());
+}
+
+#[test]
+fn test_clicks_past_the_end_of_a_line_are_flagged() {
+    use editor::input::coordinates::{screen_to_clicked_position_in_area, ClickedPosition};
+    use ratatui::layout::Rect;
+
+    let mut app = App::default();
+    app.buffers[0].content = vec!["short".to_string(), "\tx".to_string()].into();
+    let text_x = app.gutter_width() as u16;
+    let area = Rect::new(0, 0, 40, 10);
+    let click = |app: &App, x: u16, y: u16| {
+        let clicked = screen_to_clicked_position_in_area(app, area, text_x + x, y).unwrap();
+        (clicked.row, clicked.col, clicked.past_line_end)
+    };
+
+    assert_eq!(click(&app, 4, 0), (0, 4, false));
+    // The cell right after the text and any further right are clamped
+    assert_eq!(click(&app, 5, 0), (0, 5, true));
+    assert_eq!(click(&app, 30, 0), (0, 5, true));
+    // Tabs count with their drawn width
+    assert_eq!(click(&app, 4, 1), (1, 1, false));
+    assert_eq!(click(&app, 5, 1), (1, 2, true));
+    // Below the last line is past its end
+    assert_eq!(click(&app, 1, 5), (1, 2, true));
+
+    // Only the last row of a wrapped line ends it
+    app.config.editor.word_wrap = true;
+    app.buffers[0].content = vec!["x".repeat(50)].into();
+    let text_width = 40 - text_x;
+    assert!(!click(&app, text_width - 1, 0).2);
+    assert_eq!(
+        screen_to_clicked_position_in_area(&app, area, text_x + 20, 1),
+        Some(ClickedPosition {
+            row: 0,
+            col: 50,
+            past_line_end: true,
+            in_gutter: false,
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_pasting_replaces_the_selection_with_the_text() {
+    let mut app = App::default();
+    app.buffers[0].content = vec!["keep CUT keep".to_string()].into();
+    app.buffers[0].visual_mode = true;
+    app.buffers[0].selection_start = Some((0, 5));
+    app.buffers[0].cursor_pos = (0, 8);
+
+    let editor = HeadlessEditor::new(app).await.unwrap();
+    editor.paste("one\rtwo").await.unwrap();
+    let app = editor.into_app().await;
+    assert_eq!(app.active_content(), "keep one\ntwo keep");
+    assert_eq!(app.buffers[0].cursor_pos, (1, 3));
+    assert_eq!(app.buffers[0].get_selected_text(), None);
+}
+
+#[tokio::test]
+async fn test_status_bar_shows_the_line_ending_of_a_crlf_file() {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("dos.txt");
+    fs::write(&path, "one\r\ntwo\r\n").unwrap();
+
+    let mut app = App {
+        buffers: vec![Buffer::from_path(path).unwrap()],
+        ..App::default()
+    };
+    assert_eq!(app.active_content(), "one\ntwo");
+
+    let mut terminal = Terminal::new(TestBackend::new(100, 6)).unwrap();
+    terminal.draw(|f| app.render(f)).unwrap();
+
+    let status: String = (0..100)
+        .map(|x| terminal.backend().buffer()[(x, 5)].symbol().to_string())
+        .collect();
+    assert!(status.contains("UTF-8 | CRLF"), "{status:?}");
+}
+
+#[tokio::test]
+async fn test_adding_buffers_past_the_limit_warns() {
+    use editor::widgets::toast::ToastType;
+
+    let mut app = App::default();
+    app.config.editor.max_open_buffers = 2;
+
+    app.add_buffer(Buffer::new());
+    assert!(app.toast_manager.toasts().is_empty());
+
+    let index = app.add_buffer(Buffer::new());
+    assert_eq!((index, app.buffers.len()), (2, 3));
+    let toast = app.toast_manager.toasts().last().unwrap();
+    assert_eq!(toast.toast_type, ToastType::Warning);
+    assert!(toast.message.starts_with("3 buffers open"));
+}
+
+#[tokio::test]
+async fn test_close_lru_closes_the_oldest_clean_buffer() {
+    let temp_dir = TempDir::new().unwrap();
+    let named = |name: &str| {
+        let mut buffer = Buffer::new();
+        buffer.name = name.to_string();
+        buffer
+    };
+    let mut app = App {
+        buffers: vec![named("a")],
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    };
+    app.config.editor.max_open_buffers = 3;
+    app.config.editor.close_lru = true;
+    app.add_buffer(named("b"));
+    app.add_buffer(named("c"));
+    app.buffers[1].modified = true;
+    app.switch_to_buffer(0);
+
+    // "b" has unsaved changes and "a" was just used, so "c" goes
+    let index = app.add_buffer(named("d"));
+    let names: Vec<&str> = app.buffers.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "b", "d"]);
+    assert_eq!(index, 2);
+    assert_eq!(app.active_buffer, 2);
+}
+
+#[tokio::test]
+async fn test_switching_with_tab_keeps_a_buffer_from_being_closed() {
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    let temp_dir = TempDir::new().unwrap();
+    let named = |name: &str| {
+        let mut buffer = Buffer::new();
+        buffer.name = name.to_string();
+        buffer
+    };
+    let mut app = App {
+        buffers: vec![named("a")],
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    };
+    app.config.editor.max_open_buffers = 3;
+    app.config.editor.close_lru = true;
+    app.add_buffer(named("b"));
+    app.add_buffer(named("c"));
+
+    // Tab from "c" wraps around to "a", which makes "b" the oldest
+    let editor = HeadlessEditor::new(app).await.unwrap();
+    editor
+        .press_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE))
+        .await
+        .unwrap();
+    let mut app = editor.into_app().await;
+    assert_eq!(app.active_buffer, 0);
+
+    app.add_buffer(named("d"));
+    let names: Vec<&str> = app.buffers.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "c", "d"]);
+}
+
+#[tokio::test]
+async fn test_render_to_test_buffer_draws_the_text_after_the_gutter() {
+    let mut app = App::default();
+    app.buffers[0].content = vec!["hello".to_string(), "world".to_string()].into();
+
+    let cells = app.render_to_test_buffer(30, 6);
+    let row = |y: u16| -> String {
+        (0..10)
+            .map(|x| cells[(x, y)].symbol().to_string())
+            .collect()
+    };
+    assert_eq!(row(0), "   1 hello");
+    assert_eq!(row(1), "   2 world");
+    assert_eq!(app.last_editor_area.map(|area| area.width), Some(30));
+}
+
+#[tokio::test]
+async fn test_help_lists_commands_with_their_keys_and_descriptions() {
+    let mut app = App::default();
+    app.config
+        .keybindings
+        .insert("space s".to_string(), "save".to_string());
+    app.open_help();
+
+    let lines = app.help_lines();
+    let save = lines
+        .iter()
+        .position(|line| line.starts_with("save (w)"))
+        .unwrap();
+    assert_eq!(lines[save], "save (w)  [Ctrl+S, space s]");
+    assert_eq!(lines[save + 1], "    Save the active buffer, or save it to the given file");
+    assert!(lines.iter().any(|line| line == "back  [Ctrl+O]"));
+    assert!(lines.iter().any(|line| line == "forward  [Ctrl+I, Alt+I]"));
+
+    // The modal shows them, scrolled no further than its last line
+    app.help_scroll = usize::MAX;
+    let cells = app.render_to_test_buffer(80, 12);
+    let screen: String = (0..12)
+        .flat_map(|y| (0..80).map(move |x| (x, y)))
+        .map(|position| cells[position].symbol().to_string())
+        .collect();
+    assert!(screen.contains(" Help "));
+    assert_eq!(app.help_scroll, lines.len() - 8);
+
+    app.help_scroll = save;
+    let cells = app.render_to_test_buffer(80, 12);
+    let screen: String = (0..12)
+        .flat_map(|y| (0..80).map(move |x| (x, y)))
+        .map(|position| cells[position].symbol().to_string())
+        .collect();
+    assert!(screen.contains("save (w)  [Ctrl+S, space s]"));
+    assert!(screen.contains("Save the active buffer, or save it to the given file"));
+}
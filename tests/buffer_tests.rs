@@ -5,7 +5,7 @@
 use std::fs;
 use tempfile::TempDir;
 
-use editor::buffer::Buffer;
+use editor::buffer::{toggle_case, Buffer};
 
 #[tokio::test]
 async fn test_buffer_creation() {
@@ -146,9 +146,9 @@ async fn test_buffer_file_operations() {
     buffer.save_to_path_async(file_path.clone()).await.unwrap();
     assert!(!buffer.modified);
 
-    // Verify file was saved correctly
+    // Verify file was saved correctly, with a final newline added
     let content = fs::read_to_string(&file_path).unwrap();
-    assert_eq!(content, "!Hello\nWorld\nTest");
+    assert_eq!(content, "!Hello\nWorld\nTest\n");
 }
 
 #[tokio::test]
@@ -173,6 +173,41 @@ async fn test_buffer_multiline_selection() {
     assert_eq!(selected_text, Some("Line 1\nLin".to_string()));
 }
 
+#[tokio::test]
+async fn test_block_selection_extracts_and_deletes_columns() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["abcdef", "ghijkl", "mnopqr"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+    // Columns 2..4 of all three lines
+    buffer.start_block_selection((0, 2));
+    buffer.cursor_pos = (2, 4);
+
+    assert_eq!(buffer.get_selected_text(), Some("cd\nij\nop".to_string()));
+
+    assert!(buffer.delete_selection());
+    assert_eq!(buffer.content, vec!["abef", "ghkl", "mnqr"]);
+    assert_eq!(buffer.cursor_pos, (0, 2));
+    assert_eq!(buffer.get_selected_text(), None);
+}
+
+#[tokio::test]
+async fn test_replace_all_regex_with_capture_groups() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["width=80 height=24", "depth=3", "none"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let re = regex::Regex::new(r"(\w+)=(\d+)").unwrap();
+
+    assert_eq!(buffer.search_regex(&re).len(), 3);
+    assert_eq!(buffer.replace_all_regex(&re, "$2:$1"), 3);
+    assert_eq!(buffer.content, vec!["80:width 24:height", "3:depth", "none"]);
+    assert!(buffer.modified);
+}
+
 #[tokio::test]
 async fn test_buffer_line_boundaries() {
     let mut buffer = Buffer::new();
@@ -194,3 +229,899 @@ async fn test_buffer_line_boundaries() {
     buffer.move_cursor(editor::buffer::CursorMovement::Right); // Should not go beyond line end
     assert_eq!(buffer.cursor_pos, (0, 2));
 }
+
+#[tokio::test]
+async fn test_matching_bracket_nested() {
+    use editor::buffer::Position;
+
+    let mut buffer = Buffer::new();
+    buffer.content = vec![
+        "fn f(a: [u8; 2]) {".to_string(),
+        "    g((a[0]), {1})".to_string(),
+        "}".to_string(),
+    ].into();
+
+    // Forward and backward across nested pairs of the same kind
+    assert_eq!(
+        buffer.matching_bracket(Position::new(1, 5)),
+        Some(Position::new(1, 17))
+    );
+    assert_eq!(
+        buffer.matching_bracket(Position::new(1, 17)),
+        Some(Position::new(1, 5))
+    );
+    assert_eq!(
+        buffer.matching_bracket(Position::new(1, 6)),
+        Some(Position::new(1, 11))
+    );
+    // Across lines
+    assert_eq!(
+        buffer.matching_bracket(Position::new(0, 17)),
+        Some(Position::new(2, 0))
+    );
+    assert_eq!(
+        buffer.matching_bracket(Position::new(2, 0)),
+        Some(Position::new(0, 17))
+    );
+    // Not on a bracket
+    assert_eq!(buffer.matching_bracket(Position::new(0, 0)), None);
+
+    // The cursor just after a closing bracket still finds the pair
+    buffer.cursor_pos = (0, 16);
+    assert_eq!(
+        buffer.bracket_pair_at_cursor(),
+        Some((Position::new(0, 15), Position::new(0, 4)))
+    );
+}
+
+#[tokio::test]
+async fn test_matching_bracket_unbalanced_and_in_strings() {
+    use editor::buffer::Position;
+
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["let s = (1, \"(\";".to_string(), "x)".to_string()].into();
+
+    // Matching isn't string-aware: the "(" inside the string counts as a
+    // nested opener, so it pairs with the ")" on the next line
+    assert_eq!(
+        buffer.matching_bracket(Position::new(0, 13)),
+        Some(Position::new(1, 1))
+    );
+    // ...which leaves the outer opener without a match
+    assert_eq!(buffer.matching_bracket(Position::new(0, 8)), None);
+    assert_eq!(
+        buffer.matching_bracket(Position::new(1, 1)),
+        Some(Position::new(0, 13))
+    );
+}
+
+#[tokio::test]
+async fn test_save_trims_trailing_whitespace() {
+    use editor::buffer::SaveOptions;
+
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("trim.txt");
+    fs::write(&file_path, "foo   \nbar\t\n").unwrap();
+
+    let mut buffer = Buffer::from_path(file_path.clone()).unwrap();
+    buffer.save_options = SaveOptions {
+        trim_trailing_whitespace: true,
+        ..SaveOptions::default()
+    };
+    // Cursor sitting in the whitespace that's about to be trimmed
+    buffer.cursor_pos = (0, 5);
+    buffer.save().unwrap();
+
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "foo\nbar\n");
+    assert_eq!(buffer.content, vec!["foo", "bar"]);
+    assert_eq!(buffer.cursor_pos, (0, 3));
+    assert!(!buffer.modified);
+
+    // The async path applies the same clean-up
+    let async_path = temp_dir.path().join("trim_async.txt");
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["foo   ".to_string(), "bar\t".to_string()].into();
+    buffer.save_options.trim_trailing_whitespace = true;
+    buffer.save_to_path_async(async_path.clone()).await.unwrap();
+    assert_eq!(fs::read_to_string(&async_path).unwrap(), "foo\nbar\n");
+}
+
+#[tokio::test]
+async fn test_save_keeps_trailing_whitespace_by_default() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("keep.txt");
+    fs::write(&file_path, "foo   \nbar\t\n").unwrap();
+
+    let mut buffer = Buffer::from_path(file_path.clone()).unwrap();
+    buffer.save().unwrap();
+
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "foo   \nbar\t\n");
+}
+
+#[tokio::test]
+async fn test_utf16le_file_round_trips() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("utf16.txt");
+
+    let mut bytes = vec![0xFF, 0xFE];
+    for unit in "héllo\nwörld\n".encode_utf16() {
+        bytes.extend_from_slice(&unit.to_le_bytes());
+    }
+    fs::write(&file_path, &bytes).unwrap();
+
+    let mut buffer = Buffer::from_path_async(file_path.clone()).await.unwrap();
+    assert_eq!(buffer.content, vec!["héllo", "wörld"]);
+    assert_eq!(buffer.encoding.name(), "UTF-16LE");
+    assert!(buffer.encoding.bom);
+    assert!(buffer.encoding_warning().is_none());
+
+    // Saving writes the same bytes back, BOM included
+    buffer.save().unwrap();
+    assert_eq!(fs::read(&file_path).unwrap(), bytes);
+
+    buffer.content[0] = "hi".to_string();
+    buffer.save_async().await.unwrap();
+    let reloaded = Buffer::from_path_async(file_path).await.unwrap();
+    assert_eq!(reloaded.content, vec!["hi", "wörld"]);
+    assert_eq!(reloaded.encoding, buffer.encoding);
+}
+
+#[tokio::test]
+async fn test_latin1_file_is_decoded_and_preserved() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("latin1.txt");
+    // "café" in Latin-1 isn't valid UTF-8
+    fs::write(&file_path, b"caf\xE9\n").unwrap();
+
+    let mut buffer = Buffer::from_path(file_path.clone()).unwrap();
+    assert_eq!(buffer.content, vec!["café"]);
+    assert_eq!(buffer.encoding.name(), "windows-1252");
+
+    buffer.save().unwrap();
+    assert_eq!(fs::read(&file_path).unwrap(), b"caf\xE9\n");
+}
+
+#[tokio::test]
+async fn test_undetectable_encoding_falls_back_to_lossy_utf8() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("binary.dat");
+    fs::write(&file_path, b"ok\x00\xFF\n").unwrap();
+
+    let buffer = Buffer::from_path_async(file_path).await.unwrap();
+    assert_eq!(buffer.content, vec!["ok\0\u{FFFD}"]);
+    assert_eq!(buffer.encoding.name(), "UTF-8");
+    assert!(buffer.encoding_warning().is_some());
+}
+
+#[test]
+fn test_replacing_in_a_lazy_file_only_loads_the_matching_lines() {
+    use regex::Regex;
+
+    const LINES: usize = 100_000;
+
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("big.log");
+    let text: String = (0..LINES).map(|i| format!("line {}\n", i)).collect();
+    fs::write(&file_path, text).unwrap();
+
+    let mut buffer = Buffer::open_lazy(file_path).unwrap();
+    let re = Regex::new(r"^line (5000\d)$").unwrap();
+    assert_eq!(buffer.replace_all_regex(&re, "row $1"), 10);
+    assert_eq!(buffer.content[50_003], "row 50003");
+    assert_eq!(buffer.content[50_010], "line 50010");
+    let materialized = buffer.content.materialized_lines();
+    assert!(materialized < LINES / 10, "{} lines in memory", materialized);
+}
+
+#[test]
+fn test_open_lazy_only_reads_visible_lines() {
+    use editor::widgets::editor::Editor;
+    use ratatui::{backend::TestBackend, layout::Rect, Terminal};
+
+    const LINES: usize = 200_000;
+
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("big.log");
+    let text: String = (0..LINES).map(|i| format!("line {}\n", i)).collect();
+    fs::write(&file_path, text).unwrap();
+
+    let mut buffer = Buffer::open_lazy(file_path.clone()).unwrap();
+    assert_eq!(buffer.content.len(), LINES);
+    assert_eq!(buffer.content.materialized_lines(), 0);
+
+    // Rendering a viewport deep in the file only reads the chunk it's in
+    buffer.path = Some(file_path.with_extension("rs"));
+    let editor = Editor {
+        scroll_offset: (150_000, 0),
+        ..Editor::new(&buffer)
+    };
+    let mut terminal = Terminal::new(TestBackend::new(30, 10)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(editor, Rect::new(0, 0, 30, 10)))
+        .unwrap();
+    let row: String = (0..30)
+        .map(|x| terminal.backend().buffer()[(x, 0)].symbol().to_string())
+        .collect();
+    assert!(row.contains("line 150000"), "{:?}", row);
+    assert_eq!(buffer.content.last().unwrap(), "line 199999");
+    let materialized = buffer.content.materialized_lines();
+    assert!(materialized < LINES / 10, "{} lines in memory", materialized);
+
+    // Edits stay in memory and are written back with the rest of the file
+    buffer.path = Some(file_path.clone());
+    buffer.cursor_pos = (42, 0);
+    buffer.insert_char('>');
+    buffer.save().unwrap();
+    let saved = fs::read_to_string(&file_path).unwrap();
+    assert_eq!(saved.lines().count(), LINES);
+    assert_eq!(saved.lines().nth(42), Some(">line 42"));
+    assert_eq!(saved.lines().nth(LINES - 1), Some("line 199999"));
+}
+
+#[test]
+fn test_lines_edits_match_vec_across_chunks() {
+    use editor::buffer::Lines;
+
+    let mut expected: Vec<String> = (0..5000).map(|i| i.to_string()).collect();
+    let mut lines = Lines::from(expected.clone());
+
+    // A mix of edits that grow, shrink, split and empty out chunks
+    for i in 0..3000 {
+        let row = (i * 7919) % expected.len();
+        if i % 3 == 0 {
+            expected.remove(row);
+            lines.remove(row);
+        } else {
+            expected.insert(row, format!("new {}", i));
+            lines.insert(row, format!("new {}", i));
+        }
+    }
+    expected.splice(100..4000, vec!["joined".to_string()]);
+    lines.splice(100..4000, vec!["joined".to_string()]);
+    expected.push("end".to_string());
+    lines.push("end".to_string());
+
+    assert_eq!(lines, expected);
+    assert_eq!(lines[101], expected[101]);
+    assert_eq!(
+        lines.range(95..105).collect::<Vec<_>>(),
+        expected[95..105].iter().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_inserts_at_the_start_only_shift_their_own_chunk() {
+    const LINES: usize = 500_000;
+    const EDITS: usize = 2_000;
+
+    let mut buffer = Buffer::new();
+    buffer.content = (0..LINES).map(|i| format!("line {}", i)).collect();
+
+    for row in [LINES - 1, 0] {
+        for _ in 0..EDITS {
+            buffer.cursor_pos = (row, 0);
+            buffer.insert_newline();
+        }
+    }
+
+    // With a flat Vec every insert at the top would shift all 500k lines;
+    // chunks are split once they pass twice their 1024-line target
+    assert!(
+        buffer.content.largest_chunk() <= 2 * 1024,
+        "largest chunk: {}",
+        buffer.content.largest_chunk()
+    );
+    assert_eq!(buffer.content.len(), LINES + 2 * EDITS);
+    assert_eq!(buffer.content[EDITS], "line 0");
+}
+
+#[test]
+fn test_insert_char_at_three_cursors() {
+    use editor::buffer::Position;
+
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["foo bar".to_string(), "foo".to_string(), "x foo".to_string()].into();
+    buffer.cursor_pos = (0, 3);
+    buffer.add_cursor(Position::new(0, 7));
+    buffer.add_cursor(Position::new(2, 5));
+
+    buffer.insert_char('!');
+    buffer.insert_char('?');
+
+    assert_eq!(buffer.content, vec!["foo!? bar!?", "foo", "x foo!?"]);
+    assert_eq!(buffer.cursor_pos, (0, 5));
+    assert_eq!(
+        buffer.all_cursors(),
+        vec![Position::new(0, 5), Position::new(0, 11), Position::new(2, 7)]
+    );
+
+    // Backspace and Enter keep every cursor in step too
+    buffer.backspace();
+    buffer.insert_newline();
+    assert_eq!(buffer.content, vec!["foo!", " bar!", "", "foo", "x foo!", ""]);
+    assert_eq!(
+        buffer.all_cursors(),
+        vec![Position::new(1, 0), Position::new(2, 0), Position::new(5, 0)]
+    );
+}
+
+#[test]
+fn test_add_cursor_at_next_occurrence_of_word() {
+    use editor::buffer::Position;
+
+    let mut buffer = Buffer::new();
+    buffer.content = vec![
+        "let count = 1;".to_string(),
+        "count += counter;".to_string(),
+        "print(count);".to_string(),
+    ]
+    .into();
+    buffer.cursor_pos = (1, 2);
+
+    // Each press adds the next whole-word occurrence, wrapping past the end
+    assert!(buffer.add_cursor_at_next_occurrence());
+    assert!(buffer.add_cursor_at_next_occurrence());
+    assert!(!buffer.add_cursor_at_next_occurrence());
+    assert_eq!(
+        buffer.secondary_cursors,
+        vec![Position::new(2, 8), Position::new(0, 6)]
+    );
+
+    // Typing replaces the same spot in every occurrence
+    buffer.insert_char('_');
+    assert_eq!(
+        buffer.content,
+        vec!["let co_unt = 1;", "co_unt += counter;", "print(co_unt);"]
+    );
+}
+
+#[tokio::test]
+async fn test_line_statuses_mark_changes_since_load() {
+    use editor::buffer::LineStatus;
+
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("diff.txt");
+    fs::write(&file_path, "one\ntwo\nthree\nfour\n").unwrap();
+    let mut buffer = Buffer::from_path(file_path).unwrap();
+    assert!(buffer.has_diff_base());
+    assert!(buffer.line_statuses().iter().all(Option::is_none));
+
+    // Modify line 2
+    buffer.cursor_pos = (1, 3);
+    buffer.insert_char('!');
+    assert_eq!(buffer.line_status(0), None);
+    assert_eq!(buffer.line_status(1), Some(LineStatus::Modified));
+    assert_eq!(buffer.line_status(2), None);
+
+    // Add a line after it, then remove "four"
+    buffer.insert_newline();
+    buffer.insert_char('x');
+    buffer.cursor_pos = (4, 4);
+    for _ in 0..5 {
+        buffer.backspace();
+    }
+    assert_eq!(
+        buffer.line_statuses().to_vec(),
+        vec![
+            None,
+            Some(LineStatus::Modified),
+            Some(LineStatus::Added),
+            Some(LineStatus::Removed),
+        ]
+    );
+
+    // Saving makes the new content the baseline
+    buffer.save().unwrap();
+    assert!(buffer.line_statuses().iter().all(Option::is_none));
+}
+
+#[tokio::test]
+async fn test_line_statuses_after_edits_match_a_fresh_comparison() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("diff.txt");
+    let text: String = (0..300).map(|i| format!("line {}\n", i % 7)).collect();
+    fs::write(&file_path, text).unwrap();
+    let mut buffer = Buffer::from_path(file_path.clone()).unwrap();
+
+    // Edits far apart, with the statuses asked for between some of them
+    let edits: [(usize, usize, &str); 6] = [
+        (10, 0, "x"),
+        (250, 2, "\n"),
+        (0, 0, "top\n"),
+        (120, 4, "y"),
+        (299, 0, "\n\n"),
+        (11, 0, "line 4"),
+    ];
+    for (i, (row, col, text)) in edits.into_iter().enumerate() {
+        buffer.cursor_pos = (row, col);
+        buffer.insert_str(text);
+        if i % 2 == 0 {
+            buffer.line_statuses();
+        }
+
+        let mut fresh = Buffer::from_path(file_path.clone()).unwrap();
+        fresh.content = buffer.content.clone();
+        assert_eq!(buffer.line_statuses(), fresh.line_statuses(), "after edit {}", i);
+    }
+}
+
+#[tokio::test]
+async fn test_stats_of_an_empty_buffer() {
+    use editor::buffer::BufferStats;
+
+    assert_eq!(Buffer::new().stats(), BufferStats::default());
+}
+
+#[tokio::test]
+async fn test_stats_count_the_whole_buffer() {
+    use editor::buffer::BufferStats;
+
+    let mut buffer = Buffer::new();
+    buffer.content = vec![
+        "fn main() {".to_string(),
+        "    println!(\"héllo\");".to_string(),
+        "}".to_string(),
+    ]
+    .into();
+
+    assert_eq!(
+        buffer.stats(),
+        BufferStats {
+            lines: 3,
+            words: 5,
+            chars: 36,
+            bytes: 37,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_stats_only_count_the_selection() {
+    use editor::buffer::BufferStats;
+
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["one two three".to_string(), "four five".to_string()].into();
+    buffer.selection_start = Some((0, 4));
+    buffer.cursor_pos = (1, 4);
+
+    assert_eq!(
+        buffer.stats(),
+        BufferStats {
+            lines: 2,
+            words: 3,
+            chars: 14,
+            bytes: 14,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_toggle_line_comment_round_trips() {
+    let original = vec!["fn main() {", "", "    let x = 1;", "}"];
+    let mut buffer = Buffer::new();
+    buffer.content = original.iter().map(|line| line.to_string()).collect::<Vec<_>>().into();
+    buffer.selection_start = Some((0, 0));
+    buffer.cursor_pos = (2, 14);
+
+    assert!(buffer.toggle_line_comment("//"));
+    assert_eq!(
+        buffer.content,
+        vec!["// fn main() {", "", "    // let x = 1;", "}"]
+    );
+    assert_eq!(buffer.cursor_pos, (2, 17));
+
+    assert!(buffer.toggle_line_comment("//"));
+    assert_eq!(buffer.content, original);
+    assert_eq!(buffer.cursor_pos, (2, 14));
+}
+
+#[tokio::test]
+async fn test_toggle_line_comment_comments_when_any_line_is_uncommented() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["# done".to_string(), "todo".to_string()].into();
+    buffer.selection_start = Some((0, 0));
+    buffer.cursor_pos = (1, 4);
+
+    assert!(buffer.toggle_line_comment("#"));
+    assert_eq!(buffer.content, vec!["# # done", "# todo"]);
+
+    // Without a selection only the cursor's line changes
+    buffer.clear_selection();
+    assert!(buffer.toggle_line_comment("#"));
+    assert_eq!(buffer.content, vec!["# # done", "todo"]);
+}
+
+#[test]
+fn test_comment_prefix_follows_the_extension() {
+    use editor::syntax::comment_prefix_for_path;
+    use std::path::Path;
+
+    assert_eq!(comment_prefix_for_path(Some(Path::new("main.rs"))), "//");
+    assert_eq!(comment_prefix_for_path(Some(Path::new("build.py"))), "#");
+    assert_eq!(comment_prefix_for_path(Some(Path::new("run.sh"))), "#");
+    assert_eq!(comment_prefix_for_path(Some(Path::new("notes.xyz"))), "//");
+    assert_eq!(comment_prefix_for_path(None), "//");
+}
+
+#[tokio::test]
+async fn test_retab_converts_tabs_to_spaces() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec![
+        "fn main() {".to_string(),
+        "\tlet s = \"a\tb  c\";".to_string(),
+        "\t\tif x {\t}".to_string(),
+        "  \tmixed".to_string(),
+    ]
+    .into();
+    buffer.cursor_pos = (2, 3);
+
+    assert_eq!(buffer.retab(true, 4), 3);
+    assert_eq!(
+        buffer.content,
+        vec![
+            "fn main() {",
+            "    let s = \"a\tb  c\";",
+            "        if x {\t}",
+            "    mixed",
+        ]
+    );
+    assert_eq!(buffer.cursor_pos, (2, 9));
+    assert!(buffer.is_dirty());
+
+    // Already converted, so nothing changes the second time
+    assert_eq!(buffer.retab(true, 4), 0);
+}
+
+#[tokio::test]
+async fn test_retab_converts_spaces_to_tabs() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec![
+        "    a  =  1".to_string(),
+        "          b".to_string(),
+        "c    d".to_string(),
+    ]
+    .into();
+
+    assert_eq!(buffer.retab(false, 4), 2);
+    assert_eq!(buffer.content, vec!["\ta  =  1", "\t\t  b", "c    d"]);
+}
+
+#[tokio::test]
+async fn test_edits_report_whether_and_where_they_changed_the_text() {
+    use editor::buffer::LineChange;
+
+    let mut buffer = Buffer::new();
+    assert!(!buffer.backspace());
+    assert!(!buffer.delete());
+    assert!(!buffer.is_dirty());
+    assert_eq!(buffer.take_change(), None);
+
+    buffer.content = vec!["one".to_string(), "two".to_string(), "three".to_string()].into();
+    buffer.cursor_pos = (1, 3);
+    assert!(buffer.insert_newline());
+    assert_eq!(buffer.take_change(), Some(LineChange::new(1, 1, 2)));
+
+    // Changes not taken yet are merged: joining "three" onto the empty line
+    // and then typing into "one" covers everything from row 0 down
+    buffer.cursor_pos = (3, 0);
+    assert!(buffer.backspace());
+    buffer.cursor_pos = (0, 3);
+    assert!(buffer.insert_char('!'));
+    let change = buffer.take_change().unwrap();
+    assert_eq!(change, LineChange::new(0, 4, 3));
+    assert_eq!(change.lines(), 0..3);
+    assert_eq!(buffer.content, vec!["one!", "two", "three"]);
+    assert_eq!(buffer.take_change(), None);
+}
+
+#[tokio::test]
+async fn test_smart_line_start_toggles_between_indent_and_column_zero() {
+    use editor::buffer::CursorMovement;
+
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["    let x = 1;".to_string(), "   ".to_string()].into();
+    buffer.cursor_pos = (0, 10);
+
+    buffer.move_cursor(CursorMovement::SmartLineStart);
+    assert_eq!(buffer.cursor_pos, (0, 4));
+    buffer.move_cursor(CursorMovement::SmartLineStart);
+    assert_eq!(buffer.cursor_pos, (0, 0));
+    buffer.move_cursor(CursorMovement::SmartLineStart);
+    assert_eq!(buffer.cursor_pos, (0, 4));
+
+    // A blank line's "first non-blank char" is its end
+    buffer.cursor_pos = (1, 1);
+    buffer.move_cursor(CursorMovement::SmartLineStart);
+    assert_eq!(buffer.cursor_pos, (1, 3));
+}
+
+#[tokio::test]
+async fn test_external_writes_mark_the_buffer_changed_on_disk() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("notes.txt");
+    fs::write(&path, "mine").unwrap();
+    let mut buffer = Buffer::from_path_async(path.clone()).await.unwrap();
+    assert!(!buffer.check_disk());
+    assert!(!buffer.changed_on_disk);
+
+    // Another program rewrites the file
+    fs::write(&path, "theirs, and longer").unwrap();
+    assert!(buffer.check_disk());
+    assert!(buffer.changed_on_disk);
+    // Only reported the first time
+    assert!(!buffer.check_disk());
+
+    // Saving makes the file ours again
+    buffer.save_async().await.unwrap();
+    assert!(!buffer.changed_on_disk);
+    assert!(!buffer.check_disk());
+}
+
+#[test]
+fn test_insert_str_splits_lines_at_embedded_newlines() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["hello world".to_string()].into();
+    buffer.cursor_pos = (0, 6);
+
+    assert!(buffer.insert_str("a\nb\nc"));
+    assert_eq!(buffer.content, vec!["hello a", "b", "cworld"]);
+    assert_eq!(buffer.cursor_pos, (2, 1));
+    assert!(buffer.modified);
+    assert_eq!(buffer.take_change().map(|change| change.lines()), Some(0..3));
+
+    // Without a newline it stays on the line, and \r\n counts as one
+    buffer.insert_str("xy");
+    assert_eq!(buffer.content[2], "cxyworld");
+    assert_eq!(buffer.cursor_pos, (2, 3));
+    buffer.insert_str("1\r\n2");
+    assert_eq!(buffer.content, vec!["hello a", "b", "cxy1", "2world"]);
+    assert!(!buffer.insert_str(""));
+}
+
+#[test]
+fn test_delete_to_line_end_removes_the_rest_of_the_line() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["hello world".to_string(), "next".to_string()].into();
+    buffer.cursor_pos = (0, 5);
+
+    assert!(buffer.delete_to_line_end());
+    assert_eq!(buffer.content, vec!["hello", "next"]);
+    assert_eq!(buffer.cursor_pos, (0, 5));
+    assert!(buffer.modified);
+    assert_eq!(buffer.take_change().map(|change| change.lines()), Some(0..1));
+
+    // At the end of a line there's nothing left to delete
+    assert!(!buffer.delete_to_line_end());
+    assert_eq!(buffer.content, vec!["hello", "next"]);
+}
+
+#[test]
+fn test_delete_line_on_the_last_line_moves_the_cursor_up() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["one".to_string(), "two".to_string(), "three".to_string()].into();
+    buffer.cursor_pos = (2, 3);
+
+    assert!(buffer.delete_line());
+    assert_eq!(buffer.content, vec!["one", "two"]);
+    assert_eq!(buffer.cursor_pos, (1, 0));
+    assert!(buffer.modified);
+
+    // Anywhere else the cursor lands on the line that followed
+    buffer.cursor_pos = (0, 2);
+    assert!(buffer.delete_line());
+    assert_eq!(buffer.content, vec!["two"]);
+    assert_eq!(buffer.cursor_pos, (0, 0));
+
+    // The only line is emptied rather than removed
+    assert!(buffer.delete_line());
+    assert_eq!(buffer.content, vec![""]);
+    assert!(!buffer.delete_line());
+}
+
+#[test]
+fn test_transpose_chars_swaps_around_the_cursor_and_moves_past() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["abc".to_string()].into();
+    buffer.cursor_pos = (0, 2);
+
+    assert!(buffer.transpose_chars());
+    assert_eq!(buffer.content, vec!["acb"]);
+    assert_eq!(buffer.cursor_pos, (0, 3));
+    assert!(buffer.modified);
+
+    // At the line end the two chars before the cursor swap
+    assert!(buffer.transpose_chars());
+    assert_eq!(buffer.content, vec!["abc"]);
+    assert_eq!(buffer.cursor_pos, (0, 3));
+
+    // Nothing to swap with at the line start
+    buffer.cursor_pos = (0, 0);
+    assert!(!buffer.transpose_chars());
+}
+
+#[test]
+fn test_transpose_chars_keeps_multibyte_chars_whole() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["é→x".to_string()].into();
+    buffer.cursor_pos = (0, 'é'.len_utf8());
+
+    assert!(buffer.transpose_chars());
+    assert_eq!(buffer.content, vec!["→éx"]);
+    assert_eq!(buffer.cursor_pos, (0, "→é".len()));
+}
+
+#[test]
+fn test_transform_selection_uppercases_and_keeps_it_selected() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["say Hello World now".to_string()].into();
+    buffer.selection_start = Some((0, 4));
+    buffer.cursor_pos = (0, 15);
+
+    assert!(buffer.transform_selection(str::to_uppercase));
+    assert_eq!(buffer.content, vec!["say HELLO WORLD now"]);
+    assert_eq!(buffer.get_selected_text().as_deref(), Some("HELLO WORLD"));
+    assert!(buffer.modified);
+
+    // Already uppercase, so nothing changes
+    assert!(!buffer.transform_selection(str::to_uppercase));
+}
+
+#[test]
+fn test_togglecase_swaps_case_and_follows_length_changes() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["Hello World".to_string()].into();
+    buffer.selection_start = Some((0, 11));
+    buffer.cursor_pos = (0, 0);
+
+    assert!(buffer.transform_selection(toggle_case));
+    assert_eq!(buffer.content, vec!["hELLO wORLD"]);
+    assert_eq!(buffer.selection_start, Some((0, 11)));
+    assert_eq!(buffer.cursor_pos, (0, 0));
+
+    // "ß" uppercases to two chars, and the selection grows with it
+    buffer.content = vec!["straße".to_string()].into();
+    buffer.selection_start = Some((0, 0));
+    buffer.cursor_pos = (0, "straße".len());
+    assert!(buffer.transform_selection(toggle_case));
+    assert_eq!(buffer.get_selected_text().as_deref(), Some("STRASSE"));
+
+    // Without a selection the word at the cursor is toggled
+    buffer.clear_selection();
+    buffer.content = vec!["one Two".to_string()].into();
+    buffer.cursor_pos = (0, 5);
+    assert!(buffer.transform_selection(toggle_case));
+    assert_eq!(buffer.content, vec!["one tWO"]);
+    assert_eq!(buffer.cursor_pos, (0, 5));
+}
+
+#[test]
+fn test_sort_lines_sorts_the_selected_lines_and_selects_them() {
+    let mut buffer = Buffer::new();
+    let lines = ["top", "pear", "apple", "Fig", "bottom"];
+    buffer.content = lines.map(String::from).to_vec().into();
+    buffer.selection_start = Some((1, 0));
+    buffer.cursor_pos = (4, 0);
+
+    assert!(buffer.sort_lines(false, false));
+    assert_eq!(buffer.content, vec!["top", "Fig", "apple", "pear", "bottom"]);
+    assert_eq!(buffer.selection_start, Some((1, 0)));
+    assert_eq!(buffer.cursor_pos, (3, 4));
+    assert!(buffer.modified);
+    assert!(!buffer.sort_lines(false, false));
+
+    // Ignoring case, "Fig" sorts between the others
+    assert!(buffer.sort_lines(false, true));
+    assert_eq!(buffer.content, vec!["top", "apple", "Fig", "pear", "bottom"]);
+}
+
+#[test]
+fn test_dedup_lines_only_removes_adjacent_repeats() {
+    let mut buffer = Buffer::new();
+    buffer.content = ["a", "a", "b", "a"].map(String::from).to_vec().into();
+    buffer.cursor_pos = (1, 1);
+
+    assert_eq!(buffer.dedup_lines(), 1);
+    assert_eq!(buffer.content, vec!["a", "b", "a"]);
+    assert_eq!(buffer.cursor_pos, (0, 1));
+    assert!(buffer.modified);
+    assert_eq!(buffer.dedup_lines(), 0);
+}
+
+#[tokio::test]
+async fn test_saving_unchanged_content_leaves_the_file_alone() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("notes.txt");
+    fs::write(&path, "one\ntwo\n").unwrap();
+    let mtime = || fs::metadata(&path).unwrap().modified().unwrap();
+    let loaded_at = mtime();
+    let mut buffer = Buffer::from_path_async(path.clone()).await.unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+
+    // An edit that's undone by hand leaves nothing to write
+    buffer.insert_char('x');
+    buffer.backspace();
+    assert!(buffer.modified);
+    assert!(!buffer.save_async().await.unwrap());
+    assert!(!buffer.modified);
+    assert_eq!(mtime(), loaded_at);
+
+    // A real change is written
+    buffer.insert_char('x');
+    assert!(buffer.save_async().await.unwrap());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "xone\ntwo\n");
+    assert_ne!(mtime(), loaded_at);
+}
+
+#[test]
+fn test_line_visual_mode_selects_whole_lines_with_their_newline() {
+    use editor::buffer::CursorMovement;
+
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["one".to_string(), "two".to_string(), "three".to_string()].into();
+    buffer.cursor_pos = (0, 1);
+
+    buffer.toggle_line_visual_mode();
+    buffer.move_cursor(CursorMovement::Down);
+    assert_eq!(buffer.get_selected_text(), Some("one\ntwo\n".to_string()));
+
+    buffer.delete_selection();
+    assert_eq!(buffer.content, vec!["three"]);
+    assert_eq!(buffer.cursor_pos, (0, 0));
+
+    // The last line has no newline to take along
+    buffer.toggle_line_visual_mode();
+    assert_eq!(buffer.get_selected_text(), Some("three".to_string()));
+    buffer.toggle_line_visual_mode();
+    assert!(!buffer.visual_mode);
+}
+
+#[test]
+fn test_select_all_covers_the_whole_content() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["one".to_string(), "two".to_string(), "three".to_string()].into();
+    buffer.cursor_pos = (1, 1);
+
+    buffer.select_all();
+    assert!(buffer.visual_mode);
+    assert_eq!(buffer.cursor_pos, (2, 5));
+    assert_eq!(buffer.get_selected_text(), Some("one\ntwo\nthree".to_string()));
+
+    assert!(buffer.delete_selection());
+    assert_eq!(buffer.content, vec![""]);
+    assert_eq!(buffer.cursor_pos, (0, 0));
+}
+
+#[tokio::test]
+async fn test_save_paths_agree_on_the_final_newline() {
+    let temp_dir = TempDir::new().unwrap();
+    for (original, insert_final_newline, expected) in [
+        ("one\ntwo", true, "one\ntwo\n"),
+        ("one\ntwo", false, "one\ntwo"),
+        // A final newline the file already had is kept either way
+        ("one\ntwo\n", false, "one\ntwo\n"),
+        ("one\ntwo\n", true, "one\ntwo\n"),
+    ] {
+        let sync_path = temp_dir.path().join("sync.txt");
+        let async_path = temp_dir.path().join("async.txt");
+        fs::write(&sync_path, original).unwrap();
+        fs::write(&async_path, original).unwrap();
+
+        let mut sync_buffer = Buffer::from_path(sync_path.clone()).unwrap();
+        let mut async_buffer = Buffer::from_path_async(async_path.clone()).await.unwrap();
+        for buffer in [&mut sync_buffer, &mut async_buffer] {
+            buffer.save_options.insert_final_newline = insert_final_newline;
+            buffer.insert_char('x');
+        }
+        sync_buffer.save().unwrap();
+        async_buffer.save_async().await.unwrap();
+
+        let written = fs::read(&sync_path).unwrap();
+        assert_eq!(written, fs::read(&async_path).unwrap());
+        assert_eq!(written, format!("x{}", expected).as_bytes());
+    }
+
+    // An empty buffer stays empty
+    let empty_path = temp_dir.path().join("empty.txt");
+    Buffer::new().save_to_path_async(empty_path.clone()).await.unwrap();
+    assert_eq!(fs::read(&empty_path).unwrap(), b"");
+}
@@ -0,0 +1,102 @@
+//! Integration tests for the event bus
+//!
+//! Tests subscribing, dispatching and unsubscribing event handlers
+
+use editor::events::{AppEvent, EventBus};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Publish a refresh marker and wait until the bus has dispatched it,
+/// which guarantees every event published before it was handled too
+async fn flush(event_bus: &EventBus, flushed: &mut mpsc::UnboundedReceiver<()>) {
+    event_bus.publish(AppEvent::Refresh).unwrap();
+    tokio::time::timeout(Duration::from_secs(1), flushed.recv())
+        .await
+        .expect("event bus did not process events in time");
+}
+
+#[tokio::test]
+async fn test_unsubscribe_stops_handler() {
+    let event_bus = EventBus::new();
+
+    let (flush_tx, mut flush_rx) = mpsc::unbounded_channel();
+    event_bus
+        .subscribe("refresh", move |_| {
+            let _ = flush_tx.send(());
+            Ok(())
+        })
+        .await;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let id = event_bus
+        .subscribe("quit", move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        })
+        .await;
+
+    let processor = event_bus.clone();
+    tokio::spawn(async move { processor.start_processing().await });
+
+    event_bus.publish(AppEvent::Quit).unwrap();
+    flush(&event_bus, &mut flush_rx).await;
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    assert!(event_bus.unsubscribe("quit", id).await);
+
+    event_bus.publish(AppEvent::Quit).unwrap();
+    flush(&event_bus, &mut flush_rx).await;
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    // Unsubscribing twice is a no-op
+    assert!(!event_bus.unsubscribe("quit", id).await);
+}
+
+#[tokio::test]
+async fn test_unsubscribe_from_inside_handler() {
+    let event_bus = EventBus::new();
+
+    let (flush_tx, mut flush_rx) = mpsc::unbounded_channel();
+    event_bus
+        .subscribe("refresh", move |_| {
+            let _ = flush_tx.send(());
+            Ok(())
+        })
+        .await;
+
+    // An async handler that removes itself the first time it runs
+    let calls = Arc::new(AtomicUsize::new(0));
+    let own_id = Arc::new(tokio::sync::Mutex::new(None));
+    let id = event_bus
+        .subscribe_async("quit", {
+            let calls = calls.clone();
+            let own_id = own_id.clone();
+            let bus = event_bus.clone();
+            move |_| {
+                let calls = calls.clone();
+                let own_id = own_id.clone();
+                let bus = bus.clone();
+                async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    if let Some(id) = *own_id.lock().await {
+                        bus.unsubscribe("quit", id).await;
+                    }
+                    Ok(())
+                }
+            }
+        })
+        .await;
+    *own_id.lock().await = Some(id);
+
+    let processor = event_bus.clone();
+    tokio::spawn(async move { processor.start_processing().await });
+
+    event_bus.publish(AppEvent::Quit).unwrap();
+    event_bus.publish(AppEvent::Quit).unwrap();
+    flush(&event_bus, &mut flush_rx).await;
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
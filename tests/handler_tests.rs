@@ -0,0 +1,1766 @@
+//! Integration tests for the keyboard and mouse handlers
+//!
+//! Drives the handlers directly and inspects the events they emit
+
+use std::sync::Arc;
+
+use editor::buffer::{Buffer, Position, SelectionMode};
+use editor::events::AppEvent;
+use editor::handlers::{KeyboardHandler, MouseHandler};
+use editor::{App, CommandMode, UnsavedPrompt};
+use ratatui::crossterm::event::{
+    KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use tokio::sync::{mpsc, RwLock};
+
+/// Keyboard handler over `app`, plus the receiving end of its event channel
+fn keyboard_handler(app: App) -> (KeyboardHandler, mpsc::UnboundedReceiver<AppEvent>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(Arc::new(RwLock::new(app)), sender);
+    (handler, receiver)
+}
+
+/// Send a key press through the handler
+async fn press(handler: &KeyboardHandler, code: KeyCode, modifiers: KeyModifiers) {
+    handler
+        .handle_key_event(AppEvent::KeyInput(KeyEvent::new(code, modifiers)))
+        .await
+        .unwrap();
+}
+
+/// Send a left click at a screen cell through the mouse handler
+async fn click(handler: &MouseHandler, column: u16, row: u16, modifiers: KeyModifiers) {
+    let mouse = MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column,
+        row,
+        modifiers,
+    };
+    handler
+        .handle_mouse_event(AppEvent::MouseInput(mouse))
+        .await
+        .unwrap();
+}
+
+/// Collect every event the handler has emitted so far
+fn drain(receiver: &mut mpsc::UnboundedReceiver<AppEvent>) -> Vec<AppEvent> {
+    std::iter::from_fn(|| receiver.try_recv().ok()).collect()
+}
+
+#[tokio::test]
+async fn test_buffer_events_carry_active_buffer_index() {
+    let app = App {
+        buffers: vec![Buffer::new(), Buffer::new(), Buffer::new()],
+        active_buffer: 2,
+        ..App::default()
+    };
+    let (handler, mut events) = keyboard_handler(app);
+
+    press(&handler, KeyCode::Char('x'), KeyModifiers::NONE).await;
+
+    let events = drain(&mut events);
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, AppEvent::BufferEdited { buffer_id: 2, .. })));
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, AppEvent::BufferCursorMoved { buffer_id: 2, .. })));
+    assert!(!events.iter().any(|event| matches!(
+        event,
+        AppEvent::BufferEdited { buffer_id: 0, .. }
+            | AppEvent::BufferCursorMoved { buffer_id: 0, .. }
+    )));
+}
+
+#[tokio::test]
+async fn test_buffer_ids_are_stable_across_close() {
+    let mut app = App {
+        buffers: vec![Buffer::new(), Buffer::new(), Buffer::new()],
+        ..App::default()
+    };
+    let last_id = app.buffers[2].id;
+    assert_ne!(app.buffers[0].id, app.buffers[1].id);
+
+    app.switch_to_buffer(0);
+    app.close_current_buffer();
+
+    assert_eq!(app.buffer_index_by_id(last_id), Some(1));
+}
+
+#[tokio::test]
+async fn test_quit_with_unsaved_changes_shows_prompt() {
+    let mut app = App {
+        command_mode: CommandMode::Command,
+        command_input: "quit".to_string(),
+        ..App::default()
+    };
+    app.buffers[0].insert_char('x');
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+
+    assert_eq!(
+        app_state.read().await.unsaved_prompt,
+        Some(UnsavedPrompt::Quit)
+    );
+    assert!(app_state.read().await.running);
+    assert!(!drain(&mut events)
+        .iter()
+        .any(|event| matches!(event, AppEvent::Quit)));
+
+    // Cancelling keeps the editor open, discarding quits
+    app_state.write().await.command_mode = CommandMode::Normal;
+    press(&handler, KeyCode::Esc, KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.unsaved_prompt, None);
+    press(&handler, KeyCode::Char('q'), KeyModifiers::CONTROL).await;
+    press(&handler, KeyCode::Char('d'), KeyModifiers::NONE).await;
+    assert!(drain(&mut events)
+        .iter()
+        .any(|event| matches!(event, AppEvent::Quit)));
+}
+
+#[tokio::test]
+async fn test_force_quit_skips_prompt() {
+    let mut app = App {
+        command_mode: CommandMode::Command,
+        command_input: "q!".to_string(),
+        ..App::default()
+    };
+    app.buffers[0].insert_char('x');
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+
+    assert_eq!(app_state.read().await.unsaved_prompt, None);
+    assert!(drain(&mut events)
+        .iter()
+        .any(|event| matches!(event, AppEvent::Quit)));
+}
+
+#[tokio::test]
+async fn test_saveas_writes_untitled_buffer_and_renames_it() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("new/dir/notes.txt");
+
+    let mut app = App {
+        command_mode: CommandMode::Command,
+        command_input: format!("saveas {}", path.display()),
+        ..App::default()
+    };
+    app.buffers[0].content = vec!["hello".to_string(), "world".to_string()].into();
+    app.buffers[0].modified = true;
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello\nworld\n");
+    let app = app_state.read().await;
+    assert_eq!(app.buffers[0].name, "notes.txt");
+    assert_eq!(app.buffers[0].path.as_ref(), Some(&path));
+    assert!(!app.buffers[0].modified);
+}
+
+#[tokio::test]
+async fn test_saveas_needs_force_to_overwrite() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("existing.txt");
+    std::fs::write(&path, "keep me").unwrap();
+
+    let mut app = App {
+        command_mode: CommandMode::Command,
+        command_input: format!("w {}", path.display()),
+        ..App::default()
+    };
+    app.buffers[0].content = vec!["replacement".to_string()].into();
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+    assert_eq!(app_state.read().await.buffers[0].path, None);
+
+    app_state.write().await.command_input = format!("w! {}", path.display());
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "replacement\n");
+}
+
+#[tokio::test]
+async fn test_close_buffer_updates_active_buffer() {
+    let mut first = Buffer::new();
+    first.name = "first.txt".to_string();
+    let mut second = Buffer::new();
+    second.name = "second.txt".to_string();
+    let app = App {
+        buffers: vec![first, second],
+        active_buffer: 1,
+        ..App::default()
+    };
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Char('w'), KeyModifiers::CONTROL).await;
+
+    {
+        let app = app_state.read().await;
+        assert_eq!(app.buffers.len(), 1);
+        assert_eq!(app.active_buffer, 0);
+        assert_eq!(app.buffers[0].name, "first.txt");
+    }
+    assert!(drain(&mut events).iter().any(|event| matches!(
+        event,
+        AppEvent::ToastMessage { message, .. } if message.contains("first.txt")
+    )));
+
+    // The last buffer is swapped for an empty one, after asking if it's dirty
+    app_state.write().await.buffers[0].insert_char('x');
+    press(&handler, KeyCode::Char('w'), KeyModifiers::CONTROL).await;
+    assert_eq!(
+        app_state.read().await.unsaved_prompt,
+        Some(UnsavedPrompt::CloseBuffer)
+    );
+    press(&handler, KeyCode::Char('d'), KeyModifiers::NONE).await;
+
+    let app = app_state.read().await;
+    assert_eq!(app.unsaved_prompt, None);
+    assert_eq!(app.buffers.len(), 1);
+    assert_eq!(app.buffers[0].name, "untitled");
+    assert!(!app.buffers[0].is_dirty());
+}
+
+#[tokio::test]
+async fn test_substitute_command_swaps_capture_groups() {
+    let mut app = App {
+        command_mode: CommandMode::Command,
+        command_input: "%s/(\\w+) (\\w+)/$2 $1/g".to_string(),
+        ..App::default()
+    };
+    app.buffers[0].content = vec!["hello world".to_string(), "one two".to_string()].into();
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+
+    let app = app_state.read().await;
+    assert_eq!(app.buffers[0].content, vec!["world hello", "two one"]);
+    assert!(app.buffers[0].modified);
+}
+
+#[tokio::test]
+async fn test_substitute_command_reports_invalid_regex() {
+    let mut app = App {
+        command_mode: CommandMode::Command,
+        command_input: "%s/(unclosed/x/g".to_string(),
+        ..App::default()
+    };
+    app.buffers[0].content = vec!["(unclosed".to_string()].into();
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+
+    assert!(drain(&mut events).iter().any(|event| matches!(
+        event,
+        AppEvent::ToastMessage { message, toast_type, .. }
+            if message.starts_with("Invalid regex") && toast_type.as_ref() == "error"
+    )));
+    let app = app_state.read().await;
+    assert_eq!(app.buffers[0].content, vec!["(unclosed"]);
+    assert!(!app.buffers[0].modified);
+}
+
+#[tokio::test]
+async fn test_palette_suggests_commands_matching_the_input() {
+    let app = App {
+        command_mode: CommandMode::Command,
+        ..App::default()
+    };
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Char('s'), KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Char('a'), KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.command_suggestions[0], "save");
+
+    // Tab completes the input to the picked suggestion
+    press(&handler, KeyCode::Down, KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Down, KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Tab, KeyModifiers::NONE).await;
+    let app = app_state.read().await;
+    assert_eq!(app.command_input, "saveas");
+    assert_eq!(app.selected_suggestion, None);
+}
+
+#[tokio::test]
+async fn test_palette_recalls_earlier_commands() {
+    let app = App {
+        command_mode: CommandMode::Command,
+        command_input: "open foo".to_string(),
+        ..App::default()
+    };
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    for c in "save".chars() {
+        press(&handler, KeyCode::Char(c), KeyModifiers::NONE).await;
+    }
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    app_state.write().await.command_input = "save".to_string();
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.command_history, ["open foo", "save"]);
+
+    press(&handler, KeyCode::Up, KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.command_input, "save");
+    press(&handler, KeyCode::Up, KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.command_input, "open foo");
+
+    press(&handler, KeyCode::Down, KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Down, KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.command_input, "");
+}
+
+#[tokio::test]
+async fn test_search_all_buffers_jumps_to_the_picked_match() {
+    use editor::commands::buffer_match_rows;
+
+    let mut first = Buffer::new();
+    first.content = vec!["nothing here".to_string()].into();
+    let mut second = Buffer::new();
+    second.name = "second.txt".to_string();
+    second.content = vec!["alpha".to_string(), "  the needle".to_string()].into();
+    let app = App {
+        buffers: vec![first, second],
+        command_mode: CommandMode::Command,
+        ..App::default()
+    };
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    for c in "search_all_buffers needle".chars() {
+        press(&handler, KeyCode::Char(c), KeyModifiers::NONE).await;
+    }
+    {
+        let app = app_state.read().await;
+        let (rows, match_rows) = buffer_match_rows(&app.buffers, &app.buffer_matches);
+        assert_eq!(rows, ["second.txt", "  2: the needle"]);
+        assert_eq!(match_rows, [1]);
+    }
+
+    press(&handler, KeyCode::Down, KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    let app = app_state.read().await;
+    assert_eq!(app.active_buffer, 1);
+    assert_eq!(app.buffers[1].cursor_pos, (1, 6));
+}
+
+#[test]
+fn test_search_all_buffers_stops_once_the_list_is_full() {
+    use editor::commands::{buffer_match_rows, search_all_buffers, MAX_BUFFER_MATCHES};
+
+    let mut first = Buffer::new();
+    first.name = "first.txt".to_string();
+    first.content = vec!["x".to_string(); 3].into();
+    let mut second = Buffer::new();
+    second.name = "second.txt".to_string();
+    second.content = vec!["x".to_string(); 100_000].into();
+    let buffers = [first, second];
+
+    let matches = search_all_buffers(&buffers, "x");
+    let (rows, match_rows) = buffer_match_rows(&buffers, &matches);
+    assert_eq!(rows.len(), MAX_BUFFER_MATCHES);
+    assert_eq!(matches.len(), MAX_BUFFER_MATCHES - 2);
+    assert_eq!(rows[0], "first.txt");
+    assert_eq!(rows[4], "second.txt");
+    assert_eq!(match_rows[..4], [1, 2, 3, 5]);
+}
+
+#[tokio::test]
+async fn test_double_click_selects_word_and_triple_click_the_line() {
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock = {
+        let now = now.clone();
+        Arc::new(move || *now.lock().unwrap())
+    };
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["hello world".to_string(), "next".to_string()].into();
+    let app = App {
+        buffers: vec![buffer],
+        clock,
+        ..App::default()
+    };
+
+    // Screen cell over the "o" of "world"
+    let editor = app.layout(editor::input::terminal_area()).editor;
+    let column = editor.x + app.gutter_width() as u16 + 7;
+    let row = editor.y;
+
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = MouseHandler::new(app_state.clone(), sender);
+    let click_word = || click(&handler, column, row, KeyModifiers::NONE);
+
+    click_word().await;
+    *now.lock().unwrap() += Duration::from_millis(100);
+    click_word().await;
+    {
+        let app = app_state.read().await;
+        assert_eq!(app.buffers[0].get_selected_text().as_deref(), Some("world"));
+        assert_eq!(app.buffers[0].cursor_pos, (0, 11));
+    }
+
+    click_word().await;
+    assert_eq!(
+        app_state.read().await.buffers[0]
+            .get_selected_text()
+            .as_deref(),
+        Some("hello world\n")
+    );
+
+    // Too slow for another multi-click: just places the cursor
+    *now.lock().unwrap() += Duration::from_secs(1);
+    click_word().await;
+    let app = app_state.read().await;
+    assert_eq!(app.buffers[0].get_selected_text(), None);
+    assert_eq!(app.buffers[0].cursor_pos, (0, 7));
+}
+
+#[tokio::test]
+async fn test_shift_click_extends_selection_from_the_earlier_click() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["hello world".to_string(), "next".to_string()].into();
+    let app = App {
+        buffers: vec![buffer],
+        ..App::default()
+    };
+    let editor = app.layout(editor::input::terminal_area()).editor;
+    let text_x = editor.x + app.gutter_width() as u16;
+
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = MouseHandler::new(app_state.clone(), sender);
+
+    click(&handler, text_x + 2, editor.y, KeyModifiers::NONE).await;
+    click(&handler, text_x + 3, editor.y + 1, KeyModifiers::SHIFT).await;
+    {
+        let app = app_state.read().await;
+        assert_eq!(
+            app.buffers[0]
+                .get_selection_range()
+                .map(|(s, e)| (s.to_tuple(), e.to_tuple())),
+            Some(((0, 2), (1, 3)))
+        );
+        assert_eq!(
+            app.buffers[0].get_selected_text().as_deref(),
+            Some("llo world\nnex")
+        );
+    }
+
+    // Another Shift+click moves the end, keeping the anchor
+    click(&handler, text_x + 8, editor.y, KeyModifiers::SHIFT).await;
+    let app = app_state.read().await;
+    assert_eq!(
+        app.buffers[0].get_selected_text().as_deref(),
+        Some("llo wo")
+    );
+}
+
+#[tokio::test]
+async fn test_gutter_click_selects_the_whole_line() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["first".to_string(), "second".to_string(), "third".to_string()].into();
+    buffer.cursor_pos = (0, 3);
+    let app = App {
+        buffers: vec![buffer],
+        ..App::default()
+    };
+    let editor = app.layout(editor::input::terminal_area()).editor;
+
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = MouseHandler::new(app_state.clone(), sender);
+
+    click(&handler, editor.x, editor.y + 1, KeyModifiers::NONE).await;
+    let app = app_state.read().await;
+    let buffer = &app.buffers[0];
+    assert_eq!(buffer.selection_mode, SelectionMode::Line);
+    assert_eq!(buffer.get_selected_text().as_deref(), Some("second\n"));
+    assert_eq!(buffer.cursor_pos, (1, 0));
+}
+
+#[tokio::test]
+async fn test_gutter_click_selects_the_line_shown_there_when_wrapped() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["x".repeat(1000), "second".to_string(), "third".to_string()].into();
+    let mut app = App {
+        buffers: vec![buffer],
+        ..App::default()
+    };
+    app.config.editor.word_wrap = true;
+    let editor = app.layout(editor::input::terminal_area()).editor;
+    let text_width = editor.width as usize - app.gutter_width();
+    let first_rows = app.buffers[0].visual_rows(0, text_width, 4) as u16;
+    assert!(first_rows > 1);
+
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = MouseHandler::new(app_state.clone(), sender);
+
+    // The row below the wrapped first line is the second line's number
+    click(&handler, editor.x, editor.y + first_rows, KeyModifiers::NONE).await;
+    let app = app_state.read().await;
+    let buffer = &app.buffers[0];
+    assert_eq!(buffer.get_selected_text().as_deref(), Some("second\n"));
+    assert_eq!(buffer.cursor_pos, (1, 0));
+}
+
+#[tokio::test]
+async fn test_shift_scroll_moves_the_view_sideways() {
+    let mut buffer = Buffer::new();
+    let long_line: String = ('a'..='z').cycle().take(400).collect();
+    buffer.content = vec![long_line; 100].into();
+    let app = App {
+        buffers: vec![buffer],
+        ..App::default()
+    };
+    let editor = app.layout(editor::input::terminal_area()).editor;
+    let text_x = editor.x + app.gutter_width() as u16;
+
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = MouseHandler::new(app_state.clone(), sender);
+    let scroll = |kind, modifiers| {
+        let mouse = MouseEvent {
+            kind,
+            column: text_x,
+            row: editor.y,
+            modifiers,
+        };
+        handler.handle_mouse_event(AppEvent::MouseInput(mouse))
+    };
+
+    scroll(MouseEventKind::ScrollDown, KeyModifiers::SHIFT)
+        .await
+        .unwrap();
+    assert_eq!(app_state.read().await.scroll_offset, (0, 8));
+
+    // Clicks land on the scrolled-to text
+    click(&handler, text_x + 2, editor.y, KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.buffers[0].cursor_pos, (0, 10));
+
+    scroll(MouseEventKind::ScrollUp, KeyModifiers::SHIFT)
+        .await
+        .unwrap();
+    scroll(MouseEventKind::ScrollDown, KeyModifiers::NONE)
+        .await
+        .unwrap();
+    assert_eq!(app_state.read().await.scroll_offset, (3, 0));
+}
+
+#[tokio::test]
+async fn test_tab_indents_and_shift_tab_unindents_selected_lines() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec![
+        "one".to_string(),
+        "  two".to_string(),
+        "three".to_string(),
+        "four".to_string(),
+    ]
+    .into();
+    buffer.selection_start = Some((0, 0));
+    buffer.cursor_pos = (2, 5);
+    let app = App {
+        buffers: vec![buffer, Buffer::new()],
+        ..App::default()
+    };
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Tab, KeyModifiers::NONE).await;
+    {
+        let app = app_state.read().await;
+        assert_eq!(app.active_buffer, 0);
+        let buffer = &app.buffers[0];
+        assert_eq!(buffer.content, vec!["    one", "      two", "    three", "four"]);
+        assert_eq!(buffer.selection_start, Some((0, 0)));
+        assert_eq!(buffer.cursor_pos, (2, 9));
+        assert_eq!(
+            buffer.get_selected_text().unwrap(),
+            "    one\n      two\n    three"
+        );
+    }
+
+    // Unindenting twice takes "two" back past its own indentation, no further
+    press(&handler, KeyCode::BackTab, KeyModifiers::SHIFT).await;
+    press(&handler, KeyCode::BackTab, KeyModifiers::SHIFT).await;
+    {
+        let app = app_state.read().await;
+        let buffer = &app.buffers[0];
+        assert_eq!(buffer.content, vec!["one", "two", "three", "four"]);
+        assert_eq!(buffer.cursor_pos, (2, 5));
+    }
+
+    // Without a multi-line selection Tab still switches buffers
+    app_state.write().await.buffers[0].clear_selection();
+    press(&handler, KeyCode::Tab, KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.active_buffer, 1);
+}
+
+#[tokio::test]
+async fn test_opened_files_are_listed_most_recent_first() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let dir = temp_dir.path().canonicalize().unwrap();
+    let paths: Vec<_> = ["a.txt", "b.txt", "c.txt"]
+        .iter()
+        .map(|name| {
+            let path = dir.join(name);
+            std::fs::write(&path, *name).unwrap();
+            path
+        })
+        .collect();
+    let app = App {
+        user_dir: dir.clone(),
+        command_mode: CommandMode::Command,
+        ..App::default()
+    };
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+    let run = |command: String| {
+        let handler = &handler;
+        async move {
+            for c in command.chars() {
+                press(handler, KeyCode::Char(c), KeyModifiers::NONE).await;
+            }
+            press(handler, KeyCode::Enter, KeyModifiers::NONE).await;
+        }
+    };
+
+    for path in &paths {
+        run(format!("open {}", path.display())).await;
+    }
+    assert_eq!(
+        app_state.read().await.recent_files,
+        [paths[2].clone(), paths[1].clone(), paths[0].clone()]
+    );
+
+    // Reopening an earlier file moves it to the front instead of repeating it
+    run(format!("open {}", paths[0].display())).await;
+    assert_eq!(
+        app_state.read().await.recent_files,
+        [paths[0].clone(), paths[2].clone(), paths[1].clone()]
+    );
+    assert_eq!(
+        editor::recent::load_recent_files(&dir).unwrap(),
+        app_state.read().await.recent_files
+    );
+
+    // `open recent` lists them and switches to the picked one
+    for c in "open recent".chars() {
+        press(&handler, KeyCode::Char(c), KeyModifiers::NONE).await;
+    }
+    assert_eq!(app_state.read().await.recent_matches.len(), 3);
+    press(&handler, KeyCode::Down, KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Down, KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    let app = app_state.read().await;
+    assert_eq!(app.buffers[app.active_buffer].path.as_ref(), Some(&paths[2]));
+}
+
+#[tokio::test]
+async fn test_wheel_scrolls_the_configured_lines_per_tick() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec![String::new(); 500].into();
+    let mut app = App {
+        buffers: vec![buffer],
+        ..App::default()
+    };
+    app.config.ui.scroll_lines_per_tick = 5;
+    let editor = app.layout(editor::input::terminal_area()).editor;
+
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = MouseHandler::new(app_state.clone(), sender);
+    let scroll = |kind, modifiers| {
+        let mouse = MouseEvent {
+            kind,
+            column: editor.x,
+            row: editor.y,
+            modifiers,
+        };
+        handler.handle_mouse_event(AppEvent::MouseInput(mouse))
+    };
+
+    scroll(MouseEventKind::ScrollDown, KeyModifiers::NONE)
+        .await
+        .unwrap();
+    scroll(MouseEventKind::ScrollDown, KeyModifiers::NONE)
+        .await
+        .unwrap();
+    assert_eq!(app_state.read().await.scroll_offset.0, 10);
+    scroll(MouseEventKind::ScrollUp, KeyModifiers::NONE)
+        .await
+        .unwrap();
+    assert_eq!(app_state.read().await.scroll_offset.0, 5);
+
+    // Ctrl scrolls a whole page of the editor
+    scroll(MouseEventKind::ScrollDown, KeyModifiers::CONTROL)
+        .await
+        .unwrap();
+    assert_eq!(
+        app_state.read().await.scroll_offset.0,
+        5 + editor.height as usize
+    );
+}
+
+#[tokio::test]
+async fn test_only_edits_that_change_the_text_emit_buffer_edited() {
+    let (handler, mut events) = keyboard_handler(App::default());
+
+    // Nothing before the start of the buffer to delete
+    press(&handler, KeyCode::Backspace, KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Delete, KeyModifiers::NONE).await;
+    assert!(!drain(&mut events)
+        .iter()
+        .any(|event| matches!(event, AppEvent::BufferEdited { .. })));
+}
+
+/// Every `BufferEdited` event emitted so far, as (start, old count, new count)
+fn edits(receiver: &mut mpsc::UnboundedReceiver<AppEvent>) -> Vec<(usize, usize, usize)> {
+    drain(receiver)
+        .into_iter()
+        .filter_map(|event| match event {
+            AppEvent::BufferEdited {
+                start_line,
+                old_line_count,
+                new_line_count,
+                ..
+            } => Some((start_line, old_line_count, new_line_count)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_edits_report_the_lines_they_replaced() {
+    let mut buffer = Buffer::new();
+    buffer.content = (0..10).map(|i| format!("line {}", i)).collect();
+    buffer.cursor_pos = (5, 4);
+    let app = App {
+        buffers: vec![buffer],
+        ..App::default()
+    };
+    let (handler, mut events) = keyboard_handler(app);
+
+    press(&handler, KeyCode::Char('!'), KeyModifiers::NONE).await;
+    assert_eq!(edits(&mut events), vec![(5, 1, 1)]);
+
+    // Splitting line 5 turns it into two lines
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    assert_eq!(edits(&mut events), vec![(5, 1, 2)]);
+
+    // Joining them back turns two lines into one
+    press(&handler, KeyCode::Backspace, KeyModifiers::NONE).await;
+    assert_eq!(edits(&mut events), vec![(5, 2, 1)]);
+}
+
+#[tokio::test]
+async fn test_home_goes_to_the_indent_first_and_then_column_zero() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["\t  indented".to_string()].into();
+    buffer.cursor_pos = (0, 8);
+    let app_state = Arc::new(RwLock::new(App {
+        buffers: vec![buffer],
+        ..App::default()
+    }));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Home, KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.buffers[0].cursor_pos, (0, 3));
+    press(&handler, KeyCode::Home, KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.buffers[0].cursor_pos, (0, 0));
+}
+
+/// Every `BufferModifiedChanged` event emitted so far, as (buffer, modified)
+fn modified_changes(receiver: &mut mpsc::UnboundedReceiver<AppEvent>) -> Vec<(usize, bool)> {
+    drain(receiver)
+        .into_iter()
+        .filter_map(|event| match event {
+            AppEvent::BufferModifiedChanged {
+                buffer_id,
+                modified,
+            } => Some((buffer_id, modified)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_saving_a_dirty_buffer_reports_it_unmodified() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("notes.txt");
+    let mut buffer = Buffer::new();
+    buffer.path = Some(path.clone());
+    let app = App {
+        buffers: vec![buffer],
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    };
+    let (handler, mut events) = keyboard_handler(app);
+
+    // Only the first edit turns the buffer modified
+    press(&handler, KeyCode::Char('a'), KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Char('b'), KeyModifiers::NONE).await;
+    assert_eq!(modified_changes(&mut events), vec![(0, true)]);
+
+    press(&handler, KeyCode::Char('s'), KeyModifiers::CONTROL).await;
+    assert_eq!(modified_changes(&mut events), vec![(0, false)]);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "ab\n");
+
+    // Saving again changes nothing
+    press(&handler, KeyCode::Char('s'), KeyModifiers::CONTROL).await;
+    assert!(modified_changes(&mut events).is_empty());
+}
+
+#[tokio::test]
+async fn test_ctrl_s_saves_the_buffer_and_clears_modified() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("notes.txt");
+    std::fs::write(&path, "old").unwrap();
+    let mut buffer = Buffer::from_path(path.clone()).unwrap();
+    buffer.content = vec!["first".to_string(), "second".to_string()].into();
+    buffer.modified = true;
+    let app_state = Arc::new(RwLock::new(App {
+        buffers: vec![buffer],
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    }));
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Char('s'), KeyModifiers::CONTROL).await;
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+    assert!(!app_state.read().await.buffers[0].modified);
+    assert!(drain(&mut events).iter().any(|event| matches!(
+        event,
+        AppEvent::ToastMessage { toast_type, .. } if &**toast_type == "success"
+    )));
+}
+
+#[tokio::test]
+async fn test_leader_sequences_run_their_binding_and_a_lone_leader_types_a_space() {
+    use std::sync::Mutex;
+    use std::time::Instant;
+
+    use editor::input::chords::CHORD_TIMEOUT;
+
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let mut app = App {
+        clock: {
+            let now = now.clone();
+            Arc::new(move || *now.lock().unwrap())
+        },
+        ..App::default()
+    };
+    app.config
+        .keybindings
+        .insert("space f".to_string(), "open ".to_string());
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    // Space then f opens the palette to pick a file
+    press(&handler, KeyCode::Char(' '), KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Char('f'), KeyModifiers::NONE).await;
+    {
+        let mut app = app_state.write().await;
+        assert_eq!(app.command_mode, CommandMode::Command);
+        assert_eq!(app.command_input, "open ");
+        assert_eq!(app.active_content(), "");
+        app.command_mode = CommandMode::Normal;
+    }
+
+    // Space alone waits for the rest of the sequence, then types a space.
+    // The timer runs with the app's other tasks, to be aborted on quit.
+    press(&handler, KeyCode::Char(' '), KeyModifiers::NONE).await;
+    let app = app_state.read().await;
+    assert!(app.background_tasks.running().contains(&"chord timeout"));
+    drop(app);
+    handler.handle_key_event(AppEvent::ChordTimeout).await.unwrap();
+    assert_eq!(app_state.read().await.active_content(), "");
+    *now.lock().unwrap() += CHORD_TIMEOUT;
+    handler.handle_key_event(AppEvent::ChordTimeout).await.unwrap();
+    assert_eq!(app_state.read().await.active_content(), " ");
+
+    // Space then a key no sequence continues with types both
+    press(&handler, KeyCode::Char(' '), KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Char('x'), KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.active_content(), "  x");
+}
+
+#[tokio::test]
+async fn test_ctrl_w_w_focuses_the_next_pane_and_ctrl_w_alone_closes() {
+    use std::sync::Mutex;
+    use std::time::Instant;
+
+    use editor::input::chords::CHORD_TIMEOUT;
+
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let mut first = Buffer::new();
+    first.name = "first.txt".to_string();
+    let mut second = Buffer::new();
+    second.name = "second.txt".to_string();
+    let mut app = App {
+        buffers: vec![first, second],
+        clock: {
+            let now = now.clone();
+            Arc::new(move || *now.lock().unwrap())
+        },
+        ..App::default()
+    };
+    app.split_horizontal();
+    assert_eq!(app.focused_pane, 1);
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Char('w'), KeyModifiers::CONTROL).await;
+    press(&handler, KeyCode::Char('w'), KeyModifiers::NONE).await;
+    {
+        let app = app_state.read().await;
+        assert_eq!(app.focused_pane, 0);
+        assert_eq!(app.buffers.len(), 2);
+        assert_eq!(app.active_content(), "");
+    }
+
+    // Ctrl+W on its own still closes the buffer once nothing follows it
+    press(&handler, KeyCode::Char('w'), KeyModifiers::CONTROL).await;
+    assert_eq!(app_state.read().await.buffers.len(), 2);
+    *now.lock().unwrap() += CHORD_TIMEOUT;
+    handler.handle_key_event(AppEvent::ChordTimeout).await.unwrap();
+    assert_eq!(app_state.read().await.buffers.len(), 1);
+}
+
+#[tokio::test]
+async fn test_reload_restores_the_file_and_clears_modified() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("notes.txt");
+    std::fs::write(&path, "one\ntwo").unwrap();
+    let mut buffer = Buffer::from_path(path.clone()).unwrap();
+    buffer.cursor_pos = (1, 3);
+    let app_state = Arc::new(RwLock::new(App {
+        buffers: vec![buffer],
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    }));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+    let run = |command: &str| {
+        let app_state = app_state.clone();
+        let command = command.to_string();
+        let handler = &handler;
+        async move {
+            {
+                let mut app = app_state.write().await;
+                app.command_mode = CommandMode::Command;
+                app.command_input = command;
+            }
+            press(handler, KeyCode::Enter, KeyModifiers::NONE).await;
+        }
+    };
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Char('x'), KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.active_content(), "one\ntwo\nx");
+
+    // Unsaved changes are only thrown away once confirmed
+    run("reload").await;
+    let prompt = app_state.read().await.unsaved_prompt;
+    assert_eq!(prompt, Some(UnsavedPrompt::Reload));
+    press(&handler, KeyCode::Char('d'), KeyModifiers::NONE).await;
+    {
+        let app = app_state.read().await;
+        assert_eq!(app.active_content(), "one\ntwo");
+        assert!(!app.buffers[0].modified);
+        // The cursor was on the dropped line, so it's pulled back onto the last
+        assert_eq!(app.buffers[0].cursor_pos, (1, 1));
+    }
+
+    // The forced form doesn't ask
+    press(&handler, KeyCode::Char('!'), KeyModifiers::NONE).await;
+    run("e!").await;
+    let app = app_state.read().await;
+    assert_eq!(app.unsaved_prompt, None);
+    assert_eq!(app.active_content(), "one\ntwo");
+    assert!(!app.buffers[0].modified);
+}
+
+#[tokio::test]
+async fn test_saving_over_an_externally_changed_file_asks_first() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("notes.txt");
+    std::fs::write(&path, "mine").unwrap();
+    let app_state = Arc::new(RwLock::new(App {
+        buffers: vec![Buffer::from_path(path.clone()).unwrap()],
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    }));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    std::fs::write(&path, "theirs, and longer").unwrap();
+    press(&handler, KeyCode::Char('s'), KeyModifiers::CONTROL).await;
+    assert_eq!(
+        app_state.read().await.unsaved_prompt,
+        Some(UnsavedPrompt::Overwrite)
+    );
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "theirs, and longer");
+
+    // Confirming writes over it
+    press(&handler, KeyCode::Char('s'), KeyModifiers::NONE).await;
+    let app = app_state.read().await;
+    assert_eq!(app.unsaved_prompt, None);
+    assert!(!app.buffers[0].changed_on_disk);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "mine\n");
+}
+
+#[tokio::test]
+async fn test_quit_save_and_saveas_ask_before_saving_over_a_changed_file() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let first = temp_dir.path().join("first.txt");
+    let second = temp_dir.path().join("second.txt");
+    std::fs::write(&first, "one").unwrap();
+    std::fs::write(&second, "two").unwrap();
+    let mut buffers = vec![
+        Buffer::from_path(first.clone()).unwrap(),
+        Buffer::from_path(second.clone()).unwrap(),
+    ];
+    buffers[0].insert_char('x');
+    buffers[1].insert_char('y');
+    let app_state = Arc::new(RwLock::new(App {
+        buffers,
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    }));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    // Saving everything to quit stops at the changed file
+    std::fs::write(&second, "theirs, and longer").unwrap();
+    press(&handler, KeyCode::Char('q'), KeyModifiers::CONTROL).await;
+    press(&handler, KeyCode::Char('s'), KeyModifiers::NONE).await;
+    {
+        let app = app_state.read().await;
+        assert_eq!(app.unsaved_prompt, Some(UnsavedPrompt::Overwrite));
+        assert_eq!(app.active_buffer, 1);
+        assert!(app.running);
+    }
+    assert_eq!(std::fs::read_to_string(&second).unwrap(), "theirs, and longer");
+    assert_eq!(std::fs::read_to_string(&first).unwrap(), "one");
+
+    // So does saving as the buffer's own file, unless forced
+    press(&handler, KeyCode::Esc, KeyModifiers::NONE).await;
+    let saveas = |command: String| {
+        let app_state = app_state.clone();
+        let handler = &handler;
+        async move {
+            let mut app = app_state.write().await;
+            app.command_mode = CommandMode::Command;
+            app.command_input = command;
+            drop(app);
+            press(handler, KeyCode::Enter, KeyModifiers::NONE).await;
+        }
+    };
+    saveas(format!("saveas {}", second.display())).await;
+    assert_eq!(
+        app_state.read().await.unsaved_prompt,
+        Some(UnsavedPrompt::Overwrite)
+    );
+    assert_eq!(std::fs::read_to_string(&second).unwrap(), "theirs, and longer");
+
+    press(&handler, KeyCode::Esc, KeyModifiers::NONE).await;
+    saveas(format!("saveas! {}", second.display())).await;
+    assert_eq!(app_state.read().await.unsaved_prompt, None);
+    assert_eq!(std::fs::read_to_string(&second).unwrap(), "ytwo\n");
+}
+
+#[tokio::test]
+async fn test_page_down_moves_by_the_height_of_the_editor() {
+    use ratatui::layout::Rect;
+
+    let mut buffer = Buffer::new();
+    buffer.content = (0..100).map(|i| format!("line {}", i)).collect();
+    buffer.content[85] = "x".to_string();
+    buffer.cursor_pos = (5, 6);
+    let app_state = Arc::new(RwLock::new(App {
+        buffers: vec![buffer],
+        last_editor_area: Some(Rect::new(0, 1, 80, 40)),
+        ..App::default()
+    }));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::PageDown, KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.buffers[0].cursor_pos, (45, 6));
+
+    // The column is kept within the shorter line landed on
+    press(&handler, KeyCode::PageDown, KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.buffers[0].cursor_pos, (85, 1));
+    press(&handler, KeyCode::PageDown, KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.buffers[0].cursor_pos, (99, 1));
+    press(&handler, KeyCode::PageUp, KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.buffers[0].cursor_pos, (59, 1));
+}
+
+#[tokio::test]
+async fn test_clicks_past_the_end_of_a_line_select_up_to_it() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["hello world".to_string(), "next".to_string()].into();
+    let app = App {
+        buffers: vec![buffer],
+        ..App::default()
+    };
+    let editor = app.layout(editor::input::terminal_area()).editor;
+    let text_x = editor.x + app.gutter_width() as u16;
+
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = MouseHandler::new(app_state.clone(), sender);
+
+    // Shift+click far right of a short line selects to its end
+    click(&handler, text_x + 6, editor.y, KeyModifiers::NONE).await;
+    click(&handler, text_x + 30, editor.y + 1, KeyModifiers::SHIFT).await;
+    assert_eq!(
+        app_state.read().await.buffers[0]
+            .get_selected_text()
+            .as_deref(),
+        Some("world\nnext")
+    );
+
+    // A double click there has no word to select and just places the cursor
+    click(&handler, text_x + 30, editor.y, KeyModifiers::NONE).await;
+    click(&handler, text_x + 30, editor.y, KeyModifiers::NONE).await;
+    let app = app_state.read().await;
+    assert_eq!(app.buffers[0].get_selected_text(), None);
+    assert_eq!(app.buffers[0].cursor_pos, (0, 11));
+}
+
+#[tokio::test]
+async fn test_sort_command_with_the_reverse_flag_sorts_descending() {
+    let mut app = App {
+        command_mode: CommandMode::Command,
+        command_input: "sort -r".to_string(),
+        ..App::default()
+    };
+    app.buffers[0].content = vec!["b".to_string(), "c".to_string(), "a".to_string()].into();
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+
+    let app = app_state.read().await;
+    assert_eq!(app.buffers[0].content, vec!["c", "b", "a"]);
+    assert!(app.buffers[0].modified);
+}
+
+#[tokio::test]
+async fn test_buffer_command_switches_to_the_numbered_buffer() {
+    let app = App {
+        command_mode: CommandMode::Command,
+        command_input: "buffer 2".to_string(),
+        buffers: vec![Buffer::new(), Buffer::new(), Buffer::new()],
+        ..App::default()
+    };
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    assert_eq!(app_state.read().await.active_buffer, 1);
+
+    // Alt+3 jumps straight to the third, and there's no fourth. Leaving the
+    // palette goes through the app state handler, so do it here.
+    app_state.write().await.command_mode = CommandMode::Normal;
+    press(&handler, KeyCode::Char('3'), KeyModifiers::ALT).await;
+    assert_eq!(app_state.read().await.active_buffer, 2);
+    drain(&mut events);
+    press(&handler, KeyCode::Char('4'), KeyModifiers::ALT).await;
+    assert_eq!(app_state.read().await.active_buffer, 2);
+    assert!(drain(&mut events).iter().any(|event| matches!(
+        event,
+        AppEvent::ToastMessage { toast_type, .. } if toast_type.as_ref() == "error"
+    )));
+}
+
+#[tokio::test]
+async fn test_reopening_a_closed_file_restores_its_cursor_line() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("long.txt");
+    let lines: Vec<String> = (1..=20).map(|n| format!("line {}", n)).collect();
+    std::fs::write(&path, lines.join("\n")).unwrap();
+    let mut buffer = Buffer::from_path(path.clone()).unwrap();
+    buffer.cursor_pos = (9, 3);
+    let app_state = Arc::new(RwLock::new(App {
+        buffers: vec![buffer],
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    }));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+    let reopen = || async {
+        let mut app = app_state.write().await;
+        app.command_mode = CommandMode::Command;
+        app.command_input = format!("open {}", path.display());
+        drop(app);
+        press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+        let app = app_state.read().await;
+        app.buffers[app.active_buffer].cursor_pos
+    };
+
+    press(&handler, KeyCode::Char('w'), KeyModifiers::CONTROL).await;
+    assert_eq!(app_state.read().await.buffers[0].path, None);
+    assert_eq!(reopen().await, (9, 3));
+    assert!(temp_dir.path().join("cursor_positions.json").exists());
+
+    // A file that got shorter puts the cursor on its last line instead
+    app_state.write().await.command_mode = CommandMode::Normal;
+    press(&handler, KeyCode::Char('w'), KeyModifiers::CONTROL).await;
+    std::fs::write(&path, "one\ntwo").unwrap();
+    assert_eq!(reopen().await, (1, 3));
+}
+
+#[tokio::test]
+async fn test_saving_remembers_the_cursor_position() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("saved.txt");
+    std::fs::write(&path, "one\ntwo\nthree").unwrap();
+    let mut buffer = Buffer::from_path(path.clone()).unwrap();
+    buffer.cursor_pos = (2, 1);
+    buffer.modified = true;
+    let app_state = Arc::new(RwLock::new(App {
+        buffers: vec![buffer],
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    }));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Char('s'), KeyModifiers::CONTROL).await;
+
+    let path = path.canonicalize().unwrap();
+    let app = app_state.read().await;
+    assert_eq!(app.cursor_positions, vec![(path.clone(), Position::new(2, 1))]);
+    let positions_file = temp_dir.path().join("cursor_positions.json");
+    let written = std::fs::read_to_string(positions_file).unwrap();
+    assert!(written.contains(&*path.to_string_lossy()));
+}
+
+#[tokio::test]
+async fn test_configured_palette_key_replaces_alt_p() {
+    let mut app = App::default();
+    app.config.ui.command_palette_key = "ctrl+k".to_string();
+    app.buffers[0].content = vec!["keep me".to_string()].into();
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+    let opens_palette =
+        |events: Vec<AppEvent>| events.iter().any(|e| matches!(e, AppEvent::ShowCommandPalette));
+
+    press(&handler, KeyCode::Char('p'), KeyModifiers::ALT).await;
+    assert!(!opens_palette(drain(&mut events)));
+
+    // Ctrl+K opens the palette instead of deleting to the line end
+    press(&handler, KeyCode::Char('k'), KeyModifiers::CONTROL).await;
+    assert!(opens_palette(drain(&mut events)));
+    assert_eq!(app_state.read().await.buffers[0].content, vec!["keep me"]);
+}
+
+#[tokio::test]
+async fn test_dragging_to_the_bottom_row_scrolls_and_extends_the_selection() {
+    let mut buffer = Buffer::new();
+    buffer.content = (0..100).map(|i| format!("line {}", i)).collect::<Vec<_>>().into();
+    let app = App {
+        buffers: vec![buffer],
+        ..App::default()
+    };
+    let editor = app.layout(editor::input::terminal_area()).editor;
+    let text_x = editor.x + app.gutter_width() as u16;
+    let bottom = editor.y + editor.height - 1;
+
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = MouseHandler::new(app_state.clone(), sender);
+
+    click(&handler, text_x, editor.y + 1, KeyModifiers::NONE).await;
+    for _ in 0..3 {
+        let drag = MouseEvent {
+            kind: MouseEventKind::Drag(MouseButton::Left),
+            column: text_x + 2,
+            row: bottom,
+            modifiers: KeyModifiers::NONE,
+        };
+        handler
+            .handle_mouse_event(AppEvent::MouseInput(drag))
+            .await
+            .unwrap();
+    }
+
+    // Each drag event scrolls a line, so the cursor ends past the first page
+    let app = app_state.read().await;
+    assert_eq!(app.scroll_offset.0, 3);
+    let last_visible = editor.height as usize - 1;
+    assert_eq!(app.buffers[0].cursor_pos, (last_visible + 3, 2));
+    assert_eq!(app.buffers[0].selection_start, Some((1, 0)));
+}
+
+#[tokio::test]
+async fn test_eol_crlf_converts_the_line_endings_written_on_save() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("unix.txt");
+    std::fs::write(&path, "one\ntwo").unwrap();
+    let app_state = Arc::new(RwLock::new(App {
+        buffers: vec![Buffer::from_path(path.clone()).unwrap()],
+        command_mode: CommandMode::Command,
+        command_input: "eol crlf".to_string(),
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    }));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    {
+        let mut app = app_state.write().await;
+        assert_eq!(app.buffers[0].line_ending.name(), "CRLF");
+        assert!(app.buffers[0].modified);
+        // Leaving command mode is up to the app state handler
+        app.command_mode = CommandMode::Normal;
+    }
+
+    press(&handler, KeyCode::Char('s'), KeyModifiers::CONTROL).await;
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\r\ntwo\r\n");
+}
+
+#[tokio::test]
+async fn test_enter_trims_the_line_left_behind_with_trim_on_newline() {
+    let mut app = App::default();
+    app.config.editor.trim_on_newline = true;
+    app.buffers[0].content = vec!["foo   ".to_string()].into();
+    app.buffers[0].cursor_pos = (0, 6);
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    let app = app_state.read().await;
+    assert_eq!(app.buffers[0].content, vec!["foo", ""]);
+    assert_eq!(app.buffers[0].cursor_pos, (1, 0));
+}
+
+#[tokio::test]
+async fn test_ctrl_o_and_ctrl_i_walk_the_jump_list_after_gotos() {
+    let mut buffer = Buffer::new();
+    buffer.content = (1..=20).map(|n| format!("line {}", n)).collect::<Vec<_>>().into();
+    let app_state = Arc::new(RwLock::new(App {
+        buffers: vec![buffer],
+        ..App::default()
+    }));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    for command in ["goto 5", "goto 10"] {
+        {
+            let mut app = app_state.write().await;
+            app.command_mode = CommandMode::Command;
+            app.command_input = command.to_string();
+        }
+        press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+        // Leaving command mode is up to the app state handler
+        app_state.write().await.command_mode = CommandMode::Normal;
+    }
+    assert_eq!(app_state.read().await.buffers[0].cursor_pos, (9, 0));
+
+    press(&handler, KeyCode::Char('o'), KeyModifiers::CONTROL).await;
+    assert_eq!(app_state.read().await.buffers[0].cursor_pos, (4, 0));
+
+    press(&handler, KeyCode::Char('i'), KeyModifiers::CONTROL).await;
+    assert_eq!(app_state.read().await.buffers[0].cursor_pos, (9, 0));
+
+    // Nothing newer to go forward to
+    press(&handler, KeyCode::Char('i'), KeyModifiers::CONTROL).await;
+    assert_eq!(app_state.read().await.buffers[0].cursor_pos, (9, 0));
+}
+
+#[tokio::test]
+async fn test_local_mark_jumps_back_after_moving_away() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["alpha".to_string(), "beta".to_string(), "gamma".to_string()].into();
+    buffer.cursor_pos = (1, 2);
+    let app_state = Arc::new(RwLock::new(App {
+        buffers: vec![buffer, Buffer::new()],
+        ..App::default()
+    }));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+    let run = |command: &'static str| {
+        let app_state = app_state.clone();
+        let handler = &handler;
+        async move {
+            {
+                let mut app = app_state.write().await;
+                app.command_mode = CommandMode::Command;
+                app.command_input = command.to_string();
+            }
+            press(handler, KeyCode::Enter, KeyModifiers::NONE).await;
+            // Leaving command mode is up to the app state handler
+            app_state.write().await.command_mode = CommandMode::Normal;
+        }
+    };
+
+    run("mark a").await;
+    {
+        let mut app = app_state.write().await;
+        app.buffers[0].content[1] = "b".to_string();
+        app.switch_to_buffer(1);
+    }
+
+    run("'a").await;
+    let app = app_state.read().await;
+    assert_eq!(app.active_buffer, 0);
+    // Pulled back onto the line, which got shorter
+    assert_eq!(app.buffers[0].cursor_pos, (1, 1));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_slow_config_save_does_not_hold_up_the_next_key() {
+    use std::sync::mpsc as std_mpsc;
+    use std::time::Duration;
+
+    // A FIFO in place of the config file blocks whoever writes it until a
+    // reader opens it, like a disk that takes its time
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.json");
+    let mkfifo = std::process::Command::new("mkfifo")
+        .arg(&config_path)
+        .status()
+        .unwrap();
+    assert!(mkfifo.success());
+    let (release, released) = std_mpsc::channel::<()>();
+    let reader = std::thread::spawn(move || {
+        // Stop waiting eventually, so a save that blocks can't hang the test
+        let in_time = released.recv_timeout(Duration::from_secs(5)).is_ok();
+        (in_time, std::fs::read_to_string(config_path).unwrap())
+    });
+
+    let app_state = Arc::new(RwLock::new(App {
+        command_mode: CommandMode::Command,
+        command_input: "toggle_line_numbers".to_string(),
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    }));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    // Leaving command mode is up to the app state handler
+    app_state.write().await.command_mode = CommandMode::Normal;
+    press(&handler, KeyCode::Char('x'), KeyModifiers::NONE).await;
+    {
+        let app = app_state.read().await;
+        assert_eq!(app.buffers[0].content, vec!["x"]);
+        assert!(!app.config.editor.show_line_numbers);
+    }
+    release.send(()).unwrap();
+
+    let (in_time, written) = tokio::task::spawn_blocking(move || reader.join().unwrap())
+        .await
+        .unwrap();
+    assert!(in_time, "saving the config held up the next key");
+    let config: editor::config::Config = serde_json::from_str(&written).unwrap();
+    assert!(!config.editor.show_line_numbers);
+}
+
+#[tokio::test]
+async fn test_config_save_writes_the_settings_the_command_left() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let app_state = Arc::new(RwLock::new(App {
+        command_mode: CommandMode::Command,
+        command_input: "toggle_line_numbers".to_string(),
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    }));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    // Whatever the app holds by the time the write runs doesn't matter
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    let user_dir = temp_dir.path().join("elsewhere");
+    *app_state.write().await = App {
+        user_dir: user_dir.clone(),
+        ..App::default()
+    };
+
+    let config_path = temp_dir.path().join("config.json");
+    let mut written = None;
+    for _ in 0..100 {
+        written = std::fs::read_to_string(&config_path)
+            .ok()
+            .and_then(|text| serde_json::from_str::<editor::config::Config>(&text).ok());
+        if written.is_some() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert!(!written.unwrap().editor.show_line_numbers);
+    assert!(!user_dir.join("config.json").exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_slow_save_does_not_hold_the_app() {
+    use std::time::Duration;
+
+    // Saving to a FIFO blocks until a reader opens it, like a slow disk
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("slow.txt");
+    let mkfifo = std::process::Command::new("mkfifo")
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(mkfifo.success());
+
+    let mut buffer = Buffer::new();
+    buffer.path = Some(path.clone());
+    buffer.mark_saved();
+    buffer.insert_char('x');
+    let app_state = Arc::new(RwLock::new(App {
+        buffers: vec![buffer],
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    }));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    let save = tokio::spawn(async move {
+        press(&handler, KeyCode::Char('s'), KeyModifiers::CONTROL).await;
+    });
+    // The app can be drawn while the file is being written
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let dirty_while_writing = tokio::time::timeout(Duration::from_secs(1), app_state.read())
+        .await
+        .map(|app| app.buffers[0].is_dirty());
+
+    // Reading the FIFO lets the write finish either way
+    let written = tokio::task::spawn_blocking(move || std::fs::read_to_string(path))
+        .await
+        .unwrap()
+        .unwrap();
+    save.await.unwrap();
+    assert_eq!(dirty_while_writing, Ok(true), "the save held the app while writing");
+    assert_eq!(written, "x\n");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_jumping_to_a_global_mark_reads_its_file_without_holding_the_app() {
+    use std::time::Duration;
+
+    use editor::buffer::Position;
+    use editor::marks::GlobalMark;
+
+    // Reading a FIFO blocks until a writer opens it, like a slow disk
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("slow.txt");
+    let mkfifo = std::process::Command::new("mkfifo")
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(mkfifo.success());
+
+    let mut app = App {
+        command_mode: CommandMode::Command,
+        command_input: "jump A".to_string(),
+        ..App::default()
+    };
+    app.global_marks.insert(
+        'A',
+        GlobalMark {
+            path: path.clone(),
+            position: Position::from_tuple((1, 2)),
+        },
+    );
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    let jump = tokio::spawn(async move {
+        press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    });
+    // The app can be drawn while the file is being read
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let readable_while_reading = tokio::time::timeout(Duration::from_secs(1), app_state.read())
+        .await
+        .is_ok();
+
+    // Writing the FIFO lets the read finish either way
+    tokio::task::spawn_blocking(move || std::fs::write(path, "one\ntwo"))
+        .await
+        .unwrap()
+        .unwrap();
+    jump.await.unwrap();
+    assert!(readable_while_reading, "the jump held the app while reading");
+    let app = app_state.read().await;
+    assert_eq!(app.active_content(), "one\ntwo");
+    assert_eq!(app.buffers[app.active_buffer].cursor_pos, (1, 2));
+}
+
+#[tokio::test]
+async fn test_registered_command_runs_by_its_alias() {
+    use editor::commands::CommandCall;
+    use std::sync::Mutex;
+
+    let calls: Arc<Mutex<Vec<CommandCall>>> = Arc::default();
+    let mut app = App {
+        command_mode: CommandMode::Command,
+        command_input: "gr hello there".to_string(),
+        ..App::default()
+    };
+    app.commands.register("greet", &["gr"], {
+        let calls = calls.clone();
+        move |_keyboard, call| {
+            calls.lock().unwrap().push(call);
+            async { Ok(()) }
+        }
+    });
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec![CommandCall {
+            input: "gr hello there".to_string(),
+            name: "gr".to_string(),
+            args: vec!["hello".to_string(), "there".to_string()],
+        }]
+    );
+
+    // The palette suggests it by its full name
+    let mut app = app_state.write().await;
+    assert_eq!(app.command_history, vec!["gr hello there"]);
+    app.command_input = "gree".to_string();
+    app.update_command_suggestions();
+    assert_eq!(app.command_suggestions[0], "greet");
+}
+
+#[tokio::test]
+async fn test_plugin_command_runs_by_its_alias_on_the_editor() {
+    use editor::plugins::UppercaseSelection;
+
+    let mut app = App {
+        command_mode: CommandMode::Command,
+        command_input: "up".to_string(),
+        ..App::default()
+    };
+    app.commands.register_plugin_command(Arc::new(UppercaseSelection), &["up"]);
+    app.buffers[0].content = vec!["hello world".to_string()].into();
+    app.buffers[0].selection_start = Some((0, 0));
+    app.buffers[0].cursor_pos = (0, 5);
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    let app = app_state.read().await;
+    assert_eq!(app.buffers[0].content, vec!["HELLO world"]);
+    assert!(app.buffers[0].modified);
+    assert!(drain(&mut events)
+        .iter()
+        .any(|event| matches!(event, AppEvent::BufferEdited { buffer_id: 0, .. })));
+
+    // It's listed under its own name, described by the plugin
+    let command = app.commands.commands().find(|c| c.name() == "uppercase").unwrap();
+    assert_eq!(command.aliases(), ["up"]);
+    assert_eq!(command.description(), "Uppercase the selected text");
+}
+
+#[tokio::test]
+async fn test_f1_opens_the_help_and_its_keys_scroll_and_close_it() {
+    let app_state = Arc::new(RwLock::new(App::default()));
+    let (sender, _events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    press(&handler, KeyCode::F(1), KeyModifiers::NONE).await;
+    assert!(app_state.read().await.show_help);
+
+    // Keys scroll the help instead of reaching the buffer
+    press(&handler, KeyCode::Down, KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Down, KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Up, KeyModifiers::NONE).await;
+    press(&handler, KeyCode::Char('x'), KeyModifiers::NONE).await;
+    {
+        let app = app_state.read().await;
+        assert_eq!(app.help_scroll, 1);
+        assert_eq!(app.buffers[0].content[0], "");
+    }
+
+    press(&handler, KeyCode::Esc, KeyModifiers::NONE).await;
+    assert!(!app_state.read().await.show_help);
+
+    // The help command opens it too
+    let mut app = app_state.write().await;
+    app.command_mode = CommandMode::Command;
+    app.command_input = "help".to_string();
+    drop(app);
+    press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+    let app = app_state.read().await;
+    assert!(app.show_help);
+    assert_eq!(app.help_scroll, 0);
+}
+
+#[tokio::test]
+async fn test_uniq_reports_how_many_duplicates_it_removed() {
+    let mut app = App::default();
+    app.buffers[0].content = vec!["a", "a", "b"].into_iter().map(String::from).collect();
+    let app_state = Arc::new(RwLock::new(app));
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let handler = KeyboardHandler::new(app_state.clone(), sender);
+
+    let mut toasts = Vec::new();
+    for _ in 0..2 {
+        {
+            let mut app = app_state.write().await;
+            app.command_mode = CommandMode::Command;
+            app.command_input = "uniq".to_string();
+        }
+        press(&handler, KeyCode::Enter, KeyModifiers::NONE).await;
+        toasts.extend(drain(&mut events).into_iter().filter_map(|event| match event {
+            AppEvent::ToastMessage { message, .. } => Some(message.to_string()),
+            _ => None,
+        }));
+    }
+
+    assert_eq!(toasts, ["Removed 1 duplicate line", "No duplicate lines"]);
+    assert_eq!(app_state.read().await.buffers[0].content, vec!["a", "b"]);
+}
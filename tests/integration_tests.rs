@@ -2,7 +2,12 @@
 
 mod app_tests;
 mod buffer_tests;
+mod event_bus_tests;
+mod handler_tests;
 mod input_tests;
+mod marks_tests;
+mod performance_tests;
 mod smoke_tests;
 mod status_bar_tests;
+mod syntax_tests;
 mod widget_tests;
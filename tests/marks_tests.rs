@@ -0,0 +1,106 @@
+//! Integration tests for marks
+//!
+//! Tests local marks that jump within the session, and global marks that jump
+//! across buffers and persist to the user directory
+
+use std::fs;
+use tempfile::TempDir;
+
+use editor::{buffer::Buffer, App};
+
+/// App with two file-backed buffers and its user directory pointed at a temp dir
+async fn app_with_two_files(temp_dir: &TempDir) -> App {
+    let first = temp_dir.path().join("first.txt");
+    let second = temp_dir.path().join("second.txt");
+    fs::write(&first, "alpha\nbeta\ngamma").unwrap();
+    fs::write(&second, "one\ntwo").unwrap();
+
+    App {
+        user_dir: temp_dir.path().to_path_buf(),
+        buffers: vec![
+            Buffer::from_path_async(first).await.unwrap(),
+            Buffer::from_path_async(second).await.unwrap(),
+        ],
+        ..App::default()
+    }
+}
+
+#[tokio::test]
+async fn test_global_mark_jumps_across_buffers() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut app = app_with_two_files(&temp_dir).await;
+
+    app.buffers[0].cursor_pos = (2, 3);
+    app.set_global_mark('A').unwrap();
+
+    app.switch_to_buffer(1);
+    app.buffers[1].cursor_pos = (1, 1);
+    app.jump_to_global_mark('A').await.unwrap();
+
+    assert_eq!(app.active_buffer, 0);
+    assert_eq!(app.buffers[0].cursor_pos, (2, 3));
+}
+
+#[tokio::test]
+async fn test_global_mark_reopens_closed_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut app = app_with_two_files(&temp_dir).await;
+
+    app.switch_to_buffer(1);
+    app.buffers[1].cursor_pos = (1, 2);
+    app.set_global_mark('B').unwrap();
+    assert!(app.close_current_buffer());
+    assert_eq!(app.buffers.len(), 1);
+
+    app.jump_to_global_mark('B').await.unwrap();
+
+    assert_eq!(app.buffers.len(), 2);
+    assert_eq!(app.buffers[app.active_buffer].name, "second.txt");
+    assert_eq!(app.buffers[app.active_buffer].cursor_pos, (1, 2));
+}
+
+#[tokio::test]
+async fn test_global_marks_persist() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut app = app_with_two_files(&temp_dir).await;
+
+    app.buffers[0].cursor_pos = (1, 0);
+    app.set_global_mark('C').unwrap();
+
+    let mut restored = App {
+        user_dir: temp_dir.path().to_path_buf(),
+        ..App::default()
+    };
+    restored.load_global_marks();
+
+    assert_eq!(restored.global_marks.get(&'C'), app.global_marks.get(&'C'));
+}
+
+#[tokio::test]
+async fn test_global_mark_rejects_invalid_names() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut app = app_with_two_files(&temp_dir).await;
+
+    assert!(app.set_global_mark('a').is_err());
+    assert!(app.jump_to_global_mark('Z').await.is_err());
+
+    // Unsaved buffers have no path to remember
+    app.add_buffer(Buffer::new());
+    assert!(app.set_global_mark('D').is_err());
+}
+
+#[tokio::test]
+async fn test_local_mark_fails_once_its_buffer_is_closed() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut app = app_with_two_files(&temp_dir).await;
+
+    app.switch_to_buffer(1);
+    app.buffers[1].cursor_pos = (1, 2);
+    app.set_mark('b').unwrap();
+    assert!(app.close_current_buffer());
+
+    let error = app.jump_to_mark('b').await.unwrap_err();
+    assert!(error.to_string().contains("closed"));
+    assert!(app.jump_to_mark('c').await.is_err());
+    assert!(app.set_mark('1').is_err());
+}
@@ -0,0 +1,45 @@
+//! Tests for frame and event timing
+
+use std::time::Duration;
+
+use editor::performance::PerformanceMonitor;
+
+#[test]
+fn test_known_frame_times_give_average_and_fps() {
+    let mut monitor = PerformanceMonitor::new(4);
+    assert_eq!(monitor.average_frame_time(), None);
+    assert_eq!(monitor.fps(), None);
+
+    // The oldest sample falls out once more than 4 are recorded
+    for millis in [100, 10, 20, 10, 20] {
+        monitor.add_frame_time(Duration::from_millis(millis));
+    }
+    monitor.add_event_time(Duration::from_millis(2));
+    monitor.add_event_time(Duration::from_millis(4));
+
+    assert_eq!(
+        monitor.average_frame_time(),
+        Some(Duration::from_millis(15))
+    );
+    assert_eq!(monitor.average_event_time(), Some(Duration::from_millis(3)));
+    let fps = monitor.fps().unwrap();
+    assert!((fps - 66.67).abs() < 0.01, "fps was {}", fps);
+    assert_eq!(
+        monitor.stats_string(),
+        "Frame: 15.00ms | Events: 3.00ms | FPS: 66.7"
+    );
+    assert!(!monitor.is_performance_degraded());
+}
+
+#[test]
+fn test_degraded_streak_counts_consecutive_slow_frames() {
+    let mut monitor = PerformanceMonitor::new(1);
+    for _ in 0..3 {
+        monitor.add_frame_time(Duration::from_millis(50));
+    }
+    assert!(monitor.is_performance_degraded());
+    assert_eq!(monitor.degraded_streak(), 3);
+
+    monitor.add_frame_time(Duration::from_millis(5));
+    assert_eq!(monitor.degraded_streak(), 0);
+}
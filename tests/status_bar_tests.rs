@@ -238,3 +238,40 @@ fn test_status_bar_clone() {
     assert_eq!(cloned_bar.slot_count(), 1);
     assert!(cloned_bar.get_slot("test").is_some());
 }
+
+#[test]
+fn test_status_bar_truncates_multibyte_content_between_chars() {
+    let mut status_bar = StatusBar::new();
+    status_bar.set_slot(StatusSlot::new("file", "café-longname").with_max_width(7));
+
+    let mut terminal = Terminal::new(TestBackend::new(20, 1)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(status_bar.clone(), Rect::new(0, 0, 20, 1)))
+        .unwrap();
+
+    let shown: String = (0..8)
+        .map(|x| terminal.backend().buffer()[(x, 0)].symbol().to_string())
+        .collect();
+    assert_eq!(shown, "café... ");
+}
+
+#[test]
+fn test_status_bar_lays_out_wide_chars_by_display_width() {
+    let mut status_bar = StatusBar::new();
+    status_bar.set_slot(StatusSlot::new("file", "日本語.txt"));
+    status_bar.set_slot(StatusSlot::new("mode", "END").with_alignment(SlotAlignment::Right));
+
+    // Each CJK char takes two columns, so the name spans ten
+    assert_eq!(status_bar.slot_at(16, 9), Some("file"));
+    assert_eq!(status_bar.slot_at(16, 10), None);
+    assert_eq!(status_bar.slot_at(16, 13), Some("mode"));
+
+    let mut terminal = Terminal::new(TestBackend::new(16, 1)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(status_bar.clone(), Rect::new(0, 0, 16, 1)))
+        .unwrap();
+    let end: String = (13..16)
+        .map(|x| terminal.backend().buffer()[(x, 0)].symbol().to_string())
+        .collect();
+    assert_eq!(end, "END");
+}
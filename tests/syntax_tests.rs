@@ -0,0 +1,162 @@
+//! Integration tests for syntax highlighting
+//!
+//! Tests the Rust and JSON highlighters and how the editor widget colors their output
+
+use std::path::{Path, PathBuf};
+
+use editor::buffer::Buffer;
+use editor::syntax::{
+    highlighter_for_path, HighlightKind, HighlightState, Highlighter, JsonHighlighter,
+    RustHighlighter,
+};
+use editor::theme::Theme;
+use editor::widgets::editor::Editor;
+use ratatui::{backend::TestBackend, layout::Rect, Terminal};
+
+/// Highlight a line from a fresh state and return each span's text and kind
+fn highlight<'a>(highlighter: &dyn Highlighter, line: &'a str) -> Vec<(&'a str, HighlightKind)> {
+    let mut state = HighlightState::default();
+    highlighter
+        .highlight_line(line, &mut state)
+        .into_iter()
+        .map(|span| (&line[span.range], span.kind))
+        .collect()
+}
+
+#[test]
+fn test_highlighter_selected_by_extension() {
+    assert!(highlighter_for_path(Path::new("src/main.rs")).is_some());
+    assert!(highlighter_for_path(Path::new("package.json")).is_some());
+    assert!(highlighter_for_path(Path::new("notes.txt")).is_none());
+    assert!(highlighter_for_path(Path::new("Makefile")).is_none());
+}
+
+#[test]
+fn test_rust_highlighter_tokens() {
+    let spans = highlight(&RustHighlighter, r#"let s = "a \"b\""; // done 'x' 42"#);
+    assert_eq!(
+        spans,
+        vec![
+            ("let", HighlightKind::Keyword),
+            (r#""a \"b\"""#, HighlightKind::String),
+            ("// done 'x' 42", HighlightKind::Comment),
+        ]
+    );
+
+    let spans = highlight(
+        &RustHighlighter,
+        "fn f<'a>(c: char) -> u8 { 0x1F + 2.5e-3 as u8 + 'z' }",
+    );
+    assert_eq!(spans[0], ("fn", HighlightKind::Keyword));
+    assert!(spans.contains(&("0x1F", HighlightKind::Number)));
+    assert!(spans.contains(&("2.5e-3", HighlightKind::Number)));
+    assert!(spans.contains(&("'z'", HighlightKind::String)));
+    // Lifetimes and identifiers that merely contain keywords aren't highlighted
+    assert!(!spans
+        .iter()
+        .any(|(text, _)| text.contains('a') && *text != "as"));
+}
+
+#[test]
+fn test_rust_block_comment_carries_across_lines() {
+    let mut state = HighlightState::default();
+    let first = RustHighlighter.highlight_line("let x = 1; /* start", &mut state);
+    assert_eq!(state, HighlightState::BlockComment);
+    assert_eq!(first.last().unwrap().kind, HighlightKind::Comment);
+
+    let second = RustHighlighter.highlight_line("still comment */ fn", &mut state);
+    assert_eq!(state, HighlightState::Normal);
+    assert_eq!(second[0].range, 0..16);
+    assert_eq!(second[0].kind, HighlightKind::Comment);
+    assert_eq!(second[1].kind, HighlightKind::Keyword);
+}
+
+#[test]
+fn test_json_highlighter_tokens() {
+    let spans = highlight(&JsonHighlighter, r#"{"a": [true, null, -1.5e3, "x"]}"#);
+    assert_eq!(
+        spans,
+        vec![
+            (r#""a""#, HighlightKind::String),
+            ("true", HighlightKind::Keyword),
+            ("null", HighlightKind::Keyword),
+            ("-1.5e3", HighlightKind::Number),
+            (r#""x""#, HighlightKind::String),
+        ]
+    );
+}
+
+/// Render a single-line Rust buffer and return the terminal buffer
+fn render_rust_line(
+    text: &str,
+    select: Option<((usize, usize), (usize, usize))>,
+) -> ratatui::buffer::Buffer {
+    let mut buffer = Buffer::new();
+    for ch in text.chars() {
+        buffer.insert_char(ch);
+    }
+    buffer.path = Some(PathBuf::from("main.rs"));
+    if let Some((start, end)) = select {
+        buffer.cursor_pos = start;
+        buffer.toggle_visual_mode();
+        buffer.cursor_pos = end;
+    }
+
+    let editor = Editor {
+        show_line_numbers: false,
+        ..Editor::new(&buffer)
+    };
+    let mut terminal = Terminal::new(TestBackend::new(20, 1)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(editor, Rect::new(0, 0, 20, 1)))
+        .unwrap();
+    terminal.backend().buffer().clone()
+}
+
+#[test]
+fn test_editor_colors_fn_keyword() {
+    let theme = Theme::default();
+    let buf = render_rust_line("fn main() {}", None);
+
+    assert_eq!(buf[(0, 0)].symbol(), "f");
+    assert_eq!(buf[(0, 0)].fg, theme.keyword);
+    assert_eq!(buf[(1, 0)].fg, theme.keyword);
+    // Plain identifiers keep the editor's text color
+    assert_eq!(buf[(3, 0)].fg, theme.editor_fg);
+}
+
+#[test]
+fn test_selection_composes_over_syntax_colors() {
+    let theme = Theme::default();
+    let buf = render_rust_line("fn main() {}", Some(((0, 0), (0, 4))));
+
+    // Selected keyword keeps its color on the selection background
+    assert_eq!(buf[(0, 0)].fg, theme.keyword);
+    assert_eq!(buf[(0, 0)].bg, theme.selection_bg);
+    // Selected plain text uses the selection style
+    assert_eq!(buf[(3, 0)].fg, theme.selection_fg);
+    assert_eq!(buf[(3, 0)].bg, theme.selection_bg);
+    // Text after the selection is unaffected
+    assert_eq!(buf[(4, 0)].bg, theme.editor_bg);
+}
+
+#[test]
+fn test_highlight_state_at_resumes_from_checkpoints_and_follows_edits() {
+    let mut buffer = Buffer::new();
+    buffer.path = Some(PathBuf::from("big.rs"));
+    buffer.content = vec!["let x = 1;".to_string(); 2000].into();
+    let rust: &dyn Highlighter = &RustHighlighter;
+    assert_eq!(buffer.highlight_state_at(rust, 1500), HighlightState::Normal);
+
+    // Opening a block comment near the top reaches lines past the checkpoints
+    buffer.cursor_pos = (10, 0);
+    buffer.insert_str("/* ");
+    assert_eq!(buffer.highlight_state_at(rust, 1500), HighlightState::BlockComment);
+    assert_eq!(buffer.highlight_state_at(rust, 10), HighlightState::Normal);
+
+    // Closing it far below leaves the lines above it alone
+    buffer.cursor_pos = (1800, 0);
+    buffer.insert_str("*/ ");
+    assert_eq!(buffer.highlight_state_at(rust, 1500), HighlightState::BlockComment);
+    assert_eq!(buffer.highlight_state_at(rust, 1900), HighlightState::Normal);
+}
@@ -6,7 +6,7 @@ use editor::widgets::{
     cursor::CursorManager,
     editor::Editor,
     modal::CommandPalette,
-    toast::ToastManager,
+    toast::{ToastManager, ToastWidget},
 };
 use editor::buffer::Buffer;
 use ratatui::{
@@ -171,6 +171,67 @@ fn test_toast_manager_update_and_expiry() {
     // We can't easily test timing without sleeping or mocking time
 }
 
+#[test]
+fn test_toasts_stack_up_from_bottom_left() {
+    use editor::config::ToastPosition;
+
+    let mut toast_manager = ToastManager::new();
+    toast_manager.configure(ToastPosition::BottomLeft, 5);
+    for message in ["first", "second", "third"] {
+        toast_manager.add_info(message.to_string());
+    }
+
+    let area = Rect::new(0, 0, 90, 30);
+    let areas = toast_manager.toast_areas(area);
+    assert_eq!(areas.len(), 3);
+
+    // The oldest sits in the corner and newer ones stack upwards from it
+    assert_eq!(areas[0].bottom(), 28);
+    assert_eq!(areas[1].bottom(), areas[0].y);
+    assert_eq!(areas[2].bottom(), areas[1].y);
+    for toast_area in &areas {
+        assert_eq!(toast_area.x, 2);
+        assert!(toast_area.y >= area.height / 2, "{:?}", toast_area);
+    }
+
+    let mut terminal = Terminal::new(TestBackend::new(90, 30)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(ToastWidget::new(&toast_manager), area))
+        .unwrap();
+    let buf = terminal.backend().buffer();
+    for toast_area in &areas {
+        assert_eq!(buf[(toast_area.x, toast_area.y)].symbol(), "┌");
+    }
+    assert_eq!(buf[(2, 2)].symbol(), " ");
+}
+
+#[test]
+fn test_dismissing_a_toast_removes_only_the_clicked_one() {
+    use ratatui::layout::Position;
+
+    let mut toast_manager = ToastManager::new();
+    for message in ["first", "second", "third"] {
+        toast_manager.add_info(message.to_string());
+    }
+    let area = Rect::new(0, 0, 90, 30);
+    let second = toast_manager.toast_areas(area)[1];
+
+    // Clicking outside every toast leaves them all
+    assert!(!toast_manager.dismiss_at(area, Position::new(0, 0)));
+    assert_eq!(toast_manager.toasts().len(), 3);
+
+    assert!(toast_manager.dismiss_at(area, Position::new(second.x + 5, second.y + 1)));
+    let messages: Vec<&str> = toast_manager
+        .toasts()
+        .iter()
+        .map(|toast| toast.message.as_str())
+        .collect();
+    assert_eq!(messages, ["first", "third"]);
+
+    toast_manager.clear_all();
+    assert!(!toast_manager.has_active_toasts());
+}
+
 #[test]
 fn test_editor_widget_with_selection() {
     let mut buffer = Buffer::new();
@@ -305,3 +366,591 @@ fn test_cursor_manager_multiple_contexts() {
     assert_eq!(search_pos.unwrap().x, 0);
     assert_eq!(search_pos.unwrap().y, 15);
 }
+
+#[test]
+fn test_editor_gutter_uses_theme_colors() {
+    use editor::theme::Theme;
+    use ratatui::style::Color;
+
+    let mut buffer = Buffer::new();
+    for ch in "Hi".chars() {
+        buffer.insert_char(ch);
+    }
+
+    let theme = Theme {
+        gutter_bg: Color::Rgb(40, 40, 50),
+        gutter_gap: 2,
+        ..Theme::default()
+    };
+    let editor = Editor::new(&buffer).with_theme(theme.clone());
+
+    let backend = TestBackend::new(20, 3);
+    let mut terminal = Terminal::new(backend).unwrap();
+    terminal
+        .draw(|f| {
+            f.render_widget(editor, Rect::new(0, 0, 20, 3));
+        })
+        .unwrap();
+
+    let buf = terminal.backend().buffer();
+
+    // Line number cells use the gutter background
+    assert_eq!(buf[(3, 0)].symbol(), "1");
+    assert_eq!(buf[(0, 0)].bg, theme.gutter_bg);
+    assert_eq!(buf[(3, 0)].bg, theme.gutter_bg);
+
+    // Both gap cells are styled with the gutter background, not left at the default
+    assert_eq!(buf[(4, 0)].symbol(), " ");
+    assert_eq!(buf[(4, 0)].bg, theme.gutter_bg);
+    assert_eq!(buf[(5, 0)].bg, theme.gutter_bg);
+
+    // Text starts right after the configured gap
+    assert_eq!(buf[(6, 0)].symbol(), "H");
+    assert_eq!(buf[(6, 0)].bg, theme.editor_bg);
+}
+
+#[test]
+fn test_editor_word_wrap_long_line() {
+    let mut buffer = Buffer::new();
+    let text: String = ('a'..='z').cycle().take(200).collect();
+    for ch in text.chars() {
+        buffer.insert_char(ch);
+    }
+    buffer.insert_newline();
+    buffer.insert_char('z');
+
+    let editor = Editor {
+        word_wrap: true,
+        ..Editor::new(&buffer)
+    };
+
+    let backend = TestBackend::new(80, 6);
+    let mut terminal = Terminal::new(backend).unwrap();
+    terminal
+        .draw(|f| f.render_widget(editor, Rect::new(0, 0, 80, 6)))
+        .unwrap();
+    let buf = terminal.backend().buffer();
+    let row_text = |y: u16| -> String { (0..80).map(|x| buf[(x, y)].symbol()).collect() };
+
+    // The 5-column gutter leaves 75 columns of text, so 200 chars take 3 rows
+    assert!(row_text(0).starts_with("   1 abc"));
+    assert_eq!(&row_text(1)[..5], "     ", "continuation rows have no line number");
+    assert_eq!(&row_text(1)[5..], &text[75..150]);
+    assert_eq!(row_text(2).trim_end(), format!("     {}", &text[150..]));
+    assert!(row_text(3).starts_with("   2 z"));
+}
+
+#[test]
+fn test_editor_word_wrap_counts_wide_chars_as_two_columns() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["日本語テキスト".to_string()].into();
+
+    // 5 columns fit two wide chars a row, leaving one column unused
+    assert_eq!(buffer.visual_rows(0, 5, 4), 4);
+    assert_eq!(buffer.wrapped_position(0, "日本語".len(), 5, 4), (1, 2));
+    assert_eq!(buffer.col_at_wrapped_position(0, 1, 3, 5, 4), "日本語".len());
+    assert_eq!(buffer.col_at_wrapped_position(0, 1, 5, 5, 4), "日本語テ".len());
+
+    // Filling the last row exactly leaves a row for the cursor after it
+    assert_eq!(buffer.visual_rows(0, 14, 4), 2);
+
+    let editor = Editor {
+        word_wrap: true,
+        show_line_numbers: false,
+        ..Editor::new(&buffer)
+    };
+    let mut terminal = Terminal::new(TestBackend::new(5, 4)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(editor, Rect::new(0, 0, 5, 4)))
+        .unwrap();
+    let buf = terminal.backend().buffer();
+    assert_eq!((buf[(0, 0)].symbol(), buf[(2, 0)].symbol()), ("日", "本"));
+    assert_eq!((buf[(0, 1)].symbol(), buf[(2, 1)].symbol()), ("語", "テ"));
+    assert_eq!(buf[(0, 3)].symbol(), "ト");
+}
+
+#[test]
+fn test_editor_word_wrap_counts_tabs_to_their_tab_stops() {
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["\t\t\tabcdefg".to_string()].into();
+
+    // Three tabs take 12 columns, so the tabs and letters need three rows of 10
+    assert_eq!(buffer.visual_rows(0, 10, 4), 3);
+    assert_eq!(buffer.wrapped_position(0, 3, 10, 4), (1, 4));
+    assert_eq!(buffer.col_at_wrapped_position(0, 1, 5, 10, 4), 4);
+
+    let editor = Editor {
+        word_wrap: true,
+        show_line_numbers: false,
+        tab_size: 4,
+        ..Editor::new(&buffer)
+    };
+    let mut terminal = Terminal::new(TestBackend::new(10, 3)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(editor, Rect::new(0, 0, 10, 3)))
+        .unwrap();
+    let buf = terminal.backend().buffer();
+    let row_text = |y: u16| -> String { (0..10).map(|x| buf[(x, y)].symbol()).collect() };
+    assert_eq!(row_text(0), " ".repeat(10));
+    assert_eq!(row_text(1), "    abcdef");
+    assert_eq!(row_text(2).trim_end(), "g");
+}
+
+#[test]
+fn test_editor_relative_line_numbers() {
+    let mut buffer = Buffer::new();
+    buffer.content = (1..=5).map(|i| format!("line {}", i)).collect();
+    buffer.cursor_pos = (2, 0);
+
+    let editor = Editor {
+        show_line_numbers: false,
+        relative_line_numbers: true,
+        ..Editor::new(&buffer)
+    };
+
+    let mut terminal = Terminal::new(TestBackend::new(20, 5)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(editor, Rect::new(0, 0, 20, 5)))
+        .unwrap();
+    let buf = terminal.backend().buffer();
+    let gutter = |y: u16| -> String { (0..5).map(|x| buf[(x, y)].symbol()).collect() };
+
+    // The cursor line keeps its absolute number, its neighbours show 1
+    assert_eq!(gutter(2), "   3 ");
+    assert_eq!(gutter(1), "   1 ");
+    assert_eq!(gutter(3), "   1 ");
+    assert_eq!(gutter(0), "   2 ");
+    assert_eq!(gutter(4), "   2 ");
+}
+
+#[test]
+fn test_editor_highlights_block_selection() {
+    use editor::theme::Theme;
+
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["abcdef".to_string(), "gh".to_string(), "mnopqr".to_string()].into();
+    buffer.start_block_selection((0, 2));
+    buffer.cursor_pos = (2, 4);
+
+    let editor = Editor {
+        show_line_numbers: false,
+        ..Editor::new(&buffer)
+    };
+
+    let mut terminal = Terminal::new(TestBackend::new(10, 3)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(editor, Rect::new(0, 0, 10, 3)))
+        .unwrap();
+    let buf = terminal.backend().buffer();
+    let theme = Theme::default();
+
+    // Only columns 2 and 3 are selected, on the rows long enough to have them
+    for y in [0, 2] {
+        for x in 0..6 {
+            let selected = (2..4).contains(&x);
+            assert_eq!(buf[(x, y)].bg == theme.selection_bg, selected, "({}, {})", x, y);
+        }
+    }
+    for x in 0..6 {
+        assert_ne!(buf[(x, 1)].bg, theme.selection_bg);
+    }
+}
+
+#[test]
+fn test_editor_highlights_every_visible_search_match() {
+    use editor::buffer::SearchQuery;
+    use editor::theme::Theme;
+
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["foo bar".to_string(), "bar foo".to_string()].into();
+    buffer.cursor_pos = (0, 0);
+    let query = SearchQuery::new("foo");
+
+    let editor = Editor {
+        show_line_numbers: false,
+        search: Some(&query),
+        ..Editor::new(&buffer)
+    };
+
+    let mut terminal = Terminal::new(TestBackend::new(10, 2)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(editor, Rect::new(0, 0, 10, 2)))
+        .unwrap();
+    let buf = terminal.backend().buffer();
+    let theme = Theme::default();
+
+    // The match at the cursor is the active one; the other still stands out
+    for x in 0..3 {
+        assert_eq!(buf[(x, 0)].bg, theme.active_search_match_bg);
+        assert_eq!(buf[(x + 4, 1)].bg, theme.search_match_bg);
+    }
+    for x in [3, 4, 6] {
+        assert_ne!(buf[(x, 0)].bg, theme.search_match_bg);
+        assert_ne!(buf[(x - 3, 1)].bg, theme.search_match_bg);
+    }
+}
+
+#[test]
+fn test_editor_highlights_current_line() {
+    use editor::theme::Theme;
+
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["first".to_string(), "second".to_string(), "third".to_string()].into();
+    buffer.cursor_pos = (1, 0);
+    // Select "se" on the cursor line, which must keep the selection background
+    buffer.toggle_visual_mode();
+    buffer.cursor_pos = (1, 2);
+
+    let editor = Editor {
+        highlight_current_line: true,
+        ..Editor::new(&buffer)
+    };
+
+    let mut terminal = Terminal::new(TestBackend::new(20, 3)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(editor, Rect::new(0, 0, 20, 3)))
+        .unwrap();
+    let buf = terminal.backend().buffer();
+    let theme = Theme::default();
+
+    // Gutter, text and the empty space after it all get the highlight
+    for x in [0, 4, 7, 8, 19] {
+        assert_eq!(buf[(x, 1)].bg, theme.current_line_bg, "column {}", x);
+    }
+    assert_eq!(buf[(5, 1)].bg, theme.selection_bg);
+    assert_eq!(buf[(6, 1)].bg, theme.selection_bg);
+
+    for y in [0, 2] {
+        for x in [0, 5, 19] {
+            assert_ne!(buf[(x, y)].bg, theme.current_line_bg);
+        }
+    }
+}
+
+#[test]
+fn test_editor_renders_whitespace_and_expands_tabs() {
+    use editor::theme::Theme;
+
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["a\tb c".to_string()].into();
+
+    let editor = Editor {
+        show_line_numbers: false,
+        render_whitespace: true,
+        tab_size: 4,
+        ..Editor::new(&buffer)
+    };
+
+    let mut terminal = Terminal::new(TestBackend::new(10, 1)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(editor, Rect::new(0, 0, 10, 1)))
+        .unwrap();
+    let buf = terminal.backend().buffer();
+    let row: String = (0..10).map(|x| buf[(x, 0)].symbol()).collect();
+
+    // The tab fills up to the next stop, so b lands on column 4
+    assert_eq!(row, "a→  b·c   ");
+    let theme = Theme::default();
+    assert_eq!(buf[(1, 0)].fg, theme.whitespace);
+    assert_eq!(buf[(5, 0)].fg, theme.whitespace);
+    assert_ne!(buf[(4, 0)].fg, theme.whitespace);
+}
+
+#[test]
+fn test_editor_highlights_matching_brackets() {
+    use editor::theme::Theme;
+
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["f(a[1])".to_string()].into();
+    buffer.cursor_pos = (0, 1);
+
+    let editor = Editor {
+        show_line_numbers: false,
+        ..Editor::new(&buffer)
+    };
+
+    let mut terminal = Terminal::new(TestBackend::new(10, 1)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(editor, Rect::new(0, 0, 10, 1)))
+        .unwrap();
+    let buf = terminal.backend().buffer();
+    let theme = Theme::default();
+
+    assert_eq!(buf[(1, 0)].bg, theme.matching_bracket_bg);
+    assert_eq!(buf[(6, 0)].bg, theme.matching_bracket_bg);
+    for x in [0, 2, 3, 5] {
+        assert_ne!(buf[(x, 0)].bg, theme.matching_bracket_bg, "column {}", x);
+    }
+}
+
+#[test]
+fn test_editor_draws_secondary_cursors() {
+    use editor::buffer::Position;
+    use editor::theme::Theme;
+
+    let mut buffer = Buffer::new();
+    buffer.content = vec!["abc".to_string(), "de".to_string()].into();
+    buffer.add_cursor(Position::new(0, 1));
+    buffer.add_cursor(Position::new(1, 2));
+
+    let editor = Editor {
+        show_line_numbers: false,
+        ..Editor::new(&buffer)
+    };
+
+    let mut terminal = Terminal::new(TestBackend::new(10, 2)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(editor, Rect::new(0, 0, 10, 2)))
+        .unwrap();
+    let buf = terminal.backend().buffer();
+    let theme = Theme::default();
+
+    // Including one past the end of a line
+    assert_eq!(buf[(1, 0)].bg, theme.secondary_cursor_bg);
+    assert_eq!(buf[(2, 1)].bg, theme.secondary_cursor_bg);
+    for (x, y) in [(0, 0), (2, 0), (0, 1), (1, 1)] {
+        assert_ne!(buf[(x, y)].bg, theme.secondary_cursor_bg, "cell ({}, {})", x, y);
+    }
+}
+
+#[test]
+fn test_minimap_fills_its_area() {
+    use editor::theme::Theme;
+    use editor::widgets::minimap::Minimap;
+
+    let mut buffer = Buffer::new();
+    buffer.content = (0..100_000)
+        .map(|i| if i % 3 == 0 { String::new() } else { "    let x = 1;".to_string() })
+        .collect();
+
+    let theme = Theme::default();
+    let minimap = Minimap::new(&buffer, 50_000, 30);
+
+    let backend = TestBackend::new(30, 25);
+    let mut terminal = Terminal::new(backend).unwrap();
+    let area = Rect::new(5, 2, 12, 20);
+    terminal.draw(|f| f.render_widget(minimap, area)).unwrap();
+    let buf = terminal.backend().buffer();
+
+    // Every cell in the area is drawn and nothing outside it is touched
+    for y in 0..25 {
+        for x in 0..30 {
+            let inside = area.contains(ratatui::layout::Position::new(x, y));
+            let styled = buf[(x, y)].fg == theme.minimap_fg;
+            assert_eq!(inside, styled, "cell ({}, {})", x, y);
+        }
+    }
+
+    // 5000 lines per row: row 10 covers the viewport starting at line 50000
+    assert_eq!(buf[(5, 12)].bg, theme.minimap_viewport_bg);
+    assert_eq!(buf[(5, 11)].bg, theme.editor_bg);
+    assert_eq!(buf[(5, 13)].bg, theme.editor_bg);
+
+    // Rows sample an indented line: blank for the indent, then blocks for the text
+    assert_eq!(buf[(5, 3)].symbol(), " ");
+    assert_eq!(buf[(6, 3)].symbol(), "▬");
+    assert_eq!(buf[(9, 3)].symbol(), " ");
+}
+
+#[test]
+fn test_tab_bar_highlights_active_buffer() {
+    use editor::theme::Theme;
+    use editor::widgets::tab_bar::TabBar;
+
+    let mut buffers = vec![Buffer::new(), Buffer::new(), Buffer::new()];
+    buffers[0].name = "main.rs".to_string();
+    buffers[1].name = "lib.rs".to_string();
+    buffers[2].name = "notes.txt".to_string();
+    buffers[2].modified = true;
+
+    let theme = Theme::default();
+    let tab_bar = TabBar::new(&buffers, 1);
+
+    let backend = TestBackend::new(60, 1);
+    let mut terminal = Terminal::new(backend).unwrap();
+    terminal
+        .draw(|f| f.render_widget(tab_bar, Rect::new(0, 0, 60, 1)))
+        .unwrap();
+    let buf = terminal.backend().buffer();
+    let text: String = (0..60).map(|x| buf[(x, 0)].symbol()).collect();
+
+    assert!(text.starts_with(" main.rs  lib.rs  notes.txt ● "));
+    // " main.rs " is columns 0-8, " lib.rs " is 9-16
+    assert_eq!(buf[(1, 0)].bg, theme.tab_bg);
+    assert_eq!(buf[(10, 0)].bg, theme.active_tab_bg);
+    assert_ne!(buf[(1, 0)].style(), buf[(10, 0)].style());
+    assert_eq!(buf[(20, 0)].bg, theme.tab_bg);
+}
+
+#[test]
+fn test_tab_bar_truncates_names_and_maps_clicks() {
+    use editor::widgets::tab_bar::TabBar;
+
+    let mut buffers = vec![Buffer::new(), Buffer::new()];
+    buffers[0].name = "a_very_long_file_name.rs".to_string();
+    buffers[1].name = "another_long_name.rs".to_string();
+
+    let tab_bar = TabBar::new(&buffers, 0);
+    let ranges = tab_bar.tab_ranges(20);
+    assert_eq!(ranges, vec![(0, 0..10), (1, 10..20)]);
+    assert_eq!(tab_bar.tab_at(3, 20), Some(0));
+    assert_eq!(tab_bar.tab_at(15, 20), Some(1));
+    assert_eq!(tab_bar.tab_at(25, 20), None);
+
+    let backend = TestBackend::new(20, 1);
+    let mut terminal = Terminal::new(backend).unwrap();
+    terminal
+        .draw(|f| f.render_widget(tab_bar, Rect::new(0, 0, 20, 1)))
+        .unwrap();
+    let buf = terminal.backend().buffer();
+    let text: String = (0..20).map(|x| buf[(x, 0)].symbol()).collect();
+    assert_eq!(text, " a_very_…  another… ");
+}
+
+#[test]
+fn test_tab_bar_measures_wide_names_in_columns() {
+    use editor::widgets::tab_bar::TabBar;
+
+    let mut buffers = vec![Buffer::new(), Buffer::new()];
+    buffers[0].name = "日本語のファイル.txt".to_string();
+    buffers[1].name = "b.rs".to_string();
+
+    // The wide name is cut to fit, so both tabs share the 20 columns
+    let tab_bar = TabBar::new(&buffers, 0);
+    assert_eq!(tab_bar.tab_ranges(20), vec![(0, 0..9), (1, 9..15)]);
+    assert_eq!(tab_bar.tab_at(12, 20), Some(1));
+
+    // Names that fit take their full width
+    buffers[0].name = "日本.rs".to_string();
+    let tab_bar = TabBar::new(&buffers, 0);
+    assert_eq!(tab_bar.tab_ranges(20), vec![(0, 0..9), (1, 9..15)]);
+}
+
+#[test]
+fn test_command_context_draws_a_bar_cursor() {
+    use editor::widgets::{Cursor, CursorShape};
+    use ratatui::buffer::Buffer as TuiBuffer;
+    use ratatui::widgets::StatefulWidget;
+
+    let mut cursor_manager = CursorManager::new();
+    cursor_manager.set_active_context("editor");
+    assert_eq!(cursor_manager.get_cursor_shape("editor"), Some(CursorShape::Block));
+
+    cursor_manager.set_active_context("command_palette");
+    assert_eq!(
+        cursor_manager.get_cursor_shape("command_palette"),
+        Some(CursorShape::Bar)
+    );
+
+    // A bar on a blank cell is drawn as a thin glyph instead of a block
+    let area = Rect::new(0, 0, 4, 1);
+    let mut cells = TuiBuffer::empty(area);
+    let state = cursor_manager
+        .get_cursor_state_mut("command_palette")
+        .unwrap();
+    Cursor::new("command_palette")
+        .with_position(2, 0)
+        .render(area, &mut cells, state);
+    assert_eq!(cells[(2, 0)].symbol(), "▏");
+}
+
+#[test]
+fn test_editor_renders_lines_from_a_provider() {
+    use editor::buffer::LineProvider;
+    use std::borrow::Cow;
+    use std::cell::RefCell;
+
+    /// A million synthetic lines, remembering which were asked for
+    struct Synthetic {
+        requested: RefCell<Vec<usize>>,
+    }
+
+    impl LineProvider for Synthetic {
+        fn line(&self, idx: usize) -> Option<Cow<'_, str>> {
+            self.requested.borrow_mut().push(idx);
+            (idx < self.line_count()).then(|| Cow::Owned(format!("synthetic {}", idx)))
+        }
+
+        fn line_count(&self) -> usize {
+            1_000_000
+        }
+    }
+
+    let provider = Synthetic {
+        requested: RefCell::new(Vec::new()),
+    };
+    let mut editor = Editor::new(&provider);
+    editor.show_line_numbers = false;
+    editor.scroll_offset = (500_000, 0);
+
+    let backend = TestBackend::new(30, 3);
+    let mut terminal = Terminal::new(backend).unwrap();
+    terminal
+        .draw(|f| f.render_widget(editor, Rect::new(0, 0, 30, 3)))
+        .unwrap();
+
+    let buf = terminal.backend().buffer();
+    let row = |y: u16| -> String { (0..30).map(|x| buf[(x, y)].symbol()).collect() };
+    assert_eq!(row(0).trim_end(), "synthetic 500000");
+    assert_eq!(row(2).trim_end(), "synthetic 500002");
+
+    // Only the lines in view were requested
+    assert_eq!(*provider.requested.borrow(), vec![500_000, 500_001, 500_002]);
+}
+
+#[test]
+fn test_cursor_with_blinking_disabled_stays_solid() {
+    use editor::config::UiConfig;
+    use editor::widgets::{Cursor, CursorState};
+    use ratatui::buffer::Buffer as TuiBuffer;
+    use ratatui::widgets::StatefulWidget;
+    use std::time::Duration;
+
+    let ui = UiConfig {
+        cursor_blink_enabled: false,
+        cursor_blink_ms: 1,
+        ..UiConfig::default()
+    };
+    let state = CursorState::from_config(&ui);
+    assert!(!state.blink_enabled);
+    assert!(state.blink_on);
+
+    let mut cursor_manager = CursorManager::new();
+    cursor_manager.configure(&ui);
+    cursor_manager.set_active_context("editor");
+    cursor_manager.get_cursor_state_mut("editor").unwrap().activity_timeout = Duration::ZERO;
+
+    // Well past the blink interval every time, yet the cursor is always drawn
+    let area = Rect::new(0, 0, 4, 1);
+    for _ in 0..5 {
+        std::thread::sleep(Duration::from_millis(5));
+        cursor_manager.tick_animation();
+
+        let mut cells = TuiBuffer::empty(area);
+        let state = cursor_manager.get_cursor_state_mut("editor").unwrap();
+        Cursor::new("editor").with_position(1, 0).render(area, &mut cells, state);
+        assert!(state.blink_on);
+        assert_ne!(cells, TuiBuffer::empty(area));
+    }
+}
+
+#[test]
+fn test_long_accented_toast_is_cut_between_chars() {
+    let mut toast_manager = ToastManager::new();
+    toast_manager.add_error("Erreur : déjà vérifié dans « résumé.txt »".to_string());
+    let area = Rect::new(0, 0, 60, 12);
+    let toast_area = toast_manager.toast_areas(area)[0];
+
+    let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+    terminal
+        .draw(|f| f.render_widget(ToastWidget::new(&toast_manager), area))
+        .unwrap();
+
+    // The message row, inside the border
+    let buf = terminal.backend().buffer();
+    let row: String = (toast_area.x + 1..toast_area.right() - 1)
+        .map(|x| buf[(x, toast_area.y + 1)].symbol().to_string())
+        .collect();
+    // Cutting at the byte budget would land inside the "é"
+    assert_eq!(row.trim_end(), "✗ Erreur : dé...");
+}